@@ -0,0 +1,1977 @@
+//! Board input as data. Keys map to an [`Action`] with [`from_key`] through
+//! the [keymap](crate::keymap), mouse events with [`from_mouse`], and
+//! [`App::update`] applies it: it changes the board's state and returns the
+//! [`Effect`]s that need `gh`, `git` or the multiplexer, so the reducer can
+//! be tested without a terminal and never waits on a subprocess. What the
+//! effects produce comes back as [`Action::Completed`].
+
+use std::collections::HashSet;
+
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+use crate::app::App;
+use crate::arrange::{self, GroupMode, Row, SortMode};
+use crate::config;
+use crate::effect::{describe_conflicts, Effect, Outcome};
+use crate::git::{self, extract_issue_number, UpdateOutcome, UpdateStrategy};
+use crate::keymap::{Binding, Context};
+use crate::models::{
+    ChoiceField, ConfigEditState, ConfirmAction, ConfirmModal, EditIssueModal, IssueModal,
+    MergeStrategy, Mode, Screen, StateFilter, TextInput, SECTION_NAMES,
+};
+use crate::session::{expand_editor_command, MAIN_SESSION_NAME};
+use crate::ui;
+
+/// Something the user asked the board to do.
+#[derive(Debug)]
+pub enum Action {
+    Quit,
+    /// Drop the card filter (and any issue search it started).
+    ClearFilter,
+    FocusFilter,
+    SwitchRepo,
+    NextColumn,
+    PrevColumn,
+    Up,
+    Down,
+    Refresh,
+    PullBase,
+    ShowDependencies,
+    NewIssue,
+    /// Create a worktree and session for the selected issue.
+    StartWorktree,
+    /// Like [`Action::StartWorktree`], asking for the branch to start from.
+    StartWorktreeFrom,
+    EditIssue,
+    CloseIssue,
+    /// Start a session in the selected worktree.
+    StartSession,
+    RemoveWorktree,
+    Verify,
+    OpenEditor,
+    UpdateFromBase(UpdateStrategy),
+    Restack,
+    CreateLocalPr,
+    ToggleLocalMode,
+    ExportStore,
+    ImportStore,
+    Configure,
+    AttachMain,
+    AttachSession,
+    KillSession,
+    OpenPr,
+    MarkPrReady,
+    RevertPr,
+    MergePr,
+    ToggleStateFilter,
+    ToggleAssigneeFilter,
+    ToggleMessages,
+    ExpandMessages,
+    /// Scroll the message center by this many messages, towards older ones
+    /// when positive.
+    ScrollMessages(isize),
+    FocusColumn(usize),
+    /// Focus a column and select its `index`th (visible) card.
+    SelectCard {
+        section: usize,
+        index: usize,
+    },
+    /// Open the selected card's detail view.
+    ShowDetail,
+    /// Scroll the detail view by this many lines, down when positive.
+    ScrollDetail(i16),
+    CloseDetail,
+    /// Answer yes to the confirmation modal.
+    Confirm,
+    /// Dismiss the confirmation modal.
+    Cancel,
+    /// Switch to the repo's `n`th saved view, or back to the whole board.
+    SwitchView(Option<usize>),
+    /// Ask for a name to save the column filters under.
+    SaveView,
+    /// Sort the focused column the next way it can be sorted.
+    CycleSort,
+    /// Group the focused column the next way it can be grouped.
+    CycleGroup,
+    /// Collapse or expand the group the selection is in.
+    ToggleGroup,
+    /// Mark or unmark the selected card, or mark the range being marked.
+    ToggleMark,
+    /// Start marking a range of cards at the selection, or mark the range.
+    MarkRange,
+    /// Unmark the focused column's cards.
+    ClearMarks,
+    Completed(Outcome),
+}
+
+/// The action `key` stands for on the board, if any. Only covers the board
+/// itself and the confirmation modal; text fields take keys as input.
+pub fn from_key(app: &App, key: KeyEvent) -> Option<Action> {
+    if app.screen != Screen::Board {
+        return None;
+    }
+    if app.detail_scroll.is_some() {
+        let binding = app.keymap.lookup(Context::Detail, key).next()?;
+        return Some(binding.action());
+    }
+    match app.mode {
+        Mode::Confirming => confirm_key(app, key),
+        Mode::Normal | Mode::Filtering { focused: false, .. } => board_key(app, key),
+        _ => None,
+    }
+}
+
+fn confirm_key(app: &App, key: KeyEvent) -> Option<Action> {
+    let binding = app.keymap.lookup(Context::Confirm, key).next()?;
+    Some(binding.action())
+}
+
+/// The action a mouse event on the board stands for, if any. `area` is the
+/// terminal the board was drawn in; clicks are resolved against the same
+/// layout [`ui`] draws.
+pub fn from_mouse(app: &App, mouse: MouseEvent, area: Rect) -> Option<Action> {
+    if app.screen != Screen::Board {
+        return None;
+    }
+    let position = Position::new(mouse.column, mouse.row);
+    let clicked = mouse.kind == MouseEventKind::Down(MouseButton::Left);
+    if app.detail_scroll.is_some() {
+        return match mouse.kind {
+            MouseEventKind::ScrollDown => Some(Action::ScrollDetail(3)),
+            MouseEventKind::ScrollUp => Some(Action::ScrollDetail(-3)),
+            _ => None,
+        };
+    }
+    match app.mode {
+        Mode::Confirming if clicked => {
+            let message = app
+                .confirm_modal
+                .as_ref()
+                .map_or("", |m| m.message.as_str());
+            let layout = ui::confirm_layout(area, message);
+            if layout.confirm.contains(position) {
+                Some(Action::Confirm)
+            } else if layout.cancel.contains(position) {
+                Some(Action::Cancel)
+            } else {
+                None
+            }
+        }
+        Mode::Normal | Mode::Filtering { focused: false, .. } => {
+            board_mouse(app, mouse.kind, position, area)
+        }
+        _ => None,
+    }
+}
+
+fn board_mouse(app: &App, kind: MouseEventKind, position: Position, area: Rect) -> Option<Action> {
+    let layout = ui::board_layout(area, app);
+    if layout.messages.contains(position) {
+        return match kind {
+            MouseEventKind::ScrollUp => Some(Action::ScrollMessages(1)),
+            MouseEventKind::ScrollDown => Some(Action::ScrollMessages(-1)),
+            _ => None,
+        };
+    }
+    let section = layout.columns.iter().position(|c| c.contains(position))?;
+    let selected = app.selected_card[section];
+    match kind {
+        MouseEventKind::ScrollUp => Some(Action::SelectCard {
+            section,
+            index: selected.saturating_sub(1),
+        }),
+        MouseEventKind::ScrollDown => Some(Action::SelectCard {
+            section,
+            index: selected + 1,
+        }),
+        MouseEventKind::Down(MouseButton::Left) if !app.section_loading[section] => {
+            let filtering =
+                matches!(app.mode, Mode::Filtering { .. }) && section == app.active_section;
+            let column = ui::column_layout(
+                layout.columns[section],
+                filtering,
+                &app.rows(section)
+                    .iter()
+                    .map(Row::height)
+                    .collect::<Vec<_>>(),
+                (section == app.active_section).then_some(selected),
+            );
+            Some(
+                match column.slots.iter().position(|s| s.contains(position)) {
+                    Some(slot) => Action::SelectCard {
+                        section,
+                        index: column.first + slot,
+                    },
+                    None => Action::FocusColumn(section),
+                },
+            )
+        }
+        MouseEventKind::Down(MouseButton::Left) => Some(Action::FocusColumn(section)),
+        _ => None,
+    }
+}
+
+/// The first binding for `key` that does something right now: filter keys
+/// first, then the focused column's, then the board's.
+fn board_key(app: &App, key: KeyEvent) -> Option<Action> {
+    let filtering = matches!(app.mode, Mode::Filtering { .. });
+    let marking = app.mark_anchor.is_some() || !app.marked[app.active_section].is_empty();
+    let column = Context::column(app.active_section);
+    let mut contexts = vec![column, Context::Board];
+    if filtering {
+        contexts.insert(0, Context::Filter);
+    }
+    if marking {
+        contexts.insert(0, Context::Marked);
+    }
+    contexts
+        .into_iter()
+        .flat_map(|context| app.keymap.lookup(context, key))
+        .find(|binding| enabled(app, binding.name))
+        .map(Binding::action)
+}
+
+/// Whether the action bound as `name` applies in the board's current state.
+fn enabled(app: &App, name: &str) -> bool {
+    let filtering = matches!(app.mode, Mode::Filtering { .. });
+    match name {
+        "quit" | "switch_repo" | "new_issue" => !filtering,
+        "refresh" => !app.is_section_loading(),
+        "export_store" | "import_store" | "create_local_pr" => app.local_mode,
+        "expand_messages" => app.show_messages,
+        "start_worktree" | "start_worktree_from" | "start_session" => {
+            app.worktree_create_rx.is_none()
+        }
+        "close_issue" => app.issue_state_filter == StateFilter::Open,
+        _ => true,
+    }
+}
+
+/// Cards a bulk action's confirmation lists before summing up the rest.
+const BULK_LISTED: usize = 10;
+
+impl App {
+    /// Apply `action` to the board and return the side effects it needs.
+    pub fn update(&mut self, action: Action) -> Vec<Effect> {
+        match action {
+            // With cards marked, the column's actions apply to all of them
+            action @ (Action::StartWorktree
+            | Action::CloseIssue
+            | Action::RemoveWorktree
+            | Action::KillSession
+            | Action::MarkPrReady
+            | Action::MergePr)
+                if !self.marked_cards(self.active_section).is_empty() =>
+            {
+                self.confirm_marked(action)
+            }
+            Action::Quit => {}
+            Action::ClearFilter => {
+                // Clear any server-side search along with the filter
+                let search = match self.active_section {
+                    0 => self.issue_search_query.take(),
+                    3 => self.pr_search_query.take(),
+                    _ => None,
+                };
+                if search.is_some() {
+                    self.start_async_refresh();
+                }
+                self.mode = Mode::Normal;
+                self.clamp_selected();
+            }
+            Action::FocusFilter => {
+                if let Mode::Filtering { focused, .. } = &mut self.mode {
+                    *focused = true;
+                } else {
+                    // Start from the view's filter, which this one replaces
+                    let view = self.active_view.as_ref();
+                    let query = view.map_or("", |v| v.query(self.active_section));
+                    self.mode = Mode::Filtering {
+                        query: TextInput::from(query.to_string()),
+                        focused: true,
+                    };
+                }
+            }
+            Action::SwitchRepo => self.enter_repo_select(),
+            Action::NextColumn => {
+                self.active_section = (self.active_section + 1) % 4;
+                self.mark_anchor = None;
+            }
+            Action::PrevColumn => {
+                self.active_section = (self.active_section + 3) % 4;
+                self.mark_anchor = None;
+            }
+            Action::Up => self.move_card_up(),
+            Action::Down => {
+                self.move_card_down();
+                self.failed_issue_cursor = None;
+                return self.load_more_issues();
+            }
+            Action::Refresh => {
+                self.start_async_refresh();
+                self.set_status("Refreshing…".to_string());
+            }
+            Action::PullBase => {
+                return vec![Effect::PullBase {
+                    repo: self.repo.clone(),
+                }]
+            }
+            Action::ShowDependencies => return vec![Effect::CheckDependencies],
+            Action::NewIssue => {
+                self.mode = Mode::CreatingIssue;
+                let mut modal = IssueModal::new();
+                if self.local_mode {
+                    // Local issues can't be assigned.
+                    modal.assignees = ChoiceField::new(true);
+                }
+                self.issue_modal = Some(modal);
+                return vec![
+                    Effect::LoadTemplates,
+                    Effect::LoadIssueOptions {
+                        repo: self.repo.clone(),
+                        local: self.local_mode,
+                    },
+                ];
+            }
+            Action::StartWorktree => {
+                if let Some(card) = self.selected(0) {
+                    // Issue number from id "issue-N" or "local-issue-N"
+                    if let Some(number) = extract_issue_number(&card.id) {
+                        let title = card.title.clone();
+                        let body = card.full_description.clone().unwrap_or_default();
+                        self.start_issue_worktree(number, title, body, None);
+                    }
+                }
+            }
+            Action::StartWorktreeFrom => {
+                if let Some(card) = self.selected(0) {
+                    let issue_id = card.id.clone();
+                    let base = git::default_base_branch(&self.repo).unwrap_or_default();
+                    self.mode = Mode::ChoosingBase {
+                        issue_id,
+                        input: TextInput::from(base),
+                    };
+                }
+            }
+            Action::EditIssue => {
+                if let Some(card) = self.selected(0) {
+                    if let Some(number) = extract_issue_number(&card.id) {
+                        // Title without the "#N " prefix
+                        let title = card
+                            .title
+                            .strip_prefix(&format!("#{} ", number))
+                            .unwrap_or(&card.title)
+                            .to_string();
+                        let body = card.full_description.clone().unwrap_or_default();
+                        self.edit_issue_modal = Some(EditIssueModal::new(number, title, body));
+                        self.mode = Mode::EditingIssue;
+                    }
+                }
+            }
+            Action::CloseIssue => {
+                if let Some(card) = self.selected(0) {
+                    if let Some(number) = extract_issue_number(&card.id) {
+                        let message = format!("Close issue #{}?\n\n{}", number, card.title);
+                        self.confirm(message, ConfirmAction::CloseIssue { number });
+                    }
+                }
+            }
+            Action::StartSession => {
+                if let Some(card) = self.selected(1) {
+                    let branch = card.title.clone();
+                    let path = card.description.clone();
+                    // The issue this branch was created for
+                    if let Some(number) = git::issue_number_for_branch(&branch) {
+                        if self.sessions.iter().any(|s| s.title == branch) {
+                            self.set_status(format!(
+                                "Session '{}' already exists — use 'a' to attach",
+                                branch
+                            ));
+                        } else {
+                            self.start_worktree_session(number, branch, path);
+                        }
+                    } else {
+                        self.set_status(
+                            "Cannot create session: branch is not an issue branch".to_string(),
+                        );
+                    }
+                }
+            }
+            Action::RemoveWorktree => {
+                if let Some(card) = self.selected(1) {
+                    let branch = card.title.clone();
+                    if branch == "main" || branch == "master" {
+                        self.set_status("Cannot remove main/master worktree".to_string());
+                    } else {
+                        let path = card.description.clone();
+                        let children = git::stack_children(&branch);
+                        let stack_note = if children.is_empty() {
+                            String::new()
+                        } else {
+                            format!(
+                                "\n\nStacked on it: {} — restack them first with 'S' if it was merged.",
+                                children.join(", ")
+                            )
+                        };
+                        let message = format!(
+                            "Remove worktree '{}'?\n\nPath: {}\nThis will also delete the branch and kill any associated session.{}",
+                            branch, path, stack_note
+                        );
+                        self.confirm(message, ConfirmAction::RemoveWorktree { path, branch });
+                    }
+                }
+            }
+            Action::Verify => {
+                if let Some(card) = self.selected(1) {
+                    match config::get_verify_command(&self.repo) {
+                        Some(cmd) => {
+                            return vec![Effect::Launch {
+                                command: expand_editor_command(&cmd, &card.description),
+                                what: format!("verify for '{}'", card.title),
+                            }]
+                        }
+                        // No verify command configured — prompt for one
+                        None => {
+                            self.mode = Mode::EditingVerifyCommand {
+                                input: TextInput::new(),
+                            }
+                        }
+                    }
+                }
+            }
+            Action::OpenEditor => {
+                if let Some(card) = self.selected(1) {
+                    match config::get_editor_command(&self.repo) {
+                        Some(cmd) => {
+                            return vec![Effect::Launch {
+                                command: expand_editor_command(&cmd, &card.description),
+                                what: format!("editor for '{}'", card.title),
+                            }]
+                        }
+                        None => {
+                            self.mode = Mode::EditingEditorCommand {
+                                input: TextInput::new(),
+                            }
+                        }
+                    }
+                }
+            }
+            Action::UpdateFromBase(strategy) => {
+                if let Some(card) = self.selected(1) {
+                    return vec![Effect::UpdateFromBase {
+                        repo: self.repo.clone(),
+                        branch: card.title.clone(),
+                        path: card.description.clone(),
+                        strategy,
+                        mux: self.multiplexer,
+                    }];
+                }
+            }
+            Action::Restack => {
+                if let Some(card) = self.selected(1) {
+                    return vec![Effect::LoadStack {
+                        parent: card.title.clone(),
+                    }];
+                }
+            }
+            Action::CreateLocalPr => {
+                if let Some(card) = self.selected(1) {
+                    return vec![Effect::CreateLocalPr {
+                        repo: self.repo.clone(),
+                        branch: card.title.clone(),
+                    }];
+                }
+            }
+            // Leaving local mode needs gh, which is looked for first
+            Action::ToggleLocalMode if self.local_mode => return vec![Effect::CheckGh],
+            Action::ToggleLocalMode => return self.toggle_local_mode(),
+            Action::ExportStore => {
+                self.mode = Mode::ExportingStore {
+                    input: TextInput::new(),
+                }
+            }
+            Action::ImportStore => {
+                self.mode = Mode::ImportingStore {
+                    input: TextInput::new(),
+                }
+            }
+            Action::Configure => {
+                let repo = &self.repo;
+                self.config_edit = Some(ConfigEditState::new(
+                    config::get_verify_command(repo).unwrap_or_default(),
+                    config::get_editor_command(repo).unwrap_or_default(),
+                    config::get_pr_ready(repo),
+                    config::get_auto_open_pr(repo),
+                    config::get_session_command(repo).unwrap_or_default(),
+                    self.multiplexer,
+                    config::get_auto_refresh_secs(),
+                    config::get_worktree_dir_template(repo).unwrap_or_default(),
+                    config::get_branch_template(repo).unwrap_or_default(),
+                    config::get_base_branch(repo).unwrap_or_default(),
+                ));
+                self.screen = Screen::Configuration;
+            }
+            // Main worktree session: created if needed, then attached
+            Action::AttachMain => {
+                return vec![Effect::Attach {
+                    session: MAIN_SESSION_NAME.to_string(),
+                    mux: self.multiplexer,
+                }]
+            }
+            Action::AttachSession => {
+                if let Some(card) = self.selected(2) {
+                    return vec![Effect::Attach {
+                        session: card.title.clone(),
+                        mux: self.multiplexer,
+                    }];
+                }
+            }
+            Action::KillSession => {
+                if let Some(card) = self.selected(2) {
+                    let name = card.title.clone();
+                    self.confirm(
+                        format!("Kill session '{}'?", name),
+                        ConfirmAction::KillSession { name },
+                    );
+                }
+            }
+            Action::OpenPr => {
+                if let Some(url) = self.selected(3).and_then(|card| card.url.clone()) {
+                    return vec![Effect::OpenUrl { url }];
+                }
+            }
+            Action::MarkPrReady => {
+                if let Some(card) = self.selected(3) {
+                    if card.is_draft != Some(true) {
+                        self.set_status("PR is already ready".to_string());
+                    } else if let Some(number) = card.pr_number {
+                        return vec![Effect::MarkPrReady {
+                            repo: self.repo.clone(),
+                            number,
+                            local: self.local_mode,
+                        }];
+                    }
+                }
+            }
+            Action::RevertPr => {
+                if self.local_mode {
+                    self.set_status("Revert is not available in local mode".to_string());
+                } else if let Some(card) = self.selected(3) {
+                    if let Some(number) = card.pr_number {
+                        if card.is_merged != Some(true) {
+                            self.set_status("Can only revert merged PRs".to_string());
+                        } else {
+                            self.confirm(
+                                format!(
+                                    "Revert PR #{}? This will create a new PR that undoes its changes.",
+                                    number
+                                ),
+                                ConfirmAction::RevertPr { number },
+                            );
+                        }
+                    }
+                }
+            }
+            Action::MergePr => {
+                if let Some(card) = self.selected(3) {
+                    if let Some(number) = card.pr_number {
+                        let branch = card.head_branch.clone();
+                        if card.is_draft == Some(true) {
+                            self.set_status("Cannot merge a draft PR".to_string());
+                        } else if self.local_mode {
+                            self.confirm(
+                                format!("Merge local PR #{} (git merge)?", number),
+                                ConfirmAction::MergeLocalPr { number, branch },
+                            );
+                        } else {
+                            self.confirm(
+                                format!("Merge PR #{} with merge strategy?", number),
+                                ConfirmAction::MergePr {
+                                    number,
+                                    strategy: MergeStrategy::Merge,
+                                    branch,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            Action::ToggleStateFilter => {
+                if self.active_section == 0 {
+                    self.issue_state_filter = self.issue_state_filter.toggle();
+                } else {
+                    self.pr_state_filter = self.pr_state_filter.toggle();
+                }
+                self.start_async_refresh();
+            }
+            Action::ToggleAssigneeFilter => {
+                if self.active_section == 0 {
+                    self.issue_assignee_filter = self.issue_assignee_filter.toggle();
+                } else {
+                    self.pr_assignee_filter = self.pr_assignee_filter.toggle();
+                }
+                self.start_async_refresh();
+            }
+            Action::ToggleMessages => {
+                self.show_messages = !self.show_messages;
+                if !self.show_messages {
+                    self.messages_expanded = false;
+                }
+            }
+            Action::ExpandMessages => self.messages_expanded = !self.messages_expanded,
+            Action::ScrollMessages(delta) => {
+                let count = self.message_log.lock().map(|log| log.len()).unwrap_or(0);
+                self.message_scroll = self
+                    .message_scroll
+                    .saturating_add_signed(delta)
+                    .min(count.saturating_sub(1));
+            }
+            Action::FocusColumn(section) => {
+                if section != self.active_section {
+                    self.mark_anchor = None;
+                }
+                self.active_section = section;
+            }
+            Action::ShowDetail => {
+                if self.selected(self.active_section).is_some() {
+                    self.detail_scroll = Some(0);
+                }
+            }
+            Action::ScrollDetail(delta) => {
+                // Scrolls by source line, so the end is known without the
+                // terminal's size
+                let lines = match self.selected(self.active_section) {
+                    Some(card) => ui::detail_lines(self, card).len(),
+                    None => 0,
+                };
+                if let Some(scroll) = &mut self.detail_scroll {
+                    *scroll = scroll
+                        .saturating_add_signed(delta)
+                        .min(lines.saturating_sub(1) as u16);
+                }
+            }
+            Action::CloseDetail => self.detail_scroll = None,
+            Action::SelectCard { section, index } => {
+                if section != self.active_section {
+                    self.mark_anchor = None;
+                }
+                self.active_section = section;
+                self.selected_card[section] = index;
+                self.clamp_selected();
+                self.failed_issue_cursor = None;
+                return self.load_more_issues();
+            }
+            Action::Confirm => {
+                self.mode = Mode::Normal;
+                if let Some(modal) = self.confirm_modal.take() {
+                    return self.confirmed(modal.on_confirm);
+                }
+            }
+            Action::Cancel => {
+                self.confirm_modal = None;
+                self.mode = Mode::Normal;
+            }
+            Action::SwitchView(index) => {
+                let view = match index {
+                    Some(i) => match config::get_views(&self.repo).into_iter().nth(i) {
+                        Some(view) => Some(view),
+                        None => {
+                            self.set_status(format!("No saved view {}", i + 1));
+                            return Vec::new();
+                        }
+                    },
+                    None => None,
+                };
+                self.set_status(match &view {
+                    Some(view) => format!("View: {}", view.name),
+                    None => "Showing every card".to_string(),
+                });
+                self.active_view = view;
+                self.show_view_results();
+            }
+            Action::SaveView => {
+                let filter = match &self.mode {
+                    Mode::Filtering { query, .. } => Some(query.value().to_string()),
+                    _ => None,
+                };
+                let name = self.active_view.as_ref().map(|v| v.name.clone());
+                self.mode = Mode::NamingView {
+                    input: TextInput::from(name.unwrap_or_default()),
+                    filter,
+                };
+            }
+            Action::CycleSort => {
+                let s = self.active_section;
+                let sort = &mut self.arrangements[s].sort;
+                *sort = arrange::next(SortMode::options(s), *sort);
+                let message = format!("{} sorted by {}", SECTION_NAMES[s], sort.label());
+                self.save_arrangement(message);
+            }
+            Action::CycleGroup => {
+                let s = self.active_section;
+                let group = &mut self.arrangements[s].group;
+                *group = arrange::next(GroupMode::options(s), *group);
+                let message = match *group {
+                    GroupMode::None => format!("{} not grouped", SECTION_NAMES[s]),
+                    group => format!("{} grouped by {}", SECTION_NAMES[s], group.label()),
+                };
+                self.collapsed_groups[s].clear();
+                self.selected_card[s] = 0;
+                self.save_arrangement(message);
+            }
+            Action::ToggleGroup => {
+                let s = self.active_section;
+                let rows = self.rows(s);
+                let selected = self.selected_card[s].min(rows.len().saturating_sub(1));
+                // The nearest header at or above the selection
+                let header = rows.iter().take(selected + 1).enumerate().rev().find_map(
+                    |(i, row)| match row {
+                        Row::Group { name, .. } => Some((i, name.clone())),
+                        Row::Card(_) => None,
+                    },
+                );
+                if let Some((i, name)) = header {
+                    if !self.collapsed_groups[s].remove(&name) {
+                        self.collapsed_groups[s].insert(name);
+                    }
+                    self.selected_card[s] = i;
+                }
+            }
+            Action::ToggleMark if self.mark_anchor.is_some() => self.mark_range_cards(),
+            Action::ToggleMark => {
+                let s = self.active_section;
+                if let Some(id) = self.selected(s).map(|c| c.id.clone()) {
+                    if !self.marked[s].remove(&id) {
+                        self.marked[s].insert(id);
+                    }
+                }
+            }
+            Action::MarkRange => match self.mark_anchor {
+                Some(_) => self.mark_range_cards(),
+                None => self.mark_anchor = Some(self.selected_card[self.active_section]),
+            },
+            Action::ClearMarks => {
+                self.marked[self.active_section].clear();
+                self.mark_anchor = None;
+            }
+            Action::Completed(outcome) => {
+                let mut effects = self.apply_outcome(outcome);
+                effects.extend(self.load_more_issues());
+                if self.reload_pending && self.effects_running == 0 {
+                    self.reload_pending = false;
+                    effects.push(self.reload_sessions());
+                }
+                return effects;
+            }
+        }
+        Vec::new()
+    }
+
+    /// Store the focused column's arrangement for the repo and report it.
+    fn save_arrangement(&mut self, message: String) {
+        let s = self.active_section;
+        match config::set_arrangement(&self.repo, s, self.arrangements[s]) {
+            Ok(()) => self.set_status(message),
+            Err(e) => self.set_status(format!("{} (not saved: {})", message, e)),
+        }
+        self.clamp_selected();
+    }
+
+    /// Mark the cards in the range being marked and stop marking it.
+    fn mark_range_cards(&mut self) {
+        let Some(range) = self.mark_range() else {
+            return;
+        };
+        self.mark_anchor = None;
+        let s = self.active_section;
+        let ids: Vec<String> = self
+            .rows(s)
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| range.contains(i))
+            .filter_map(|(_, row)| match row {
+                Row::Card(card) => Some(card.id.clone()),
+                Row::Group { .. } => None,
+            })
+            .collect();
+        self.marked[s].extend(ids);
+    }
+
+    /// Ask once to apply `action` to every marked card of the focused
+    /// column, listing them. Cards it can't apply to are left out, with the
+    /// reason in the message log.
+    fn confirm_marked(&mut self, action: Action) {
+        let s = self.active_section;
+        let mut items = Vec::new();
+        let mut titles = Vec::new();
+        let mut skipped = Vec::new();
+        for card in self.marked_cards(s) {
+            let item = match action {
+                Action::StartWorktree | Action::CloseIssue => {
+                    match extract_issue_number(&card.id) {
+                        Some(number) if matches!(action, Action::CloseIssue) => {
+                            Ok(ConfirmAction::CloseIssue { number })
+                        }
+                        Some(number) => Ok(ConfirmAction::StartWorktree {
+                            number,
+                            title: card.title.clone(),
+                            body: card.full_description.clone().unwrap_or_default(),
+                        }),
+                        None => Err("not an issue"),
+                    }
+                }
+                Action::RemoveWorktree if card.title == "main" || card.title == "master" => {
+                    Err("the main worktree stays")
+                }
+                Action::RemoveWorktree => Ok(ConfirmAction::RemoveWorktree {
+                    path: card.description.clone(),
+                    branch: card.title.clone(),
+                }),
+                Action::KillSession => Ok(ConfirmAction::KillSession {
+                    name: card.title.clone(),
+                }),
+                Action::MarkPrReady | Action::MergePr => match card.pr_number {
+                    None => Err("not a PR"),
+                    Some(_) if card.is_merged == Some(true) => Err("already merged"),
+                    Some(number) if matches!(action, Action::MarkPrReady) => match card.is_draft {
+                        Some(true) => Ok(ConfirmAction::MarkPrReady { number }),
+                        _ => Err("already ready"),
+                    },
+                    Some(_) if card.is_draft == Some(true) => Err("a draft can't be merged"),
+                    Some(number) if self.local_mode => Ok(ConfirmAction::MergeLocalPr {
+                        number,
+                        branch: card.head_branch.clone(),
+                    }),
+                    Some(number) => Ok(ConfirmAction::MergePr {
+                        number,
+                        strategy: MergeStrategy::Merge,
+                        branch: card.head_branch.clone(),
+                    }),
+                },
+                _ => return,
+            };
+            match item {
+                Ok(item) => {
+                    items.push(item);
+                    titles.push(card.title.clone());
+                }
+                Err(reason) => skipped.push(format!("Skipping '{}': {}", card.title, reason)),
+            }
+        }
+        for message in skipped {
+            self.set_status(message);
+        }
+        if items.is_empty() {
+            self.set_status("None of the marked cards can be done that way".to_string());
+            return;
+        }
+        let count = items.len();
+        let question = match action {
+            Action::StartWorktree => format!("Start worktrees and sessions for {} issues?", count),
+            Action::CloseIssue => format!("Close {} issues?", count),
+            Action::RemoveWorktree => format!(
+                "Remove {} worktrees? Their branches are deleted and their sessions killed.",
+                count
+            ),
+            Action::KillSession => format!("Kill {} sessions?", count),
+            Action::MarkPrReady => format!("Mark {} PRs as ready?", count),
+            _ => format!("Merge {} PRs with merge strategy?", count),
+        };
+        let mut message = format!("{}\n", question);
+        for title in titles.iter().take(BULK_LISTED) {
+            message.push_str(&format!("\n  {}", title));
+        }
+        if count > BULK_LISTED {
+            message.push_str(&format!("\n  …and {} more", count - BULK_LISTED));
+        }
+        self.confirm(message, ConfirmAction::Bulk(items));
+    }
+
+    /// Ask for confirmation before `on_confirm`.
+    fn confirm(&mut self, message: String, on_confirm: ConfirmAction) {
+        self.confirm_modal = Some(ConfirmModal {
+            message,
+            on_confirm,
+        });
+        self.mode = Mode::Confirming;
+    }
+
+    /// The user said yes to `action`.
+    fn confirmed(&mut self, action: ConfirmAction) -> Vec<Effect> {
+        let repo = self.repo.clone();
+        let mux = self.multiplexer;
+        let effect = match action {
+            ConfirmAction::CloseIssue { number } => Effect::CloseIssue {
+                repo,
+                number,
+                local: self.local_mode,
+            },
+            ConfirmAction::RemoveWorktree { path, branch } => {
+                Effect::RemoveWorktree { path, branch, mux }
+            }
+            ConfirmAction::KillSession { name } => Effect::KillSession { name, mux },
+            ConfirmAction::RevertPr { number } => Effect::RevertPr { repo, number },
+            ConfirmAction::ImportStore { path, format } => Effect::ImportStore {
+                repo,
+                path,
+                format,
+                dry_run: false,
+            },
+            ConfirmAction::MergePr {
+                number,
+                strategy,
+                branch,
+            } => Effect::MergePr {
+                repo,
+                number,
+                strategy,
+                worktree: self.worktree_path(branch.as_deref()),
+                branch,
+                mux,
+            },
+            ConfirmAction::MergeLocalPr { number, branch } => Effect::MergeLocalPr {
+                repo,
+                number,
+                worktree: self.worktree_path(branch.as_deref()),
+                branch,
+                mux,
+            },
+            ConfirmAction::RestackChildren { parent, onto } => Effect::Restack {
+                repo,
+                parent,
+                onto,
+                local: self.local_mode,
+                mux,
+            },
+            ConfirmAction::SyncLocalToGithub => {
+                self.start_sync_to_github();
+                return Vec::new();
+            }
+            ConfirmAction::StartWorktree {
+                number,
+                title,
+                body,
+            } => {
+                self.start_issue_worktree(number, title, body, None);
+                return Vec::new();
+            }
+            ConfirmAction::MarkPrReady { number } => Effect::MarkPrReady {
+                repo,
+                number,
+                local: self.local_mode,
+            },
+            ConfirmAction::Bulk(actions) => {
+                self.marked[self.active_section].clear();
+                let mut issues = Vec::new();
+                let mut effects = Vec::new();
+                for action in actions {
+                    match action {
+                        ConfirmAction::StartWorktree {
+                            number,
+                            title,
+                            body,
+                        } => issues.push((number, title, body)),
+                        action => effects.extend(self.confirmed(action)),
+                    }
+                }
+                if !issues.is_empty() {
+                    self.start_issue_worktrees(issues);
+                }
+                if effects.is_empty() {
+                    return Vec::new();
+                }
+                Effect::Sequence(effects)
+            }
+        };
+        vec![effect]
+    }
+
+    /// Path of the worktree on the board for `branch`.
+    fn worktree_path(&self, branch: Option<&str>) -> Option<String> {
+        let branch = branch?;
+        self.worktrees
+            .iter()
+            .find(|w| w.title == branch)
+            .map(|w| w.description.clone())
+    }
+
+    /// Switch between GitHub and local mode, offering to carry local work
+    /// over to GitHub when leaving local mode.
+    /// Switch between local and GitHub mode; leaving local mode offers to
+    /// sync what was created locally.
+    fn toggle_local_mode(&mut self) -> Vec<Effect> {
+        self.local_mode = !self.local_mode;
+        let mode_label = if self.local_mode { "LOCAL" } else { "GITHUB" };
+        self.set_status(format!("Switched to {} mode", mode_label));
+        self.start_async_refresh();
+        let mut effects = vec![Effect::SaveLocalMode {
+            enabled: self.local_mode,
+        }];
+        if !self.local_mode && self.sync_rx.is_none() {
+            effects.push(Effect::CountUnsynced {
+                repo: self.repo.clone(),
+            });
+        }
+        effects
+    }
+
+    /// Pick up the worktrees and sessions a finished effect changed.
+    pub fn reload_sessions(&self) -> Effect {
+        Effect::ReloadSessions {
+            repo: self.repo.clone(),
+            states: self
+                .session_states
+                .lock()
+                .map(|states| states.clone())
+                .unwrap_or_default(),
+            mux: self.multiplexer,
+        }
+    }
+
+    /// Take in what an effect came back with, returning any follow-up work.
+    fn apply_outcome(&mut self, outcome: Outcome) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        match outcome {
+            Outcome::Pulled(Ok(branch)) => {
+                self.main_behind_count = 0;
+                self.set_status(format!("Pulled latest changes for {}", branch));
+            }
+            Outcome::Pulled(Err(e)) => self.set_status(format!("Pull failed: {}", e)),
+            Outcome::Updated {
+                branch,
+                strategy,
+                result,
+                asked_agent,
+                stacked,
+            } => {
+                match result {
+                    Ok((base, UpdateOutcome::Updated)) => self.set_status(format!(
+                        "Updated '{}' from {} ({})",
+                        branch,
+                        base,
+                        strategy.label()
+                    )),
+                    Ok((_, UpdateOutcome::Conflicts(files))) => {
+                        self.set_status(describe_conflicts(&branch, strategy, &files, asked_agent))
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e)),
+                }
+                self.reload_pending = true;
+                // The branches stacked on this one are now behind it
+                self.ask_restack(&branch, stacked, None);
+            }
+            Outcome::StackLoaded { parent, children } => {
+                if children.is_empty() {
+                    self.set_status(format!("No branches are stacked on '{}'", parent));
+                } else {
+                    self.ask_restack(&parent, children, None);
+                }
+            }
+            Outcome::Restacked {
+                parent,
+                onto,
+                report,
+            } => {
+                for note in &report.notes {
+                    self.add_message(note);
+                }
+                self.set_status(match onto {
+                    Some(onto) => format!(
+                        "Moved {} branch(es) from '{}' onto {}{}",
+                        report.restacked,
+                        parent,
+                        onto,
+                        if report.left_on_parent > 0 {
+                            format!(", {} still on it", report.left_on_parent)
+                        } else {
+                            String::new()
+                        }
+                    ),
+                    None => format!(
+                        "Rebased {} branch(es) onto '{}'{}",
+                        report.restacked,
+                        parent,
+                        if report.left_on_parent > 0 {
+                            format!(", {} not rebased", report.left_on_parent)
+                        } else {
+                            String::new()
+                        }
+                    ),
+                });
+                self.reload_pending = true;
+            }
+            Outcome::LocalPrCreated { branch, result } => match result {
+                Ok(Some(number)) => {
+                    self.start_async_refresh();
+                    self.set_status(format!("Created local PR #{} for '{}'", number, branch));
+                }
+                Ok(None) => self.set_status(format!("Local PR already exists for '{}'", branch)),
+                Err(e) => self.set_status(format!("Error: {}", e)),
+            },
+            Outcome::LocalPrsAutoCreated(results) => {
+                let mut created = false;
+                for (branch, result) in results {
+                    match result {
+                        Ok(number) => {
+                            created = true;
+                            self.set_status(format!(
+                                "Auto-created local PR #{} for {}",
+                                number, branch
+                            ));
+                            self.add_message(&format!(
+                                "[monitor] Auto-created local PR #{} for {}",
+                                number, branch
+                            ));
+                        }
+                        Err(e) => self.add_message(&format!(
+                            "[monitor] Failed to auto-create local PR for {}: {}",
+                            branch, e
+                        )),
+                    }
+                }
+                if created {
+                    self.start_async_refresh();
+                }
+            }
+            Outcome::Nudged(results) => {
+                for (branch, result) in results {
+                    match result {
+                        Ok(()) => self.add_message(&format!(
+                            "[monitor] Nudged {} to continue (no PR found)",
+                            branch
+                        )),
+                        Err(e) => self
+                            .add_message(&format!("[monitor] Failed to nudge {}: {}", branch, e)),
+                    }
+                }
+            }
+            Outcome::MergedCleanedUp(result) => {
+                // The worktrees are re-read right after the cleanup
+                if !result.cleaned.is_empty() {
+                    self.set_status(format!("Cleaned up merged: {}", result.cleaned.join(", ")));
+                }
+                for parent in result.held {
+                    if !self.restack_offered.contains(&parent) && self.offer_merged_restack(&parent)
+                    {
+                        self.restack_offered.insert(parent);
+                    }
+                }
+            }
+            Outcome::LocalModeSaved(result) => crate::log::warn_on_err("config", result),
+            Outcome::UnsyncedCounted(Ok((issues, prs))) => {
+                let offer = !self.local_mode
+                    && self.sync_rx.is_none()
+                    && self.mode == Mode::Normal
+                    && (issues > 0 || prs > 0);
+                if offer {
+                    self.confirm(
+                        format!(
+                            "Sync {} local issue(s) and {} PR(s) to GitHub?",
+                            issues, prs
+                        ),
+                        ConfirmAction::SyncLocalToGithub,
+                    );
+                }
+            }
+            Outcome::UnsyncedCounted(Err(e)) => crate::log::warn("local", e),
+            Outcome::TemplatesLoaded(templates) => {
+                if let Some(modal) = &mut self.issue_modal {
+                    modal.offer_templates(templates);
+                }
+            }
+            Outcome::Exported(result) => match result {
+                Ok(summary) => self.set_status(summary),
+                Err(e) => self.set_status(format!("Error: {}", e)),
+            },
+            Outcome::Launched { what, result } => match result {
+                Ok(()) => self.set_status(format!("Launched {}", what)),
+                Err(e) => self.set_status(format!("Failed to launch {}: {}", what, e)),
+            },
+            Outcome::MarkedReady { number, result } => match result {
+                Ok(()) => {
+                    self.start_async_refresh();
+                    self.set_status(format!("PR #{} marked as ready", number));
+                }
+                Err(e) => self.set_status(format!("Error: PR #{}: {}", number, e)),
+            },
+            Outcome::Reverted { number, result } => match result {
+                Ok(()) => {
+                    self.start_async_refresh();
+                    self.set_status(format!("Created revert PR for #{}", number));
+                }
+                Err(e) => self.set_status(format!("Error: {}", e)),
+            },
+            Outcome::IssueClosed { number, result } => match result {
+                Ok(()) => {
+                    self.start_async_refresh();
+                    self.set_status(format!("Closed issue #{}", number));
+                }
+                Err(e) => self.set_status(format!("Error: issue #{}: {}", number, e)),
+            },
+            Outcome::WorktreeRemoved { branch, result } => match result {
+                Ok(()) => {
+                    self.reload_pending = true;
+                    self.set_status(format!("Removed worktree '{}'", branch));
+                }
+                Err(e) => self.set_status(format!("Error: worktree '{}': {}", branch, e)),
+            },
+            Outcome::SessionKilled { name, result } => match result {
+                Ok(()) => {
+                    self.reload_pending = true;
+                    self.set_status(format!("Killed session '{}'", name));
+                }
+                Err(e) => self.set_status(format!("Error: session '{}': {}", name, e)),
+            },
+            Outcome::Imported {
+                path,
+                format,
+                dry_run: true,
+                result,
+            } => match result {
+                // The dry run, so the confirmation shows what will change
+                Ok(report) => self.confirm(
+                    format!(
+                        "Import {} into the local store? {}",
+                        path,
+                        report.describe()
+                    ),
+                    ConfirmAction::ImportStore { path, format },
+                ),
+                Err(e) => self.set_status(format!("Error: {}", e)),
+            },
+            Outcome::Imported { path, result, .. } => match result {
+                Ok(report) => {
+                    self.set_status(format!("Imported {}: {}", path, report.describe()));
+                    self.start_async_refresh();
+                }
+                Err(e) => self.set_status(format!("Error: {}", e)),
+            },
+            Outcome::PrMerged {
+                number,
+                strategy,
+                cleanup,
+                result,
+            } => match result {
+                Ok(()) => {
+                    let merged = format!("Merged PR #{} ({})", number, strategy.label());
+                    self.set_status(match cleanup {
+                        Some((worktree, Ok(()))) => {
+                            format!("{} — cleaned up worktree '{}'", merged, worktree)
+                        }
+                        Some((worktree, Err(e))) => format!(
+                            "{} — could not remove worktree '{}': {}",
+                            merged, worktree, e
+                        ),
+                        None => merged,
+                    });
+                    self.reload_pending = true;
+                    self.start_async_refresh();
+                    // Refresh again shortly so GitHub-side changes (e.g.
+                    // linked issues closing) are picked up.
+                    self.pending_refresh =
+                        Some(std::time::Instant::now() + std::time::Duration::from_secs(3));
+                }
+                Err(e) => self.set_status(format!("Error: PR #{}: {}", number, e)),
+            },
+            Outcome::LocalPrMerged {
+                number,
+                branch,
+                result,
+            } => match result {
+                Ok(()) => {
+                    self.set_status(match branch {
+                        Some(branch) => {
+                            format!("Merged local PR #{} (branch: {})", number, branch)
+                        }
+                        None => format!("Merged local PR #{}", number),
+                    });
+                    self.start_async_refresh();
+                }
+                Err(e) => self.set_status(format!("Merge of local PR #{} failed: {}", number, e)),
+            },
+            Outcome::IssueOptionsLoaded(result) => match result {
+                Ok(options) => {
+                    if let Some(modal) = &mut self.issue_modal {
+                        modal.set_options(options);
+                    }
+                }
+                Err(e) => self.set_status(format!(
+                    "Could not load labels, assignees and milestones: {}",
+                    e
+                )),
+            },
+            Outcome::MoreIssues { cursor, result } => {
+                self.loading_more_issues = false;
+                match result {
+                    // A refresh replaced the column meanwhile
+                    Ok(_) if self.issue_cursor.as_deref() != Some(cursor.as_str()) => {}
+                    Ok(page) => {
+                        let loaded: HashSet<String> =
+                            self.issues.iter().map(|c| c.id.clone()).collect();
+                        self.issues
+                            .extend(page.issues.into_iter().filter(|c| !loaded.contains(&c.id)));
+                        self.issue_total = page.total.or(self.issue_total);
+                        self.issue_cursor = page.cursor;
+                        self.rate_limit = page.rate_limit.or(self.rate_limit);
+                    }
+                    Err(e) => {
+                        self.failed_issue_cursor = Some(cursor);
+                        self.set_status(format!("Could not load more issues: {}", e));
+                    }
+                }
+            }
+            Outcome::Detached {
+                created_main,
+                result,
+            } => {
+                if created_main {
+                    self.set_status("Created main explore session".to_string());
+                }
+                if let Err(e) = result {
+                    self.set_status(format!("Failed to attach: {}", e));
+                }
+                // The session may have created PRs in the meantime
+                self.start_async_refresh();
+            }
+            Outcome::SessionsReloaded {
+                worktrees,
+                sessions,
+            } => {
+                self.worktrees = worktrees;
+                self.sessions = sessions;
+                self.clamp_selected();
+                self.last_refresh = std::time::Instant::now();
+                effects = self.monitor_sessions();
+            }
+            Outcome::DependenciesChecked(dependencies) => {
+                self.dependencies = dependencies;
+                self.screen = Screen::Dependencies;
+            }
+            Outcome::GhChecked(true) => effects.extend(self.toggle_local_mode()),
+            Outcome::GhChecked(false) => {
+                self.set_status("Cannot disable local mode: gh CLI not available".to_string())
+            }
+            Outcome::Sequence(outcomes) => {
+                for outcome in outcomes {
+                    effects.extend(self.apply_outcome(outcome));
+                }
+            }
+        }
+        effects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::*;
+    use crate::error::{Error, GhError};
+    use crate::models::Card;
+    use crate::session::Multiplexer;
+    use crate::testing::Sandbox;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn card(id: &str, title: &str, description: &str) -> Card {
+        Card {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn pr(number: u64, draft: bool) -> Card {
+        Card {
+            pr_number: Some(number),
+            head_branch: Some(format!("issue-{}", number)),
+            is_draft: Some(draft),
+            is_merged: Some(false),
+            ..card(
+                &format!("pr-{}", number),
+                &format!("#{} Fix login", number),
+                "",
+            )
+        }
+    }
+
+    fn board() -> App {
+        let mut app = App::new(
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::default(),
+            Multiplexer::Tmux,
+        );
+        app.screen = Screen::Board;
+        app.repo = "acme/widgets".to_string();
+        app.active_section = 3;
+        app
+    }
+
+    fn last_message(app: &App) -> String {
+        app.message_log
+            .lock()
+            .unwrap()
+            .back()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn pr_actions_confirm_then_run_off_the_ui_thread() {
+        let Some(_sandbox) =
+            Sandbox::enter("action::tests::pr_actions_confirm_then_run_off_the_ui_thread")
+        else {
+            return;
+        };
+        let mut app = board();
+        app.pull_requests = vec![pr(12, false), pr(13, true)];
+        app.worktrees = vec![card("wt-issue-12", "issue-12", "/src/widgets-issue-12")];
+
+        // M asks first, and only y runs the merge
+        let merge = from_key(&app, key('M')).unwrap();
+        assert!(app.update(merge).is_empty());
+        assert!(app.mode == Mode::Confirming);
+        let cancel = from_key(&app, key('n')).unwrap();
+        assert!(app.update(cancel).is_empty());
+        assert!(app.mode == Mode::Normal && app.confirm_modal.is_none());
+
+        app.update(Action::MergePr);
+        assert_eq!(
+            app.update(from_key(&app, key('y')).unwrap()),
+            vec![Effect::MergePr {
+                repo: "acme/widgets".to_string(),
+                number: 12,
+                strategy: MergeStrategy::Merge,
+                branch: Some("issue-12".to_string()),
+                worktree: Some("/src/widgets-issue-12".to_string()),
+                mux: Multiplexer::Tmux,
+            }]
+        );
+
+        // Drafts can't be merged, only marked ready
+        app.selected_card[3] = 1;
+        assert!(app.update(Action::MergePr).is_empty());
+        assert_eq!(last_message(&app), "Cannot merge a draft PR");
+        assert_eq!(
+            app.update(Action::MarkPrReady),
+            vec![Effect::MarkPrReady {
+                repo: "acme/widgets".to_string(),
+                number: 13,
+                local: false,
+            }]
+        );
+        app.update(Action::Completed(Outcome::MarkedReady {
+            number: 13,
+            result: Err(Error::Gh(GhError::RateLimited)),
+        }));
+        assert!(last_message(&app).starts_with("Error: "));
+
+        // Only merged PRs can be reverted
+        assert!(app.update(Action::RevertPr).is_empty());
+        assert_eq!(last_message(&app), "Can only revert merged PRs");
+        app.pull_requests[1].is_merged = Some(true);
+        app.update(Action::RevertPr);
+        assert_eq!(
+            app.update(Action::Confirm),
+            vec![Effect::RevertPr {
+                repo: "acme/widgets".to_string(),
+                number: 13,
+            }]
+        );
+    }
+
+    #[test]
+    fn git_and_tool_checks_run_as_effects() {
+        let Some(_sandbox) = Sandbox::enter("action::tests::git_and_tool_checks_run_as_effects")
+        else {
+            return;
+        };
+        let mut app = board();
+        let reload = Effect::ReloadSessions {
+            repo: "acme/widgets".to_string(),
+            states: HashMap::new(),
+            mux: Multiplexer::Tmux,
+        };
+
+        assert_eq!(
+            app.update(Action::ShowDependencies),
+            vec![Effect::CheckDependencies]
+        );
+        assert!(app.screen == Screen::Board);
+        app.local_mode = true;
+        assert_eq!(app.update(Action::ToggleLocalMode), vec![Effect::CheckGh]);
+        app.update(Action::Completed(Outcome::GhChecked(false)));
+        assert!(app.local_mode);
+        app.local_mode = false;
+
+        // Restacking looks up the stack, asks, then rebases in the background
+        app.active_section = 1;
+        app.worktrees = vec![card("wt-issue-12", "issue-12", "/src/widgets-issue-12")];
+        assert_eq!(
+            app.update(Action::Restack),
+            vec![Effect::LoadStack {
+                parent: "issue-12".to_string(),
+            }]
+        );
+        app.update(Action::Completed(Outcome::StackLoaded {
+            parent: "issue-12".to_string(),
+            children: vec!["issue-13".to_string()],
+        }));
+        assert!(app.mode == Mode::Confirming);
+        assert_eq!(
+            app.update(Action::Confirm),
+            vec![Effect::Restack {
+                repo: "acme/widgets".to_string(),
+                parent: "issue-12".to_string(),
+                onto: None,
+                local: false,
+                mux: Multiplexer::Tmux,
+            }]
+        );
+        let restacked = app.update(Action::Completed(Outcome::Restacked {
+            parent: "issue-12".to_string(),
+            onto: None,
+            report: crate::effect::RestackReport {
+                restacked: 0,
+                left_on_parent: 1,
+                notes: vec!["[restack] No worktree for 'issue-13', skipped".to_string()],
+            },
+        }));
+        assert_eq!(restacked, vec![reload]);
+        assert_eq!(
+            last_message(&app),
+            "Rebased 0 branch(es) onto 'issue-12', 1 not rebased"
+        );
+    }
+
+    #[test]
+    fn refresh_follow_ups_and_store_work_run_as_effects() {
+        let Some(_sandbox) =
+            Sandbox::enter("action::tests::refresh_follow_ups_and_store_work_run_as_effects")
+        else {
+            return;
+        };
+        let mut app = board();
+        app.pull_requests = vec![pr(12, false)];
+        app.worktrees = vec![card("wt-issue-12", "issue-12", "/src/widgets-issue-12")];
+        let reload = Effect::ReloadSessions {
+            repo: "acme/widgets".to_string(),
+            states: HashMap::new(),
+            mux: Multiplexer::Tmux,
+        };
+
+        // Merged worktrees are cleaned up, then the sessions re-read
+        assert_eq!(
+            app.post_refresh_cleanup(),
+            vec![Effect::Sequence(vec![
+                Effect::CleanupMerged {
+                    repo: "acme/widgets".to_string(),
+                    merged: Some(Vec::new()),
+                    worktrees: vec![("issue-12".to_string(), "/src/widgets-issue-12".to_string())],
+                    mux: Multiplexer::Tmux,
+                },
+                reload,
+            ])]
+        );
+
+        // An idle agent without a PR is nudged once
+        let idle = |branch: &str| Card {
+            tag: "idle".to_string(),
+            ..card(&format!("session-{}", branch), branch, "")
+        };
+        let reloaded = || {
+            Action::Completed(Outcome::SessionsReloaded {
+                worktrees: Vec::new(),
+                sessions: vec![idle("issue-12"), idle("issue-13")],
+            })
+        };
+        assert_eq!(
+            app.update(reloaded()),
+            vec![Effect::Nudge {
+                branches: vec!["issue-13".to_string()],
+                mux: Multiplexer::Tmux,
+            }]
+        );
+        assert!(app.update(reloaded()).is_empty());
+
+        // In local mode it gets a local PR instead
+        app.local_mode = true;
+        assert_eq!(
+            app.update(reloaded()),
+            vec![Effect::AutoCreateLocalPrs {
+                repo: "acme/widgets".to_string(),
+                branches: vec!["issue-13".to_string()],
+            }]
+        );
+
+        // Leaving local mode saves it, then asks to sync what is unsynced
+        assert_eq!(
+            app.update(Action::Completed(Outcome::GhChecked(true))),
+            vec![
+                Effect::SaveLocalMode { enabled: false },
+                Effect::CountUnsynced {
+                    repo: "acme/widgets".to_string(),
+                },
+            ]
+        );
+        app.update(Action::Completed(Outcome::UnsyncedCounted(Ok((2, 1)))));
+        assert!(app.mode == Mode::Confirming);
+        app.update(Action::Cancel);
+
+        // Templates arrive after the new issue form opens
+        assert_eq!(app.update(Action::NewIssue)[0], Effect::LoadTemplates);
+        let template = crate::templates::parse_template("bug.md", "Steps").unwrap();
+        app.update(Action::Completed(Outcome::TemplatesLoaded(vec![template])));
+        assert_eq!(app.issue_modal.as_ref().unwrap().choosing_template, Some(0));
+    }
+
+    #[test]
+    fn keys_depend_on_the_column_and_mode() {
+        let Some(_sandbox) = Sandbox::enter("action::tests::keys_depend_on_the_column_and_mode")
+        else {
+            return;
+        };
+        let mut app = board();
+        assert!(from_key(&app, key('d')).is_none());
+        app.active_section = 2;
+        assert!(matches!(
+            from_key(&app, key('d')),
+            Some(Action::KillSession)
+        ));
+        app.active_section = 1;
+        assert!(matches!(
+            from_key(&app, key('d')),
+            Some(Action::RemoveWorktree)
+        ));
+        assert!(matches!(
+            from_key(&app, key('U')),
+            Some(Action::UpdateFromBase(UpdateStrategy::Merge))
+        ));
+
+        // While typing a filter, keys are text
+        app.update(Action::FocusFilter);
+        assert!(from_key(&app, key('q')).is_none());
+        // Once the filter is applied, q no longer quits but Esc clears it
+        if let Mode::Filtering { focused, .. } = &mut app.mode {
+            *focused = false;
+        }
+        assert!(from_key(&app, key('q')).is_none());
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        let clear = from_key(&app, esc).unwrap();
+        assert!(matches!(clear, Action::ClearFilter));
+        app.update(clear);
+        assert!(matches!(from_key(&app, esc), Some(Action::Quit)));
+
+        // The detail view takes the keys while it is open, and scrolls no
+        // further than its last line
+        app.worktrees = vec![card("wt-issue-7", "issue-7", "/src/widgets-issue-7")];
+        app.update(from_key(&app, key('i')).unwrap());
+        assert_eq!(app.detail_scroll, Some(0));
+        for _ in 0..10 {
+            app.update(from_key(&app, key('j')).unwrap());
+        }
+        let lines = ui::detail_lines(&app, &app.worktrees[0]).len() as u16;
+        assert_eq!(app.detail_scroll, Some(lines - 1));
+        assert!(from_key(&app, key('d')).is_none());
+        app.update(from_key(&app, esc).unwrap());
+        assert_eq!(app.detail_scroll, None);
+    }
+
+    #[test]
+    fn issues_load_page_by_page_near_the_end() {
+        let Some(sandbox) = Sandbox::enter("action::tests::issues_load_page_by_page_near_the_end")
+        else {
+            return;
+        };
+        let mut app = board();
+        app.active_section = 0;
+        let issues = |numbers: std::ops::RangeInclusive<u64>| -> Vec<Card> {
+            numbers
+                .map(|n| card(&format!("issue-{}", n), &format!("#{} Bug", n), ""))
+                .collect()
+        };
+        app.issues = issues(1..=12);
+        app.issue_total = Some(30);
+        app.issue_cursor = Some("page-2".to_string());
+
+        // Ten cards below the selection are enough; nine are not
+        assert!(app.update(Action::Down).is_empty());
+        assert_eq!(
+            app.update(Action::Down),
+            vec![Effect::LoadMoreIssues {
+                search: "repo:acme/widgets is:issue is:open sort:created-asc assignee:@me"
+                    .to_string(),
+                cursor: "page-2".to_string(),
+            }]
+        );
+        assert!(app.update(Action::Down).is_empty());
+
+        // The page is appended without the issues that moved up into it
+        let page = |cursor: &str, numbers, next: Option<&str>| {
+            Action::Completed(Outcome::MoreIssues {
+                cursor: cursor.to_string(),
+                result: Ok(crate::github::IssuePage {
+                    issues: issues(numbers),
+                    total: Some(30),
+                    cursor: next.map(String::from),
+                    rate_limit: None,
+                }),
+            })
+        };
+        assert!(app
+            .update(page("page-2", 12..=24, Some("page-3")))
+            .is_empty());
+        assert_eq!(app.issues.len(), 24);
+        assert_eq!(app.issue_cursor.as_deref(), Some("page-3"));
+
+        // A page of a search the column no longer shows is dropped
+        app.update(page("page-2", 25..=30, None));
+        assert_eq!(app.issues.len(), 24);
+
+        // A refresh pages on to keep the loaded issues, but not for another search
+        let mut answer = crate::testing::gh_board(&[crate::testing::gh_issue(1, "Bug")], &[], &[]);
+        answer["data"]["issues"]["pageInfo"] =
+            serde_json::json!({ "hasNextPage": true, "endCursor": "next" });
+        sandbox.respond("api graphql", &answer);
+        let queries_for_refresh = |app: &mut App| {
+            let before = sandbox.calls().len();
+            let rx = app.section_rx.take().unwrap();
+            while !matches!(
+                rx.recv_timeout(std::time::Duration::from_secs(10)),
+                Ok(crate::models::SectionData::GithubBoard(_))
+            ) {}
+            let calls = sandbox.calls();
+            calls[before..]
+                .iter()
+                .filter(|c| c.contains("gh api graphql"))
+                .count()
+        };
+        app.loaded_issue_search = app.board_query(&app.board_searches()).searches().0;
+        app.start_async_refresh();
+        assert_eq!(queries_for_refresh(&mut app), 24);
+        app.update(Action::ToggleStateFilter);
+        assert_eq!(queries_for_refresh(&mut app), 1);
+    }
+
+    #[test]
+    fn a_failed_issue_page_waits_for_the_next_scroll() {
+        let Some(_sandbox) =
+            Sandbox::enter("action::tests::a_failed_issue_page_waits_for_the_next_scroll")
+        else {
+            return;
+        };
+        let mut app = board();
+        app.active_section = 0;
+        app.issues = (1..=5)
+            .map(|n| card(&format!("issue-{}", n), &format!("#{} Bug", n), ""))
+            .collect();
+        app.issue_cursor = Some("page-2".to_string());
+        let load = |app: &mut App, action| {
+            app.update(action)
+                .iter()
+                .filter(|e| matches!(e, Effect::LoadMoreIssues { .. }))
+                .count()
+        };
+
+        assert_eq!(load(&mut app, Action::Down), 1);
+        let failed = Action::Completed(Outcome::MoreIssues {
+            cursor: "page-2".to_string(),
+            result: Err(GhError::Offline.into()),
+        });
+        assert_eq!(load(&mut app, failed), 0);
+        assert_eq!(app.failed_issue_cursor.as_deref(), Some("page-2"));
+
+        // Other effects finishing don't retry it, scrolling again does
+        let reloaded = Action::Completed(Outcome::SessionsReloaded {
+            worktrees: Vec::new(),
+            sessions: Vec::new(),
+        });
+        assert_eq!(load(&mut app, reloaded), 0);
+        assert_eq!(load(&mut app, Action::Down), 1);
+    }
+
+    #[test]
+    fn a_failed_refresh_mentions_cached_data_only_when_it_is_shown() {
+        let mut app = board();
+        app.apply_board(Err(GhError::Offline));
+        assert_eq!(
+            last_message(&app),
+            "[github] Could not reach GitHub — check your connection"
+        );
+        assert!(!app.board_stale);
+
+        app.board_fetched_at = Some(0);
+        app.apply_board(Err(GhError::Offline));
+        assert!(last_message(&app).ends_with("; showing cached data"));
+        assert!(app.board_stale);
+    }
+
+    #[test]
+    fn marked_cards_share_one_confirmation() {
+        let Some(_sandbox) = Sandbox::enter("action::tests::marked_cards_share_one_confirmation")
+        else {
+            return;
+        };
+        let mut app = board();
+        app.pull_requests = vec![pr(12, false), pr(13, true), pr(14, false)];
+        app.worktrees = vec![card("wt-issue-12", "issue-12", "/src/widgets-issue-12")];
+
+        // Space marks one card, Ctrl+V and Space a range of them
+        app.update(from_key(&app, key(' ')).unwrap());
+        app.update(Action::Down);
+        let ctrl_v = KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL);
+        app.update(from_key(&app, ctrl_v).unwrap());
+        app.update(Action::Down);
+        assert_eq!(app.mark_range(), Some(1..=2));
+        app.update(from_key(&app, key(' ')).unwrap());
+        assert_eq!(app.marked_cards(3).len(), 3);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert!(matches!(from_key(&app, esc), Some(Action::ClearMarks)));
+
+        // M merges them all after one confirmation, leaving out the draft
+        assert!(app.update(Action::MergePr).is_empty());
+        assert_eq!(
+            last_message(&app),
+            "Skipping '#13 Fix login': a draft can't be merged"
+        );
+        let message = &app.confirm_modal.as_ref().unwrap().message;
+        assert_eq!(
+            message,
+            "Merge 2 PRs with merge strategy?\n\n  #12 Fix login\n  #14 Fix login"
+        );
+        let merge = |number, worktree: Option<&str>| Effect::MergePr {
+            repo: "acme/widgets".to_string(),
+            number,
+            strategy: MergeStrategy::Merge,
+            branch: Some(format!("issue-{}", number)),
+            worktree: worktree.map(String::from),
+            mux: Multiplexer::Tmux,
+        };
+        assert_eq!(
+            app.update(Action::Confirm),
+            vec![Effect::Sequence(vec![
+                merge(12, Some("/src/widgets-issue-12")),
+                merge(14, None),
+            ])]
+        );
+        assert!(app.marked[3].is_empty());
+
+        // Worktrees and sessions are re-read once, after the last merge
+        let merged = |number| {
+            Action::Completed(Outcome::PrMerged {
+                number,
+                strategy: MergeStrategy::Merge,
+                cleanup: None,
+                result: Ok(()),
+            })
+        };
+        let reloads = |effects: Vec<Effect>| {
+            effects
+                .iter()
+                .filter(|e| matches!(e, Effect::ReloadSessions { .. }))
+                .count()
+        };
+        app.effects_running = 1;
+        assert_eq!(reloads(app.update(merged(12))), 0);
+        app.effects_running = 0;
+        assert_eq!(reloads(app.update(merged(14))), 1);
+
+        // A session that could not be killed is reported, not left to a reload
+        let killed = app.update(Action::Completed(Outcome::SessionKilled {
+            name: "issue-12".to_string(),
+            result: Err(Error::Session(crate::error::SessionError::Failed(
+                "no such session".to_string(),
+            ))),
+        }));
+        assert_eq!(reloads(killed), 0);
+        assert_eq!(
+            last_message(&app),
+            "Error: session 'issue-12': no such session"
+        );
+    }
+
+    #[test]
+    fn clicks_land_on_what_is_drawn() {
+        let Some(_sandbox) = Sandbox::enter("action::tests::clicks_land_on_what_is_drawn") else {
+            return;
+        };
+        let mut app = board();
+        app.worktrees = (1..=3)
+            .map(|n| card(&format!("wt-{}", n), &format!("issue-{}", n), ""))
+            .collect();
+        app.pull_requests = vec![pr(12, false)];
+        let area = Rect::new(0, 0, 120, 40);
+        let mouse = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        let click = |column, row| mouse(MouseEventKind::Down(MouseButton::Left), column, row);
+
+        // Worktrees is the second 30-column-wide column; its cards are 4
+        // rows tall, below the 3-row header, the border and the padding.
+        let action = from_mouse(&app, click(35, 10), area).unwrap();
+        assert!(matches!(
+            action,
+            Action::SelectCard {
+                section: 1,
+                index: 1
+            }
+        ));
+        app.update(action);
+        assert_eq!((app.active_section, app.selected_card[1]), (1, 1));
+
+        // The header or an empty part of a column only focuses it
+        assert!(matches!(
+            from_mouse(&app, click(95, 3), area),
+            Some(Action::FocusColumn(3))
+        ));
+        assert!(matches!(
+            from_mouse(&app, click(95, 30), area),
+            Some(Action::FocusColumn(3))
+        ));
+
+        // The wheel moves the selection, which stops at the last card
+        for _ in 0..3 {
+            let scroll = from_mouse(&app, mouse(MouseEventKind::ScrollDown, 35, 20), area);
+            app.update(scroll.unwrap());
+        }
+        assert_eq!(app.selected_card[1], 2);
+
+        // ...and scrolls the message center back through older messages
+        for n in 0..3 {
+            crate::models::push_message(&app.message_log, &format!("message {}", n));
+        }
+        let up = from_mouse(&app, mouse(MouseEventKind::ScrollUp, 10, 36), area).unwrap();
+        assert!(matches!(up, Action::ScrollMessages(1)));
+        for _ in 0..5 {
+            app.update(Action::ScrollMessages(1));
+        }
+        assert_eq!(app.message_scroll, 2);
+
+        // The confirmation modal's buttons answer it
+        app.active_section = 3;
+        app.update(Action::MergePr);
+        let message = &app.confirm_modal.as_ref().unwrap().message;
+        let buttons = ui::confirm_layout(area, message);
+        let (x, y) = (buttons.cancel.x, buttons.cancel.y);
+        assert!(from_mouse(&app, click(x - 2, y), area).is_none());
+        assert!(matches!(
+            from_mouse(&app, click(x, y), area),
+            Some(Action::Cancel)
+        ));
+        let (x, y) = (buttons.confirm.x, buttons.confirm.y);
+        assert!(matches!(
+            from_mouse(&app, click(x, y), area),
+            Some(Action::Confirm)
+        ));
+    }
+}
@@ -3,17 +3,31 @@ use std::sync::mpsc;
 use std::time::Instant;
 
 use crate::deps::Dependency;
-use crate::git::{cleanup_merged_worktrees, fetch_main_behind_count, fetch_worktrees};
-use crate::github::{assign_pr, fetch_prs};
+use crate::effect::{Effect, Outcome};
+use crate::error::{Error, GhError, StoreError};
+use crate::git::{
+    base_for_branch, default_base_branch, fetch_base_behind_count, fetch_worktrees, stack_children,
+};
+use crate::github::{assign_pr, fetch_board, Board, BoardQuery, RateLimit};
 
+use crate::arrange::{self, Arrangement, Row};
+use crate::config::SavedView;
 use crate::hooks::ensure_hook_script;
+use crate::keymap::Keymap;
 use crate::models::{
-    AiSetupState, AssigneeFilter, Card, ConfigEditState, ConfirmModal, DepInstallConfirm,
-    EditIssueModal, IssueEditResult, IssueModal, IssueSubmitResult, MessageLog, Mode,
-    RepoSelectState, Screen, SectionData, SessionStates, StateFilter, WorktreeCreateResult,
-    MAX_MESSAGES,
+    push_message, AiSetupState, AssigneeFilter, Card, ConfigEditState, ConfirmAction, ConfirmModal,
+    DepInstallConfirm, EditIssueModal, IssueEditResult, IssueModal, IssueSubmitResult, MessageLog,
+    Mode, RepoSelectState, Screen, SectionData, SessionStates, StateFilter, WorktreeCreateResult,
+};
+use crate::query::Query;
+use crate::session::{
+    create_session_for_worktree, create_worktree_and_session, fetch_sessions, Multiplexer,
 };
-use crate::session::{fetch_sessions, Multiplexer};
+use crate::sync::SyncSummary;
+
+/// How close to the end of the Issues column the selection gets before the
+/// next page is fetched.
+const PREFETCH_MARGIN: usize = 10;
 
 pub struct App {
     pub screen: Screen,
@@ -39,6 +53,7 @@ pub struct App {
     pub issue_edit_rx: Option<mpsc::Receiver<IssueEditResult>>,
     pub edit_issue_modal: Option<EditIssueModal>,
     pub worktree_create_rx: Option<mpsc::Receiver<WorktreeCreateResult>>,
+    pub sync_rx: Option<mpsc::Receiver<Result<SyncSummary, Error>>>,
     pub loading_message: Option<String>,
     pub spinner_tick: usize,
     pub dependencies: Vec<Dependency>,
@@ -46,22 +61,72 @@ pub struct App {
     pub message_log: MessageLog,
     pub show_messages: bool,
     pub messages_expanded: bool,
+    /// How many of the newest messages the message center is scrolled past.
+    pub message_scroll: usize,
+    /// Set while the selected card's detail view is open: how far it is
+    /// scrolled down.
+    pub detail_scroll: Option<u16>,
     pub pending_refresh: Option<Instant>,
     pub main_behind_count: usize,
     pub multiplexer: Multiplexer,
     /// Tracks sessions that have been nudged to continue (to avoid repeated nudges).
     /// Maps branch name to the number of nudges sent.
     pub nudged_sessions: HashMap<String, usize>,
+    /// Merged stack parents we already offered to restack, so declining is
+    /// not asked again on every refresh.
+    pub restack_offered: HashSet<String>,
+    /// Head branches of recently merged GitHub PRs, from the last refresh.
+    pub merged_pr_branches: Vec<String>,
+    /// GitHub API quota reported by the last board query.
+    pub rate_limit: Option<RateLimit>,
+    /// When the GitHub issues and PRs on the board were fetched.
+    pub board_fetched_at: Option<u64>,
+    /// Set while the board shows cached GitHub data: loaded at startup, or
+    /// kept after a refresh failed.
+    pub board_stale: bool,
     pub ai_setup: Option<AiSetupState>,
     pub local_mode: bool,
     pub dep_selected: usize,
     pub dep_install_confirm: Option<DepInstallConfirm>,
     /// Server-side search query for GitHub issues.
     pub issue_search_query: Option<String>,
+    /// Server-side search query for GitHub PRs.
+    pub pr_search_query: Option<String>,
+    /// The saved view whose column filters apply when no `/` filter does.
+    pub active_view: Option<SavedView>,
+    /// The GitHub search the loaded issues were found with.
+    pub loaded_issue_search: Option<String>,
+    /// How many issues GitHub's search found, loaded or not.
+    pub issue_total: Option<usize>,
+    /// Where the next page of GitHub issues starts, if there is one.
+    pub issue_cursor: Option<String>,
+    /// Set while the next page of issues is being fetched.
+    pub loading_more_issues: bool,
+    /// The cursor whose page last failed to load. It is not fetched again
+    /// until the selection moves or the board is refreshed.
+    pub failed_issue_cursor: Option<String>,
+    /// How each column sorts and groups its cards.
+    pub arrangements: [Arrangement; 4],
+    /// Per column, the groups whose cards are hidden.
+    pub collapsed_groups: [HashSet<String>; 4],
+    /// Per column, the ids of the cards marked for a bulk action.
+    pub marked: [HashSet<String>; 4],
+    /// The row of the focused column a range being marked starts at.
+    pub mark_anchor: Option<usize>,
     /// Per-section loading state: [issues, worktrees, sessions, pull_requests].
     pub section_loading: [bool; 4],
     /// Receiver for per-section async refresh results.
     pub section_rx: Option<mpsc::Receiver<SectionData>>,
+    /// Outcomes of effects running in the background, and how many have
+    /// not reported back yet.
+    effect_tx: mpsc::Sender<Outcome>,
+    effect_rx: mpsc::Receiver<Outcome>,
+    pub effects_running: usize,
+    /// Set when finished effects changed the worktrees or sessions. They
+    /// are re-read once no other effect is running, so that a bulk action
+    /// reloads them once rather than after every card.
+    pub reload_pending: bool,
+    pub keymap: Keymap,
 }
 
 impl App {
@@ -73,6 +138,7 @@ impl App {
         let hook_script_path = ensure_hook_script()
             .ok()
             .map(|p| p.to_string_lossy().to_string());
+        let (effect_tx, effect_rx) = mpsc::channel();
         Self {
             screen: Screen::RepoSelect,
             repo_select: RepoSelectState::new(),
@@ -97,6 +163,7 @@ impl App {
             issue_edit_rx: None,
             edit_issue_modal: None,
             worktree_create_rx: None,
+            sync_rx: None,
             loading_message: None,
             spinner_tick: 0,
             dependencies: Vec::new(),
@@ -104,27 +171,54 @@ impl App {
             message_log: message_log.clone(),
             show_messages: true,
             messages_expanded: false,
+            message_scroll: 0,
+            detail_scroll: None,
             pending_refresh: None,
             main_behind_count: 0,
             multiplexer,
             nudged_sessions: HashMap::new(),
+            restack_offered: HashSet::new(),
+            merged_pr_branches: Vec::new(),
+            rate_limit: None,
+            board_fetched_at: None,
+            board_stale: false,
             ai_setup: None,
             local_mode: false,
             dep_selected: 0,
             dep_install_confirm: None,
             issue_search_query: None,
+            pr_search_query: None,
+            active_view: None,
+            loaded_issue_search: None,
+            issue_total: None,
+            issue_cursor: None,
+            loading_more_issues: false,
+            failed_issue_cursor: None,
+            arrangements: Default::default(),
+            collapsed_groups: Default::default(),
+            marked: Default::default(),
+            mark_anchor: None,
             section_loading: [false; 4],
             section_rx: None,
+            effect_tx,
+            effect_rx,
+            effects_running: 0,
+            reload_pending: false,
+            keymap: Keymap::load(),
         }
     }
 
     pub fn add_message(&self, msg: &str) {
-        if let Ok(mut log) = self.message_log.lock() {
-            log.push_back(msg.to_string());
-            while log.len() > MAX_MESSAGES {
-                log.pop_front();
-            }
-        }
+        push_message(&self.message_log, msg);
+    }
+
+    /// Unwrap cards read from the local store, logging the error and showing
+    /// an empty column if the store could not be read.
+    fn local_cards_or_log(&self, result: Result<Vec<Card>, StoreError>) -> Vec<Card> {
+        result.unwrap_or_else(|e| {
+            self.add_message(&format!("[local] {}", e));
+            Vec::new()
+        })
     }
 
     pub fn set_status(&mut self, msg: String) {
@@ -141,8 +235,137 @@ impl App {
         }
     }
 
+    /// The filter on column `section`: the `/` filter while it applies to
+    /// that column, otherwise the active view's.
+    pub fn column_query(&self, section: usize) -> Query {
+        match &self.mode {
+            Mode::Filtering { query, .. } if section == self.active_section => {
+                Query::parse(query.value())
+            }
+            _ => Query::parse(self.active_view.as_ref().map_or("", |v| v.query(section))),
+        }
+    }
+
+    /// The cards of column `section` that pass its filter, in the column's
+    /// sort order.
+    pub fn visible_cards(&self, section: usize) -> Vec<&Card> {
+        let query = self.column_query(section);
+        let mut cards: Vec<&Card> = self
+            .section_cards(section)
+            .iter()
+            .filter(|c| query.matches(c))
+            .collect();
+        arrange::sort(&mut cards, self.arrangements[section].sort);
+        cards
+    }
+
+    /// The rows column `section` shows: its visible cards, under group
+    /// headers if it is grouped. Selection indexes into these.
+    pub fn rows(&self, section: usize) -> Vec<Row<'_>> {
+        arrange::rows(
+            self.visible_cards(section),
+            self.arrangements[section].group,
+            &self.collapsed_groups[section],
+        )
+    }
+
     pub fn section_card_count(&self, section: usize) -> usize {
-        self.section_cards(section).len()
+        self.rows(section).len()
+    }
+
+    /// The selected card in `section`, unless a group header is selected.
+    pub fn selected(&self, section: usize) -> Option<&Card> {
+        match self.rows(section).get(self.selected_card[section]) {
+            Some(Row::Card(card)) => Some(card),
+            _ => None,
+        }
+    }
+
+    /// The rows of the focused column between the range's start and the
+    /// selection, while a range is being marked.
+    pub fn mark_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let anchor = self.mark_anchor?;
+        let selected = self.selected_card[self.active_section];
+        Some(anchor.min(selected)..=anchor.max(selected))
+    }
+
+    /// The cards of `section` a bulk action applies to: its marked cards
+    /// that are on the board, in column order.
+    pub fn marked_cards(&self, section: usize) -> Vec<&Card> {
+        self.visible_cards(section)
+            .into_iter()
+            .filter(|c| self.marked[section].contains(&c.id))
+            .collect()
+    }
+
+    /// What GitHub should search for in the Issues (`section` 0) or Pull
+    /// Requests column: the submitted `/` search, or the active view's
+    /// filter.
+    fn column_search(&self, section: usize) -> Option<String> {
+        let submitted = match section {
+            0 => self.issue_search_query.as_deref(),
+            _ => self.pr_search_query.as_deref(),
+        };
+        let view = self.active_view.as_ref().map(|v| v.query(section));
+        let query = Query::parse(submitted.or(view)?);
+        (!query.is_empty()).then(|| query.search())
+    }
+
+    /// Save the active view's filters, with `filter` on the focused column,
+    /// as view `name` and switch to it. A view without filters is deleted.
+    pub fn save_view(&mut self, name: &str, filter: Option<String>) {
+        let mut view = self.active_view.clone().unwrap_or_default();
+        view.name = name.to_string();
+        if let Some(filter) = filter {
+            view.set_query(self.active_section, filter.trim().to_string());
+        }
+        if let Err(e) = crate::config::save_view(&self.repo, &view) {
+            self.set_status(format!("Could not save view: {}", e));
+            return;
+        }
+        if view.is_empty() {
+            self.set_status(format!("Deleted view {}", name));
+            self.active_view = None;
+        } else {
+            self.set_status(format!("Saved view {}", name));
+            self.active_view = Some(view);
+        }
+        self.show_view_results();
+    }
+
+    /// Start fetching the next page of GitHub issues if the selection is
+    /// near the end of the Issues column and there is more to load.
+    pub fn load_more_issues(&mut self) -> Vec<Effect> {
+        let near_end = self.selected_card[0] + PREFETCH_MARGIN >= self.section_card_count(0);
+        if self.local_mode
+            || self.loading_more_issues
+            || self.section_loading[0]
+            || !near_end
+            || self.rate_limit.is_some_and(|r| r.exhausted())
+        {
+            return Vec::new();
+        }
+        let Some(cursor) = self.issue_cursor.clone() else {
+            return Vec::new();
+        };
+        if self.failed_issue_cursor.as_ref() == Some(&cursor) {
+            return Vec::new();
+        }
+        let searches = self.board_searches();
+        let Some(search) = self.board_query(&searches).searches().0 else {
+            return Vec::new();
+        };
+        self.loading_more_issues = true;
+        vec![Effect::LoadMoreIssues { search, cursor }]
+    }
+
+    /// Drop `/` filters and searches and fetch what the active view shows.
+    pub fn show_view_results(&mut self) {
+        self.issue_search_query = None;
+        self.pr_search_query = None;
+        self.mode = Mode::Normal;
+        self.selected_card = [0; 4];
+        self.start_async_refresh();
     }
 
     pub fn clamp_selected(&mut self) {
@@ -171,9 +394,7 @@ impl App {
     }
 
     pub fn selected_card_related_ids(&self) -> HashSet<String> {
-        let cards = self.section_cards(self.active_section);
-        let idx = self.selected_card[self.active_section];
-        let card = match cards.get(idx) {
+        let card = match self.selected(self.active_section) {
             Some(c) => c,
             None => return HashSet::new(),
         };
@@ -212,61 +433,40 @@ impl App {
 
     pub fn refresh_data(&mut self) {
         if self.local_mode {
-            self.issues = crate::local::fetch_local_issues(
+            self.issues = self.local_cards_or_log(crate::local::fetch_local_issues(
                 &self.repo,
                 self.issue_state_filter,
                 self.issue_assignee_filter,
-            );
-            self.pull_requests = crate::local::fetch_local_prs(
+            ));
+            self.loaded_issue_search = None;
+            self.pull_requests = self.local_cards_or_log(crate::local::fetch_local_prs(
                 &self.repo,
                 self.pr_state_filter,
                 self.pr_assignee_filter,
-            );
+            ));
         } else {
-            self.issues = crate::github::fetch_issues(
-                &self.repo,
-                self.issue_state_filter,
-                self.issue_assignee_filter,
-                self.issue_search_query.as_deref(),
-            );
-            self.pull_requests =
-                fetch_prs(&self.repo, self.pr_state_filter, self.pr_assignee_filter);
+            if !self.rate_limit.is_some_and(|r| r.exhausted()) {
+                let board = fetch_board(&self.board_query(&self.board_searches()));
+                self.apply_board(board);
+            }
 
             // Auto-assign unassigned PRs to the current user
             for card in &self.pull_requests {
                 if card.is_assigned == Some(false) {
                     if let Some(number) = card.pr_number {
-                        assign_pr(&self.repo, number);
+                        crate::log::warn_on_err("github", assign_pr(&self.repo, number));
                     }
                 }
             }
         }
-        self.worktrees = fetch_worktrees();
+        self.worktrees = fetch_worktrees(&self.repo);
 
-        if self.local_mode {
-            // Clean up worktrees for locally merged PRs
-            let merged = crate::local::fetch_local_merged_pr_branches(&self.repo);
-            let cleaned = crate::git::cleanup_local_merged_worktrees(
-                &merged,
-                &self.worktrees,
-                self.multiplexer,
-            );
-            if !cleaned.is_empty() {
-                self.set_status(format!("Cleaned up merged: {}", cleaned.join(", ")));
-                self.worktrees = fetch_worktrees();
-            }
-        } else {
-            // Clean up worktrees and sessions for merged PRs
-            let cleaned = cleanup_merged_worktrees(&self.repo, &self.worktrees, self.multiplexer);
-            if !cleaned.is_empty() {
-                self.set_status(format!("Cleaned up merged: {}", cleaned.join(", ")));
-                // Re-fetch worktrees after cleanup
-                self.worktrees = fetch_worktrees();
-            }
-        }
+        self.cleanup_merged();
 
         self.sessions = fetch_sessions(&self.session_states, self.multiplexer);
-        self.main_behind_count = fetch_main_behind_count();
+        self.main_behind_count = default_base_branch(&self.repo)
+            .map(|base| fetch_base_behind_count(&base))
+            .unwrap_or(0);
 
         // Auto-nudge idle sessions that have no associated PR.
         // Only nudge once per session to avoid spamming.
@@ -297,9 +497,9 @@ impl App {
 
             if self.local_mode {
                 // In local mode, auto-create a local PR when the session is
-                // idle and the branch has commits ahead of main.
+                // idle and the branch has commits ahead of its base.
                 if !crate::local::has_local_pr_for_branch(&self.repo, branch)
-                    && crate::git::branch_has_commits(branch)
+                    && crate::git::branch_has_commits(&self.repo, branch)
                 {
                     actions.push(SessionAction::AutoCreateLocalPr(branch.clone()));
                 }
@@ -321,10 +521,17 @@ impl App {
                 }
                 SessionAction::AutoCreateLocalPr(branch) => {
                     let pr_ready = crate::config::get_pr_ready(&self.repo);
-                    let title = crate::git::first_commit_summary(&branch)
+                    let title = crate::git::first_commit_summary(&self.repo, &branch)
                         .unwrap_or_else(|| format!("PR for {}", branch));
-                    match crate::local::create_local_pr(&self.repo, &title, "", &branch, !pr_ready)
-                    {
+                    let base = crate::git::base_for_branch(&self.repo, &branch);
+                    match crate::local::create_local_pr(
+                        &self.repo,
+                        &title,
+                        "",
+                        &branch,
+                        base.as_deref(),
+                        !pr_ready,
+                    ) {
                         Ok(number) => {
                             self.set_status(format!(
                                 "Auto-created local PR #{} for {}",
@@ -334,11 +541,12 @@ impl App {
                                 "[monitor] Auto-created local PR #{} for {}",
                                 number, branch
                             ));
-                            self.pull_requests = crate::local::fetch_local_prs(
-                                &self.repo,
-                                self.pr_state_filter,
-                                self.pr_assignee_filter,
-                            );
+                            self.pull_requests =
+                                self.local_cards_or_log(crate::local::fetch_local_prs(
+                                    &self.repo,
+                                    self.pr_state_filter,
+                                    self.pr_assignee_filter,
+                                ));
                         }
                         Err(e) => {
                             self.add_message(&format!(
@@ -349,11 +557,14 @@ impl App {
                     }
                 }
                 SessionAction::Nudge(branch) => {
-                    self.multiplexer.send_keys(&branch, "continue");
-                    self.add_message(&format!(
-                        "[monitor] Nudged {} to continue (no PR found)",
-                        branch
-                    ));
+                    match self.multiplexer.send_keys(&branch, "continue") {
+                        Ok(()) => self.add_message(&format!(
+                            "[monitor] Nudged {} to continue (no PR found)",
+                            branch
+                        )),
+                        Err(e) => self
+                            .add_message(&format!("[monitor] Failed to nudge {}: {}", branch, e)),
+                    }
                 }
             }
         }
@@ -371,6 +582,8 @@ impl App {
     /// Launch per-section background threads to fetch data without blocking the UI.
     /// Each section sends its results via a shared channel as soon as it's ready.
     pub fn start_async_refresh(&mut self) {
+        // The repo may have changed since the last refresh
+        self.arrangements = crate::config::get_arrangements(&self.repo);
         self.section_loading = [true; 4];
         let (tx, rx) = mpsc::channel();
         self.section_rx = Some(rx);
@@ -378,25 +591,17 @@ impl App {
         let repo = self.repo.clone();
         let local_mode = self.local_mode;
 
-        // Issues thread
-        let tx_issues = tx.clone();
-        let repo_i = repo.clone();
-        let isf = self.issue_state_filter;
-        let iaf = self.issue_assignee_filter;
-        let search_q = self.issue_search_query.clone();
-        std::thread::spawn(move || {
-            let issues = if local_mode {
-                crate::local::fetch_local_issues(&repo_i, isf, iaf)
-            } else {
-                crate::github::fetch_issues(&repo_i, isf, iaf, search_q.as_deref())
-            };
-            let _ = tx_issues.send(SectionData::Issues(issues));
-        });
+        if local_mode {
+            self.spawn_local_board_refresh(&tx);
+        } else {
+            self.spawn_github_board_refresh(&tx);
+        }
 
         // Worktrees thread
         let tx_wt = tx.clone();
+        let repo_wt = repo.clone();
         std::thread::spawn(move || {
-            let worktrees = fetch_worktrees();
+            let worktrees = fetch_worktrees(&repo_wt);
             let _ = tx_wt.send(SectionData::Worktrees(worktrees));
         });
 
@@ -409,58 +614,163 @@ impl App {
             let _ = tx_sess.send(SectionData::Sessions(sessions));
         });
 
-        // Pull Requests thread
+        // Base branch behind count thread
+        std::thread::spawn(move || {
+            let count = default_base_branch(&repo)
+                .map(|base| fetch_base_behind_count(&base))
+                .unwrap_or(0);
+            let _ = tx.send(SectionData::MainBehindCount(count));
+        });
+    }
+
+    /// Fetch local issues and PRs on their own threads.
+    fn spawn_local_board_refresh(&self, tx: &mpsc::Sender<SectionData>) {
+        let tx_issues = tx.clone();
+        let repo_i = self.repo.clone();
+        let isf = self.issue_state_filter;
+        let iaf = self.issue_assignee_filter;
+        let log_i = self.message_log.clone();
+        std::thread::spawn(move || {
+            let issues = crate::local::fetch_local_issues(&repo_i, isf, iaf).unwrap_or_else(|e| {
+                push_message(&log_i, &format!("[local] {}", e));
+                Vec::new()
+            });
+            let _ = tx_issues.send(SectionData::Issues(issues));
+        });
+
         let tx_pr = tx.clone();
-        let repo_pr = repo.clone();
+        let repo_pr = self.repo.clone();
         let psf = self.pr_state_filter;
         let paf = self.pr_assignee_filter;
+        let log_pr = self.message_log.clone();
         std::thread::spawn(move || {
-            let prs = if local_mode {
-                crate::local::fetch_local_prs(&repo_pr, psf, paf)
-            } else {
-                fetch_prs(&repo_pr, psf, paf)
-            };
+            let prs = crate::local::fetch_local_prs(&repo_pr, psf, paf).unwrap_or_else(|e| {
+                push_message(&log_pr, &format!("[local] {}", e));
+                Vec::new()
+            });
             let _ = tx_pr.send(SectionData::PullRequests(prs));
         });
+    }
 
-        // Main behind count thread
+    /// Fetch GitHub issues, PRs and merged branches in one query on a
+    /// background thread, showing the cached board first if there is nothing
+    /// on screen yet.
+    fn spawn_github_board_refresh(&mut self, tx: &mpsc::Sender<SectionData>) {
+        if self.issues.is_empty() && self.pull_requests.is_empty() {
+            let searches = self.board_searches();
+            if let Some(board) = crate::github::load_cached_board(&self.board_query(&searches)) {
+                self.issues = board.issues;
+                self.loaded_issue_search = board.issue_search;
+                self.pull_requests = board.prs;
+                self.merged_pr_branches = board.merged_branches;
+                self.board_fetched_at = Some(board.fetched_at);
+                self.board_stale = true;
+            }
+        }
+        if self.rate_limit.is_some_and(|r| r.exhausted()) {
+            // Keep the current cards until the quota resets
+            self.section_loading[0] = false;
+            self.section_loading[3] = false;
+            return;
+        }
+
+        let tx_board = tx.clone();
+        let repo = self.repo.clone();
+        let isf = self.issue_state_filter;
+        let iaf = self.issue_assignee_filter;
+        let searches = self.board_searches();
+        let issue_limit = self.board_query(&searches).issue_limit;
+        let [issue_search, pr_search] = searches;
+        let psf = self.pr_state_filter;
+        let paf = self.pr_assignee_filter;
         std::thread::spawn(move || {
-            let count = fetch_main_behind_count();
-            let _ = tx.send(SectionData::MainBehindCount(count));
+            let board = fetch_board(&BoardQuery {
+                repo: &repo,
+                issues: Some((isf, iaf, issue_search.as_deref())),
+                issue_limit,
+                prs: Some((psf, paf, pr_search.as_deref())),
+                merged: true,
+            });
+            let _ = tx_board.send(SectionData::GithubBoard(board));
         });
     }
 
-    /// Run cleanup and auto-nudge logic after all sections have loaded.
-    pub fn post_refresh_cleanup(&mut self) {
-        if self.local_mode {
-            let merged = crate::local::fetch_local_merged_pr_branches(&self.repo);
-            let cleaned = crate::git::cleanup_local_merged_worktrees(
-                &merged,
-                &self.worktrees,
-                self.multiplexer,
-            );
-            if !cleaned.is_empty() {
-                self.set_status(format!("Cleaned up merged: {}", cleaned.join(", ")));
-                self.worktrees = fetch_worktrees();
+    /// The searches of the Issues and Pull Requests columns.
+    pub fn board_searches(&self) -> [Option<String>; 2] {
+        [self.column_search(0), self.column_search(3)]
+    }
+
+    /// The GitHub query for the current repo, column filters and
+    /// `searches`.
+    pub fn board_query<'a>(&'a self, searches: &'a [Option<String>; 2]) -> BoardQuery<'a> {
+        let mut query = BoardQuery {
+            repo: &self.repo,
+            issues: Some((
+                self.issue_state_filter,
+                self.issue_assignee_filter,
+                searches[0].as_deref(),
+            )),
+            issue_limit: 0,
+            prs: Some((
+                self.pr_state_filter,
+                self.pr_assignee_filter,
+                searches[1].as_deref(),
+            )),
+            merged: true,
+        };
+        // Keep as many issues as are loaded, so the column doesn't shrink,
+        // unless they were found with another search
+        if query.searches().0 == self.loaded_issue_search {
+            query.issue_limit = self.issues.len();
+        }
+        query
+    }
+
+    /// Store the result of a GitHub board query. On failure the current
+    /// cards are kept and the error is logged, saying so if there are any.
+    pub fn apply_board(&mut self, board: Result<Board, GhError>) {
+        match board {
+            Ok(board) => {
+                self.issues = board.issues;
+                self.loaded_issue_search = board.issue_search;
+                self.issue_total = board.issue_total;
+                self.issue_cursor = board.issue_cursor;
+                self.failed_issue_cursor = None;
+                self.pull_requests = board.prs;
+                self.merged_pr_branches = board.merged_branches;
+                self.rate_limit = board.rate_limit.or(self.rate_limit);
+                self.board_fetched_at = Some(board.fetched_at);
+                self.board_stale = false;
             }
-        } else {
-            let cleaned = cleanup_merged_worktrees(&self.repo, &self.worktrees, self.multiplexer);
-            if !cleaned.is_empty() {
-                self.set_status(format!("Cleaned up merged: {}", cleaned.join(", ")));
-                self.worktrees = fetch_worktrees();
+            Err(e) => {
+                if self.board_fetched_at.is_some() {
+                    self.add_message(&format!("[github] {}; showing cached data", e));
+                    self.board_stale = true;
+                } else {
+                    self.add_message(&format!("[github] {}", e));
+                }
             }
         }
+    }
 
-        self.sessions = fetch_sessions(&self.session_states, self.multiplexer);
+    /// Run cleanup and auto-nudge logic after all sections have loaded:
+    /// remove merged worktrees, then re-read the worktrees and sessions,
+    /// which checks on the idle ones (see [`App::monitor_sessions`]).
+    pub fn post_refresh_cleanup(&mut self) -> Vec<Effect> {
+        self.clamp_selected();
+        self.last_refresh = Instant::now();
+        vec![Effect::Sequence(vec![
+            self.cleanup_merged(),
+            self.reload_sessions(),
+        ])]
+    }
 
-        // Auto-nudge idle sessions
+    /// Check on the idle sessions that have no PR: in local mode open a
+    /// local PR for them once their branch has commits, otherwise nudge
+    /// each agent to continue, only once to avoid spamming.
+    pub fn monitor_sessions(&mut self) -> Vec<Effect> {
         let max_nudges = 1;
-        enum SessionAction {
-            ClearNudge(String),
-            AutoCreateLocalPr(String),
-            Nudge(String),
-        }
-        let mut actions = Vec::new();
+        let mut without_pr = Vec::new();
         for session in &self.sessions {
             if session.tag != "idle" {
                 continue;
@@ -471,81 +781,272 @@ impl App {
                 .iter()
                 .any(|pr| pr.head_branch.as_deref() == Some(branch));
             if has_pr {
-                actions.push(SessionAction::ClearNudge(branch.clone()));
-                continue;
+                self.nudged_sessions.remove(branch);
+            } else {
+                without_pr.push(branch.clone());
             }
+        }
 
-            if self.local_mode {
-                if !crate::local::has_local_pr_for_branch(&self.repo, branch)
-                    && crate::git::branch_has_commits(branch)
-                {
-                    actions.push(SessionAction::AutoCreateLocalPr(branch.clone()));
-                }
-                continue;
-            }
+        // Forget the nudges of sessions that no longer exist
+        let active_branches: HashSet<String> =
+            self.sessions.iter().map(|s| s.title.clone()).collect();
+        self.nudged_sessions
+            .retain(|k, _| active_branches.contains(k));
 
-            let nudge_count = self.nudged_sessions.entry(branch.clone()).or_insert(0);
-            if *nudge_count >= max_nudges {
-                continue;
+        if self.local_mode {
+            if without_pr.is_empty() {
+                return Vec::new();
             }
-            *nudge_count += 1;
-            actions.push(SessionAction::Nudge(branch.clone()));
+            return vec![Effect::AutoCreateLocalPrs {
+                repo: self.repo.clone(),
+                branches: without_pr,
+            }];
         }
-
-        for action in actions {
-            match action {
-                SessionAction::ClearNudge(branch) => {
-                    self.nudged_sessions.remove(&branch);
-                }
-                SessionAction::AutoCreateLocalPr(branch) => {
-                    let pr_ready = crate::config::get_pr_ready(&self.repo);
-                    let title = crate::git::first_commit_summary(&branch)
-                        .unwrap_or_else(|| format!("PR for {}", branch));
-                    match crate::local::create_local_pr(&self.repo, &title, "", &branch, !pr_ready)
-                    {
-                        Ok(number) => {
-                            self.set_status(format!(
-                                "Auto-created local PR #{} for {}",
-                                number, branch
-                            ));
-                            self.add_message(&format!(
-                                "[monitor] Auto-created local PR #{} for {}",
-                                number, branch
-                            ));
-                            self.pull_requests = crate::local::fetch_local_prs(
-                                &self.repo,
-                                self.pr_state_filter,
-                                self.pr_assignee_filter,
-                            );
-                        }
-                        Err(e) => {
-                            self.add_message(&format!(
-                                "[monitor] Failed to auto-create local PR for {}: {}",
-                                branch, e
-                            ));
-                        }
-                    }
-                }
-                SessionAction::Nudge(branch) => {
-                    self.multiplexer.send_keys(&branch, "continue");
-                    self.add_message(&format!(
-                        "[monitor] Nudged {} to continue (no PR found)",
-                        branch
-                    ));
+        let branches: Vec<String> = without_pr
+            .into_iter()
+            .filter(|branch| {
+                let nudge_count = self.nudged_sessions.entry(branch.clone()).or_insert(0);
+                if *nudge_count >= max_nudges {
+                    return false;
                 }
-            }
+                *nudge_count += 1;
+                true
+            })
+            .collect();
+        if branches.is_empty() {
+            return Vec::new();
         }
+        vec![Effect::Nudge {
+            branches,
+            mux: self.multiplexer,
+        }]
+    }
 
-        let active_branches: HashSet<String> =
-            self.sessions.iter().map(|s| s.title.clone()).collect();
-        self.nudged_sessions
-            .retain(|k, _| active_branches.contains(k));
+    /// Remove worktrees and sessions whose branches were merged. Merged
+    /// branches that others are stacked on are kept, and restacking their
+    /// children onto the parent's base is offered instead.
+    fn cleanup_merged(&self) -> Effect {
+        // In local mode the store knows which branches were merged
+        let merged = (!self.local_mode).then(|| {
+            // The PR column lists merged PRs too when it shows closed ones
+            let listed = self
+                .pull_requests
+                .iter()
+                .filter(|pr| pr.is_merged == Some(true))
+                .filter_map(|pr| pr.head_branch.clone());
+            self.merged_pr_branches
+                .iter()
+                .cloned()
+                .chain(listed)
+                .collect()
+        });
+        Effect::CleanupMerged {
+            repo: self.repo.clone(),
+            merged,
+            // A worktree card's title is its branch, its description the path
+            worktrees: self
+                .worktrees
+                .iter()
+                .map(|wt| (wt.title.clone(), wt.description.clone()))
+                .collect(),
+            mux: self.multiplexer,
+        }
+    }
 
-        self.clamp_selected();
-        self.last_refresh = Instant::now();
+    /// Ask whether to move the branches stacked on `parent`, which was
+    /// merged, onto the parent's own base. Returns whether the question was
+    /// shown.
+    pub fn offer_merged_restack(&mut self, parent: &str) -> bool {
+        if self.mode != Mode::Normal || self.confirm_modal.is_some() {
+            return false;
+        }
+        let children = stack_children(parent);
+        if children.is_empty() {
+            return false;
+        }
+        let Some(onto) = base_for_branch(&self.repo, parent) else {
+            return false;
+        };
+        self.ask_restack(parent, children, Some(onto))
+    }
+
+    /// Ask whether to restack `children`, the branches stacked on `parent`:
+    /// rebase them onto it, or move them onto `onto`, the merged parent's
+    /// own base. Returns whether the question was shown.
+    pub fn ask_restack(
+        &mut self,
+        parent: &str,
+        children: Vec<String>,
+        onto: Option<String>,
+    ) -> bool {
+        if self.mode != Mode::Normal || self.confirm_modal.is_some() || children.is_empty() {
+            return false;
+        }
+        let message = if let Some(onto) = &onto {
+            format!(
+                "'{}' was merged. Move {} stacked branch(es) onto {}?\n\n{}\n\nTheir PRs will target {} and '{}' will be removed.",
+                parent,
+                children.len(),
+                onto,
+                children.join(", "),
+                onto,
+                parent
+            )
+        } else {
+            format!(
+                "Rebase {} branch(es) stacked on '{}' onto it?\n\n{}",
+                children.len(),
+                parent,
+                children.join(", ")
+            )
+        };
+        self.confirm_modal = Some(ConfirmModal {
+            message,
+            on_confirm: ConfirmAction::RestackChildren {
+                parent: parent.to_string(),
+                onto,
+            },
+        });
+        self.mode = Mode::Confirming;
+        true
+    }
+
+    /// Create a worktree and session for issue `number` in the background,
+    /// branching from `base` (the repo's default base branch if `None`).
+    pub fn start_issue_worktree(
+        &mut self,
+        number: u64,
+        title: String,
+        body: String,
+        base: Option<String>,
+    ) {
+        self.loading_message = Some(match &base {
+            Some(base) => format!(
+                "Creating worktree and session for issue #{} from {}...",
+                number, base
+            ),
+            None => format!("Creating worktree and session for issue #{}...", number),
+        });
+        self.spawn_issue_worktrees(vec![(number, title, body)], base);
+    }
+
+    /// Create a worktree and session for each `(number, title, body)` issue
+    /// from the default base branch, one after another in the background.
+    pub fn start_issue_worktrees(&mut self, issues: Vec<(u64, String, String)>) {
+        self.loading_message = Some(format!(
+            "Creating worktrees and sessions for {} issues...",
+            issues.len()
+        ));
+        self.spawn_issue_worktrees(issues, None);
+    }
+
+    fn spawn_issue_worktrees(&mut self, issues: Vec<(u64, String, String)>, base: Option<String>) {
+        let repo = self.repo.clone();
+        let hook_script = self.hook_script_path.clone();
+        let mux = self.multiplexer;
+        let is_local = self.local_mode;
+        let (tx, rx) = mpsc::channel();
+        self.worktree_create_rx = Some(rx);
+        std::thread::spawn(move || {
+            let pr_ready = crate::config::get_pr_ready(&repo);
+            let auto_open_pr = crate::config::get_auto_open_pr(&repo);
+            let claude_cmd = crate::config::get_session_command(&repo);
+            let base = base.or_else(|| default_base_branch(&repo));
+            for (number, title, body) in issues {
+                let result = create_worktree_and_session(
+                    &repo,
+                    number,
+                    &title,
+                    &body,
+                    base.as_deref(),
+                    hook_script.as_deref(),
+                    pr_ready,
+                    auto_open_pr,
+                    claude_cmd.as_deref(),
+                    mux,
+                    is_local,
+                );
+                let _ = tx.send(WorktreeCreateResult::WorktreeAndSession { number, result });
+            }
+        });
+    }
+
+    /// Create a session in the existing worktree at `path` for issue
+    /// `number`, in the background.
+    pub fn start_worktree_session(&mut self, number: u64, branch: String, path: String) {
+        let repo = self.repo.clone();
+        let hook_script = self.hook_script_path.clone();
+        let mux = self.multiplexer;
+        let is_local = self.local_mode;
+        let (tx, rx) = mpsc::channel();
+        self.worktree_create_rx = Some(rx);
+        self.loading_message = Some(format!("Creating session for '{}'...", branch));
+        std::thread::spawn(move || {
+            let result = if is_local {
+                crate::local::fetch_local_issue(&repo, number).map_err(Error::from)
+            } else {
+                crate::github::fetch_issue(&repo, number).map_err(Error::from)
+            }
+            .and_then(|(title, body)| {
+                let pr_ready = crate::config::get_pr_ready(&repo);
+                let auto_open_pr = crate::config::get_auto_open_pr(&repo);
+                let claude_cmd = crate::config::get_session_command(&repo);
+                create_session_for_worktree(
+                    &repo,
+                    number,
+                    &title,
+                    &body,
+                    &branch,
+                    &path,
+                    hook_script.as_deref(),
+                    pr_ready,
+                    auto_open_pr,
+                    claude_cmd.as_deref(),
+                    mux,
+                    is_local,
+                )
+            });
+            let _ = tx.send(WorktreeCreateResult::SessionOnly { branch, result });
+        });
+    }
+
+    /// Push unsynced local issues and PRs to GitHub in the background.
+    pub fn start_sync_to_github(&mut self) {
+        let repo = self.repo.clone();
+        let mux = self.multiplexer;
+        let log = self.message_log.clone();
+        let (tx, rx) = mpsc::channel();
+        self.sync_rx = Some(rx);
+        self.loading_message = Some("Syncing local work to GitHub…".to_string());
+        std::thread::spawn(move || {
+            let _ = tx.send(crate::sync::sync_local_to_github(&repo, mux, &log));
+        });
+    }
+
+    /// Run `effect` on a background thread; its outcome is picked up by
+    /// [`App::take_outcomes`].
+    pub fn spawn_effect(&mut self, effect: Effect) {
+        let tx = self.effect_tx.clone();
+        // A sequence reports each effect as it finishes
+        let effects = match effect {
+            Effect::Sequence(effects) => effects,
+            effect => vec![effect],
+        };
+        self.effects_running += effects.len();
+        std::thread::spawn(move || {
+            for effect in effects {
+                let _ = tx.send(crate::effect::run(effect));
+            }
+        });
+    }
+
+    /// Outcomes of background effects that finished since the last call.
+    pub fn take_outcomes(&mut self) -> Vec<Outcome> {
+        let outcomes: Vec<Outcome> = self.effect_rx.try_iter().collect();
+        self.effects_running -= outcomes.len();
+        outcomes
     }
 
-    /// Returns true if any section is currently loading asynchronously.
     pub fn is_section_loading(&self) -> bool {
         self.section_loading.iter().any(|&x| x)
     }
@@ -557,6 +1058,10 @@ impl App {
             String::new()
         };
         self.repo_select = RepoSelectState::new();
+        self.active_view = None;
+        self.collapsed_groups = Default::default();
+        self.marked = Default::default();
+        self.mark_anchor = None;
         self.repo_select.input = crate::models::TextInput::from(owner);
         self.screen = Screen::RepoSelect;
     }
@@ -0,0 +1,321 @@
+//! How a column's cards are ordered and grouped. Each column offers the sort
+//! and group modes that make sense for its cards; groups get a header row
+//! that can be collapsed to hide their cards.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Card;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortMode {
+    /// As fetched: oldest first, unless an issue search sorts otherwise.
+    #[default]
+    Default,
+    /// Newest first, by number.
+    Created,
+    /// Most recently updated (or committed to) first.
+    Updated,
+    /// Lowest number first.
+    Number,
+    /// By `priority` / `P0`…`P4` / `critical`…`low` label, unlabelled last.
+    Priority,
+    /// Sessions that need attention first.
+    State,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupMode {
+    #[default]
+    None,
+    /// By first label.
+    Label,
+    Milestone,
+    /// By first assignee.
+    Assignee,
+    State,
+}
+
+/// The sort and group modes of one column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Arrangement {
+    #[serde(default)]
+    pub sort: SortMode,
+    #[serde(default)]
+    pub group: GroupMode,
+}
+
+impl SortMode {
+    /// The modes that apply to board column `section`, in cycling order.
+    pub fn options(section: usize) -> &'static [SortMode] {
+        use SortMode::*;
+        match section {
+            0 | 3 => &[Default, Created, Updated, Number, Priority],
+            1 => &[Default, Updated],
+            _ => &[Default, State],
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Default => "default",
+            SortMode::Created => "newest",
+            SortMode::Updated => "updated",
+            SortMode::Number => "number",
+            SortMode::Priority => "priority",
+            SortMode::State => "state",
+        }
+    }
+}
+
+impl GroupMode {
+    /// The modes that apply to board column `section`, in cycling order.
+    pub fn options(section: usize) -> &'static [GroupMode] {
+        use GroupMode::*;
+        match section {
+            0 => &[None, Label, Milestone, Assignee],
+            3 => &[None, Label, Milestone, Assignee, State],
+            _ => &[None, State],
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupMode::None => "none",
+            GroupMode::Label => "label",
+            GroupMode::Milestone => "milestone",
+            GroupMode::Assignee => "assignee",
+            GroupMode::State => "state",
+        }
+    }
+}
+
+/// The option after `current` among `options`, wrapping around.
+pub fn next<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let i = options.iter().position(|o| *o == current).unwrap_or(0);
+    options[(i + 1) % options.len()]
+}
+
+/// One line of a column: a group header or a card.
+pub enum Row<'a> {
+    Group {
+        name: String,
+        /// Cards in the group, shown or not.
+        count: usize,
+        collapsed: bool,
+    },
+    Card(&'a Card),
+}
+
+impl Row<'_> {
+    /// Rows the row takes on screen.
+    pub fn height(&self) -> u16 {
+        match self {
+            Row::Group { .. } => 1,
+            Row::Card(_) => 4,
+        }
+    }
+}
+
+/// Sort `cards` by `mode`, keeping their order where the mode doesn't tell
+/// them apart.
+pub fn sort(cards: &mut [&Card], mode: SortMode) {
+    match mode {
+        SortMode::Default => {}
+        SortMode::Created => cards.sort_by_key(|c| std::cmp::Reverse(number(c))),
+        SortMode::Updated => cards.sort_by_key(|c| std::cmp::Reverse(updated(c))),
+        SortMode::Number => cards.sort_by_key(|c| number(c).unwrap_or(u64::MAX)),
+        SortMode::Priority => cards.sort_by_key(|c| priority(c).unwrap_or(u8::MAX)),
+        SortMode::State => cards.sort_by_key(|c| session_rank(&c.tag)),
+    }
+}
+
+/// The rows showing `cards` (already sorted) grouped by `group`: groups in
+/// name order with the catch-all group last, each followed by its cards
+/// unless its name is in `collapsed`.
+pub fn rows<'a>(
+    cards: Vec<&'a Card>,
+    group: GroupMode,
+    collapsed: &HashSet<String>,
+) -> Vec<Row<'a>> {
+    if group == GroupMode::None {
+        return cards.into_iter().map(Row::Card).collect();
+    }
+    let mut groups: BTreeMap<(bool, String), Vec<&Card>> = BTreeMap::new();
+    for card in cards {
+        let key = match group_name(card, group) {
+            Some(name) => (false, name),
+            None => (true, catch_all(group).to_string()),
+        };
+        groups.entry(key).or_default().push(card);
+    }
+    let mut rows = Vec::new();
+    for ((_, name), cards) in groups {
+        let collapsed = collapsed.contains(&name);
+        rows.push(Row::Group {
+            count: cards.len(),
+            name,
+            collapsed,
+        });
+        if !collapsed {
+            rows.extend(cards.into_iter().map(Row::Card));
+        }
+    }
+    rows
+}
+
+fn group_name(card: &Card, group: GroupMode) -> Option<String> {
+    match group {
+        GroupMode::None => None,
+        GroupMode::Label => card.labels.first().cloned(),
+        GroupMode::Milestone => card.milestone.clone(),
+        GroupMode::Assignee => card.assignees.first().cloned(),
+        GroupMode::State => Some(state(card).to_string()),
+    }
+}
+
+/// The group of cards without what `group` goes by.
+fn catch_all(group: GroupMode) -> &'static str {
+    match group {
+        GroupMode::Label => "No label",
+        GroupMode::Milestone => "No milestone",
+        GroupMode::Assignee => "Unassigned",
+        GroupMode::None | GroupMode::State => "Other",
+    }
+}
+
+/// A PR's, worktree's or session's state as a group name.
+fn state(card: &Card) -> &str {
+    if card.is_merged == Some(true) {
+        return "merged";
+    }
+    if let Some(draft) = card.is_draft {
+        return if draft { "draft" } else { "ready" };
+    }
+    match &card.worktree_status {
+        Some(status) if status.conflicts == Some(true) => "conflict",
+        Some(status) if status.changed > 0 || status.untracked > 0 => "changed",
+        Some(_) => "clean",
+        None => &card.tag,
+    }
+}
+
+/// The issue or PR number, from the `#N` the title starts with.
+fn number(card: &Card) -> Option<u64> {
+    card.pr_number.or_else(|| {
+        let digits = card.title.strip_prefix('#')?.split(' ').next()?;
+        digits.parse().ok()
+    })
+}
+
+fn updated(card: &Card) -> Option<u64> {
+    card.updated_at.or_else(|| {
+        card.worktree_status
+            .as_ref()
+            .and_then(|s| s.last_commit_time)
+    })
+}
+
+/// The highest priority among the card's labels, 0 being the most urgent:
+/// `P0`–`P4`, `critical`/`urgent`, `high`, `medium`/`normal` and `low`,
+/// optionally after `priority` and a separator (`priority: high`,
+/// `priority/P1`).
+fn priority(card: &Card) -> Option<u8> {
+    card.labels
+        .iter()
+        .filter_map(|label| {
+            let label = label.to_lowercase();
+            let rest = label
+                .strip_prefix("priority")
+                .or_else(|| label.strip_prefix("prio"))
+                .map(|r| r.trim_start_matches([':', '/', '-', '_', ' ']))
+                .unwrap_or(&label);
+            match rest {
+                "critical" | "urgent" => Some(0),
+                "high" => Some(1),
+                "medium" | "normal" => Some(2),
+                "low" => Some(3),
+                _ => rest.strip_prefix('p')?.parse().ok().filter(|p| *p <= 4),
+            }
+        })
+        .min()
+}
+
+/// Sessions waiting on the user first, then busy ones.
+fn session_rank(state: &str) -> u8 {
+    match state {
+        "permission" => 0,
+        "idle" => 1,
+        "processing" => 2,
+        "working" => 3,
+        _ => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(number: u64, labels: &[&str]) -> Card {
+        Card {
+            id: format!("issue-{}", number),
+            title: format!("#{} Something", number),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            updated_at: Some(number * 10 % 35),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sorts_and_groups_cards() {
+        let issues = [
+            issue(1, &["bug", "priority: low"]),
+            issue(2, &["P0"]),
+            issue(3, &[]),
+            issue(4, &["bug", "Priority/High"]),
+        ];
+        let numbers =
+            |cards: &[&Card]| cards.iter().map(|c| number(c).unwrap()).collect::<Vec<_>>();
+        let mut cards: Vec<&Card> = issues.iter().collect();
+
+        sort(&mut cards, SortMode::Created);
+        assert_eq!(numbers(&cards), [4, 3, 2, 1]);
+        sort(&mut cards, SortMode::Priority);
+        assert_eq!(numbers(&cards), [2, 4, 1, 3]);
+        sort(&mut cards, SortMode::Updated);
+        assert_eq!(numbers(&cards), [3, 2, 1, 4]);
+
+        let collapsed = HashSet::from(["P0".to_string()]);
+        let rows = rows(cards, GroupMode::Label, &collapsed);
+        let lines: Vec<String> = rows
+            .iter()
+            .map(|row| match row {
+                Row::Group {
+                    name,
+                    count,
+                    collapsed,
+                } => format!("{} {} {}", name, count, collapsed),
+                Row::Card(card) => number(card).unwrap().to_string(),
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "P0 1 true",
+                "bug 2 false",
+                "1",
+                "4",
+                "No label 1 false",
+                "3"
+            ]
+        );
+
+        assert_eq!(
+            next(SortMode::options(1), SortMode::Updated),
+            SortMode::Default
+        );
+    }
+}
@@ -1,17 +1,21 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::arrange::Arrangement;
+use crate::log;
 use crate::session::Multiplexer;
 
 fn default_auto_open_pr() -> HashMap<String, bool> {
     HashMap::new()
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct Config {
     pub repo: String,
     #[serde(default)]
@@ -36,6 +40,82 @@ pub struct Config {
     /// Automatic refresh interval in seconds. 0 or absent means no auto-refresh.
     #[serde(default)]
     pub auto_refresh_secs: Option<u64>,
+    /// Per-repo template for new worktree directories, e.g. `~/worktrees/{repo_name}/{branch}`.
+    #[serde(default)]
+    pub worktree_dir_templates: HashMap<String, String>,
+    /// Per-repo template for new branch names, e.g. `feat/{issue_number}-{slug}`.
+    #[serde(default)]
+    pub branch_templates: HashMap<String, String>,
+    /// Per-repo branch new work starts from and PRs target. Absent means
+    /// whatever `origin/HEAD` points at.
+    #[serde(default)]
+    pub base_branches: HashMap<String, String>,
+    /// Keys for board actions by action name, replacing their defaults,
+    /// e.g. `"merge_pr": ["ctrl+m"]`. See `keymap::BINDINGS` for the names.
+    #[serde(default)]
+    pub keybindings: HashMap<String, Vec<String>>,
+    /// A preset (`dark`, `light`, `high-contrast`, `monochrome`) or one of
+    /// `themes`. Absent means `dark`.
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Custom themes by name: colour slot → colour, plus an optional `base`
+    /// preset, e.g. `"mine": { "base": "light", "accent": "#d75f00" }`.
+    #[serde(default)]
+    pub themes: HashMap<String, HashMap<String, String>>,
+    /// Colours for issue labels by name, e.g. `"needs design": "magenta"`.
+    #[serde(default)]
+    pub label_colors: HashMap<String, String>,
+    /// Per-repo saved views, switched to with the number keys in this order.
+    #[serde(default)]
+    pub views: HashMap<String, Vec<SavedView>>,
+    /// Per-repo sort and group modes by column (`issues`, `worktrees`,
+    /// `sessions`, `pull_requests`).
+    #[serde(default)]
+    pub arrangements: HashMap<String, HashMap<String, Arrangement>>,
+}
+
+/// Column names as used in the config file, in board order.
+const COLUMNS: [&str; 4] = ["issues", "worktrees", "sessions", "pull_requests"];
+
+/// A named set of column filters, e.g. `{ "name": "triage", "issues":
+/// "no:assignee -label:wontfix", "pull_requests": "is:draft" }`. Each
+/// filter is a [`crate::query::Query`]; an empty one shows the whole column.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SavedView {
+    pub name: String,
+    #[serde(default)]
+    pub issues: String,
+    #[serde(default)]
+    pub worktrees: String,
+    #[serde(default)]
+    pub sessions: String,
+    #[serde(default)]
+    pub pull_requests: String,
+}
+
+impl SavedView {
+    /// The filter of board column `section`.
+    pub fn query(&self, section: usize) -> &str {
+        match section {
+            0 => &self.issues,
+            1 => &self.worktrees,
+            2 => &self.sessions,
+            _ => &self.pull_requests,
+        }
+    }
+
+    pub fn set_query(&mut self, section: usize, query: String) {
+        match section {
+            0 => self.issues = query,
+            1 => self.worktrees = query,
+            2 => self.sessions = query,
+            _ => self.pull_requests = query,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..4).all(|section| self.query(section).trim().is_empty())
+    }
 }
 
 pub fn config_path() -> PathBuf {
@@ -45,59 +125,46 @@ pub fn config_path() -> PathBuf {
         .join("config.json")
 }
 
-pub fn load_config() -> Option<Config> {
+/// The config file, `None` if there is none yet. A file that doesn't parse
+/// is an error, so nothing gets saved over the user's edits.
+pub fn read_config() -> Result<Option<Config>> {
     let path = config_path();
-    let data = fs::read_to_string(path).ok()?;
-    serde_json::from_str(&data).ok()
+    let Ok(data) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let config = serde_json::from_str(&data).map_err(|e| {
+        eyre!(
+            "{} is not valid ({}); fix it or remove it",
+            path.display(),
+            e
+        )
+    })?;
+    Ok(Some(config))
 }
 
-pub fn save_config(repo: &str) -> Result<()> {
-    // Load existing config to preserve verify_commands, editor_commands, pr_ready
-    let existing = load_config();
-    let verify_commands = existing
-        .as_ref()
-        .map(|c| c.verify_commands.clone())
-        .unwrap_or_default();
-    let editor_commands = existing
-        .as_ref()
-        .map(|c| c.editor_commands.clone())
-        .unwrap_or_default();
-    let pr_ready = existing
-        .as_ref()
-        .map(|c| c.pr_ready.clone())
-        .unwrap_or_default();
-    let auto_open_pr = existing
-        .as_ref()
-        .map(|c| c.auto_open_pr.clone())
-        .unwrap_or_default();
-    let path = config_path();
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+/// The config file for reading settings from, logging (once per distinct
+/// error) why a file that exists could not be used.
+pub fn load_config() -> Option<Config> {
+    static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+    match read_config() {
+        Ok(config) => config,
+        Err(e) => {
+            let message = e.to_string();
+            let mut last = LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner());
+            if last.as_deref() != Some(message.as_str()) {
+                log::warn("config", &message);
+                *last = Some(message);
+            }
+            None
+        }
     }
-    let session_commands = existing
-        .as_ref()
-        .map(|c| c.session_commands.clone())
-        .unwrap_or_default();
-    let default_session_command = existing
-        .as_ref()
-        .and_then(|c| c.default_session_command.clone());
-    let local_mode = existing.as_ref().and_then(|c| c.local_mode);
-    let auto_refresh_secs = existing.as_ref().and_then(|c| c.auto_refresh_secs);
-    let multiplexer = existing.and_then(|c| c.multiplexer);
-    let config = Config {
-        repo: repo.to_string(),
-        verify_commands,
-        editor_commands,
-        pr_ready,
-        auto_open_pr,
-        session_commands,
-        multiplexer,
-        default_session_command,
-        local_mode,
-        auto_refresh_secs,
-    };
-    fs::write(path, serde_json::to_string_pretty(&config)?)?;
-    Ok(())
+}
+
+pub fn save_config(repo: &str) -> Result<()> {
+    // Load existing config to preserve every other setting
+    let mut config = read_config()?.unwrap_or_default();
+    config.repo = repo.to_string();
+    save_full_config(&config)
 }
 
 pub fn save_full_config(config: &Config) -> Result<()> {
@@ -125,17 +192,9 @@ pub fn get_editor_command(repo: &str) -> Option<String> {
 }
 
 pub fn set_editor_command(repo: &str, command: &str) -> Result<()> {
-    let mut config = load_config().unwrap_or(Config {
+    let mut config = read_config()?.unwrap_or_else(|| Config {
         repo: repo.to_string(),
-        verify_commands: HashMap::new(),
-        editor_commands: HashMap::new(),
-        pr_ready: HashMap::new(),
-        auto_open_pr: HashMap::new(),
-        session_commands: HashMap::new(),
-        multiplexer: None,
-        default_session_command: None,
-        local_mode: None,
-        auto_refresh_secs: None,
+        ..Config::default()
     });
     config
         .editor_commands
@@ -144,17 +203,9 @@ pub fn set_editor_command(repo: &str, command: &str) -> Result<()> {
 }
 
 pub fn set_verify_command(repo: &str, command: &str) -> Result<()> {
-    let mut config = load_config().unwrap_or(Config {
+    let mut config = read_config()?.unwrap_or_else(|| Config {
         repo: repo.to_string(),
-        verify_commands: HashMap::new(),
-        editor_commands: HashMap::new(),
-        pr_ready: HashMap::new(),
-        auto_open_pr: HashMap::new(),
-        session_commands: HashMap::new(),
-        multiplexer: None,
-        default_session_command: None,
-        local_mode: None,
-        auto_refresh_secs: None,
+        ..Config::default()
     });
     config
         .verify_commands
@@ -188,18 +239,7 @@ pub fn get_default_session_command() -> Option<String> {
 }
 
 pub fn set_default_session_command(command: &str) -> Result<()> {
-    let mut config = load_config().unwrap_or(Config {
-        repo: String::new(),
-        verify_commands: HashMap::new(),
-        editor_commands: HashMap::new(),
-        pr_ready: HashMap::new(),
-        auto_open_pr: HashMap::new(),
-        session_commands: HashMap::new(),
-        multiplexer: None,
-        default_session_command: None,
-        local_mode: None,
-        auto_refresh_secs: None,
-    });
+    let mut config = read_config()?.unwrap_or_default();
     config.default_session_command = Some(command.to_string());
     save_full_config(&config)
 }
@@ -209,18 +249,7 @@ pub fn get_local_mode() -> bool {
 }
 
 pub fn set_local_mode(enabled: bool) -> Result<()> {
-    let mut config = load_config().unwrap_or(Config {
-        repo: String::new(),
-        verify_commands: HashMap::new(),
-        editor_commands: HashMap::new(),
-        pr_ready: HashMap::new(),
-        auto_open_pr: HashMap::new(),
-        session_commands: HashMap::new(),
-        multiplexer: None,
-        default_session_command: None,
-        local_mode: None,
-        auto_refresh_secs: None,
-    });
+    let mut config = read_config()?.unwrap_or_default();
     config.local_mode = Some(enabled);
     save_full_config(&config)
 }
@@ -228,3 +257,89 @@ pub fn set_local_mode(enabled: bool) -> Result<()> {
 pub fn get_auto_refresh_secs() -> u64 {
     load_config().and_then(|c| c.auto_refresh_secs).unwrap_or(0)
 }
+
+pub fn get_worktree_dir_template(repo: &str) -> Option<String> {
+    load_config()?.worktree_dir_templates.get(repo).cloned()
+}
+
+pub fn get_branch_template(repo: &str) -> Option<String> {
+    load_config()?.branch_templates.get(repo).cloned()
+}
+
+pub fn get_base_branch(repo: &str) -> Option<String> {
+    load_config()?.base_branches.get(repo).cloned()
+}
+
+pub fn get_keybindings() -> HashMap<String, Vec<String>> {
+    load_config().map(|c| c.keybindings).unwrap_or_default()
+}
+
+pub fn get_views(repo: &str) -> Vec<SavedView> {
+    load_config()
+        .and_then(|mut c| c.views.remove(repo))
+        .unwrap_or_default()
+}
+
+/// Store `view` under its name, replacing a view of the same name, or drop
+/// that view if `view` has no filters.
+pub fn save_view(repo: &str, view: &SavedView) -> Result<()> {
+    let mut config = read_config()?.unwrap_or_else(|| Config {
+        repo: repo.to_string(),
+        ..Config::default()
+    });
+    let views = config.views.entry(repo.to_string()).or_default();
+    match views.iter().position(|v| v.name == view.name) {
+        Some(i) if view.is_empty() => {
+            views.remove(i);
+        }
+        Some(i) => views[i] = view.clone(),
+        None if !view.is_empty() => views.push(view.clone()),
+        None => {}
+    }
+    save_full_config(&config)
+}
+
+pub fn get_arrangements(repo: &str) -> [Arrangement; 4] {
+    let saved = load_config()
+        .and_then(|mut c| c.arrangements.remove(repo))
+        .unwrap_or_default();
+    COLUMNS.map(|column| saved.get(column).copied().unwrap_or_default())
+}
+
+pub fn set_arrangement(repo: &str, section: usize, arrangement: Arrangement) -> Result<()> {
+    let mut config = read_config()?.unwrap_or_else(|| Config {
+        repo: repo.to_string(),
+        ..Config::default()
+    });
+    config
+        .arrangements
+        .entry(repo.to_string())
+        .or_default()
+        .insert(COLUMNS[section].to_string(), arrangement);
+    save_full_config(&config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Sandbox;
+
+    #[test]
+    fn a_config_that_does_not_parse_is_not_overwritten() {
+        if Sandbox::enter("config::tests::a_config_that_does_not_parse_is_not_overwritten")
+            .is_none()
+        {
+            return;
+        }
+        let path = config_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let edited = r#"{ "repo": "acme/widgets", "keybindings": { "merge_pr": "ctrl+m" } }"#;
+        fs::write(&path, edited).unwrap();
+
+        assert!(read_config().is_err());
+        assert!(!get_local_mode());
+        assert!(set_local_mode(true).is_err());
+        assert!(set_arrangement("acme/widgets", 0, Arrangement::default()).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+    }
+}
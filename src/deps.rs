@@ -1,5 +1,6 @@
 use std::process::Command;
 
+#[derive(Debug)]
 pub struct Dependency {
     pub name: &'static str,
     pub description: &'static str,
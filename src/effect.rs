@@ -0,0 +1,721 @@
+//! Side effects the board asks for: everything that shells out to `gh`,
+//! `git` or the multiplexer. [`App::update`](crate::app::App::update)
+//! returns [`Effect`]s instead of running them, and [`run`] carries them out
+//! — on a background thread unless the effect needs the terminal — and
+//! reports back with an [`Outcome`] that is fed to the reducer again.
+
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::deps::{self, Dependency};
+use crate::error::{Error, GitError};
+use crate::git::{self, MergedCleanup, UpdateOutcome, UpdateStrategy};
+use crate::github::IssuePage;
+use crate::local::{self, ImportReport};
+use crate::models::{Card, IssueOptions, MergeStrategy};
+use crate::session::{ensure_main_session, session_cards, Multiplexer, MAIN_SESSION_NAME};
+use crate::templates::{self, IssueTemplate};
+use crate::{config, github, log, transfer};
+
+/// Work to do outside the reducer. Each carries everything it needs so it
+/// can run on another thread.
+#[derive(Debug, PartialEq)]
+pub enum Effect {
+    /// Pull the repo's base branch from origin.
+    PullBase {
+        repo: String,
+    },
+    /// Rebase or merge the worktree branch's base into it. On conflicts,
+    /// the agent running on the branch is asked to resolve them; without
+    /// one the rebase or merge is aborted.
+    UpdateFromBase {
+        repo: String,
+        branch: String,
+        path: String,
+        strategy: UpdateStrategy,
+        mux: Multiplexer,
+    },
+    /// Find the branches stacked on `parent`, to offer restacking them.
+    LoadStack {
+        parent: String,
+    },
+    /// Rebase every branch stacked on `parent` onto it, or when `onto` is
+    /// set, onto `onto` — re-pointing their base and PRs at it and removing
+    /// `parent` once nothing is left on it.
+    Restack {
+        repo: String,
+        parent: String,
+        onto: Option<String>,
+        local: bool,
+        mux: Multiplexer,
+    },
+    /// Open a local PR for a worktree branch, unless it has one already.
+    CreateLocalPr {
+        repo: String,
+        branch: String,
+    },
+    /// Open a local PR for each branch whose agent went idle with commits
+    /// but no PR, titled after its first commit.
+    AutoCreateLocalPrs {
+        repo: String,
+        branches: Vec<String>,
+    },
+    /// Ask the agents on `branches`, idle without a PR, to continue.
+    Nudge {
+        branches: Vec<String>,
+        mux: Multiplexer,
+    },
+    /// Remove the worktrees, given as branch and path, whose branch is
+    /// `merged`; `None` reads the merged branches from the local store.
+    CleanupMerged {
+        repo: String,
+        merged: Option<Vec<String>>,
+        worktrees: Vec<(String, String)>,
+        mux: Multiplexer,
+    },
+    /// Remember local or GitHub mode in the config file.
+    SaveLocalMode {
+        enabled: bool,
+    },
+    /// Count the local issues and PRs not on GitHub yet.
+    CountUnsynced {
+        repo: String,
+    },
+    /// Read the repository's issue templates for the new issue form.
+    LoadTemplates,
+    OpenUrl {
+        url: String,
+    },
+    /// Start a configured shell command (editor, verify) without waiting.
+    Launch {
+        command: String,
+        what: String,
+    },
+    MarkPrReady {
+        repo: String,
+        number: u64,
+        local: bool,
+    },
+    RevertPr {
+        repo: String,
+        number: u64,
+    },
+    CloseIssue {
+        repo: String,
+        number: u64,
+        local: bool,
+    },
+    RemoveWorktree {
+        path: String,
+        branch: String,
+        mux: Multiplexer,
+    },
+    KillSession {
+        name: String,
+        mux: Multiplexer,
+    },
+    ExportStore {
+        repo: String,
+        path: String,
+        format: transfer::Format,
+    },
+    /// Import a file into the local store; a dry run only reports what
+    /// would change.
+    ImportStore {
+        repo: String,
+        path: String,
+        format: transfer::Format,
+        dry_run: bool,
+    },
+    /// Merge a GitHub PR, then remove `worktree` (its path) if it has one.
+    MergePr {
+        repo: String,
+        number: u64,
+        strategy: MergeStrategy,
+        branch: Option<String>,
+        worktree: Option<String>,
+        mux: Multiplexer,
+    },
+    /// `git merge` a local PR's branch into its base and mark it merged.
+    MergeLocalPr {
+        repo: String,
+        number: u64,
+        branch: Option<String>,
+        worktree: Option<String>,
+        mux: Multiplexer,
+    },
+    /// Look up what the new issue form can offer for labels, assignees and
+    /// milestone.
+    LoadIssueOptions {
+        repo: String,
+        local: bool,
+    },
+    /// Fetch the page of a GitHub issue search that starts at `cursor`.
+    LoadMoreIssues {
+        search: String,
+        cursor: String,
+    },
+    /// Hand the terminal to a session until the user detaches, creating the
+    /// main explore session first when `session` is [`MAIN_SESSION_NAME`].
+    Attach {
+        session: String,
+        mux: Multiplexer,
+    },
+    /// Re-read the worktrees and sessions after one was added or removed.
+    /// `states` are the session states the agent hooks reported.
+    ReloadSessions {
+        repo: String,
+        states: HashMap<String, String>,
+        mux: Multiplexer,
+    },
+    /// See which tools the dependencies screen lists are installed.
+    CheckDependencies,
+    /// See whether `gh` is installed, before leaving local mode.
+    CheckGh,
+    /// Run these one after another, e.g. the merges of a bulk action, which
+    /// would race each other for the repo if run at once.
+    Sequence(Vec<Effect>),
+}
+
+impl Effect {
+    /// Whether the effect takes over the terminal, so it has to run on the
+    /// UI thread with the board suspended.
+    pub fn is_foreground(&self) -> bool {
+        matches!(self, Effect::Attach { .. })
+    }
+}
+
+/// What came of an [`Effect`].
+#[derive(Debug)]
+pub enum Outcome {
+    /// The base branch that was pulled.
+    Pulled(Result<String, Error>),
+    Updated {
+        branch: String,
+        strategy: UpdateStrategy,
+        /// The base that was brought in, and whether it applied cleanly.
+        result: Result<(String, UpdateOutcome), Error>,
+        /// Whether the agent on the branch was asked to resolve conflicts.
+        asked_agent: bool,
+        /// The branches stacked on this one, which are now behind it.
+        stacked: Vec<String>,
+    },
+    StackLoaded {
+        parent: String,
+        children: Vec<String>,
+    },
+    Restacked {
+        parent: String,
+        onto: Option<String>,
+        report: RestackReport,
+    },
+    /// The new PR's number, or `None` if the branch had one already.
+    LocalPrCreated {
+        branch: String,
+        result: Result<Option<u64>, Error>,
+    },
+    LocalPrsAutoCreated(Vec<(String, Result<u64, Error>)>),
+    Nudged(Vec<(String, Result<(), Error>)>),
+    MergedCleanedUp(MergedCleanup),
+    LocalModeSaved(Result<(), String>),
+    /// How many local issues and PRs are not on GitHub yet.
+    UnsyncedCounted(Result<(usize, usize), Error>),
+    TemplatesLoaded(Vec<IssueTemplate>),
+    /// A browser or configured command was started; `what` says which.
+    Launched {
+        what: String,
+        result: Result<(), String>,
+    },
+    MarkedReady {
+        number: u64,
+        result: Result<(), Error>,
+    },
+    Reverted {
+        number: u64,
+        result: Result<(), Error>,
+    },
+    IssueClosed {
+        number: u64,
+        result: Result<(), Error>,
+    },
+    WorktreeRemoved {
+        branch: String,
+        result: Result<(), Error>,
+    },
+    SessionKilled {
+        name: String,
+        result: Result<(), Error>,
+    },
+    Exported(Result<String, String>),
+    Imported {
+        path: String,
+        format: transfer::Format,
+        dry_run: bool,
+        result: Result<ImportReport, String>,
+    },
+    PrMerged {
+        number: u64,
+        strategy: MergeStrategy,
+        /// The worktree branch removed along with the PR, if it had one,
+        /// and whether removing it worked.
+        cleanup: Option<(String, Result<(), Error>)>,
+        result: Result<(), Error>,
+    },
+    LocalPrMerged {
+        number: u64,
+        branch: Option<String>,
+        result: Result<(), Error>,
+    },
+    IssueOptionsLoaded(Result<IssueOptions, Error>),
+    /// The page of issues that starts at `cursor`.
+    MoreIssues {
+        cursor: String,
+        result: Result<IssuePage, Error>,
+    },
+    /// The user detached from a session.
+    Detached {
+        /// Set when the main explore session had to be created first.
+        created_main: bool,
+        result: Result<(), Error>,
+    },
+    SessionsReloaded {
+        worktrees: Vec<Card>,
+        sessions: Vec<Card>,
+    },
+    DependenciesChecked(Vec<Dependency>),
+    GhChecked(bool),
+    /// What came of each effect of an [`Effect::Sequence`], in order.
+    Sequence(Vec<Outcome>),
+}
+
+/// What an [`Effect::Restack`] did.
+#[derive(Debug, Default)]
+pub struct RestackReport {
+    pub restacked: usize,
+    /// Branches that are still stacked on the parent.
+    pub left_on_parent: usize,
+    /// What happened to the branches that were not simply moved, for the
+    /// message log.
+    pub notes: Vec<String>,
+}
+
+/// Carry out `effect`, blocking until it is done.
+pub fn run(effect: Effect) -> Outcome {
+    match effect {
+        Effect::PullBase { repo } => Outcome::Pulled(
+            git::default_base_branch(&repo)
+                .ok_or_else(|| GitError::failed("git pull", "could not determine the base branch"))
+                .and_then(|base| git::pull_base_branch(&base).map(|_| base))
+                .map_err(Error::from),
+        ),
+        Effect::UpdateFromBase {
+            repo,
+            branch,
+            path,
+            strategy,
+            mux,
+        } => {
+            let result = git::base_for_branch(&repo, &branch)
+                .ok_or_else(|| {
+                    GitError::failed(strategy.label(), "could not determine the base branch")
+                })
+                .and_then(|base| {
+                    git::update_from_base(&path, &base, strategy).map(|outcome| (base, outcome))
+                })
+                .map_err(Error::from);
+            let asked_agent = match &result {
+                Ok((base, UpdateOutcome::Conflicts(files))) => {
+                    resolve_conflicts(&branch, &path, strategy, base, files, mux)
+                }
+                _ => false,
+            };
+            Outcome::Updated {
+                stacked: git::stack_children(&branch),
+                branch,
+                strategy,
+                result,
+                asked_agent,
+            }
+        }
+        Effect::LoadStack { parent } => Outcome::StackLoaded {
+            children: git::stack_children(&parent),
+            parent,
+        },
+        Effect::Restack {
+            repo,
+            parent,
+            onto,
+            local,
+            mux,
+        } => Outcome::Restacked {
+            report: restack(&repo, &parent, onto.as_deref(), local, mux),
+            parent,
+            onto,
+        },
+        Effect::CreateLocalPr { repo, branch } => {
+            let result = if local::has_local_pr_for_branch(&repo, &branch) {
+                Ok(None)
+            } else {
+                let title = format!("PR for {}", branch);
+                create_local_pr(&repo, &branch, &title).map(Some)
+            };
+            Outcome::LocalPrCreated { branch, result }
+        }
+        Effect::AutoCreateLocalPrs { repo, branches } => Outcome::LocalPrsAutoCreated(
+            branches
+                .into_iter()
+                .filter(|branch| {
+                    !local::has_local_pr_for_branch(&repo, branch)
+                        && git::branch_has_commits(&repo, branch)
+                })
+                .map(|branch| {
+                    let title = git::first_commit_summary(&repo, &branch)
+                        .unwrap_or_else(|| format!("PR for {}", branch));
+                    let result = create_local_pr(&repo, &branch, &title);
+                    (branch, result)
+                })
+                .collect(),
+        ),
+        Effect::Nudge { branches, mux } => Outcome::Nudged(
+            branches
+                .into_iter()
+                .map(|branch| {
+                    let result = mux.send_keys(&branch, "continue").map_err(Error::from);
+                    (branch, result)
+                })
+                .collect(),
+        ),
+        Effect::CleanupMerged {
+            repo,
+            merged,
+            worktrees,
+            mux,
+        } => {
+            let merged = merged.unwrap_or_else(|| local::fetch_local_merged_pr_branches(&repo));
+            Outcome::MergedCleanedUp(git::cleanup_merged_worktrees(&merged, &worktrees, mux))
+        }
+        Effect::SaveLocalMode { enabled } => {
+            Outcome::LocalModeSaved(config::set_local_mode(enabled).map_err(|e| e.to_string()))
+        }
+        Effect::CountUnsynced { repo } => Outcome::UnsyncedCounted(
+            local::fetch_unsynced(&repo)
+                .map(|(issues, prs)| (issues.len(), prs.len()))
+                .map_err(Error::from),
+        ),
+        Effect::LoadTemplates => Outcome::TemplatesLoaded(templates::load_templates()),
+        Effect::OpenUrl { url } => Outcome::Launched {
+            what: format!("browser for {}", url),
+            result: Command::new("open")
+                .arg(&url)
+                .output()
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        },
+        Effect::Launch { command, what } => Outcome::Launched {
+            what,
+            result: Command::new("sh")
+                .args(["-c", &command])
+                .spawn()
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        },
+        Effect::MarkPrReady {
+            repo,
+            number,
+            local,
+        } => Outcome::MarkedReady {
+            number,
+            result: if local {
+                local::mark_local_pr_ready(&repo, number).map_err(Error::from)
+            } else {
+                github::mark_pr_ready(&repo, number).map_err(Error::from)
+            },
+        },
+        Effect::RevertPr { repo, number } => Outcome::Reverted {
+            number,
+            result: github::revert_pr(&repo, number).map_err(Error::from),
+        },
+        Effect::CloseIssue {
+            repo,
+            number,
+            local,
+        } => Outcome::IssueClosed {
+            number,
+            result: if local {
+                local::close_local_issue(&repo, number).map_err(Error::from)
+            } else {
+                github::close_issue(&repo, number).map_err(Error::from)
+            },
+        },
+        Effect::RemoveWorktree { path, branch, mux } => {
+            let result = git::remove_worktree(&path, &branch, mux).map_err(Error::from);
+            Outcome::WorktreeRemoved { branch, result }
+        }
+        Effect::KillSession { name, mux } => Outcome::SessionKilled {
+            result: mux.kill_session(&name).map_err(Error::from),
+            name,
+        },
+        Effect::ExportStore { repo, path, format } => {
+            Outcome::Exported(transfer::export_to_file(&repo, &path, format))
+        }
+        Effect::ImportStore {
+            repo,
+            path,
+            format,
+            dry_run,
+        } => {
+            let result = transfer::import_from_file(
+                &repo,
+                &path,
+                format,
+                local::ConflictPolicy::Renumber,
+                dry_run,
+            );
+            Outcome::Imported {
+                path,
+                format,
+                dry_run,
+                result,
+            }
+        }
+        Effect::MergePr {
+            repo,
+            number,
+            strategy,
+            branch,
+            worktree,
+            mux,
+        } => {
+            let result = github::merge_pr(&repo, number, strategy).map_err(Error::from);
+            // Clean up the merged branch's worktree right away instead of
+            // waiting for a refresh to see the PR as merged.
+            let cleanup = match (&result, branch, worktree) {
+                (Ok(()), Some(branch), Some(path)) => {
+                    let removed = git::remove_worktree(&path, &branch, mux);
+                    Some((branch, removed.map_err(Error::from)))
+                }
+                _ => None,
+            };
+            Outcome::PrMerged {
+                number,
+                strategy,
+                cleanup,
+                result,
+            }
+        }
+        Effect::MergeLocalPr {
+            repo,
+            number,
+            branch,
+            worktree,
+            mux,
+        } => {
+            let result = merge_local_pr(&repo, number, branch.as_deref(), worktree, mux);
+            Outcome::LocalPrMerged {
+                number,
+                branch,
+                result,
+            }
+        }
+        Effect::LoadIssueOptions { repo, local } => Outcome::IssueOptionsLoaded(if local {
+            local::fetch_local_issue_options(&repo).map_err(Error::from)
+        } else {
+            github::fetch_issue_options(&repo).map_err(Error::from)
+        }),
+        Effect::LoadMoreIssues { search, cursor } => Outcome::MoreIssues {
+            result: github::fetch_issue_page(&search, &cursor).map_err(Error::from),
+            cursor,
+        },
+        Effect::Attach { session, mux } => {
+            let mut created_main = false;
+            let result = if session == MAIN_SESSION_NAME {
+                ensure_main_session(mux).map(|created| created_main = created)
+            } else {
+                Ok(())
+            }
+            .and_then(|()| mux.attach(&session).map_err(Error::from));
+            Outcome::Detached {
+                created_main,
+                result,
+            }
+        }
+        Effect::ReloadSessions { repo, states, mux } => Outcome::SessionsReloaded {
+            worktrees: git::fetch_worktrees(&repo),
+            sessions: session_cards(&states, mux),
+        },
+        Effect::CheckDependencies => Outcome::DependenciesChecked(deps::check_dependencies()),
+        Effect::CheckGh => Outcome::GhChecked(deps::gh_available()),
+        Effect::Sequence(effects) => Outcome::Sequence(effects.into_iter().map(run).collect()),
+    }
+}
+
+/// Open a local PR titled `title` for `branch`, against its base; a draft
+/// unless the repo is configured to open PRs ready for review.
+fn create_local_pr(repo: &str, branch: &str, title: &str) -> Result<u64, Error> {
+    let base = git::base_for_branch(repo, branch);
+    let draft = !config::get_pr_ready(repo);
+    local::create_local_pr(repo, title, "", branch, base.as_deref(), draft).map_err(Error::from)
+}
+
+/// A rebase or merge of `base` into `branch` stopped on conflicts. If an
+/// agent session is running on the branch, leave the operation in progress
+/// and ask it to resolve them (returns `true`); otherwise abort.
+fn resolve_conflicts(
+    branch: &str,
+    path: &str,
+    strategy: UpdateStrategy,
+    base: &str,
+    files: &[String],
+    mux: Multiplexer,
+) -> bool {
+    if !mux.list_sessions().iter().any(|s| s == branch) {
+        git::abort_update(path, strategy);
+        return false;
+    }
+    let asked = mux.send_keys(
+        branch,
+        &format!(
+            "A git {} of {} into this branch stopped with conflicts in: {}. Please resolve the conflicts, then run `git {} --continue`.",
+            strategy.label(),
+            base,
+            files.join(", "),
+            strategy.label()
+        ),
+    );
+    if let Err(e) = asked {
+        log::warn("session", e);
+        git::abort_update(path, strategy);
+        return false;
+    }
+    true
+}
+
+/// What became of a rebase or merge into `branch` that stopped on
+/// conflicts in `files`.
+pub fn describe_conflicts(
+    branch: &str,
+    strategy: UpdateStrategy,
+    files: &[String],
+    asked_agent: bool,
+) -> String {
+    if asked_agent {
+        format!(
+            "Conflicts in {} file(s) — asked the agent in '{}' to resolve",
+            files.len(),
+            branch
+        )
+    } else {
+        format!(
+            "Aborted {} of '{}', conflicts in: {}",
+            strategy.label(),
+            branch,
+            files.join(", ")
+        )
+    }
+}
+
+/// Carry out an [`Effect::Restack`].
+fn restack(
+    repo: &str,
+    parent: &str,
+    onto: Option<&str>,
+    local: bool,
+    mux: Multiplexer,
+) -> RestackReport {
+    let mut report = RestackReport::default();
+    let worktrees = git::fetch_worktrees(repo);
+    let path_of = |branch: &str| {
+        worktrees
+            .iter()
+            .find(|w| w.title == branch)
+            .map(|w| w.description.clone())
+    };
+    for child in git::stack_children(parent) {
+        let Some(path) = path_of(&child) else {
+            report
+                .notes
+                .push(format!("[restack] No worktree for '{}', skipped", child));
+            report.left_on_parent += 1;
+            continue;
+        };
+        let new_base = onto.unwrap_or(parent);
+        let outcome = match onto {
+            Some(onto) => git::rebase_onto(&path, onto, parent),
+            None => git::update_from_base(&path, parent, UpdateStrategy::Rebase),
+        };
+        let moved = match outcome {
+            Ok(UpdateOutcome::Updated) => {
+                report.restacked += 1;
+                true
+            }
+            Ok(UpdateOutcome::Conflicts(files)) => {
+                let strategy = UpdateStrategy::Rebase;
+                let asked_agent = resolve_conflicts(&child, &path, strategy, new_base, &files, mux);
+                report.notes.push(format!(
+                    "[restack] {}",
+                    describe_conflicts(&child, strategy, &files, asked_agent)
+                ));
+                asked_agent
+            }
+            Err(e) => {
+                report.notes.push(format!("[restack] {}: {}", child, e));
+                false
+            }
+        };
+        if !moved {
+            report.left_on_parent += 1;
+            continue;
+        }
+        if let Some(onto) = onto {
+            log::warn_on_err("restack", git::set_branch_base(&child, onto));
+            let retargeted = if local {
+                local::retarget_local_prs(repo, &child, onto).map_err(Error::from)
+            } else {
+                github::retarget_pr(repo, &child, onto).map_err(Error::from)
+            };
+            if let Err(e) = retargeted {
+                report.notes.push(format!(
+                    "[restack] Could not retarget PR for {}: {}",
+                    child, e
+                ));
+            }
+        }
+    }
+
+    if onto.is_some() && report.left_on_parent == 0 {
+        if let Some(path) = path_of(parent) {
+            log::warn_on_err("restack", git::remove_worktree(&path, parent, mux));
+        }
+    }
+    report
+}
+
+/// Merge local PR `number`: `git merge` its branch into the PR's base, mark
+/// it merged, close the issue it was for and remove its worktree. Without a
+/// branch there is nothing to merge, so it is only marked merged.
+fn merge_local_pr(
+    repo: &str,
+    number: u64,
+    branch: Option<&str>,
+    worktree: Option<String>,
+    mux: Multiplexer,
+) -> Result<(), Error> {
+    let branch = match branch {
+        Some(branch) => {
+            let base = local::fetch_local_pr_base(repo, number)
+                .or_else(|| git::default_base_branch(repo))
+                .ok_or_else(|| GitError::failed("git merge", "no base branch to merge into"))?;
+            git::merge_branch(branch, &base)?;
+            local::merge_local_pr(repo, number)?;
+            if let Some(path) = worktree {
+                log::warn_on_err("git", git::remove_worktree(&path, branch, mux));
+            }
+            branch.to_string()
+        }
+        None => local::merge_local_pr(repo, number)?,
+    };
+    if let Some(issue) = git::issue_number_for_branch(&branch) {
+        log::warn_on_err("local", local::close_local_issue(repo, issue));
+    }
+    Ok(())
+}
@@ -0,0 +1,363 @@
+//! Error types for the things octopai drives: the `gh` CLI, git, the local
+//! SQLite store and terminal sessions. Most of them end up in the message
+//! log, so their messages say what to do about the failure where possible.
+
+use std::fmt;
+use std::io;
+use std::process::Output;
+
+/// A failed `gh` invocation or GitHub API call.
+#[derive(Debug)]
+pub enum GhError {
+    /// `gh` is not on `PATH`.
+    NotInstalled,
+    /// `gh` has no valid login for github.com.
+    NotAuthenticated,
+    /// The GitHub API quota is used up.
+    RateLimited,
+    /// GitHub could not be reached.
+    Offline,
+    /// The repository, issue or PR does not exist or is not visible.
+    NotFound(String),
+    /// `gh` succeeded but printed something we could not understand.
+    UnexpectedOutput(String),
+    /// Any other failure, with gh's message.
+    Failed(String),
+}
+
+impl GhError {
+    /// Classify a failure to start `gh`.
+    pub fn spawn(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            GhError::NotInstalled
+        } else {
+            GhError::Failed(err.to_string())
+        }
+    }
+
+    /// Classify a non-zero `gh` exit by what it printed.
+    pub fn from_output(output: &Output) -> Self {
+        Self::from_message(String::from_utf8_lossy(&output.stderr).trim())
+    }
+
+    /// Classify an error message from `gh` or the GraphQL API.
+    pub fn from_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if lower.contains("gh auth login")
+            || lower.contains("not logged in")
+            || lower.contains("http 401")
+            || lower.contains("bad credentials")
+        {
+            GhError::NotAuthenticated
+        } else if lower.contains("rate limit") {
+            GhError::RateLimited
+        } else if lower.contains("could not resolve host")
+            || lower.contains("no such host")
+            || lower.contains("network is unreachable")
+            || lower.contains("connection refused")
+            || lower.contains("i/o timeout")
+        {
+            GhError::Offline
+        } else if lower.contains("could not resolve to")
+            || lower.contains("not found")
+            || lower.contains("http 404")
+        {
+            GhError::NotFound(message.to_string())
+        } else {
+            GhError::Failed(message.to_string())
+        }
+    }
+}
+
+impl fmt::Display for GhError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GhError::NotInstalled => write!(
+                f,
+                "gh is not installed — install it from https://cli.github.com or press L for local mode"
+            ),
+            GhError::NotAuthenticated => {
+                write!(f, "gh is not logged in — run `gh auth login` and refresh")
+            }
+            GhError::RateLimited => write!(
+                f,
+                "GitHub API rate limit reached — refreshes resume once it resets"
+            ),
+            GhError::Offline => write!(f, "Could not reach GitHub — check your connection"),
+            GhError::NotFound(message) => write!(f, "Not found on GitHub: {}", message),
+            GhError::UnexpectedOutput(message) => {
+                write!(f, "Unexpected output from gh: {}", message)
+            }
+            GhError::Failed(message) => write!(f, "gh error: {}", message),
+        }
+    }
+}
+
+/// A failed git operation.
+#[derive(Debug)]
+pub enum GitError {
+    /// `git` is not on `PATH`.
+    NotInstalled,
+    /// octopai was not started inside a git repository.
+    NotARepository,
+    /// The operation stopped on conflicting changes.
+    Conflict { action: String, message: String },
+    /// Any other failure of `action` (e.g. "git pull"), with git's message.
+    Failed { action: String, message: String },
+}
+
+impl GitError {
+    /// Classify a failure to start `git` for `action`.
+    pub fn spawn(action: &str, err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            GitError::NotInstalled
+        } else {
+            GitError::failed(action, err)
+        }
+    }
+
+    /// Classify a non-zero `git` exit by what it printed.
+    pub fn from_output(action: &str, output: &Output) -> Self {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let message = stderr.trim().to_string();
+        if message.contains("not a git repository") {
+            GitError::NotARepository
+        } else if stdout.contains("CONFLICT") || message.contains("CONFLICT") {
+            GitError::Conflict {
+                action: action.to_string(),
+                message: if message.is_empty() {
+                    stdout.trim().to_string()
+                } else {
+                    message
+                },
+            }
+        } else {
+            GitError::Failed {
+                action: action.to_string(),
+                message,
+            }
+        }
+    }
+
+    pub fn failed(action: &str, message: impl fmt::Display) -> Self {
+        GitError::Failed {
+            action: action.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::NotInstalled => write!(f, "git is not installed or not on PATH"),
+            GitError::NotARepository => write!(
+                f,
+                "Not inside a git repository — start octopai from your project's checkout"
+            ),
+            GitError::Conflict { action, message } => write!(
+                f,
+                "{} stopped on conflicts — resolve them in the worktree and commit, or abort: {}",
+                action, message
+            ),
+            GitError::Failed { action, message } => write!(f, "{} failed: {}", action, message),
+        }
+    }
+}
+
+/// A failure reading or writing the local SQLite store.
+#[derive(Debug)]
+pub enum StoreError {
+    /// A database operation failed; `action` says what was being done.
+    Db {
+        action: &'static str,
+        source: rusqlite::Error,
+    },
+    /// A file operation on the store's directory failed.
+    Io {
+        action: &'static str,
+        source: io::Error,
+    },
+    /// The database or an imported file is unreadable.
+    Corrupt(String),
+    /// The database was written by a newer octopai.
+    TooNew { found: usize, supported: usize },
+    /// The issue or PR does not exist.
+    NotFound(String),
+    /// The change does not apply to the record's current state.
+    Rejected(String),
+}
+
+impl StoreError {
+    /// Wrap a database error for `action`, e.g. `map_err(StoreError::db("read local PRs"))`.
+    /// Damaged database files are reported as [`StoreError::Corrupt`].
+    pub fn db(action: &'static str) -> impl FnOnce(rusqlite::Error) -> StoreError {
+        move |source| match source.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase) => {
+                StoreError::Corrupt(source.to_string())
+            }
+            _ => StoreError::Db { action, source },
+        }
+    }
+
+    /// Wrap a file error for `action`.
+    pub fn io(action: &'static str) -> impl FnOnce(io::Error) -> StoreError {
+        move |source| StoreError::Io { action, source }
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Db { action, source } => write!(f, "Failed to {}: {}", action, source),
+            StoreError::Io { action, source } => write!(f, "Failed to {}: {}", action, source),
+            StoreError::Corrupt(detail) => write!(
+                f,
+                "The local store is damaged ({}) — restore it from an `octopai export` backup or move it aside to start fresh",
+                detail
+            ),
+            StoreError::TooNew { found, supported } => write!(
+                f,
+                "Local store schema v{} is newer than this octopai supports (v{}) — upgrade octopai",
+                found, supported
+            ),
+            StoreError::NotFound(what) => write!(f, "{} not found", what),
+            StoreError::Rejected(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// A failure starting or driving a terminal session.
+#[derive(Debug)]
+pub enum SessionError {
+    /// The configured multiplexer is not on `PATH`.
+    MultiplexerMissing(&'static str),
+    /// Any other failure, with the tool's message.
+    Failed(String),
+}
+
+impl SessionError {
+    /// Classify a failure to start the multiplexer `mux`.
+    pub fn spawn(mux: &'static str, err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            SessionError::MultiplexerMissing(mux)
+        } else {
+            SessionError::Failed(format!("Failed to run {}: {}", mux, err))
+        }
+    }
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::MultiplexerMissing(name) => write!(
+                f,
+                "{} is not installed — install it or choose another multiplexer with C",
+                name
+            ),
+            SessionError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Any of the above, for flows that span several of them (creating a
+/// worktree and session, syncing the local store to GitHub, ...).
+#[derive(Debug)]
+pub enum Error {
+    Gh(GhError),
+    Git(GitError),
+    Store(StoreError),
+    Session(SessionError),
+    /// A local file octopai manages (prompt files, config) could not be written.
+    Io(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Gh(e) => e.fmt(f),
+            Error::Git(e) => e.fmt(f),
+            Error::Store(e) => e.fmt(f),
+            Error::Session(e) => e.fmt(f),
+            Error::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<GhError> for Error {
+    fn from(e: GhError) -> Self {
+        Error::Gh(e)
+    }
+}
+
+impl From<GitError> for Error {
+    fn from(e: GitError) -> Self {
+        Error::Git(e)
+    }
+}
+
+impl From<StoreError> for Error {
+    fn from(e: StoreError) -> Self {
+        Error::Store(e)
+    }
+}
+
+impl From<SessionError> for Error {
+    fn from(e: SessionError) -> Self {
+        Error::Session(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    #[test]
+    fn gh_messages_are_classified() {
+        assert!(matches!(
+            GhError::from_message("To get started with GitHub CLI, please run:  gh auth login"),
+            GhError::NotAuthenticated
+        ));
+        assert!(matches!(
+            GhError::from_message("GraphQL: API rate limit exceeded for user ID 1."),
+            GhError::RateLimited
+        ));
+        assert!(matches!(
+            GhError::from_message(
+                "error connecting to api.github.com: dial tcp: lookup api.github.com: no such host"
+            ),
+            GhError::Offline
+        ));
+        assert!(matches!(
+            GhError::from_message(
+                "GraphQL: Could not resolve to a Repository with the name 'a/b'."
+            ),
+            GhError::NotFound(_)
+        ));
+        assert!(matches!(
+            GhError::from_message("something else"),
+            GhError::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn git_conflicts_are_told_apart_from_other_failures() {
+        let output = |stdout: &str, stderr: &str| Output {
+            status: ExitStatus::from_raw(1 << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        };
+        assert!(matches!(
+            GitError::from_output(
+                "git merge",
+                &output("CONFLICT (content): Merge conflict in a.rs", "")
+            ),
+            GitError::Conflict { .. }
+        ));
+        let err = GitError::from_output("git push", &output("", "rejected"));
+        assert_eq!(err.to_string(), "git push failed: rejected");
+    }
+}
@@ -1,12 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::process::Command;
+use std::process::{Command, Output};
 
-use ratatui::style::Color;
-
-use crate::github::fetch_merged_pr_branches;
-use crate::models::Card;
+use crate::error::{Error, GitError};
+use crate::models::{Card, WorktreeStatus};
+use crate::repo_state;
 use crate::session::Multiplexer;
+use crate::theme;
 
 pub fn get_repo_name(repo: &str) -> &str {
     repo.split('/').next_back().unwrap_or(repo)
@@ -20,6 +20,125 @@ pub fn extract_issue_number(s: &str) -> Option<u64> {
         .and_then(|n| n.parse().ok())
 }
 
+/// Git config variables under `branch.<name>` recording the issue a branch
+/// works on and the branch it was started from. They live in the repo's git
+/// config, so they follow the branch through `git branch -m` and are dropped
+/// by `git branch -D`.
+const ISSUE_LINK_KEY: &str = "octopai-issue";
+const BASE_KEY: &str = "octopai-base";
+
+fn set_branch_config(branch: &str, key: &str, value: &str) -> Result<(), GitError> {
+    repo_state::set_branch_config(branch, key, value).map_err(|e| GitError::failed("git config", e))
+}
+
+/// Run `git` for `action` (e.g. "git pull"), failing with git's message.
+fn run_git(action: &str, args: &[&str]) -> Result<Output, GitError> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| GitError::spawn(action, e))?;
+    repo_state::invalidate();
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(GitError::from_output(action, &output))
+    }
+}
+
+/// Every branch's value for `branch.<name>.<key>`, keyed by branch.
+fn fetch_branch_config(key: &str) -> HashMap<String, String> {
+    repo_state::with_snapshot(|s| s.branch_config.get(key).cloned())
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Record that `branch` works on the issue card `issue_key` ("issue-N" or
+/// "local-issue-N").
+pub fn link_branch_to_issue(branch: &str, issue_key: &str) -> Result<(), GitError> {
+    set_branch_config(branch, ISSUE_LINK_KEY, issue_key)
+}
+
+/// All recorded branch → issue key links.
+pub fn fetch_issue_links() -> HashMap<String, String> {
+    fetch_branch_config(ISSUE_LINK_KEY)
+}
+
+/// Record the base branch `branch` was started from; its PR targets it.
+pub fn set_branch_base(branch: &str, base: &str) -> Result<(), GitError> {
+    set_branch_config(branch, BASE_KEY, base)
+}
+
+/// Branches started from `branch`, i.e. stacked directly on it.
+pub fn stack_children(branch: &str) -> Vec<String> {
+    let mut children: Vec<String> = fetch_branch_config(BASE_KEY)
+        .into_iter()
+        .filter(|(_, base)| base == branch)
+        .map(|(child, _)| child)
+        .collect();
+    children.sort();
+    children
+}
+
+/// The branch new work starts from and PRs target by default: the repo's
+/// configured base branch, else the branch `origin/HEAD` points at, else
+/// `main` or `master`.
+pub fn default_base_branch(repo: &str) -> Option<String> {
+    crate::config::get_base_branch(repo)
+        .or_else(origin_head_branch)
+        .or_else(|| {
+            ["main", "master"]
+                .into_iter()
+                .find(|branch| branch_exists(branch))
+                .map(String::from)
+        })
+}
+
+/// The branch `origin/HEAD` points at, e.g. `main`.
+pub fn origin_head_branch() -> Option<String> {
+    repo_state::with_snapshot(|s| s.origin_head.clone()).flatten()
+}
+
+/// The base `branch` was started from, falling back to the repo default.
+pub fn base_for_branch(repo: &str, branch: &str) -> Option<String> {
+    fetch_branch_config(BASE_KEY)
+        .remove(branch)
+        .or_else(|| default_base_branch(repo))
+}
+
+/// Whether `base` exists as a local branch or on origin.
+pub fn base_exists(base: &str) -> bool {
+    repo_state::with_snapshot(|s| {
+        s.local_branches.contains_key(base) || s.origin_branches.contains_key(base)
+    })
+    .unwrap_or(false)
+}
+
+/// The ref to branch from and compare against for `base`: the local branch,
+/// or origin's copy when there is no local one (e.g. a release branch that
+/// was never checked out).
+pub fn base_ref(base: &str) -> String {
+    if branch_exists(base) {
+        base.to_string()
+    } else {
+        format!("origin/{}", base)
+    }
+}
+
+/// The issue key a branch works on: its recorded link, or for branches
+/// created before links existed, the branch name itself if it is
+/// "issue-N" / "local-issue-N".
+pub fn issue_key_for_branch(links: &HashMap<String, String>, branch: &str) -> Option<String> {
+    if let Some(key) = links.get(branch) {
+        return Some(key.clone());
+    }
+    extract_issue_number(branch).map(|_| branch.to_string())
+}
+
+/// The issue number a branch works on (see [`issue_key_for_branch`]).
+pub fn issue_number_for_branch(branch: &str) -> Option<u64> {
+    issue_key_for_branch(&fetch_issue_links(), branch).and_then(|key| extract_issue_number(&key))
+}
+
 /// Detect the GitHub "owner/repo" for the current working directory by
 /// asking `gh` which repository this directory belongs to.
 pub fn detect_current_repo() -> Option<String> {
@@ -47,200 +166,232 @@ pub fn detect_current_repo() -> Option<String> {
     Some(repo)
 }
 
-pub fn fetch_worktrees() -> Vec<Card> {
-    let output = Command::new("git")
-        .args(["worktree", "list", "--porcelain"])
-        .output();
-
-    let output = match output {
-        Ok(o) if o.status.success() => o,
-        _ => return Vec::new(),
+pub fn fetch_worktrees(repo: &str) -> Vec<Card> {
+    let Some(entries) = repo_state::with_snapshot(|s| s.worktrees.clone()) else {
+        return Vec::new();
     };
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let mut cards = Vec::new();
+    let default_base = default_base_branch(repo);
+    let bases = fetch_branch_config(BASE_KEY);
+    let links = fetch_issue_links();
 
-    for block in stdout.split("\n\n") {
-        let mut path = String::new();
-        let mut branch = String::new();
-        let mut is_bare = false;
-
-        for line in block.lines() {
-            if let Some(p) = line.strip_prefix("worktree ") {
-                path = p.to_string();
-            } else if let Some(b) = line.strip_prefix("branch refs/heads/") {
-                branch = b.to_string();
-            } else if line == "bare" {
-                is_bare = true;
-            }
-        }
-
-        if path.is_empty() || is_bare {
-            continue;
-        }
+    // Linked worktrees only — the main one is where we run from
+    for entry in entries {
+        let path = entry.path;
+        let branch = entry.branch.unwrap_or_default();
 
         let display_name = if branch.is_empty() {
             path.split('/').next_back().unwrap_or(&path).to_string()
         } else {
             branch.clone()
         };
-
-        // Skip the main/master worktree — that's where we run from
-        let is_main = display_name == "main" || display_name == "master";
-        if is_main {
-            continue;
-        }
         let tag = "branch";
-        let tag_color = Color::Yellow;
+        let tag_color = theme::current().warning;
+
+        // Link the worktree to its issue card
+        let related: Vec<String> = issue_key_for_branch(&links, &display_name)
+            .into_iter()
+            .collect();
 
-        // Link issue-N / local-issue-N worktrees to issue cards
-        let related = if display_name.starts_with("local-issue-") {
-            vec![display_name.clone()]
-        } else if let Some(num) = display_name.strip_prefix("issue-") {
-            vec![format!("issue-{}", num)]
+        let base = bases.get(&branch).or(default_base.as_ref());
+        let mut worktree_status = match base {
+            Some(base) if !branch.is_empty() => fetch_worktree_status(&base_ref(base), &branch),
+            _ => WorktreeStatus::default(),
+        };
+        fetch_worktree_activity(&path, &mut worktree_status);
+        let (tag, tag_color) = if worktree_status.conflicts == Some(true) {
+            ("conflict", theme::current().error)
         } else {
-            Vec::new()
+            (tag, tag_color)
         };
 
         cards.push(Card {
             id: format!("wt-{}", display_name),
             title: display_name,
             description: path,
-            full_description: None,
             tag: tag.to_string(),
             tag_color,
             related,
-            url: None,
-            pr_number: None,
-            is_draft: None,
-            is_merged: None,
-            head_branch: None,
-            is_assigned: None,
+            head_branch: (!branch.is_empty()).then(|| branch.clone()),
+            worktree_status: Some(worktree_status),
+            ..Default::default()
         });
     }
 
-    cards
+    crate::models::order_stacks(cards, &bases)
 }
 
-pub fn remove_worktree(
-    path: &str,
-    branch: &str,
-    mux: Multiplexer,
-) -> std::result::Result<(), String> {
-    // Kill session if it exists (named after branch)
-    mux.kill_session(branch);
+/// Compute ahead/behind counts of `branch` against `base` and whether merging
+/// `base` into it would conflict.
+pub fn fetch_worktree_status(base: &str, branch: &str) -> WorktreeStatus {
+    let mut status = WorktreeStatus::default();
+    if let Some(d) = repo_state::divergence(base, branch) {
+        status.ahead = d.ahead;
+        status.behind = d.behind;
+        status.conflicts = d.conflicts;
+    }
+    status
+}
 
-    let output = Command::new("git")
-        .args(["worktree", "remove", "--force", path])
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
+/// Fill in uncommitted changes, untracked files and the last commit for the
+/// worktree checked out at `path`.
+pub fn fetch_worktree_activity(path: &str, status: &mut WorktreeStatus) {
+    repo_state::worktree_activity(path, status);
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("git worktree remove error: {}", stderr.trim()));
+/// How to bring the base branch into a worktree branch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpdateStrategy {
+    Rebase,
+    Merge,
+}
+
+impl UpdateStrategy {
+    pub fn label(self) -> &'static str {
+        match self {
+            UpdateStrategy::Rebase => "rebase",
+            UpdateStrategy::Merge => "merge",
+        }
     }
+}
 
-    // Delete the branch
-    let _ = Command::new("git").args(["branch", "-D", branch]).output();
+/// Result of updating a worktree branch from its base.
+#[derive(Debug)]
+pub enum UpdateOutcome {
+    Updated,
+    /// The rebase or merge stopped on these conflicting files and was left
+    /// in progress.
+    Conflicts(Vec<String>),
+}
 
-    Ok(())
+/// Rebase the worktree at `path` onto `base`, or merge `base` into it.
+///
+/// On conflicts the operation is left in progress so it can be resolved in
+/// the worktree (e.g. by the agent); call [`abort_update`] to back out.
+pub fn update_from_base(
+    path: &str,
+    base: &str,
+    strategy: UpdateStrategy,
+) -> Result<UpdateOutcome, GitError> {
+    let base = base_ref(base);
+    let mut args = vec!["-C", path];
+    match strategy {
+        UpdateStrategy::Rebase => args.extend(["rebase", base.as_str()]),
+        UpdateStrategy::Merge => args.extend(["merge", "--no-edit", base.as_str()]),
+    }
+    run_update(path, &args, strategy)
 }
 
-pub fn cleanup_merged_worktrees(repo: &str, worktrees: &[Card], mux: Multiplexer) -> Vec<String> {
-    let merged_branches = fetch_merged_pr_branches(repo);
-    if merged_branches.is_empty() {
-        return Vec::new();
+/// Replay the commits the worktree at `path` has on top of `upstream` onto
+/// `onto` (`git rebase --onto`). Used to move a stacked branch off a parent
+/// that has been merged into `onto`.
+pub fn rebase_onto(path: &str, onto: &str, upstream: &str) -> Result<UpdateOutcome, GitError> {
+    let onto = base_ref(onto);
+    let args = ["-C", path, "rebase", "--onto", onto.as_str(), upstream];
+    run_update(path, &args, UpdateStrategy::Rebase)
+}
+
+fn run_update(
+    path: &str,
+    args: &[&str],
+    strategy: UpdateStrategy,
+) -> Result<UpdateOutcome, GitError> {
+    let action = format!("git {}", strategy.label());
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| GitError::spawn(&action, e))?;
+    repo_state::invalidate();
+    if output.status.success() {
+        return Ok(UpdateOutcome::Updated);
     }
 
-    let merged_set: HashSet<&str> = merged_branches.iter().map(|s| s.as_str()).collect();
-    let mut cleaned = Vec::new();
-
-    for wt in worktrees {
-        // worktree title is the branch name, description is the path
-        if merged_set.contains(wt.title.as_str())
-            && remove_worktree(&wt.description, &wt.title, mux).is_ok()
-        {
-            cleaned.push(wt.title.clone());
-        }
+    let conflicts = conflicted_files(path);
+    if conflicts.is_empty() {
+        // Failed for another reason (e.g. uncommitted changes); nothing to resolve
+        abort_update(path, strategy);
+        return Err(GitError::from_output(&action, &output));
     }
+    Ok(UpdateOutcome::Conflicts(conflicts))
+}
 
-    cleaned
+/// Abort an in-progress rebase or merge in the worktree at `path`.
+pub fn abort_update(path: &str, strategy: UpdateStrategy) {
+    let action = format!("git {} --abort", strategy.label());
+    crate::log::warn_on_err(
+        "git",
+        run_git(&action, &["-C", path, strategy.label(), "--abort"]),
+    );
 }
 
-/// Pull the latest changes for the local main/master branch from origin.
-/// Returns Ok(branch_name) on success or Err(message) on failure.
-pub fn pull_main() -> std::result::Result<String, String> {
-    // Determine main branch name
-    let branch = if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/main"])
+fn conflicted_files(path: &str) -> Vec<String> {
+    Command::new("git")
+        .args(["-C", path, "diff", "--name-only", "--diff-filter=U"])
         .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "main"
-    } else if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/master"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "master"
-    } else {
-        return Err("Could not determine main branch".to_string());
-    };
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
-    let output = Command::new("git")
-        .args(["pull", "origin", branch])
-        .output()
-        .map_err(|e| format!("Failed to run git pull: {}", e))?;
+pub fn remove_worktree(path: &str, branch: &str, mux: Multiplexer) -> Result<(), GitError> {
+    // Kill session if it exists (named after branch)
+    if mux.list_sessions().iter().any(|s| s == branch) {
+        crate::log::warn_on_err("session", mux.kill_session(branch));
+    }
 
-    if output.status.success() {
-        Ok(branch.to_string())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("git pull failed: {}", stderr.trim()))
+    run_git(
+        "git worktree remove",
+        &["worktree", "remove", "--force", path],
+    )?;
+
+    // Delete the branch
+    crate::log::warn_on_err("git", run_git("git branch -D", &["branch", "-D", branch]));
+
+    Ok(())
+}
+
+/// Worktrees removed (or kept) after their branch was merged.
+#[derive(Debug)]
+pub struct MergedCleanup {
+    pub cleaned: Vec<String>,
+    /// Merged branches kept because other branches are stacked on them;
+    /// those need restacking before the parent can go.
+    pub held: Vec<String>,
+}
+
+/// Pull the latest changes for the local `base` branch from origin.
+pub fn pull_base_branch(base: &str) -> Result<(), GitError> {
+    if !branch_exists(base) {
+        return Err(GitError::failed(
+            "git pull",
+            format!("no local '{}' branch to pull", base),
+        ));
     }
+
+    run_git("git pull", &["pull", "origin", base])?;
+    Ok(())
 }
 
-/// Check how many commits the local main/master branch is behind its remote tracking branch.
+/// Check how many commits the local `base` branch is behind its remote tracking branch.
 /// Runs `git fetch` first to ensure we have the latest remote state.
-pub fn fetch_main_behind_count() -> usize {
-    // Fetch latest from remote (quiet, don't fail if offline)
-    let _ = Command::new("git").args(["fetch", "--quiet"]).output();
-
-    // Determine main branch name
-    let branch = if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/main"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "main"
-    } else if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/master"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "master"
-    } else {
-        return 0;
-    };
+pub fn fetch_base_behind_count(base: &str) -> usize {
+    // Fetch latest from remote; offline just means the count may be old
+    if let Err(e) = run_git("git fetch", &["fetch", "--quiet"]) {
+        crate::log::warn("git", e);
+    }
 
     // Count commits that are on the remote but not on the local branch
-    let local = format!("refs/heads/{}", branch);
-    let remote = format!("refs/remotes/origin/{}", branch);
-    let output = Command::new("git")
-        .args(["rev-list", "--count", &format!("{}..{}", local, remote)])
-        .output();
-
-    match output {
-        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout)
-            .trim()
-            .parse::<usize>()
-            .unwrap_or(0),
-        _ => 0,
+    if !branch_exists(base) {
+        return 0;
     }
+    repo_state::divergence(base, &format!("origin/{}", base))
+        .map(|d| d.ahead)
+        .unwrap_or(0)
 }
 
 /// Detect the repository name from the git remote origin URL.
@@ -284,161 +435,140 @@ fn parse_repo_from_url(url: &str) -> Option<String> {
     }
 }
 
-/// Merge a branch into the current branch (main/master) using git merge.
+/// Merge `branch` into `base` in the worktree that has `base` checked out.
 /// Used for local PR merging when not connected to GitHub.
-pub fn merge_branch(branch: &str) -> std::result::Result<(), String> {
-    let output = Command::new("git")
-        .args(["merge", branch])
-        .output()
-        .map_err(|e| format!("Failed to run git merge: {}", e))?;
+pub fn merge_branch(branch: &str, base: &str) -> Result<(), GitError> {
+    let path = worktree_path_for_branch(base).ok_or_else(|| {
+        GitError::failed(
+            "git merge",
+            format!("check out '{}' in a worktree to merge into it", base),
+        )
+    })?;
+    run_git("git merge", &["-C", &path, "merge", branch])?;
+    Ok(())
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("git merge failed: {}", stderr.trim()))
-    }
+/// Path of the worktree (including the main one) that has `branch` checked out.
+fn worktree_path_for_branch(branch: &str) -> Option<String> {
+    repo_state::with_snapshot(|s| {
+        s.main
+            .iter()
+            .chain(&s.worktrees)
+            .find(|wt| wt.branch.as_deref() == Some(branch))
+            .map(|wt| wt.path.clone())
+    })
+    .flatten()
 }
 
-/// Check if a branch has any commits ahead of main/master.
-/// Used to detect if Claude has finished work on a local branch.
-pub fn branch_has_commits(branch: &str) -> bool {
-    // Determine main branch name
-    let main_branch = if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/main"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "main"
-    } else if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/master"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "master"
-    } else {
-        return false;
-    };
+/// Check whether a local branch exists.
+pub fn branch_exists(branch: &str) -> bool {
+    repo_state::with_snapshot(|s| s.local_branches.contains_key(branch)).unwrap_or(false)
+}
 
-    let output = Command::new("git")
-        .args([
-            "rev-list",
-            "--count",
-            &format!("{}..{}", main_branch, branch),
-        ])
-        .output();
-
-    match output {
-        Ok(o) if o.status.success() => {
-            let count: usize = String::from_utf8_lossy(&o.stdout)
-                .trim()
-                .parse()
-                .unwrap_or(0);
-            count > 0
-        }
-        _ => false,
-    }
+/// Rename a local branch. Works for branches checked out in a worktree.
+pub fn rename_branch(old: &str, new: &str) -> Result<(), GitError> {
+    run_git("git branch -m", &["branch", "-m", old, new])?;
+    Ok(())
 }
 
-/// Get the first commit message on a branch (ahead of main/master).
-/// Used to generate a PR title for auto-created local PRs.
-pub fn first_commit_summary(branch: &str) -> Option<String> {
-    let main_branch = if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/main"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "main"
-    } else if Command::new("git")
-        .args(["rev-parse", "--verify", "refs/heads/master"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
-        "master"
-    } else {
-        return None;
-    };
+/// Move a worktree to a new path.
+pub fn move_worktree(path: &str, new_path: &str) -> Result<(), GitError> {
+    run_git("git worktree move", &["worktree", "move", path, new_path])?;
+    Ok(())
+}
 
-    let output = Command::new("git")
-        .args([
-            "log",
-            "--format=%s",
-            "--reverse",
-            &format!("{}..{}", main_branch, branch),
-        ])
-        .output()
-        .ok()?;
+/// Push a branch to origin and set it as the upstream.
+pub fn push_branch(branch: &str) -> Result<(), GitError> {
+    run_git("git push", &["push", "-u", "origin", branch])?;
+    Ok(())
+}
 
-    if !output.status.success() {
-        return None;
-    }
+/// Check if a branch has any commits ahead of its base.
+/// Used to detect if Claude has finished work on a local branch.
+pub fn branch_has_commits(repo: &str, branch: &str) -> bool {
+    let Some(base) = base_for_branch(repo, branch) else {
+        return false;
+    };
+    repo_state::divergence(&base_ref(&base), branch).is_some_and(|d| d.ahead > 0)
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    stdout.lines().next().map(|s| s.to_string())
+/// Get the first commit message on a branch (ahead of its base).
+/// Used to generate a PR title for auto-created local PRs.
+pub fn first_commit_summary(repo: &str, branch: &str) -> Option<String> {
+    let base = base_for_branch(repo, branch)?;
+    repo_state::commits_between(&base_ref(&base), branch)
+        .into_iter()
+        .next()
 }
 
-/// Clean up worktrees whose branches have been locally merged.
-pub fn cleanup_local_merged_worktrees(
+/// Clean up worktrees, given as branch and path, whose branches have been
+/// merged.
+pub fn cleanup_merged_worktrees(
     merged_branches: &[String],
-    worktrees: &[Card],
+    worktrees: &[(String, String)],
     mux: Multiplexer,
-) -> Vec<String> {
+) -> MergedCleanup {
+    let mut result = MergedCleanup {
+        cleaned: Vec::new(),
+        held: Vec::new(),
+    };
     if merged_branches.is_empty() {
-        return Vec::new();
+        return result;
     }
 
     let merged_set: HashSet<&str> = merged_branches.iter().map(|s| s.as_str()).collect();
-    let mut cleaned = Vec::new();
-
-    for wt in worktrees {
-        if merged_set.contains(wt.title.as_str())
-            && remove_worktree(&wt.description, &wt.title, mux).is_ok()
-        {
-            cleaned.push(wt.title.clone());
+    for (branch, path) in worktrees {
+        if !merged_set.contains(branch.as_str()) {
+            continue;
+        }
+        if !stack_children(branch).is_empty() {
+            result.held.push(branch.clone());
+        } else {
+            match remove_worktree(path, branch, mux) {
+                Ok(()) => result.cleaned.push(branch.clone()),
+                Err(e) => crate::log::warn("git", e),
+            }
         }
     }
 
-    cleaned
+    result
 }
 
-pub fn trust_directory(path: &str) -> std::result::Result<(), String> {
+pub fn trust_directory(path: &str) -> Result<(), Error> {
     let claude_json = dirs::home_dir()
-        .ok_or("Could not find home directory")?
+        .ok_or_else(|| Error::Io("Could not find home directory".to_string()))?
         .join(".claude.json");
 
     let mut config: serde_json::Value = if claude_json.exists() {
         let data = fs::read_to_string(&claude_json)
-            .map_err(|e| format!("Failed to read .claude.json: {}", e))?;
-        serde_json::from_str(&data).map_err(|e| format!("Failed to parse .claude.json: {}", e))?
+            .map_err(|e| Error::Io(format!("Failed to read .claude.json: {}", e)))?;
+        serde_json::from_str(&data)
+            .map_err(|e| Error::Io(format!("Failed to parse .claude.json: {}", e)))?
     } else {
         serde_json::json!({})
     };
 
     let abs_path = fs::canonicalize(path)
-        .map_err(|e| format!("Failed to resolve path: {}", e))?
+        .map_err(|e| Error::Io(format!("Failed to resolve path: {}", e)))?
         .to_string_lossy()
         .to_string();
 
     let projects = config
         .as_object_mut()
-        .ok_or("Invalid .claude.json format")?
+        .ok_or_else(|| Error::Io("Invalid .claude.json format".to_string()))?
         .entry("projects")
         .or_insert_with(|| serde_json::json!({}));
 
     let project = projects
         .as_object_mut()
-        .ok_or("Invalid projects format")?
+        .ok_or_else(|| Error::Io("Invalid projects format in .claude.json".to_string()))?
         .entry(&abs_path)
         .or_insert_with(|| serde_json::json!({}));
 
     project["hasTrustDialogAccepted"] = serde_json::json!(true);
 
     fs::write(&claude_json, serde_json::to_string_pretty(&config).unwrap())
-        .map_err(|e| format!("Failed to write .claude.json: {}", e))?;
+        .map_err(|e| Error::Io(format!("Failed to write .claude.json: {}", e)))?;
 
     Ok(())
 }
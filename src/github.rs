@@ -1,10 +1,18 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 use std::process::Command;
 
-use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
 
-use crate::models::{label_color, AssigneeFilter, Card, StateFilter};
+use crate::error::GhError;
+use crate::models::{
+    short_description, AssigneeFilter, Card, CheckStatus, IssueMetadata, IssueOptions,
+    MergeStrategy, StateFilter,
+};
+use crate::theme;
 
-pub fn fetch_repos(owner: &str) -> std::result::Result<Vec<String>, String> {
+pub fn fetch_repos(owner: &str) -> Result<Vec<String>, GhError> {
     let output = Command::new("gh")
         .args([
             "repo",
@@ -18,11 +26,10 @@ pub fn fetch_repos(owner: &str) -> std::result::Result<Vec<String>, String> {
             ".[].nameWithOwner",
         ])
         .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
+        .map_err(GhError::spawn)?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh error: {}", stderr.trim()));
+        return Err(GhError::from_output(&output));
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -33,213 +40,602 @@ pub fn fetch_repos(owner: &str) -> std::result::Result<Vec<String>, String> {
         .collect();
 
     if repos.is_empty() {
-        return Err(format!("No repos found for '{}'", owner));
+        return Err(GhError::NotFound(format!("No repos found for '{}'", owner)));
     }
 
     Ok(repos)
 }
 
-pub fn fetch_issues(
+/// Most issues or PRs fetched per column by a refresh; search results stop
+/// at 1000.
+const MAX_BOARD_ITEMS: usize = 500;
+
+const BOARD_QUERY: &str = r#"
+query($issues: String!, $prs: String!, $merged: String!,
+      $issueCursor: String, $prCursor: String,
+      $withIssues: Boolean!, $withPrs: Boolean!, $withMerged: Boolean!) {
+  rateLimit { limit remaining resetAt cost }
+  issues: search(query: $issues, type: ISSUE, first: 100, after: $issueCursor) @include(if: $withIssues) {
+    issueCount
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      ... on Issue {
+        number title body state updatedAt
+        milestone { title }
+        labels(first: 10) { nodes { name } }
+        assignees(first: 10) { nodes { login } }
+      }
+    }
+  }
+  prs: search(query: $prs, type: ISSUE, first: 100, after: $prCursor) @include(if: $withPrs) {
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      ... on PullRequest {
+        number title body isDraft url headRefName baseRefName state mergedAt updatedAt
+        milestone { title }
+        labels(first: 10) { nodes { name } }
+        assignees(first: 10) { totalCount nodes { login } }
+        commits(last: 1) { nodes { commit { statusCheckRollup { state } } } }
+      }
+    }
+  }
+  merged: search(query: $merged, type: ISSUE, first: 100) @include(if: $withMerged) {
+    nodes { ... on PullRequest { headRefName } }
+  }
+}
+"#;
+
+/// GraphQL API quota as of the last query.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimit {
+    pub remaining: u64,
+    pub limit: u64,
+    /// Unix timestamp when the quota resets.
+    pub reset_at: u64,
+}
+
+impl RateLimit {
+    /// Whether the quota is used up and has not reset yet.
+    pub fn exhausted(&self) -> bool {
+        self.remaining == 0 && now_secs() < self.reset_at
+    }
+
+    /// Minutes until the quota resets.
+    pub fn minutes_to_reset(&self) -> u64 {
+        self.reset_at.saturating_sub(now_secs()).div_ceil(60)
+    }
+}
+
+/// Everything a board refresh needs from GitHub.
+pub struct Board {
+    pub issues: Vec<Card>,
+    /// The search the issues were found with.
+    pub issue_search: Option<String>,
+    /// How many issues the search found, loaded or not.
+    pub issue_total: Option<usize>,
+    /// Where the next page of issues starts, if there is one.
+    pub issue_cursor: Option<String>,
+    pub prs: Vec<Card>,
+    /// Head branches of recently merged PRs, for cleaning up worktrees.
+    pub merged_branches: Vec<String>,
+    pub rate_limit: Option<RateLimit>,
+    /// Unix timestamp of when the data was fetched from GitHub.
+    pub fetched_at: u64,
+}
+
+/// Which columns to fetch and how they are filtered.
+pub struct BoardQuery<'a> {
+    pub repo: &'a str,
+    /// `None` skips issues.
+    pub issues: Option<(StateFilter, AssigneeFilter, Option<&'a str>)>,
+    /// Issues to fetch at least, paging on as needed (up to
+    /// `MAX_BOARD_ITEMS`). The first page is always fetched.
+    pub issue_limit: usize,
+    /// `None` skips PRs.
+    pub prs: Option<(StateFilter, AssigneeFilter, Option<&'a str>)>,
+    pub merged: bool,
+}
+
+/// Fetch issues, PRs (with check status) and merged PR branches with one
+/// `gh api graphql` call, following cursors for columns with more pages
+/// while the rate limit allows.
+pub fn fetch_board(query: &BoardQuery) -> Result<Board, GhError> {
+    let (issue_search, pr_search) = query.searches();
+    let merged_search = format!("repo:{} is:pr is:merged sort:updated-desc", query.repo);
+
+    let mut issues = Vec::new();
+    let mut prs = Vec::new();
+    let mut merged_branches = Vec::new();
+    let mut rate_limit = None;
+    let mut issue_cursor: Option<String> = None;
+    let mut issue_total = None;
+    let mut pr_cursor: Option<String> = None;
+    let mut with_issues = issue_search.is_some();
+    let mut with_prs = pr_search.is_some();
+    let mut with_merged = query.merged;
+
+    while with_issues || with_prs || with_merged {
+        let data = run_board_query(
+            issue_search.as_deref().unwrap_or(""),
+            pr_search.as_deref().unwrap_or(""),
+            &merged_search,
+            (with_issues, issue_cursor.as_deref()),
+            (with_prs, pr_cursor.as_deref()),
+            with_merged,
+        )?;
+
+        let cost = data["rateLimit"]["cost"].as_u64().unwrap_or(1);
+        rate_limit = parse_rate_limit(&data["rateLimit"]).or(rate_limit);
+
+        if with_issues {
+            let page = &data["issues"];
+            issues.extend(search_nodes(page));
+            issue_cursor = next_cursor(page);
+            issue_total = search_total(page).or(issue_total);
+            with_issues =
+                issue_cursor.is_some() && issues.len() < query.issue_limit.min(MAX_BOARD_ITEMS);
+        }
+        if with_prs {
+            let page = &data["prs"];
+            prs.extend(search_nodes(page));
+            pr_cursor = next_cursor(page);
+            with_prs = pr_cursor.is_some() && prs.len() < MAX_BOARD_ITEMS;
+        }
+        if with_merged {
+            merged_branches = search_nodes(&data["merged"])
+                .iter()
+                .filter_map(|pr| pr["headRefName"].as_str().map(String::from))
+                .collect();
+            with_merged = false;
+        }
+
+        // Leave quota for the rest of the session rather than paging on
+        if rate_limit.is_some_and(|r| r.remaining < cost * 2) {
+            break;
+        }
+    }
+
+    let fetched_at = now_secs();
+    save_board_cache(
+        query.repo,
+        issue_search.clone().map(|q| (q, issues.clone())),
+        pr_search.map(|q| (q, prs.clone())),
+        query.merged.then(|| merged_branches.clone()),
+        fetched_at,
+    );
+    let mut board = build_board(&issues, &prs, merged_branches, rate_limit, fetched_at);
+    board.issue_search = issue_search;
+    board.issue_total = issue_total;
+    board.issue_cursor = issue_cursor;
+    Ok(board)
+}
+
+/// A further page of an issue search.
+#[derive(Debug)]
+pub struct IssuePage {
+    pub issues: Vec<Card>,
+    pub total: Option<usize>,
+    /// Where the page after this one starts, if there is one.
+    pub cursor: Option<String>,
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// The page of the issue `search` (as built for a [`BoardQuery`]) that
+/// starts at `cursor`.
+pub fn fetch_issue_page(search: &str, cursor: &str) -> Result<IssuePage, GhError> {
+    let data = run_board_query(search, "", "", (true, Some(cursor)), (false, None), false)?;
+    let page = &data["issues"];
+    Ok(IssuePage {
+        issues: search_nodes(page).iter().map(issue_card).collect(),
+        total: search_total(page),
+        cursor: next_cursor(page),
+        rate_limit: parse_rate_limit(&data["rateLimit"]),
+    })
+}
+
+/// Turn GraphQL search nodes into cards, oldest first like the rest of the
+/// board. Issues are searched oldest first, so further pages go at the end;
+/// PRs are searched newest first, so the newest are kept when there are too
+/// many, and turned around.
+fn build_board(
+    issues: &[serde_json::Value],
+    prs: &[serde_json::Value],
+    merged_branches: Vec<String>,
+    rate_limit: Option<RateLimit>,
+    fetched_at: u64,
+) -> Board {
+    let issue_cards: Vec<Card> = issues.iter().map(issue_card).collect();
+    let links = crate::git::fetch_issue_links();
+    // Head branch -> base branch, used to nest stacked PRs under their parent
+    let bases: HashMap<String, String> = prs
+        .iter()
+        .filter_map(|pr| {
+            Some((
+                pr["headRefName"].as_str()?.to_string(),
+                pr["baseRefName"].as_str()?.to_string(),
+            ))
+        })
+        .collect();
+    let mut pr_cards: Vec<Card> = prs.iter().map(|pr| pr_card(pr, &links)).collect();
+    pr_cards.reverse();
+
+    Board {
+        issues: issue_cards,
+        issue_search: None,
+        issue_total: None,
+        issue_cursor: None,
+        prs: crate::models::order_stacks(pr_cards, &bases),
+        merged_branches,
+        rate_limit,
+        fetched_at,
+    }
+}
+
+/// Raw search results from the last successful query per column, so the
+/// board can show something before (or without) the network.
+#[derive(Default, Serialize, Deserialize)]
+struct BoardCache {
+    issues: Option<CachedColumn>,
+    prs: Option<CachedColumn>,
+    #[serde(default)]
+    merged_branches: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedColumn {
+    /// The search the nodes answer; a different filter is a cache miss.
+    query: String,
+    fetched_at: u64,
+    nodes: Vec<serde_json::Value>,
+}
+
+fn board_cache_path(repo: &str) -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("octopai")
+        .join("cache")
+        .join(format!("{}.json", repo.replace('/', "--")))
+}
+
+fn load_board_cache(repo: &str) -> BoardCache {
+    fs::read_to_string(board_cache_path(repo))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Record freshly fetched columns; columns not fetched keep their entry.
+fn save_board_cache(
+    repo: &str,
+    issues: Option<(String, Vec<serde_json::Value>)>,
+    prs: Option<(String, Vec<serde_json::Value>)>,
+    merged_branches: Option<Vec<String>>,
+    fetched_at: u64,
+) {
+    let mut cache = load_board_cache(repo);
+    let column = |(query, nodes)| CachedColumn {
+        query,
+        fetched_at,
+        nodes,
+    };
+    if let Some(issues) = issues {
+        cache.issues = Some(column(issues));
+    }
+    if let Some(prs) = prs {
+        cache.prs = Some(column(prs));
+    }
+    if let Some(merged) = merged_branches {
+        cache.merged_branches = merged;
+    }
+
+    let path = board_cache_path(repo);
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string(&cache).unwrap_or_default()));
+    if let Err(e) = written {
+        crate::log::warn("cache", format!("Could not save {}: {}", path.display(), e));
+    }
+}
+
+/// The last successfully fetched board for `query`, if the cached columns
+/// were fetched with the same filters. `fetched_at` is that of the oldest
+/// column used.
+pub fn load_cached_board(query: &BoardQuery) -> Option<Board> {
+    let cache = load_board_cache(query.repo);
+    let (issue_search, pr_search) = query.searches();
+    let matching = |column: Option<CachedColumn>, search: Option<String>| {
+        column.filter(|c| Some(&c.query) == search.as_ref())
+    };
+    let issues = matching(cache.issues, issue_search);
+    let prs = matching(cache.prs, pr_search);
+    let fetched_at = issues
+        .iter()
+        .chain(prs.iter())
+        .map(|c| c.fetched_at)
+        .min()?;
+    let mut board = build_board(
+        issues.as_ref().map_or(&[], |c| &c.nodes),
+        prs.as_ref().map_or(&[], |c| &c.nodes),
+        cache.merged_branches,
+        None,
+        fetched_at,
+    );
+    board.issue_search = issues.map(|c| c.query);
+    Some(board)
+}
+
+impl BoardQuery<'_> {
+    /// Search strings for the issue and PR columns that are requested.
+    pub fn searches(&self) -> (Option<String>, Option<String>) {
+        let issues = self.issues.map(|(state, assignee, search)| {
+            search_query(self.repo, "issue", state, assignee, search)
+        });
+        let prs = self.prs.map(|(state, assignee, search)| {
+            search_query(self.repo, "pr", state, assignee, search)
+        });
+        (issues, prs)
+    }
+}
+
+/// GitHub search qualifiers for one column: issues oldest first, so pages
+/// load in board order, and PRs newest first, unless `search` sorts
+/// otherwise.
+fn search_query(
     repo: &str,
+    kind: &str,
     state: StateFilter,
     assignee: AssigneeFilter,
     search: Option<&str>,
-) -> Vec<Card> {
-    // When assigned to current user, always fetch all issues.
-    // When searching or viewing all issues, use a smaller limit for speed.
-    let limit = if assignee == AssigneeFilter::Mine {
-        "500"
-    } else {
-        "30"
-    };
-    let mut args = vec![
-        "issue".to_string(),
-        "list".to_string(),
-        "--repo".to_string(),
-        repo.to_string(),
-        "--state".to_string(),
-        state.label().to_string(),
-        "--json".to_string(),
-        "number,title,body,labels,state".to_string(),
-        "--limit".to_string(),
-        limit.to_string(),
-    ];
+) -> String {
+    let search = search.filter(|s| !s.is_empty());
+    let mut q = format!("repo:{} is:{} is:{}", repo, kind, state.label());
+    if !search.is_some_and(|s| s.split_whitespace().any(|t| t.starts_with("sort:"))) {
+        q.push_str(match kind {
+            "issue" => " sort:created-asc",
+            _ => " sort:created-desc",
+        });
+    }
     if assignee == AssigneeFilter::Mine {
-        args.push("--assignee".to_string());
-        args.push("@me".to_string());
+        q.push_str(" assignee:@me");
     }
-    if let Some(query) = search {
-        if !query.is_empty() {
-            args.push("--search".to_string());
-            args.push(query.to_string());
-        }
+    if let Some(search) = search {
+        q.push(' ');
+        q.push_str(search);
     }
-    let output = Command::new("gh").args(&args).output();
+    q
+}
 
-    let output = match output {
-        Ok(o) if o.status.success() => o,
-        _ => return Vec::new(),
-    };
+fn run_board_query(
+    issues: &str,
+    prs: &str,
+    merged: &str,
+    (with_issues, issue_cursor): (bool, Option<&str>),
+    (with_prs, pr_cursor): (bool, Option<&str>),
+    with_merged: bool,
+) -> Result<serde_json::Value, GhError> {
+    let mut args = vec![
+        "api".to_string(),
+        "graphql".to_string(),
+        "-f".to_string(),
+        format!("query={}", BOARD_QUERY),
+        "-f".to_string(),
+        format!("issues={}", issues),
+        "-f".to_string(),
+        format!("prs={}", prs),
+        "-f".to_string(),
+        format!("merged={}", merged),
+        "-F".to_string(),
+        format!("withIssues={}", with_issues),
+        "-F".to_string(),
+        format!("withPrs={}", with_prs),
+        "-F".to_string(),
+        format!("withMerged={}", with_merged),
+    ];
+    for (name, cursor) in [("issueCursor", issue_cursor), ("prCursor", pr_cursor)] {
+        if let Some(cursor) = cursor {
+            args.push("-f".to_string());
+            args.push(format!("{}={}", name, cursor));
+        }
+    }
 
+    let output = Command::new("gh")
+        .args(&args)
+        .output()
+        .map_err(GhError::spawn)?;
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let issues: Vec<serde_json::Value> = match serde_json::from_str(&stdout) {
-        Ok(v) => v,
-        Err(_) => return Vec::new(),
-    };
+    let response: serde_json::Value = serde_json::from_str(&stdout).unwrap_or_default();
 
-    let mut cards: Vec<Card> = issues
-        .into_iter()
-        .map(|issue| {
-            let number = issue["number"].as_u64().unwrap_or(0);
-            let title = issue["title"].as_str().unwrap_or("").to_string();
-            let body = issue["body"].as_str().unwrap_or("").to_string();
-            let full_description = if body.is_empty() {
-                None
-            } else {
-                Some(body.clone())
-            };
-            let description = if body.len() > 80 {
-                format!("{}...", &body[..77])
-            } else if body.is_empty() {
-                "No description".to_string()
-            } else {
-                body
-            };
-
-            let labels = issue["labels"]
-                .as_array()
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|l| l["name"].as_str().map(String::from))
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default();
-
-            let issue_state = issue["state"].as_str().unwrap_or("OPEN").to_uppercase();
-
-            let (tag, tag_color) = if let Some(first) = labels.first() {
-                (first.clone(), label_color(first))
-            } else if issue_state == "CLOSED" {
-                ("closed".to_string(), Color::Red)
-            } else {
-                ("open".to_string(), Color::Green)
-            };
-
-            Card {
-                id: format!("issue-{}", number),
-                title: format!("#{} {}", number, title),
-                description,
-                full_description,
-                tag,
-                tag_color,
-                related: Vec::new(),
-                url: None,
-                pr_number: None,
-                is_draft: None,
-                is_merged: None,
-                head_branch: None,
-                is_assigned: None,
-            }
+    // GraphQL errors (including rate limiting) come back with a non-zero exit
+    if let Some(message) = response["errors"][0]["message"].as_str() {
+        return Err(GhError::from_message(message));
+    }
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+    Ok(response["data"].clone())
+}
+
+fn search_nodes(page: &serde_json::Value) -> Vec<serde_json::Value> {
+    page["nodes"]
+        .as_array()
+        .map(|nodes| {
+            nodes
+                .iter()
+                .filter(|n| n["number"].is_u64() || n["headRefName"].is_string())
+                .cloned()
+                .collect()
         })
-        .collect();
-    // Reverse to show oldest first (gh returns newest first)
-    cards.reverse();
-    cards
+        .unwrap_or_default()
 }
 
-pub fn fetch_prs(repo: &str, state: StateFilter, assignee: AssigneeFilter) -> Vec<Card> {
-    let mut args = vec![
-        "pr".to_string(),
-        "list".to_string(),
-        "--repo".to_string(),
-        repo.to_string(),
-        "--state".to_string(),
-        state.label().to_string(),
-        "--json".to_string(),
-        "number,title,body,isDraft,url,headRefName,state,mergedAt,assignees".to_string(),
-        "--limit".to_string(),
-        "500".to_string(),
-    ];
-    if assignee == AssigneeFilter::Mine {
-        args.push("--assignee".to_string());
-        args.push("@me".to_string());
+fn search_total(page: &serde_json::Value) -> Option<usize> {
+    page["issueCount"].as_u64().map(|n| n as usize)
+}
+
+fn next_cursor(page: &serde_json::Value) -> Option<String> {
+    if page["pageInfo"]["hasNextPage"].as_bool() != Some(true) {
+        return None;
     }
-    let output = Command::new("gh").args(&args).output();
+    page["pageInfo"]["endCursor"].as_str().map(String::from)
+}
+
+fn parse_rate_limit(value: &serde_json::Value) -> Option<RateLimit> {
+    Some(RateLimit {
+        remaining: value["remaining"].as_u64()?,
+        limit: value["limit"].as_u64()?,
+        reset_at: value["resetAt"].as_str().and_then(parse_timestamp)?,
+    })
+}
 
-    let output = match output {
-        Ok(o) if o.status.success() => o,
-        _ => return Vec::new(),
+/// Parse a UTC timestamp like `2024-05-01T12:30:00Z` into Unix seconds.
+fn parse_timestamp(s: &str) -> Option<u64> {
+    let (date, time) = s.trim_end_matches('Z').split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|n| n.parse::<i64>());
+    let (y, m, d) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time.splitn(3, ':').map(|n| n.parse::<i64>());
+    let (hh, mm, ss) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    // Days since 1970-01-01 in the proleptic Gregorian calendar
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    u64::try_from(days * 86400 + hh * 3600 + mm * 60 + ss).ok()
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn issue_card(issue: &serde_json::Value) -> Card {
+    let number = issue["number"].as_u64().unwrap_or(0);
+    let title = issue["title"].as_str().unwrap_or("").to_string();
+    let body = issue["body"].as_str().unwrap_or("").to_string();
+    let full_description = if body.is_empty() {
+        None
+    } else {
+        Some(body.clone())
     };
+    let description = short_description(&body).unwrap_or_else(|| "No description".to_string());
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let prs: Vec<serde_json::Value> = match serde_json::from_str(&stdout) {
-        Ok(v) => v,
-        Err(_) => return Vec::new(),
+    let labels = node_names(&issue["labels"], "name");
+
+    let issue_state = issue["state"].as_str().unwrap_or("OPEN").to_uppercase();
+
+    let (tag, tag_color) = if let Some(first) = labels.first() {
+        (first.clone(), theme::current().label_color(first))
+    } else if issue_state == "CLOSED" {
+        ("closed".to_string(), theme::current().error)
+    } else {
+        ("open".to_string(), theme::current().success)
+    };
+
+    Card {
+        id: format!("issue-{}", number),
+        title: format!("#{} {}", number, title),
+        description,
+        full_description,
+        tag,
+        tag_color,
+        labels,
+        assignees: node_names(&issue["assignees"], "login"),
+        milestone: issue["milestone"]["title"].as_str().map(String::from),
+        updated_at: issue["updatedAt"].as_str().and_then(parse_timestamp),
+        ..Default::default()
+    }
+}
+
+fn pr_card(pr: &serde_json::Value, links: &HashMap<String, String>) -> Card {
+    let number = pr["number"].as_u64().unwrap_or(0);
+    let title = pr["title"].as_str().unwrap_or("").to_string();
+    let body = pr["body"].as_str().unwrap_or("").to_string();
+    let is_draft = pr["isDraft"].as_bool().unwrap_or(false);
+    let url = pr["url"].as_str().unwrap_or("").to_string();
+    let branch = pr["headRefName"].as_str().unwrap_or("").to_string();
+    let is_merged = pr["mergedAt"].as_str().is_some();
+    let is_assigned = pr["assignees"]["totalCount"].as_u64().unwrap_or(0) > 0;
+    let checks = pr["commits"]["nodes"][0]["commit"]["statusCheckRollup"]["state"]
+        .as_str()
+        .and_then(CheckStatus::from_rollup);
+
+    let full_description = Some(body.clone()).filter(|b| !b.is_empty());
+    let description = short_description(&body).unwrap_or_else(|| branch.clone());
+
+    let pr_state = pr["state"].as_str().unwrap_or("OPEN").to_uppercase();
+
+    // Only an open PR can be a draft or ready for review
+    let (tag, tag_color) = if is_merged {
+        ("merged", theme::current().special)
+    } else if pr_state == "CLOSED" {
+        ("closed", theme::current().error)
+    } else if is_draft {
+        ("draft", theme::current().dim)
+    } else {
+        ("ready", theme::current().success)
     };
 
-    let mut cards: Vec<Card> = prs
+    // Link to the issue the head branch works on
+    let related: Vec<String> = crate::git::issue_key_for_branch(links, &branch)
         .into_iter()
-        .map(|pr| {
-            let number = pr["number"].as_u64().unwrap_or(0);
-            let title = pr["title"].as_str().unwrap_or("").to_string();
-            let body = pr["body"].as_str().unwrap_or("").to_string();
-            let is_draft = pr["isDraft"].as_bool().unwrap_or(false);
-            let url = pr["url"].as_str().unwrap_or("").to_string();
-            let branch = pr["headRefName"].as_str().unwrap_or("").to_string();
-            let is_merged = pr["mergedAt"].as_str().is_some();
-            let is_assigned = pr["assignees"]
-                .as_array()
-                .map(|a| !a.is_empty())
-                .unwrap_or(false);
-
-            let description = if body.len() > 80 {
-                format!("{}...", &body[..77])
-            } else if body.is_empty() {
-                branch.clone()
-            } else {
-                body
-            };
-
-            let (tag, tag_color) = if is_draft {
-                ("draft", Color::DarkGray)
-            } else {
-                ("ready", Color::Green)
-            };
-
-            // Link to related issue if branch is issue-N
-            let related = if let Some(num) = branch.strip_prefix("issue-") {
-                vec![format!("issue-{}", num)]
-            } else {
-                Vec::new()
-            };
-
-            Card {
-                id: format!("pr-{}", number),
-                title: format!("#{} {}", number, title),
-                description,
-                full_description: None,
-                tag: tag.to_string(),
-                tag_color,
-                related,
-                url: Some(url),
-                pr_number: Some(number),
-                is_draft: Some(is_draft),
-                is_merged: Some(is_merged),
-                head_branch: Some(branch),
-                is_assigned: Some(is_assigned),
-            }
-        })
         .collect();
-    // Reverse to show oldest first (gh returns newest first)
-    cards.reverse();
-    cards
+
+    Card {
+        id: format!("pr-{}", number),
+        title: format!("#{} {}", number, title),
+        description,
+        full_description,
+        tag: tag.to_string(),
+        tag_color,
+        related,
+        url: Some(url),
+        pr_number: Some(number),
+        is_draft: Some(is_draft),
+        is_merged: Some(is_merged),
+        head_branch: Some(branch),
+        is_assigned: Some(is_assigned),
+        checks,
+        labels: node_names(&pr["labels"], "name"),
+        assignees: node_names(&pr["assignees"], "login"),
+        milestone: pr["milestone"]["title"].as_str().map(String::from),
+        updated_at: pr["updatedAt"].as_str().and_then(parse_timestamp),
+        ..Default::default()
+    }
+}
+
+/// The `field` of each of a connection's nodes, e.g. label names.
+fn node_names(connection: &serde_json::Value, field: &str) -> Vec<String> {
+    connection["nodes"]
+        .as_array()
+        .map(|nodes| {
+            nodes
+                .iter()
+                .filter_map(|n| n[field].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 /// Auto-assign a pull request to the current user.
-pub fn assign_pr(repo: &str, number: u64) {
-    let _ = Command::new("gh")
+pub fn assign_pr(repo: &str, number: u64) -> Result<(), GhError> {
+    add_self_assignee("pr", repo, number)
+}
+
+/// Auto-assign an issue to the current user.
+pub fn assign_issue(repo: &str, number: u64) -> Result<(), GhError> {
+    add_self_assignee("issue", repo, number)
+}
+
+fn add_self_assignee(kind: &str, repo: &str, number: u64) -> Result<(), GhError> {
+    let output = Command::new("gh")
         .args([
-            "pr",
+            kind,
             "edit",
             "--repo",
             repo,
@@ -247,29 +643,42 @@ pub fn assign_pr(repo: &str, number: u64) {
             "--add-assignee",
             "@me",
         ])
-        .output();
+        .output()
+        .map_err(GhError::spawn)?;
+
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+
+    Ok(())
 }
 
-pub fn create_issue(repo: &str, title: &str, body: &str) -> std::result::Result<u64, String> {
+/// Open an issue with `metadata`'s labels, assignees and milestone.
+pub fn create_issue(
+    repo: &str,
+    title: &str,
+    body: &str,
+    metadata: &IssueMetadata,
+) -> Result<u64, GhError> {
+    let mut args = vec![
+        "issue", "create", "--repo", repo, "--title", title, "--body", body,
+    ];
+    for label in &metadata.labels {
+        args.extend(["--label", label]);
+    }
+    for assignee in &metadata.assignees {
+        args.extend(["--assignee", assignee]);
+    }
+    if let Some(milestone) = &metadata.milestone {
+        args.extend(["--milestone", milestone]);
+    }
     let output = Command::new("gh")
-        .args([
-            "issue",
-            "create",
-            "--repo",
-            repo,
-            "--title",
-            title,
-            "--body",
-            body,
-            "--assignee",
-            "@me",
-        ])
+        .args(&args)
         .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
+        .map_err(GhError::spawn)?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh error: {}", stderr.trim()));
+        return Err(GhError::from_output(&output));
     }
 
     // gh issue create outputs a URL like https://github.com/owner/repo/issues/10
@@ -279,13 +688,106 @@ pub fn create_issue(repo: &str, title: &str, body: &str) -> std::result::Result<
         .rsplit('/')
         .next()
         .and_then(|s| s.parse::<u64>().ok())
-        .ok_or_else(|| format!("Could not parse issue number from: {}", stdout.trim()))?;
+        .ok_or_else(|| GhError::UnexpectedOutput(stdout.trim().to_string()))?;
 
     Ok(number)
 }
 
+/// The labels, assignable users and open milestones of `repo`, for the
+/// new issue form.
+pub fn fetch_issue_options(repo: &str) -> Result<IssueOptions, GhError> {
+    Ok(IssueOptions {
+        labels: gh_lines(&[
+            "label", "list", "--repo", repo, "--limit", "500", "--json", "name", "--jq", ".[].name",
+        ])?,
+        assignees: gh_lines(&[
+            "api",
+            &format!("repos/{}/assignees", repo),
+            "--paginate",
+            "--jq",
+            ".[].login",
+        ])?,
+        milestones: gh_lines(&[
+            "api",
+            &format!("repos/{}/milestones", repo),
+            "--paginate",
+            "--jq",
+            ".[].title",
+        ])?,
+    })
+}
+
+/// The non-empty lines `gh` prints for `args`.
+fn gh_lines(args: &[&str]) -> Result<Vec<String>, GhError> {
+    let output = Command::new("gh")
+        .args(args)
+        .output()
+        .map_err(GhError::spawn)?;
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Open a pull request for an already-pushed branch against `base` (the
+/// repository's default branch if `None`). Returns the PR number.
+pub fn create_pr(
+    repo: &str,
+    branch: &str,
+    base: Option<&str>,
+    title: &str,
+    body: &str,
+    draft: bool,
+) -> Result<u64, GhError> {
+    let mut args = vec![
+        "pr", "create", "--repo", repo, "--head", branch, "--title", title, "--body", body,
+    ];
+    if let Some(base) = base {
+        args.extend(["--base", base]);
+    }
+    if draft {
+        args.push("--draft");
+    }
+    let output = Command::new("gh")
+        .args(&args)
+        .output()
+        .map_err(GhError::spawn)?;
+
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+
+    // gh pr create outputs a URL like https://github.com/owner/repo/pull/12
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .trim()
+        .rsplit('/')
+        .next()
+        .and_then(|s| s.parse::<u64>().ok())
+        .ok_or_else(|| GhError::UnexpectedOutput(stdout.trim().to_string()))
+}
+
+/// Point the open PR for `branch` at a new base branch.
+pub fn retarget_pr(repo: &str, branch: &str, base: &str) -> Result<(), GhError> {
+    let output = Command::new("gh")
+        .args(["pr", "edit", branch, "--repo", repo, "--base", base])
+        .output()
+        .map_err(GhError::spawn)?;
+
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+
+    Ok(())
+}
+
 /// Fetch a single issue's title and body by number.
-pub fn fetch_issue(repo: &str, number: u64) -> std::result::Result<(String, String), String> {
+pub fn fetch_issue(repo: &str, number: u64) -> Result<(String, String), GhError> {
     let output = Command::new("gh")
         .args([
             "issue",
@@ -297,27 +799,21 @@ pub fn fetch_issue(repo: &str, number: u64) -> std::result::Result<(String, Stri
             "title,body",
         ])
         .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
+        .map_err(GhError::spawn)?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh error: {}", stderr.trim()));
+        return Err(GhError::from_output(&output));
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let val: serde_json::Value =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        serde_json::from_str(&stdout).map_err(|e| GhError::UnexpectedOutput(e.to_string()))?;
     let title = val["title"].as_str().unwrap_or("").to_string();
     let body = val["body"].as_str().unwrap_or("").to_string();
     Ok((title, body))
 }
 
-pub fn edit_issue(
-    repo: &str,
-    number: u64,
-    title: &str,
-    body: &str,
-) -> std::result::Result<(), String> {
+pub fn edit_issue(repo: &str, number: u64, title: &str, body: &str) -> Result<(), GhError> {
     let output = Command::new("gh")
         .args([
             "issue",
@@ -331,58 +827,139 @@ pub fn edit_issue(
             body,
         ])
         .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
+        .map_err(GhError::spawn)?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh error: {}", stderr.trim()));
+        return Err(GhError::from_output(&output));
     }
 
     Ok(())
 }
 
-pub fn close_issue(repo: &str, number: u64) -> std::result::Result<(), String> {
+pub fn close_issue(repo: &str, number: u64) -> Result<(), GhError> {
     let output = Command::new("gh")
         .args(["issue", "close", "--repo", repo, &number.to_string()])
         .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
+        .map_err(GhError::spawn)?;
+
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+
+    Ok(())
+}
+
+/// Take a draft pull request out of draft.
+pub fn mark_pr_ready(repo: &str, number: u64) -> Result<(), GhError> {
+    let output = Command::new("gh")
+        .args(["pr", "ready", "--repo", repo, &number.to_string()])
+        .output()
+        .map_err(GhError::spawn)?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh error: {}", stderr.trim()));
+        return Err(GhError::from_output(&output));
     }
 
     Ok(())
 }
 
-pub fn fetch_merged_pr_branches(repo: &str) -> Vec<String> {
+/// Merge a pull request with `strategy` and delete its head branch.
+pub fn merge_pr(repo: &str, number: u64, strategy: MergeStrategy) -> Result<(), GhError> {
     let output = Command::new("gh")
         .args([
             "pr",
-            "list",
+            "merge",
+            &number.to_string(),
+            strategy.flag(),
+            "--delete-branch",
+            "--repo",
+            repo,
+        ])
+        .output()
+        .map_err(GhError::spawn)?;
+
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+
+    Ok(())
+}
+
+/// Open a pull request that reverts merged PR `number`. `gh` has no revert
+/// command, so this goes through the GraphQL mutation by the PR's node ID.
+pub fn revert_pr(repo: &str, number: u64) -> Result<(), GhError> {
+    let output = Command::new("gh")
+        .args([
+            "pr",
+            "view",
+            &number.to_string(),
             "--repo",
             repo,
-            "--state",
-            "merged",
             "--json",
-            "headRefName",
-            "--limit",
-            "500",
+            "id",
+            "--jq",
+            ".id",
         ])
-        .output();
+        .output()
+        .map_err(GhError::spawn)?;
 
-    let output = match output {
-        Ok(o) if o.status.success() => o,
-        _ => return Vec::new(),
-    };
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let prs: Vec<serde_json::Value> = match serde_json::from_str(&stdout) {
-        Ok(v) => v,
-        Err(_) => return Vec::new(),
-    };
+    let node_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let query = format!(
+        r#"mutation {{ revertPullRequest(input: {{pullRequestId: "{}"}}) {{ revertPullRequest {{ number url }} }} }}"#,
+        node_id
+    );
+    let output = Command::new("gh")
+        .args(["api", "graphql", "-f", &format!("query={}", query)])
+        .output()
+        .map_err(GhError::spawn)?;
+
+    if !output.status.success() {
+        return Err(GhError::from_output(&output));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamp_reads_utc() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2024-02-29T12:30:15Z"), Some(1_709_209_815));
+        assert_eq!(parse_timestamp("not a date"), None);
+    }
 
-    prs.into_iter()
-        .filter_map(|pr| pr["headRefName"].as_str().map(|s| s.to_string()))
-        .collect()
+    #[test]
+    fn long_bodies_are_shortened_by_character() {
+        let body = "é".repeat(100);
+        let issue = issue_card(&serde_json::json!({ "number": 1, "title": "T", "body": body }));
+        assert_eq!(issue.description.chars().count(), 80);
+        assert!(issue.description.ends_with("..."));
+        let pr = pr_card(
+            &serde_json::json!({ "number": 2, "title": "T", "body": body }),
+            &HashMap::new(),
+        );
+        assert_eq!(pr.description, issue.description);
+    }
+
+    #[test]
+    fn only_open_prs_are_tagged_ready() {
+        let tag = |pr: serde_json::Value| pr_card(&pr, &HashMap::new()).tag;
+        assert_eq!(tag(serde_json::json!({ "state": "OPEN" })), "ready");
+        assert_eq!(
+            tag(serde_json::json!({ "state": "OPEN", "isDraft": true })),
+            "draft"
+        );
+        assert_eq!(
+            tag(serde_json::json!({ "state": "MERGED", "mergedAt": "2024-02-29T12:30:15Z" })),
+            "merged"
+        );
+        assert_eq!(tag(serde_json::json!({ "state": "CLOSED" })), "closed");
+    }
 }
@@ -4,6 +4,7 @@ use std::io::Read as _;
 use std::os::unix::net::UnixListener;
 use std::path::PathBuf;
 
+use crate::error::Error;
 use crate::models::{MessageLog, SessionStates, MAX_MESSAGES, SOCKET_PATH};
 
 pub fn start_event_socket(states: SessionStates, message_log: MessageLog) -> io::Result<()> {
@@ -49,11 +50,12 @@ pub fn start_event_socket(states: SessionStates, message_log: MessageLog) -> io:
     Ok(())
 }
 
-pub fn ensure_hook_script() -> std::result::Result<PathBuf, String> {
+pub fn ensure_hook_script() -> Result<PathBuf, Error> {
     let config_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?
+        .ok_or_else(|| Error::Io("Could not find home directory".to_string()))?
         .join(".config/octopai");
-    fs::create_dir_all(&config_dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    fs::create_dir_all(&config_dir)
+        .map_err(|e| Error::Io(format!("Failed to create config dir: {}", e)))?;
 
     let script_path = config_dir.join("event-hook.sh");
     let script = format!(
@@ -61,7 +63,9 @@ pub fn ensure_hook_script() -> std::result::Result<PathBuf, String> {
 # Roctopai event hook - sends Claude session events to the Unix socket
 STATUS="$1"
 cat > /dev/null
-SESSION=$(basename "$PWD" | grep -oE '(local-)?issue-[0-9]+')
+SESSION="$2"
+# Hook configs written before sessions were passed explicitly
+[ -z "$SESSION" ] && SESSION=$(basename "$PWD" | grep -oE '(local-)?issue-[0-9]+')
 [ -z "$SESSION" ] && exit 0
 SOCKET="{socket}"
 [ -S "$SOCKET" ] || exit 0
@@ -79,24 +83,31 @@ exit 0
 "#,
         socket = SOCKET_PATH
     );
-    fs::write(&script_path, &script).map_err(|e| format!("Failed to write hook script: {}", e))?;
+    fs::write(&script_path, &script)
+        .map_err(|e| Error::Io(format!("Failed to write hook script: {}", e)))?;
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
         fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755))
-            .map_err(|e| format!("Failed to chmod hook script: {}", e))?;
+            .map_err(|e| Error::Io(format!("Failed to chmod hook script: {}", e)))?;
     }
 
     Ok(script_path)
 }
 
+/// Point the Claude and Cursor hooks in `worktree_path` at `hook_script`,
+/// reporting status for the multiplexer session `session`.
 pub fn write_worktree_hook_config(
     worktree_path: &str,
     hook_script: &str,
-) -> std::result::Result<(), String> {
+    session: &str,
+) -> Result<(), Error> {
+    let hook = |status: &str| format!("'{}' {} '{}'", hook_script, status, session);
+
     let claude_dir = format!("{}/.claude", worktree_path);
-    fs::create_dir_all(&claude_dir).map_err(|e| format!("Failed to create .claude dir: {}", e))?;
+    fs::create_dir_all(&claude_dir)
+        .map_err(|e| Error::Io(format!("Failed to create .claude dir: {}", e)))?;
 
     let settings_path = format!("{}/.claude/settings.local.json", worktree_path);
 
@@ -108,12 +119,12 @@ pub fn write_worktree_hook_config(
     };
 
     let hook_config = serde_json::json!({
-        "PreToolUse": [{"hooks": [{"type": "command", "command": format!("'{}' working", hook_script), "async": true}]}],
-        "PostToolUse": [{"hooks": [{"type": "command", "command": format!("'{}' working", hook_script), "async": true}]}],
-        "PermissionRequest": [{"hooks": [{"type": "command", "command": format!("'{}' permission", hook_script), "async": true}]}],
-        "UserPromptSubmit": [{"hooks": [{"type": "command", "command": format!("'{}' processing", hook_script), "async": true}]}],
-        "Stop": [{"hooks": [{"type": "command", "command": format!("'{}' idle", hook_script)}]}],
-        "Notification": [{"matcher": "idle_prompt", "hooks": [{"type": "command", "command": format!("'{}' idle", hook_script), "async": true}]}]
+        "PreToolUse": [{"hooks": [{"type": "command", "command": hook("working"), "async": true}]}],
+        "PostToolUse": [{"hooks": [{"type": "command", "command": hook("working"), "async": true}]}],
+        "PermissionRequest": [{"hooks": [{"type": "command", "command": hook("permission"), "async": true}]}],
+        "UserPromptSubmit": [{"hooks": [{"type": "command", "command": hook("processing"), "async": true}]}],
+        "Stop": [{"hooks": [{"type": "command", "command": hook("idle")}]}],
+        "Notification": [{"matcher": "idle_prompt", "hooks": [{"type": "command", "command": hook("idle"), "async": true}]}]
     });
 
     settings["hooks"] = hook_config;
@@ -122,20 +133,21 @@ pub fn write_worktree_hook_config(
         &settings_path,
         serde_json::to_string_pretty(&settings).unwrap(),
     )
-    .map_err(|e| format!("Failed to write hook settings: {}", e))?;
+    .map_err(|e| Error::Io(format!("Failed to write hook settings: {}", e)))?;
 
     // Write Cursor hooks config
     let cursor_dir = format!("{}/.cursor", worktree_path);
-    fs::create_dir_all(&cursor_dir).map_err(|e| format!("Failed to create .cursor dir: {}", e))?;
+    fs::create_dir_all(&cursor_dir)
+        .map_err(|e| Error::Io(format!("Failed to create .cursor dir: {}", e)))?;
 
     let cursor_hooks = serde_json::json!({
         "version": 1,
         "hooks": {
-            "beforeShellExecution": [{"command": format!("'{}' working", hook_script)}],
-            "beforeMCPExecution": [{"command": format!("'{}' working", hook_script)}],
-            "afterFileEdit": [{"command": format!("'{}' working", hook_script)}],
-            "beforeSubmitPrompt": [{"command": format!("'{}' processing", hook_script)}],
-            "stop": [{"command": format!("'{}' idle", hook_script)}]
+            "beforeShellExecution": [{"command": hook("working")}],
+            "beforeMCPExecution": [{"command": hook("working")}],
+            "afterFileEdit": [{"command": hook("working")}],
+            "beforeSubmitPrompt": [{"command": hook("processing")}],
+            "stop": [{"command": hook("idle")}]
         }
     });
 
@@ -144,7 +156,7 @@ pub fn write_worktree_hook_config(
         &cursor_hooks_path,
         serde_json::to_string_pretty(&cursor_hooks).unwrap(),
     )
-    .map_err(|e| format!("Failed to write Cursor hook settings: {}", e))?;
+    .map_err(|e| Error::Io(format!("Failed to write Cursor hook settings: {}", e)))?;
 
     Ok(())
 }
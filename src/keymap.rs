@@ -0,0 +1,612 @@
+//! Which key does what on the board. Every bindable [`Action`] has a name
+//! (`merge_pr`, `toggle_messages`, ...), a [`Context`] it applies in and
+//! default keys; the `keybindings` map in the config file replaces the keys
+//! of any of them, e.g. `"merge_pr": ["ctrl+m"]`. The footer and overlay
+//! hints are built from the active keymap, so they show custom keys too.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::action::Action;
+use crate::git::UpdateStrategy;
+
+/// Where a binding applies. Column bindings apply when that column is
+/// focused, board bindings everywhere on the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Context {
+    Board,
+    Issues,
+    Worktrees,
+    Sessions,
+    PullRequests,
+    /// While a filter is applied (but not being typed); takes precedence
+    /// over the board.
+    Filter,
+    /// While cards in the focused column are marked; takes precedence over
+    /// the filter.
+    Marked,
+    /// The confirmation modal.
+    Confirm,
+    /// The selected card's detail view.
+    Detail,
+}
+
+impl Context {
+    /// The context of board column `section`.
+    pub fn column(section: usize) -> Context {
+        match section {
+            0 => Context::Issues,
+            1 => Context::Worktrees,
+            2 => Context::Sessions,
+            _ => Context::PullRequests,
+        }
+    }
+
+    /// Contexts whose keys are live at the same time as this one's, so a
+    /// key may not mean two things across them.
+    fn overlaps(self, other: Context) -> bool {
+        use Context::*;
+        match (self, other) {
+            (a, b) if a == b => true,
+            (Board, Issues | Worktrees | Sessions | PullRequests)
+            | (Issues | Worktrees | Sessions | PullRequests, Board) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A key with the modifiers that matter for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// The chord a key event stands for. Shift is part of the character for
+    /// letters and of `BackTab`, so it is not kept as a modifier.
+    pub fn from_event(key: KeyEvent) -> KeyChord {
+        KeyChord {
+            code: key.code,
+            modifiers: key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    /// Parse a chord as written in the config file: a character (`w`, `M`,
+    /// `/`), a key name (`esc`, `enter`, `tab`, `shift+tab`, `space`, `up`,
+    /// `f5`, ...), optionally prefixed with `ctrl+` and/or `alt+`.
+    pub fn parse(spec: &str) -> Option<KeyChord> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+        loop {
+            let lower = rest.to_lowercase();
+            if let Some(tail) = lower.strip_prefix("ctrl+").filter(|t| !t.is_empty()) {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[rest.len() - tail.len()..];
+            } else if let Some(tail) = lower.strip_prefix("alt+").filter(|t| !t.is_empty()) {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[rest.len() - tail.len()..];
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "shift+tab" | "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(KeyChord { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "S-Tab"),
+            KeyCode::Backspace => write!(f, "Bksp"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// A bindable action: its config name, where it applies and its default keys.
+pub struct Binding {
+    pub name: &'static str,
+    pub context: Context,
+    keys: &'static [&'static str],
+    action: fn() -> Action,
+}
+
+impl Binding {
+    pub fn action(&self) -> Action {
+        (self.action)()
+    }
+}
+
+const fn bind(
+    name: &'static str,
+    context: Context,
+    keys: &'static [&'static str],
+    action: fn() -> Action,
+) -> Binding {
+    Binding {
+        name,
+        context,
+        keys,
+        action,
+    }
+}
+
+/// Lines a page key scrolls the detail view by.
+const DETAIL_PAGE: i16 = 10;
+
+/// Every action a key can be bound to, with its default keys.
+pub static BINDINGS: &[Binding] = &[
+    bind("quit", Context::Board, &["q", "esc"], || Action::Quit),
+    bind("next_column", Context::Board, &["l", "tab"], || {
+        Action::NextColumn
+    }),
+    bind("prev_column", Context::Board, &["h", "shift+tab"], || {
+        Action::PrevColumn
+    }),
+    bind("down", Context::Board, &["j", "down"], || Action::Down),
+    bind("up", Context::Board, &["k", "up"], || Action::Up),
+    bind("filter", Context::Board, &["/"], || Action::FocusFilter),
+    bind("switch_repo", Context::Board, &["enter"], || {
+        Action::SwitchRepo
+    }),
+    bind("refresh", Context::Board, &["R"], || Action::Refresh),
+    bind("pull_base", Context::Board, &["p"], || Action::PullBase),
+    bind("new_issue", Context::Board, &["n"], || Action::NewIssue),
+    bind("main_session", Context::Board, &["T"], || {
+        Action::AttachMain
+    }),
+    bind("dependencies", Context::Board, &["D"], || {
+        Action::ShowDependencies
+    }),
+    bind("configure", Context::Board, &["C"], || Action::Configure),
+    bind("toggle_local_mode", Context::Board, &["L"], || {
+        Action::ToggleLocalMode
+    }),
+    bind("export_store", Context::Board, &["E"], || {
+        Action::ExportStore
+    }),
+    bind("import_store", Context::Board, &["I"], || {
+        Action::ImportStore
+    }),
+    bind("toggle_messages", Context::Board, &["x"], || {
+        Action::ToggleMessages
+    }),
+    bind("expand_messages", Context::Board, &["X"], || {
+        Action::ExpandMessages
+    }),
+    bind("clear_filter", Context::Filter, &["esc"], || {
+        Action::ClearFilter
+    }),
+    bind("start_worktree", Context::Issues, &["w"], || {
+        Action::StartWorktree
+    }),
+    bind("start_worktree_from", Context::Issues, &["W"], || {
+        Action::StartWorktreeFrom
+    }),
+    bind("edit_issue", Context::Issues, &["e"], || Action::EditIssue),
+    bind("close_issue", Context::Issues, &["d"], || {
+        Action::CloseIssue
+    }),
+    bind("start_session", Context::Worktrees, &["w"], || {
+        Action::StartSession
+    }),
+    bind("open_editor", Context::Worktrees, &["e"], || {
+        Action::OpenEditor
+    }),
+    bind("verify", Context::Worktrees, &["v"], || Action::Verify),
+    bind("create_local_pr", Context::Worktrees, &["P"], || {
+        Action::CreateLocalPr
+    }),
+    bind("rebase_on_base", Context::Worktrees, &["u"], || {
+        Action::UpdateFromBase(UpdateStrategy::Rebase)
+    }),
+    bind("merge_base", Context::Worktrees, &["U"], || {
+        Action::UpdateFromBase(UpdateStrategy::Merge)
+    }),
+    bind("restack", Context::Worktrees, &["S"], || Action::Restack),
+    bind("remove_worktree", Context::Worktrees, &["d"], || {
+        Action::RemoveWorktree
+    }),
+    bind("attach_session", Context::Sessions, &["a"], || {
+        Action::AttachSession
+    }),
+    bind("kill_session", Context::Sessions, &["d"], || {
+        Action::KillSession
+    }),
+    bind("open_pr", Context::PullRequests, &["o"], || Action::OpenPr),
+    bind("mark_pr_ready", Context::PullRequests, &["r"], || {
+        Action::MarkPrReady
+    }),
+    bind("merge_pr", Context::PullRequests, &["M"], || {
+        Action::MergePr
+    }),
+    bind("revert_pr", Context::PullRequests, &["V"], || {
+        Action::RevertPr
+    }),
+    bind("toggle_issue_state", Context::Issues, &["s"], || {
+        Action::ToggleStateFilter
+    }),
+    bind("toggle_issue_assignee", Context::Issues, &["m"], || {
+        Action::ToggleAssigneeFilter
+    }),
+    bind("toggle_pr_state", Context::PullRequests, &["s"], || {
+        Action::ToggleStateFilter
+    }),
+    bind("toggle_pr_assignee", Context::PullRequests, &["m"], || {
+        Action::ToggleAssigneeFilter
+    }),
+    bind("view_1", Context::Board, &["1"], || {
+        Action::SwitchView(Some(0))
+    }),
+    bind("view_2", Context::Board, &["2"], || {
+        Action::SwitchView(Some(1))
+    }),
+    bind("view_3", Context::Board, &["3"], || {
+        Action::SwitchView(Some(2))
+    }),
+    bind("view_4", Context::Board, &["4"], || {
+        Action::SwitchView(Some(3))
+    }),
+    bind("view_5", Context::Board, &["5"], || {
+        Action::SwitchView(Some(4))
+    }),
+    bind("view_6", Context::Board, &["6"], || {
+        Action::SwitchView(Some(5))
+    }),
+    bind("view_7", Context::Board, &["7"], || {
+        Action::SwitchView(Some(6))
+    }),
+    bind("view_8", Context::Board, &["8"], || {
+        Action::SwitchView(Some(7))
+    }),
+    bind("view_9", Context::Board, &["9"], || {
+        Action::SwitchView(Some(8))
+    }),
+    bind("all_cards", Context::Board, &["0"], || {
+        Action::SwitchView(None)
+    }),
+    bind("save_view", Context::Board, &["F"], || Action::SaveView),
+    bind("cycle_sort", Context::Board, &["O"], || Action::CycleSort),
+    bind("cycle_group", Context::Board, &["g"], || Action::CycleGroup),
+    bind("toggle_group", Context::Board, &["z"], || {
+        Action::ToggleGroup
+    }),
+    bind("show_detail", Context::Board, &["i"], || Action::ShowDetail),
+    bind("toggle_mark", Context::Board, &["space"], || {
+        Action::ToggleMark
+    }),
+    bind("mark_range", Context::Board, &["ctrl+v"], || {
+        Action::MarkRange
+    }),
+    bind("clear_marks", Context::Marked, &["esc"], || {
+        Action::ClearMarks
+    }),
+    bind(
+        "scroll_detail_down",
+        Context::Detail,
+        &["j", "down"],
+        || Action::ScrollDetail(1),
+    ),
+    bind("scroll_detail_up", Context::Detail, &["k", "up"], || {
+        Action::ScrollDetail(-1)
+    }),
+    bind(
+        "page_detail_down",
+        Context::Detail,
+        &["pagedown", "space"],
+        || Action::ScrollDetail(DETAIL_PAGE),
+    ),
+    bind("page_detail_up", Context::Detail, &["pageup"], || {
+        Action::ScrollDetail(-DETAIL_PAGE)
+    }),
+    bind("close_detail", Context::Detail, &["esc", "q", "i"], || {
+        Action::CloseDetail
+    }),
+    bind("confirm", Context::Confirm, &["y", "Y"], || Action::Confirm),
+    bind("cancel", Context::Confirm, &["n", "N", "esc"], || {
+        Action::Cancel
+    }),
+];
+
+/// The keys bound to each action in [`BINDINGS`].
+pub struct Keymap {
+    keys: HashMap<&'static str, Vec<KeyChord>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::with_overrides(&HashMap::new()).0
+    }
+}
+
+impl Keymap {
+    /// The default keys with the config file's `keybindings` applied.
+    /// Problems with them are reported in the message log.
+    pub fn load() -> Keymap {
+        let (keymap, problems) = Keymap::with_overrides(&crate::config::get_keybindings());
+        for problem in problems {
+            crate::log::warn("keymap", problem);
+        }
+        keymap
+    }
+
+    /// The default keys with `overrides` (action name → keys) applied, and
+    /// what was wrong with the overrides. A configured key takes over from
+    /// a default one it clashes with; of two configured keys that clash,
+    /// the first action (by name) keeps it.
+    pub fn with_overrides(overrides: &HashMap<String, Vec<String>>) -> (Keymap, Vec<String>) {
+        let mut problems = Vec::new();
+        let mut configured: Vec<(&'static Binding, Vec<KeyChord>)> = Vec::new();
+        let mut names: Vec<&String> = overrides.keys().collect();
+        names.sort();
+        for name in names {
+            let Some(binding) = BINDINGS.iter().find(|b| b.name == name.as_str()) else {
+                problems.push(format!("Unknown action '{}'", name));
+                continue;
+            };
+            let mut chords = Vec::new();
+            for spec in &overrides[name] {
+                match KeyChord::parse(spec) {
+                    Some(chord) => chords.push(chord),
+                    None => problems.push(format!("Unknown key '{}' for {}", spec, name)),
+                }
+            }
+            configured.push((binding, chords));
+        }
+
+        let mut keymap = Keymap {
+            keys: HashMap::new(),
+        };
+        for (binding, chords) in &configured {
+            let mut kept = Vec::new();
+            for chord in chords {
+                match keymap.bound_to(binding.context, *chord) {
+                    Some(other) => problems.push(format!(
+                        "{} is bound to both {} and {}; keeping {}",
+                        chord, other.name, binding.name, other.name
+                    )),
+                    None => kept.push(*chord),
+                }
+            }
+            keymap.keys.insert(binding.name, kept);
+        }
+        for binding in BINDINGS {
+            if keymap.keys.contains_key(binding.name) {
+                continue;
+            }
+            let mut kept = Vec::new();
+            for chord in binding.keys.iter().filter_map(|k| KeyChord::parse(k)) {
+                match keymap.bound_to(binding.context, chord) {
+                    Some(other) => problems.push(format!(
+                        "{} is now bound to {} instead of {}",
+                        chord, other.name, binding.name
+                    )),
+                    None => kept.push(chord),
+                }
+            }
+            keymap.keys.insert(binding.name, kept);
+        }
+        (keymap, problems)
+    }
+
+    /// The binding already using `chord` where `context`'s keys are live.
+    fn bound_to(&self, context: Context, chord: KeyChord) -> Option<&'static Binding> {
+        BINDINGS.iter().find(|b| {
+            b.context.overlaps(context)
+                && self
+                    .keys
+                    .get(b.name)
+                    .is_some_and(|keys| keys.contains(&chord))
+        })
+    }
+
+    /// The bindings `key` triggers in `context`.
+    pub fn lookup(
+        &self,
+        context: Context,
+        key: KeyEvent,
+    ) -> impl Iterator<Item = &'static Binding> + '_ {
+        let chord = KeyChord::from_event(key);
+        BINDINGS.iter().filter(move |b| {
+            b.context == context
+                && self
+                    .keys
+                    .get(b.name)
+                    .is_some_and(|keys| keys.contains(&chord))
+        })
+    }
+
+    /// The keys of action `name` as shown in hints, e.g. `q/Esc`. Upper-case
+    /// letters that only repeat a lower-case one (`y/Y`) are left out.
+    /// Empty if nothing is bound to it.
+    pub fn keys(&self, name: &str) -> String {
+        self.shown_keys(name)
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The keys of two opposite actions side by side, e.g. `j/k ↓/↑` for
+    /// `down` and `up`.
+    pub fn pair(&self, first: &str, second: &str) -> String {
+        let first = self.shown_keys(first);
+        let second = self.shown_keys(second);
+        (0..first.len().max(second.len()))
+            .map(|i| {
+                [first.get(i), second.get(i)]
+                    .iter()
+                    .flatten()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn shown_keys(&self, name: &str) -> Vec<KeyChord> {
+        let keys = self.keys.get(name).map(Vec::as_slice).unwrap_or_default();
+        keys.iter()
+            .filter(|k| match k.code {
+                KeyCode::Char(c) if c.is_uppercase() => !keys.iter().any(|other| {
+                    other.modifiers == k.modifiers
+                        && other.code == KeyCode::Char(c.to_ascii_lowercase())
+                }),
+                _ => true,
+            })
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(name, keys)| {
+                (
+                    name.to_string(),
+                    keys.iter().map(|k| k.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn chords_parse_and_display() {
+        for (spec, shown) in [
+            ("w", "w"),
+            ("M", "M"),
+            ("ctrl+s", "Ctrl+S"),
+            ("Ctrl+Alt+x", "Ctrl+Alt+X"),
+            ("shift+tab", "S-Tab"),
+            ("esc", "Esc"),
+            ("space", "Space"),
+            ("down", "↓"),
+            ("f5", "F5"),
+            ("+", "+"),
+        ] {
+            assert_eq!(
+                KeyChord::parse(spec).unwrap().to_string(),
+                shown,
+                "{}",
+                spec
+            );
+        }
+        assert!(KeyChord::parse("hyper+q").is_none());
+        assert!(KeyChord::parse("").is_none());
+
+        // Shift comes with upper-case letters and BackTab; it is not a modifier
+        assert_eq!(
+            KeyChord::from_event(press(KeyCode::Char('M'), KeyModifiers::SHIFT)),
+            KeyChord::parse("M").unwrap()
+        );
+        assert_eq!(
+            KeyChord::from_event(press(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            KeyChord::parse("shift+tab").unwrap()
+        );
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts_and_build_hints() {
+        let (keymap, problems) = Keymap::with_overrides(&HashMap::new());
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(keymap.keys("quit"), "q/Esc");
+        assert_eq!(keymap.keys("confirm"), "y");
+        assert_eq!(keymap.keys("cancel"), "n/Esc");
+        assert_eq!(keymap.pair("prev_column", "next_column"), "h/l S-Tab/Tab");
+        let merge = keymap
+            .lookup(
+                Context::PullRequests,
+                press(KeyCode::Char('M'), KeyModifiers::SHIFT),
+            )
+            .map(|b| b.name)
+            .collect::<Vec<_>>();
+        assert_eq!(merge, vec!["merge_pr"]);
+    }
+
+    #[test]
+    fn overrides_replace_keys_and_report_conflicts() {
+        let (keymap, problems) = Keymap::with_overrides(&overrides(&[
+            ("merge_pr", &["ctrl+m", "x"]),
+            ("revert_pr", &["ctrl+m"]),
+            ("reboot", &["b"]),
+            ("open_pr", &["hyper+o"]),
+        ]));
+        assert_eq!(keymap.keys("merge_pr"), "Ctrl+M/x");
+        assert_eq!(keymap.keys("revert_pr"), "");
+        // The configured x takes over from the default toggle_messages key
+        assert_eq!(keymap.keys("toggle_messages"), "");
+        assert!(keymap
+            .lookup(
+                Context::PullRequests,
+                press(KeyCode::Char('M'), KeyModifiers::SHIFT)
+            )
+            .next()
+            .is_none());
+        assert_eq!(
+            problems,
+            vec![
+                "Unknown key 'hyper+o' for open_pr".to_string(),
+                "Unknown action 'reboot'".to_string(),
+                "Ctrl+M is bound to both merge_pr and revert_pr; keeping merge_pr".to_string(),
+                "x is now bound to merge_pr instead of toggle_messages".to_string(),
+            ]
+        );
+    }
+}
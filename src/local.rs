@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use ratatui::style::Color;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
 use serde::{Deserialize, Serialize};
 
-use crate::models::{label_color, AssigneeFilter, Card, StateFilter};
+use crate::error::StoreError;
+use crate::models::{short_description, AssigneeFilter, Card, IssueOptions, StateFilter};
+use crate::theme;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct LocalIssue {
@@ -23,8 +27,13 @@ pub struct LocalPr {
     pub branch: String,
     pub state: String, // "open" or "merged"
     pub is_draft: bool,
+    /// Branch the PR targets; `None` means the repo's default base branch.
+    #[serde(default)]
+    pub base: Option<String>,
 }
 
+/// The legacy `store.json` layout. Only used to import stores written by
+/// older versions of octopai into the SQLite database.
 #[derive(Serialize, Deserialize, Default)]
 pub struct LocalStore {
     pub issues: Vec<LocalIssue>,
@@ -33,6 +42,47 @@ pub struct LocalStore {
     pub next_pr_number: u64,
 }
 
+/// Schema migrations, applied in order. `PRAGMA user_version` records how
+/// many of them have already run against a database.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE issues (
+        number INTEGER PRIMARY KEY,
+        title  TEXT NOT NULL,
+        body   TEXT NOT NULL DEFAULT '',
+        state  TEXT NOT NULL DEFAULT 'open'
+    );
+    CREATE TABLE issue_labels (
+        issue_number INTEGER NOT NULL REFERENCES issues(number) ON DELETE CASCADE,
+        position     INTEGER NOT NULL,
+        name         TEXT NOT NULL,
+        PRIMARY KEY (issue_number, position)
+    );
+    CREATE TABLE prs (
+        number   INTEGER PRIMARY KEY,
+        title    TEXT NOT NULL,
+        body     TEXT NOT NULL DEFAULT '',
+        branch   TEXT NOT NULL,
+        state    TEXT NOT NULL DEFAULT 'open',
+        is_draft INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX prs_branch ON prs(branch);
+    CREATE TABLE counters (
+        name  TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    INSERT INTO counters (name, value) VALUES ('next_issue_number', 1), ('next_pr_number', 1);",
+    // 2: mapping of local issues/PRs to the GitHub numbers they were synced to
+    "CREATE TABLE github_links (
+        kind          TEXT NOT NULL,
+        local_number  INTEGER NOT NULL,
+        github_number INTEGER NOT NULL,
+        PRIMARY KEY (kind, local_number)
+    );",
+    // 3: base branch each PR targets
+    "ALTER TABLE prs ADD COLUMN base TEXT;",
+];
+
 fn repo_slug(repo: &str) -> String {
     repo.replace('/', "--")
 }
@@ -45,48 +95,222 @@ fn store_dir(repo: &str) -> PathBuf {
         .join(repo_slug(repo))
 }
 
-fn store_path(repo: &str) -> PathBuf {
+fn legacy_store_path(repo: &str) -> PathBuf {
     store_dir(repo).join("store.json")
 }
 
-fn load_store(repo: &str) -> LocalStore {
-    let path = store_path(repo);
-    if let Ok(data) = fs::read_to_string(&path) {
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        LocalStore {
-            next_issue_number: 1,
-            next_pr_number: 1,
-            ..Default::default()
-        }
-    }
+fn db_path(repo: &str) -> PathBuf {
+    store_dir(repo).join("store.db")
 }
 
-fn save_store(repo: &str, store: &LocalStore) -> Result<(), String> {
+/// Open the local store for a repo, creating and migrating it as needed.
+///
+/// Every call returns a fresh connection so background threads and other
+/// octopai processes can share the database; SQLite's own locking (with a
+/// busy timeout) serialises writers.
+fn open_store(repo: &str) -> Result<Connection, StoreError> {
     let dir = store_dir(repo);
-    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create local store dir: {}", e))?;
-    let path = store_path(repo);
-    let data =
-        serde_json::to_string_pretty(store).map_err(|e| format!("Failed to serialize: {}", e))?;
-    fs::write(path, data).map_err(|e| format!("Failed to write local store: {}", e))?;
-    Ok(())
+    fs::create_dir_all(&dir).map_err(StoreError::io("create local store dir"))?;
+    let mut conn = Connection::open(db_path(repo)).map_err(StoreError::db("open local store"))?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(StoreError::db("configure local store"))?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(StoreError::db("configure local store"))?;
+    conn.pragma_update(None, "foreign_keys", true)
+        .map_err(StoreError::db("configure local store"))?;
+    migrate(&mut conn)?;
+    import_legacy_store(&mut conn, repo)?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), StoreError> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(StoreError::db("start migration"))?;
+    let version: usize = tx
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(StoreError::db("read schema version"))?;
+    if version > MIGRATIONS.len() {
+        return Err(StoreError::TooNew {
+            found: version,
+            supported: MIGRATIONS.len(),
+        });
+    }
+    for migration in MIGRATIONS.iter().skip(version) {
+        tx.execute_batch(migration)
+            .map_err(StoreError::db("migrate local store"))?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())
+        .map_err(StoreError::db("record schema version"))?;
+    tx.commit().map_err(StoreError::db("commit migration"))
 }
 
-pub fn fetch_local_issues(repo: &str, state: StateFilter, _assignee: AssigneeFilter) -> Vec<Card> {
-    let store = load_store(repo);
-    let state_label = state.label();
-    let mut cards: Vec<Card> = store
+/// Import a `store.json` written by older versions, then rename it so the
+/// import only happens once. A store that fails to parse is left in place and
+/// reported instead of being treated as empty.
+fn import_legacy_store(conn: &mut Connection, repo: &str) -> Result<(), StoreError> {
+    let path = legacy_store_path(repo);
+    let Ok(data) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let store: LocalStore = serde_json::from_str(&data)
+        .map_err(|e| StoreError::Corrupt(format!("could not parse {}: {}", path.display(), e)))?;
+
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(StoreError::db("start import"))?;
+    for issue in &store.issues {
+        insert_issue(&tx, issue).map_err(StoreError::db("import issue"))?;
+    }
+    for pr in &store.prs {
+        insert_pr(&tx, pr).map_err(StoreError::db("import PR"))?;
+    }
+    let next_issue = store
         .issues
         .iter()
-        .filter(|i| i.state == state_label)
+        .map(|i| i.number + 1)
+        .chain([store.next_issue_number, 1])
+        .max()
+        .unwrap_or(1);
+    let next_pr = store
+        .prs
+        .iter()
+        .map(|p| p.number + 1)
+        .chain([store.next_pr_number, 1])
+        .max()
+        .unwrap_or(1);
+    bump_counter(&tx, "next_issue_number", next_issue)
+        .and_then(|_| bump_counter(&tx, "next_pr_number", next_pr))
+        .map_err(StoreError::db("import counters"))?;
+    tx.commit().map_err(StoreError::db("commit import"))?;
+
+    // Another process may have imported and renamed it meanwhile
+    match fs::rename(&path, path.with_extension("json.imported")) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result.map_err(StoreError::io("rename imported store.json")),
+    }
+}
+
+fn insert_issue(conn: &Connection, issue: &LocalIssue) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO issues (number, title, body, state) VALUES (?1, ?2, ?3, ?4)",
+        params![issue.number, issue.title, issue.body, issue.state],
+    )?;
+    conn.execute(
+        "DELETE FROM issue_labels WHERE issue_number = ?1",
+        params![issue.number],
+    )?;
+    for (position, label) in issue.labels.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO issue_labels (issue_number, position, name) VALUES (?1, ?2, ?3)",
+            params![issue.number, position, label],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_pr(conn: &Connection, pr: &LocalPr) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO prs (number, title, body, branch, state, is_draft, base)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            pr.number,
+            pr.title,
+            pr.body,
+            pr.branch,
+            pr.state,
+            pr.is_draft,
+            pr.base
+        ],
+    )?;
+    Ok(())
+}
+
+/// Raise a counter to at least `value`.
+fn bump_counter(conn: &Connection, name: &str, value: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE counters SET value = MAX(value, ?2) WHERE name = ?1",
+        params![name, value],
+    )?;
+    Ok(())
+}
+
+/// Allocate the next number from a counter. Must run inside an immediate
+/// transaction so concurrent writers never hand out the same number.
+fn next_number(conn: &Connection, name: &str) -> rusqlite::Result<u64> {
+    let number: u64 = conn.query_row(
+        "SELECT value FROM counters WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "UPDATE counters SET value = ?2 WHERE name = ?1",
+        params![name, number + 1],
+    )?;
+    Ok(number)
+}
+
+fn load_issues(conn: &Connection, state: Option<&str>) -> rusqlite::Result<Vec<LocalIssue>> {
+    let mut stmt = conn.prepare(
+        "SELECT number, title, body, state FROM issues
+         WHERE ?1 IS NULL OR state = ?1 ORDER BY number",
+    )?;
+    let mut issues = stmt
+        .query_map(params![state], |row| {
+            Ok(LocalIssue {
+                number: row.get(0)?,
+                title: row.get(1)?,
+                body: row.get(2)?,
+                state: row.get(3)?,
+                labels: Vec::new(),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut labels_stmt =
+        conn.prepare("SELECT name FROM issue_labels WHERE issue_number = ?1 ORDER BY position")?;
+    for issue in &mut issues {
+        issue.labels = labels_stmt
+            .query_map(params![issue.number], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+    }
+    Ok(issues)
+}
+
+fn load_prs(conn: &Connection, state: Option<&str>) -> rusqlite::Result<Vec<LocalPr>> {
+    let mut stmt = conn.prepare(
+        "SELECT number, title, body, branch, state, is_draft, base FROM prs
+         WHERE ?1 IS NULL OR state = ?1 ORDER BY number",
+    )?;
+    let prs = stmt
+        .query_map(params![state], |row| {
+            Ok(LocalPr {
+                number: row.get(0)?,
+                title: row.get(1)?,
+                body: row.get(2)?,
+                branch: row.get(3)?,
+                state: row.get(4)?,
+                is_draft: row.get(5)?,
+                base: row.get(6)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(prs)
+}
+
+pub fn fetch_local_issues(
+    repo: &str,
+    state: StateFilter,
+    _assignee: AssigneeFilter,
+) -> Result<Vec<Card>, StoreError> {
+    let conn = open_store(repo)?;
+    let issues =
+        load_issues(&conn, Some(state.label())).map_err(StoreError::db("read local issues"))?;
+    let mut cards: Vec<Card> = issues
+        .iter()
         .map(|issue| {
-            let description = if issue.body.len() > 80 {
-                format!("{}...", &issue.body[..77])
-            } else if issue.body.is_empty() {
-                "No description".to_string()
-            } else {
-                issue.body.clone()
-            };
+            let description =
+                short_description(&issue.body).unwrap_or_else(|| "No description".to_string());
 
             let full_description = if issue.body.is_empty() {
                 None
@@ -95,11 +319,11 @@ pub fn fetch_local_issues(repo: &str, state: StateFilter, _assignee: AssigneeFil
             };
 
             let (tag, tag_color) = if let Some(first) = issue.labels.first() {
-                (first.clone(), label_color(first))
+                (first.clone(), theme::current().label_color(first))
             } else if issue.state == "closed" {
-                ("closed".to_string(), Color::Red)
+                ("closed".to_string(), theme::current().error)
             } else {
-                ("local".to_string(), Color::Cyan)
+                ("local".to_string(), theme::current().accent)
             };
 
             Card {
@@ -109,122 +333,160 @@ pub fn fetch_local_issues(repo: &str, state: StateFilter, _assignee: AssigneeFil
                 full_description,
                 tag,
                 tag_color,
-                related: Vec::new(),
-                url: None,
-                pr_number: None,
-                is_draft: None,
-                is_merged: None,
-                head_branch: None,
-                is_assigned: None,
+                labels: issue.labels.clone(),
+                ..Default::default()
             }
         })
         .collect();
     cards.reverse();
-    cards
-}
-
-pub fn create_local_issue(repo: &str, title: &str, body: &str) -> Result<u64, String> {
-    let mut store = load_store(repo);
-    let number = store.next_issue_number;
-    store.next_issue_number += 1;
-    store.issues.push(LocalIssue {
-        number,
-        title: title.to_string(),
-        body: body.to_string(),
-        state: "open".to_string(),
-        labels: Vec::new(),
-    });
-    save_store(repo, &store)?;
+    Ok(cards)
+}
+
+pub fn create_local_issue(
+    repo: &str,
+    title: &str,
+    body: &str,
+    labels: &[String],
+) -> Result<u64, StoreError> {
+    let mut conn = open_store(repo)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(StoreError::db("write local store"))?;
+    let number = next_number(&tx, "next_issue_number")
+        .and_then(|number| {
+            insert_issue(
+                &tx,
+                &LocalIssue {
+                    number,
+                    title: title.to_string(),
+                    body: body.to_string(),
+                    state: "open".to_string(),
+                    labels: labels.to_vec(),
+                },
+            )?;
+            Ok(number)
+        })
+        .map_err(StoreError::db("create local issue"))?;
+    tx.commit().map_err(StoreError::db("write local store"))?;
     Ok(number)
 }
 
-pub fn fetch_local_issue(repo: &str, number: u64) -> Result<(String, String), String> {
-    let store = load_store(repo);
-    store
-        .issues
-        .iter()
-        .find(|i| i.number == number)
-        .map(|i| (i.title.clone(), i.body.clone()))
-        .ok_or_else(|| format!("Local issue #{} not found", number))
-}
-
-pub fn edit_local_issue(repo: &str, number: u64, title: &str, body: &str) -> Result<(), String> {
-    let mut store = load_store(repo);
-    if let Some(issue) = store.issues.iter_mut().find(|i| i.number == number) {
-        issue.title = title.to_string();
-        issue.body = body.to_string();
-        save_store(repo, &store)?;
-        Ok(())
-    } else {
-        Err(format!("Local issue #{} not found", number))
+/// Every label used on a local issue, for the new issue form. Local issues
+/// have no assignees or milestones.
+pub fn fetch_local_issue_options(repo: &str) -> Result<IssueOptions, StoreError> {
+    let conn = open_store(repo)?;
+    let labels = conn
+        .prepare("SELECT DISTINCT name FROM issue_labels ORDER BY name")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })
+        .map_err(StoreError::db("read local labels"))?;
+    Ok(IssueOptions {
+        labels,
+        ..IssueOptions::default()
+    })
+}
+
+pub fn fetch_local_issue(repo: &str, number: u64) -> Result<(String, String), StoreError> {
+    let conn = open_store(repo)?;
+    conn.query_row(
+        "SELECT title, body FROM issues WHERE number = ?1",
+        params![number],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(StoreError::db("read local issue"))?
+    .ok_or_else(|| StoreError::NotFound(format!("Local issue #{}", number)))
+}
+
+pub fn edit_local_issue(
+    repo: &str,
+    number: u64,
+    title: &str,
+    body: &str,
+) -> Result<(), StoreError> {
+    let conn = open_store(repo)?;
+    let updated = conn
+        .execute(
+            "UPDATE issues SET title = ?2, body = ?3 WHERE number = ?1",
+            params![number, title, body],
+        )
+        .map_err(StoreError::db("update local issue"))?;
+    if updated == 0 {
+        return Err(StoreError::NotFound(format!("Local issue #{}", number)));
     }
+    Ok(())
 }
 
-pub fn close_local_issue(repo: &str, number: u64) -> Result<(), String> {
-    let mut store = load_store(repo);
-    if let Some(issue) = store.issues.iter_mut().find(|i| i.number == number) {
-        issue.state = "closed".to_string();
-        save_store(repo, &store)?;
-        Ok(())
-    } else {
-        Err(format!("Local issue #{} not found", number))
+pub fn close_local_issue(repo: &str, number: u64) -> Result<(), StoreError> {
+    let conn = open_store(repo)?;
+    let updated = conn
+        .execute(
+            "UPDATE issues SET state = 'closed' WHERE number = ?1",
+            params![number],
+        )
+        .map_err(StoreError::db("close local issue"))?;
+    if updated == 0 {
+        return Err(StoreError::NotFound(format!("Local issue #{}", number)));
     }
+    Ok(())
 }
 
-pub fn fetch_local_prs(repo: &str, state: StateFilter, _assignee: AssigneeFilter) -> Vec<Card> {
-    let store = load_store(repo);
+pub fn fetch_local_prs(
+    repo: &str,
+    state: StateFilter,
+    _assignee: AssigneeFilter,
+) -> Result<Vec<Card>, StoreError> {
     let state_label = match state {
         StateFilter::Open => "open",
         StateFilter::Closed => "merged",
     };
-    let mut cards: Vec<Card> = store
-        .prs
+    let conn = open_store(repo)?;
+    let prs = load_prs(&conn, Some(state_label)).map_err(StoreError::db("read local PRs"))?;
+    let links = crate::git::fetch_issue_links();
+    let bases: HashMap<String, String> = prs
+        .iter()
+        .filter_map(|pr| Some((pr.branch.clone(), pr.base.clone()?)))
+        .collect();
+    let mut cards: Vec<Card> = prs
         .iter()
-        .filter(|pr| pr.state == state_label)
         .map(|pr| {
-            let description = if pr.body.len() > 80 {
-                format!("{}...", &pr.body[..77])
-            } else if pr.body.is_empty() {
-                pr.branch.clone()
-            } else {
-                pr.body.clone()
-            };
+            let description = short_description(&pr.body).unwrap_or_else(|| match &pr.base {
+                Some(base) => format!("{} → {}", pr.branch, base),
+                None => pr.branch.clone(),
+            });
 
             let (tag, tag_color) = if pr.is_draft {
-                ("draft", Color::DarkGray)
+                ("draft", theme::current().dim)
             } else if pr.state == "merged" {
-                ("merged", Color::Magenta)
+                ("merged", theme::current().special)
             } else {
-                ("local", Color::Cyan)
+                ("local", theme::current().accent)
             };
 
-            let related = if pr.branch.starts_with("local-issue-") {
-                vec![pr.branch.clone()]
-            } else if let Some(num) = pr.branch.strip_prefix("issue-") {
-                vec![format!("issue-{}", num)]
-            } else {
-                Vec::new()
-            };
+            let related: Vec<String> = crate::git::issue_key_for_branch(&links, &pr.branch)
+                .into_iter()
+                .collect();
 
             Card {
                 id: format!("pr-{}", pr.number),
                 title: format!("#{} {}", pr.number, pr.title),
                 description,
-                full_description: None,
+                full_description: Some(pr.body.clone()).filter(|b| !b.is_empty()),
                 tag: tag.to_string(),
                 tag_color,
                 related,
-                url: None,
                 pr_number: Some(pr.number),
                 is_draft: Some(pr.is_draft),
                 is_merged: Some(pr.state == "merged"),
                 head_branch: Some(pr.branch.clone()),
-                is_assigned: None,
+                ..Default::default()
             }
         })
         .collect();
     cards.reverse();
-    cards
+    Ok(crate::models::order_stacks(cards, &bases))
 }
 
 pub fn create_local_pr(
@@ -232,64 +494,539 @@ pub fn create_local_pr(
     title: &str,
     body: &str,
     branch: &str,
+    base: Option<&str>,
     is_draft: bool,
-) -> Result<u64, String> {
-    let mut store = load_store(repo);
-    let number = store.next_pr_number;
-    store.next_pr_number += 1;
-    store.prs.push(LocalPr {
-        number,
-        title: title.to_string(),
-        body: body.to_string(),
-        branch: branch.to_string(),
-        state: "open".to_string(),
-        is_draft,
-    });
-    save_store(repo, &store)?;
+) -> Result<u64, StoreError> {
+    let mut conn = open_store(repo)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(StoreError::db("write local store"))?;
+    let number = next_number(&tx, "next_pr_number")
+        .and_then(|number| {
+            insert_pr(
+                &tx,
+                &LocalPr {
+                    number,
+                    title: title.to_string(),
+                    body: body.to_string(),
+                    branch: branch.to_string(),
+                    state: "open".to_string(),
+                    is_draft,
+                    base: base.map(String::from),
+                },
+            )?;
+            Ok(number)
+        })
+        .map_err(StoreError::db("create local PR"))?;
+    tx.commit().map_err(StoreError::db("write local store"))?;
     Ok(number)
 }
 
-pub fn mark_local_pr_ready(repo: &str, number: u64) -> Result<(), String> {
-    let mut store = load_store(repo);
-    if let Some(pr) = store.prs.iter_mut().find(|p| p.number == number) {
-        pr.is_draft = false;
-        save_store(repo, &store)?;
-        Ok(())
-    } else {
-        Err(format!("Local PR #{} not found", number))
+pub fn mark_local_pr_ready(repo: &str, number: u64) -> Result<(), StoreError> {
+    let conn = open_store(repo)?;
+    let updated = conn
+        .execute(
+            "UPDATE prs SET is_draft = 0 WHERE number = ?1",
+            params![number],
+        )
+        .map_err(StoreError::db("update local PR"))?;
+    if updated == 0 {
+        return Err(StoreError::NotFound(format!("Local PR #{}", number)));
     }
+    Ok(())
 }
 
-pub fn merge_local_pr(repo: &str, number: u64) -> Result<String, String> {
-    let mut store = load_store(repo);
-    if let Some(pr) = store.prs.iter_mut().find(|p| p.number == number) {
-        if pr.state == "merged" {
-            return Err("PR is already merged".to_string());
-        }
-        pr.state = "merged".to_string();
-        let branch = pr.branch.clone();
-        save_store(repo, &store)?;
-        Ok(branch)
-    } else {
-        Err(format!("Local PR #{} not found", number))
+pub fn merge_local_pr(repo: &str, number: u64) -> Result<String, StoreError> {
+    let mut conn = open_store(repo)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(StoreError::db("write local store"))?;
+    let pr: Option<(String, String)> = tx
+        .query_row(
+            "SELECT state, branch FROM prs WHERE number = ?1",
+            params![number],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(StoreError::db("read local PR"))?;
+    let Some((state, branch)) = pr else {
+        return Err(StoreError::NotFound(format!("Local PR #{}", number)));
+    };
+    if state == "merged" {
+        return Err(StoreError::Rejected("PR is already merged".to_string()));
     }
+    tx.execute(
+        "UPDATE prs SET state = 'merged' WHERE number = ?1",
+        params![number],
+    )
+    .map_err(StoreError::db("update local PR"))?;
+    tx.commit().map_err(StoreError::db("write local store"))?;
+    Ok(branch)
+}
+
+/// The base branch a local PR targets, if one was recorded.
+pub fn fetch_local_pr_base(repo: &str, number: u64) -> Option<String> {
+    let conn = open_store(repo).ok()?;
+    conn.query_row(
+        "SELECT base FROM prs WHERE number = ?1",
+        params![number],
+        |row| row.get(0),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Point the open local PRs for `branch` at a new base branch.
+pub fn retarget_local_prs(repo: &str, branch: &str, base: &str) -> Result<(), StoreError> {
+    let conn = open_store(repo)?;
+    conn.execute(
+        "UPDATE prs SET base = ?2 WHERE branch = ?1 AND state = 'open'",
+        params![branch, base],
+    )
+    .map_err(StoreError::db("update local PR"))?;
+    Ok(())
 }
 
 pub fn fetch_local_merged_pr_branches(repo: &str) -> Vec<String> {
-    let store = load_store(repo);
-    store
-        .prs
-        .iter()
-        .filter(|pr| pr.state == "merged")
-        .map(|pr| pr.branch.clone())
-        .collect()
+    let Ok(conn) = open_store(repo) else {
+        return Vec::new();
+    };
+    load_prs(&conn, Some("merged"))
+        .map(|prs| prs.into_iter().map(|pr| pr.branch).collect())
+        .unwrap_or_default()
 }
 
 /// Check if a local PR already exists for a given branch.
 pub fn has_local_pr_for_branch(repo: &str, branch: &str) -> bool {
-    let store = load_store(repo);
-    store
-        .prs
-        .iter()
-        .any(|pr| pr.branch == branch && pr.state == "open")
+    let Ok(conn) = open_store(repo) else {
+        return false;
+    };
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM prs WHERE branch = ?1 AND state = 'open')",
+        params![branch],
+        |row| row.get(0),
+    )
+    .unwrap_or(false)
+}
+
+/// Which kind of local item a GitHub link refers to.
+#[derive(Clone, Copy)]
+pub enum LinkKind {
+    Issue,
+    Pr,
+}
+
+impl LinkKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            LinkKind::Issue => "issue",
+            LinkKind::Pr => "pr",
+        }
+    }
+}
+
+/// Open local issues and PRs that have not been synced to GitHub yet.
+pub fn fetch_unsynced(repo: &str) -> Result<(Vec<LocalIssue>, Vec<LocalPr>), StoreError> {
+    let conn = open_store(repo)?;
+    let linked = |kind: LinkKind, number: u64| -> rusqlite::Result<bool> {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM github_links WHERE kind = ?1 AND local_number = ?2)",
+            params![kind.as_str(), number],
+            |row| row.get(0),
+        )
+    };
+    let read = || -> rusqlite::Result<(Vec<LocalIssue>, Vec<LocalPr>)> {
+        let mut issues = Vec::new();
+        for issue in load_issues(&conn, Some("open"))? {
+            if !linked(LinkKind::Issue, issue.number)? {
+                issues.push(issue);
+            }
+        }
+        let mut prs = Vec::new();
+        for pr in load_prs(&conn, Some("open"))? {
+            if !linked(LinkKind::Pr, pr.number)? {
+                prs.push(pr);
+            }
+        }
+        Ok((issues, prs))
+    };
+    read().map_err(StoreError::db("read local store"))
+}
+
+/// Look up the GitHub number a local issue or PR was synced to.
+pub fn github_link(repo: &str, kind: LinkKind, local_number: u64) -> Option<u64> {
+    let conn = open_store(repo).ok()?;
+    conn.query_row(
+        "SELECT github_number FROM github_links WHERE kind = ?1 AND local_number = ?2",
+        params![kind.as_str(), local_number],
+        |row| row.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+/// Remember that a local issue or PR now lives on GitHub under `github_number`.
+pub fn record_github_link(
+    repo: &str,
+    kind: LinkKind,
+    local_number: u64,
+    github_number: u64,
+) -> Result<(), StoreError> {
+    let conn = open_store(repo)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO github_links (kind, local_number, github_number)
+         VALUES (?1, ?2, ?3)",
+        params![kind.as_str(), local_number, github_number],
+    )
+    .map_err(StoreError::db("record GitHub link"))?;
+    Ok(())
+}
+
+/// How to resolve an imported issue or PR whose number is already taken.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConflictPolicy {
+    /// Give the imported item the next free number.
+    Renumber,
+    /// Keep the existing item and drop the imported one.
+    Skip,
+    /// Replace the existing item with the imported one.
+    Overwrite,
+}
+
+impl ConflictPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "renumber" => Some(ConflictPolicy::Renumber),
+            "skip" => Some(ConflictPolicy::Skip),
+            "overwrite" => Some(ConflictPolicy::Overwrite),
+            _ => None,
+        }
+    }
+}
+
+/// Counts of what an import did (or would do, for a dry run).
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub issues_added: usize,
+    pub issues_renumbered: usize,
+    pub issues_skipped: usize,
+    pub issues_overwritten: usize,
+    pub prs_added: usize,
+    pub prs_renumbered: usize,
+    pub prs_skipped: usize,
+    pub prs_overwritten: usize,
+}
+
+impl ImportReport {
+    pub fn describe(&self) -> String {
+        format!(
+            "issues: {} new, {} renumbered, {} skipped, {} overwritten; PRs: {} new, {} renumbered, {} skipped, {} overwritten",
+            self.issues_added,
+            self.issues_renumbered,
+            self.issues_skipped,
+            self.issues_overwritten,
+            self.prs_added,
+            self.prs_renumbered,
+            self.prs_skipped,
+            self.prs_overwritten
+        )
+    }
+}
+
+/// Snapshot the whole local store, including closed issues and merged PRs.
+pub fn export_store(repo: &str) -> Result<LocalStore, StoreError> {
+    let conn = open_store(repo)?;
+    let read = || -> rusqlite::Result<LocalStore> {
+        let counter = |name: &str| -> rusqlite::Result<u64> {
+            conn.query_row(
+                "SELECT value FROM counters WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+        };
+        Ok(LocalStore {
+            issues: load_issues(&conn, None)?,
+            prs: load_prs(&conn, None)?,
+            next_issue_number: counter("next_issue_number")?,
+            next_pr_number: counter("next_pr_number")?,
+        })
+    };
+    read().map_err(StoreError::db("read local store"))
+}
+
+/// The stored form of an issue (`pr: false`) or PR state, matched
+/// case-insensitively; empty means open. `None` for a state the board has
+/// no column for.
+pub fn normalize_state(state: &str, pr: bool) -> Option<&'static str> {
+    match state.trim().to_lowercase().as_str() {
+        "" | "open" => Some("open"),
+        "closed" if !pr => Some("closed"),
+        "merged" if pr => Some("merged"),
+        _ => None,
+    }
+}
+
+/// `store` with every state in its stored form, or an error naming the
+/// first issue or PR whose state is unknown.
+fn normalize_states(store: &LocalStore) -> Result<LocalStore, StoreError> {
+    let state = |kind: &str, number: u64, state: &str, pr: bool| {
+        normalize_state(state, pr).map(String::from).ok_or_else(|| {
+            StoreError::Rejected(format!("{} #{}: unknown state '{}'", kind, number, state))
+        })
+    };
+    Ok(LocalStore {
+        issues: store
+            .issues
+            .iter()
+            .map(|issue| {
+                Ok(LocalIssue {
+                    state: state("Issue", issue.number, &issue.state, false)?,
+                    ..issue.clone()
+                })
+            })
+            .collect::<Result<_, StoreError>>()?,
+        prs: store
+            .prs
+            .iter()
+            .map(|pr| {
+                Ok(LocalPr {
+                    state: state("PR", pr.number, &pr.state, true)?,
+                    ..pr.clone()
+                })
+            })
+            .collect::<Result<_, StoreError>>()?,
+        ..*store
+    })
+}
+
+/// Merge `store` into the local store. Number conflicts are resolved with
+/// `policy`; branches linked to a renumbered local issue are linked to its
+/// new number. With `dry_run` the import runs inside a transaction that is
+/// rolled back, so the report shows exactly what would happen.
+pub fn import_store(
+    repo: &str,
+    store: &LocalStore,
+    policy: ConflictPolicy,
+    dry_run: bool,
+) -> Result<ImportReport, StoreError> {
+    let store = normalize_states(store)?;
+    let links = crate::git::fetch_issue_links();
+    let mut conn = open_store(repo)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(StoreError::db("write local store"))?;
+    let (report, relinks) = import_into(&tx, &store, policy, &links)
+        .map_err(StoreError::db("import into local store"))?;
+    if dry_run { tx.rollback() } else { tx.commit() }
+        .map_err(StoreError::db("write local store"))?;
+    if !dry_run {
+        for (branch, issue_key) in relinks {
+            crate::log::warn_on_err("git", crate::git::link_branch_to_issue(&branch, &issue_key));
+        }
+    }
+    Ok(report)
+}
+
+/// Insert `store`, returning what was done and the branches to link to a
+/// renumbered issue, as branch and issue key. `links` are the recorded
+/// branch → issue key links.
+fn import_into(
+    conn: &Connection,
+    store: &LocalStore,
+    policy: ConflictPolicy,
+    links: &HashMap<String, String>,
+) -> rusqlite::Result<(ImportReport, Vec<(String, String)>)> {
+    let exists = |table: &str, number: u64| -> rusqlite::Result<bool> {
+        conn.query_row(
+            &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE number = ?1)", table),
+            params![number],
+            |row| row.get(0),
+        )
+    };
+    let mut report = ImportReport::default();
+    let mut renumbered_issues = HashMap::new();
+    let mut relinks = Vec::new();
+
+    for issue in &store.issues {
+        let mut issue = issue.clone();
+        if !exists("issues", issue.number)? {
+            report.issues_added += 1;
+        } else {
+            match policy {
+                ConflictPolicy::Skip => {
+                    report.issues_skipped += 1;
+                    continue;
+                }
+                ConflictPolicy::Overwrite => report.issues_overwritten += 1,
+                ConflictPolicy::Renumber => {
+                    let number = next_number(conn, "next_issue_number")?;
+                    renumbered_issues.insert(issue.number, number);
+                    issue.number = number;
+                    report.issues_renumbered += 1;
+                }
+            }
+        }
+        insert_issue(conn, &issue)?;
+        bump_counter(conn, "next_issue_number", issue.number + 1)?;
+    }
+
+    for pr in &store.prs {
+        let mut pr = pr.clone();
+        // The PR stays on its branch; the branch follows its issue
+        if let Some(new_number) = crate::git::issue_key_for_branch(links, &pr.branch)
+            .and_then(|key| key.strip_prefix("local-issue-")?.parse::<u64>().ok())
+            .and_then(|n| renumbered_issues.get(&n))
+        {
+            relinks.push((pr.branch.clone(), format!("local-issue-{}", new_number)));
+        }
+        if !exists("prs", pr.number)? {
+            report.prs_added += 1;
+        } else {
+            match policy {
+                ConflictPolicy::Skip => {
+                    report.prs_skipped += 1;
+                    continue;
+                }
+                ConflictPolicy::Overwrite => report.prs_overwritten += 1,
+                ConflictPolicy::Renumber => {
+                    pr.number = next_number(conn, "next_pr_number")?;
+                    report.prs_renumbered += 1;
+                }
+            }
+        }
+        insert_pr(conn, &pr)?;
+        bump_counter(conn, "next_pr_number", pr.number + 1)?;
+    }
+
+    Ok((report, relinks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Sandbox;
+
+    fn issue(number: u64) -> LocalIssue {
+        LocalIssue {
+            number,
+            title: format!("Issue {}", number),
+            body: "é".repeat(100),
+            state: "open".to_string(),
+            labels: vec!["bug".to_string()],
+        }
+    }
+
+    fn write_legacy_store(repo: &str, data: &str) {
+        fs::create_dir_all(store_dir(repo)).unwrap();
+        fs::write(legacy_store_path(repo), data).unwrap();
+    }
+
+    #[test]
+    fn imported_prs_follow_their_renumbered_issue() {
+        let Some(_sandbox) =
+            Sandbox::enter("local::tests::imported_prs_follow_their_renumbered_issue")
+        else {
+            return;
+        };
+        let repo = "acme/widgets";
+        create_local_issue(repo, "Existing", "", &[]).unwrap();
+        crate::git::link_branch_to_issue("feat/1-login", "local-issue-1").unwrap();
+
+        let pr = LocalPr {
+            number: 1,
+            title: "Fix login".to_string(),
+            body: String::new(),
+            branch: "feat/1-login".to_string(),
+            state: "Open".to_string(),
+            is_draft: false,
+            base: None,
+        };
+        let store = LocalStore {
+            issues: vec![issue(1)],
+            prs: vec![pr],
+            ..LocalStore::default()
+        };
+        import_store(repo, &store, ConflictPolicy::Renumber, false).unwrap();
+        assert_eq!(
+            crate::git::fetch_issue_links()
+                .get("feat/1-login")
+                .map(String::as_str),
+            Some("local-issue-2")
+        );
+        let prs = export_store(repo).unwrap().prs;
+        assert_eq!(prs[0].branch, "feat/1-login");
+        assert_eq!(prs[0].state, "open");
+
+        // States the board has no column for are refused
+        let store = LocalStore {
+            issues: vec![LocalIssue {
+                state: "done".to_string(),
+                ..issue(5)
+            }],
+            ..LocalStore::default()
+        };
+        assert!(matches!(
+            import_store(repo, &store, ConflictPolicy::Renumber, true),
+            Err(StoreError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn migrates_imports_and_numbers_the_store() {
+        let Some(_sandbox) = Sandbox::enter("local::tests::migrates_imports_and_numbers_the_store")
+        else {
+            return;
+        };
+
+        // A new database gets the whole schema and its counters
+        let conn = open_store("acme/empty").unwrap();
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        assert_eq!(next_number(&conn, "next_pr_number").unwrap(), 1);
+
+        // A legacy store whose counter is behind its issues numbers after them
+        let store = LocalStore {
+            issues: vec![issue(3), issue(7)],
+            next_issue_number: 2,
+            ..LocalStore::default()
+        };
+        write_legacy_store("acme/legacy", &serde_json::to_string(&store).unwrap());
+        let cards =
+            fetch_local_issues("acme/legacy", StateFilter::Open, AssigneeFilter::All).unwrap();
+        // Multi-byte bodies are shortened by character
+        assert!(cards[0].description.ends_with("..."));
+        assert!(!legacy_store_path("acme/legacy").exists());
+        assert_eq!(
+            create_local_issue("acme/legacy", "New", "", &[]).unwrap(),
+            8
+        );
+
+        // An unreadable one is reported and kept for the user to fix
+        write_legacy_store("acme/corrupt", "{ not json");
+        assert!(matches!(
+            open_store("acme/corrupt"),
+            Err(StoreError::Corrupt(_))
+        ));
+        assert!(legacy_store_path("acme/corrupt").exists());
+
+        // Writers racing for numbers never get the same one
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    (0..10)
+                        .map(|_| create_local_issue("acme/busy", "Race", "", &[]).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut numbers: Vec<u64> = threads
+            .into_iter()
+            .flat_map(|t| t.join().unwrap())
+            .collect();
+        numbers.sort();
+        assert_eq!(numbers, (1..=20).collect::<Vec<_>>());
+    }
 }
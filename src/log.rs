@@ -0,0 +1,33 @@
+//! Reporting for failures that should not stop what octopai is doing
+//! (a hook config that could not be written, an auto-assign that failed)
+//! but should not vanish either. Messages go to the board's message log
+//! once it exists, and to stderr before that (e.g. CLI subcommands).
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use crate::models::{push_message, MessageLog};
+
+static SINK: OnceLock<MessageLog> = OnceLock::new();
+
+/// Send later warnings to the board's message log.
+pub fn init(message_log: MessageLog) {
+    let _ = SINK.set(message_log);
+}
+
+/// Record a failure that was otherwise ignored, tagged with where it
+/// happened, e.g. `warn("hooks", e)` → `[hooks] ...`.
+pub fn warn(context: &str, err: impl Display) {
+    let message = format!("[{}] {}", context, err);
+    match SINK.get() {
+        Some(log) => push_message(log, &message),
+        None => eprintln!("octopai: {}", message),
+    }
+}
+
+/// Log the error of a result whose failure is not fatal.
+pub fn warn_on_err<T, E: Display>(context: &str, result: Result<T, E>) {
+    if let Err(e) = result {
+        warn(context, e);
+    }
+}
@@ -1,12 +1,28 @@
+mod action;
 mod app;
+mod arrange;
 mod config;
 mod deps;
+mod effect;
+mod error;
 mod git;
 mod github;
 mod hooks;
+mod keymap;
 mod local;
+mod log;
+mod markdown;
 mod models;
+mod query;
+mod repo_state;
 mod session;
+mod sync;
+mod templates;
+#[cfg(test)]
+mod testing;
+mod textarea;
+mod theme;
+mod transfer;
 mod ui;
 
 use std::collections::HashMap;
@@ -19,36 +35,39 @@ use std::time::Duration;
 
 use color_eyre::Result;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent,
+    },
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 
+use ratatui::backend::Backend;
+use ratatui::layout::{Position, Rect};
+use ratatui::{Frame, Terminal};
+
+use action::Action;
 use app::App;
 use config::{
-    get_auto_open_pr, get_editor_command, get_multiplexer, get_pr_ready, get_session_command,
-    get_verify_command, load_config, save_config, set_editor_command, set_verify_command,
+    get_auto_open_pr, get_multiplexer, get_pr_ready, get_session_command, load_config, save_config,
+    set_editor_command, set_verify_command,
 };
 use deps::{
     check_dependencies, compound_choices, detect_ai_tools, detect_package_manager, gh_available,
     has_missing_required, install_command,
 };
-use git::{
-    detect_current_repo, detect_repo_from_git, extract_issue_number, fetch_worktrees, pull_main,
-    remove_worktree,
-};
-use github::{close_issue, create_issue, edit_issue, fetch_issue, fetch_prs, fetch_repos};
+use effect::Effect;
+use error::Error;
+use git::{detect_current_repo, detect_repo_from_git, extract_issue_number, fetch_worktrees};
+use github::{create_issue, edit_issue, fetch_repos};
 use hooks::start_event_socket;
 use models::{
-    AiSetupState, ConfigEditState, ConfirmAction, ConfirmModal, DepInstallConfirm, EditIssueModal,
-    IssueEditResult, IssueModal, IssueSubmitResult, MergeStrategy, MessageLog, Mode,
-    RepoSelectPhase, Screen, SectionData, SessionStates, StateFilter, TextInput,
-    WorktreeCreateResult, SOCKET_PATH,
-};
-use session::{
-    create_session_for_worktree, create_worktree_and_session, ensure_main_session,
-    expand_editor_command, fetch_sessions, Multiplexer, MAIN_SESSION_NAME,
+    AiSetupState, DepInstallConfirm, IssueEditResult, IssueSubmitResult, MessageLog, Mode,
+    RepoSelectPhase, Screen, SectionData, SessionStates, TextInput, WorktreeCreateResult,
+    SOCKET_PATH,
 };
+use session::{create_worktree_and_session, expand_editor_command, fetch_sessions, Multiplexer};
 use ui::{ui, ui_configuration, ui_dependencies, ui_repo_select};
 
 fn main() -> Result<()> {
@@ -57,16 +76,31 @@ fn main() -> Result<()> {
         println!("octopai {}", env!("CARGO_PKG_VERSION"));
         return Ok(());
     }
+    if let Some(result) = transfer::run_cli(&args) {
+        match result {
+            Ok(summary) => println!("{}", summary),
+            Err(e) => {
+                eprintln!("octopai: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
 
     color_eyre::install()?;
 
     // Start the Unix socket event server for Claude hook events
     let session_states: SessionStates = Arc::new(Mutex::new(HashMap::new()));
     let message_log: MessageLog = Arc::new(Mutex::new(std::collections::VecDeque::new()));
+    log::init(Arc::clone(&message_log));
+    theme::init(theme::Theme::load());
     start_event_socket(Arc::clone(&session_states), Arc::clone(&message_log))?;
 
     enable_raw_mode()?;
-    io::stdout().execute(EnterAlternateScreen)?;
+    io::stdout()
+        .execute(EnterAlternateScreen)?
+        .execute(EnableMouseCapture)?
+        .execute(EnableBracketedPaste)?;
 
     let mut terminal =
         ratatui::Terminal::new(ratatui::backend::CrosstermBackend::new(io::stdout()))?;
@@ -94,10 +128,10 @@ fn main() -> Result<()> {
             let (has_claude, has_cursor) = detect_ai_tools();
             match (has_claude, has_cursor) {
                 (true, false) => {
-                    let _ = config::set_default_session_command("{claude}");
+                    log::warn_on_err("config", config::set_default_session_command("{claude}"));
                 }
                 (false, true) => {
-                    let _ = config::set_default_session_command("{cursor}");
+                    log::warn_on_err("config", config::set_default_session_command("{cursor}"));
                 }
                 (true, true) => {
                     // Both installed — prompt user to choose
@@ -107,7 +141,7 @@ fn main() -> Result<()> {
                 _ => {
                     // Neither available — deps check should have caught this,
                     // but default to claude template as fallback
-                    let _ = config::set_default_session_command("{claude}");
+                    log::warn_on_err("config", config::set_default_session_command("{claude}"));
                 }
             }
         }
@@ -125,7 +159,7 @@ fn main() -> Result<()> {
 
             if let Some(repo) = repo {
                 app.repo = repo.clone();
-                let _ = save_config(&repo);
+                log::warn_on_err("config", save_config(&repo));
                 app.screen = Screen::Board;
                 app.selected_card = [0; 4];
                 app.start_async_refresh();
@@ -133,188 +167,75 @@ fn main() -> Result<()> {
         }
     }
 
-    loop {
-        terminal.draw(|frame| match app.screen {
-            Screen::RepoSelect => ui_repo_select(frame, &app.repo_select, app.local_mode),
-            Screen::Board => ui(frame, &app),
-            Screen::Dependencies => ui_dependencies(
-                frame,
-                &app.dependencies,
-                app.dep_selected,
-                app.dep_install_confirm.as_ref(),
-            ),
-            Screen::Configuration => ui_configuration(frame, &app),
-            Screen::AiSetup => {
-                if let Some(state) = &app.ai_setup {
-                    ui::ui_ai_setup(frame, state);
-                }
-            }
-        })?;
+    let result = run(&mut terminal, &mut app, &mut TerminalConsole);
 
-        // Auto-refresh when interval has elapsed and on Board screen in Normal mode
-        let auto_refresh_secs = config::get_auto_refresh_secs();
-        if auto_refresh_secs > 0
-            && app.screen == Screen::Board
-            && app.mode == Mode::Normal
-            && app.last_refresh.elapsed() >= Duration::from_secs(auto_refresh_secs)
-            && !app.is_section_loading()
-        {
-            app.start_async_refresh();
-        }
+    disable_raw_mode()?;
+    io::stdout()
+        .execute(DisableBracketedPaste)?
+        .execute(DisableMouseCapture)?
+        .execute(LeaveAlternateScreen)?;
+    let _ = fs::remove_file(SOCKET_PATH);
+    result
+}
 
-        // Delayed refresh after PR merge (gives GitHub API time to propagate)
-        if app.screen == Screen::Board
-            && app.mode == Mode::Normal
-            && !app.is_section_loading()
-            && app
-                .pending_refresh
-                .is_some_and(|t| t <= std::time::Instant::now())
-        {
-            app.pending_refresh = None;
-            app.start_async_refresh();
-        }
+/// What the event loop does after a key press.
+enum Flow {
+    Continue,
+    Quit,
+}
 
-        // Track whether any async result was received so we can re-render immediately
-        let mut needs_redraw = false;
+/// The terminal the board reads keys from, and hands over to other programs
+/// (an attached session, a dependency install) while they run.
+trait Console {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+    fn read(&mut self) -> io::Result<Event>;
+    fn suspend(&mut self) -> io::Result<()>;
+    fn resume(&mut self) -> io::Result<()>;
+}
 
-        // Poll per-section async refresh results
-        if let Some(rx) = &app.section_rx {
-            while let Ok(data) = rx.try_recv() {
-                needs_redraw = true;
-                match data {
-                    SectionData::Issues(issues) => {
-                        app.issues = issues;
-                        app.section_loading[0] = false;
-                    }
-                    SectionData::Worktrees(worktrees) => {
-                        app.worktrees = worktrees;
-                        app.section_loading[1] = false;
-                    }
-                    SectionData::Sessions(sessions) => {
-                        app.sessions = sessions;
-                        app.section_loading[2] = false;
-                    }
-                    SectionData::PullRequests(prs) => {
-                        app.pull_requests = prs;
-                        app.section_loading[3] = false;
-                    }
-                    SectionData::MainBehindCount(count) => {
-                        app.main_behind_count = count;
-                    }
-                }
-            }
-            if !app.is_section_loading() {
-                app.section_rx = None;
-                app.post_refresh_cleanup();
-            }
-        }
+/// The real terminal, through crossterm.
+struct TerminalConsole;
 
-        // Check for issue submission results from background thread
-        if let Some(rx) = &app.issue_submit_rx {
-            if let Ok(result) = rx.try_recv() {
-                needs_redraw = true;
-                app.issue_submit_rx = None;
-                match result {
-                    IssueSubmitResult::Success {
-                        number,
-                        worktree_result,
-                        ..
-                    } => {
-                        app.issue_modal = None;
-                        app.mode = Mode::Normal;
-                        app.refresh_data();
-                        match worktree_result {
-                            Some(Ok(())) => {
-                                app.worktrees = fetch_worktrees();
-                                app.sessions = fetch_sessions(&app.session_states, app.multiplexer);
-                                app.clamp_selected();
-                                app.set_status(format!(
-                                    "Created issue #{} with worktree and session",
-                                    number
-                                ));
-                            }
-                            Some(Err(e)) => {
-                                app.set_status(format!(
-                                    "Created issue #{} but failed to create worktree: {}",
-                                    number, e
-                                ));
-                            }
-                            None => {
-                                app.set_status(format!("Created issue #{}", number));
-                            }
-                        }
-                    }
-                    IssueSubmitResult::Error(e) => {
-                        if let Some(modal) = &mut app.issue_modal {
-                            modal.submitting = false;
-                            modal.error = Some(e);
-                        }
-                    }
-                }
-            }
-        }
+impl Console for TerminalConsole {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
 
-        // Check for issue edit results from background thread
-        if let Some(rx) = &app.issue_edit_rx {
-            if let Ok(result) = rx.try_recv() {
-                needs_redraw = true;
-                app.issue_edit_rx = None;
-                match result {
-                    IssueEditResult::Success { number } => {
-                        app.edit_issue_modal = None;
-                        app.mode = Mode::Normal;
-                        app.refresh_data();
-                        app.set_status(format!("Updated issue #{}", number));
-                    }
-                    IssueEditResult::Error(e) => {
-                        if let Some(modal) = &mut app.edit_issue_modal {
-                            modal.submitting = false;
-                            modal.error = Some(e);
-                        }
-                    }
-                }
-            }
-        }
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
 
-        // Check for worktree/session creation results from background thread
-        if let Some(rx) = &app.worktree_create_rx {
-            if let Ok(result) = rx.try_recv() {
-                needs_redraw = true;
-                app.worktree_create_rx = None;
-                app.loading_message = None;
-                match result {
-                    WorktreeCreateResult::WorktreeAndSession { number, result } => match result {
-                        Ok(()) => {
-                            app.worktrees = fetch_worktrees();
-                            app.sessions = fetch_sessions(&app.session_states, app.multiplexer);
-                            app.clamp_selected();
-                            app.last_refresh = std::time::Instant::now();
-                            app.set_status(format!(
-                                "Created worktree and session for issue #{}",
-                                number
-                            ));
-                        }
-                        Err(e) => {
-                            app.set_status(format!("Error: {}", e));
-                        }
-                    },
-                    WorktreeCreateResult::SessionOnly { branch, result } => match result {
-                        Ok(()) => {
-                            app.sessions = fetch_sessions(&app.session_states, app.multiplexer);
-                            app.clamp_selected();
-                            app.last_refresh = std::time::Instant::now();
-                            app.set_status(format!("Created session for '{}'", branch));
-                        }
-                        Err(e) => {
-                            app.set_status(format!("Error: {}", e));
-                        }
-                    },
-                }
-            }
-        }
+    fn suspend(&mut self) -> io::Result<()> {
+        disable_raw_mode()?;
+        io::stdout()
+            .execute(DisableBracketedPaste)?
+            .execute(DisableMouseCapture)?
+            .execute(LeaveAlternateScreen)?;
+        Ok(())
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        io::stdout()
+            .execute(EnterAlternateScreen)?
+            .execute(EnableMouseCapture)?
+            .execute(EnableBracketedPaste)?;
+        Ok(())
+    }
+}
+
+/// Draw the current screen, pick up background results and handle keys
+/// until the user quits.
+fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    console: &mut impl Console,
+) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
 
         // If any async result was received, immediately re-render before blocking on input
-        if needs_redraw {
+        if poll_background(app) {
             continue;
         }
 
@@ -322,12 +243,15 @@ fn main() -> Result<()> {
         let has_spinner = app.issue_submit_rx.is_some()
             || app.issue_edit_rx.is_some()
             || app.worktree_create_rx.is_some()
+            || app.sync_rx.is_some()
+            || app.effects_running > 0
             || app.is_section_loading();
         if has_spinner {
             app.spinner_tick = app.spinner_tick.wrapping_add(1);
         }
 
         // Poll for events with a short timeout so the refresh timer updates every second
+        let auto_refresh_secs = config::get_auto_refresh_secs();
         let poll_timeout = if has_spinner {
             // Fast polling for spinner animation
             Duration::from_millis(100)
@@ -342,1740 +266,1372 @@ fn main() -> Result<()> {
             Duration::from_secs(60)
         };
 
-        if !event::poll(poll_timeout)? {
+        if !console.poll(poll_timeout)? {
             continue;
         }
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind != KeyEventKind::Press {
-                continue;
+        match console.read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if let Flow::Quit = handle_key(terminal, app, console, key)? {
+                    return Ok(());
+                }
             }
+            Event::Mouse(mouse) => handle_mouse(terminal, app, console, mouse)?,
+            Event::Paste(text) => {
+                if let Flow::Quit = handle_paste(terminal, app, console, &text)? {
+                    return Ok(());
+                }
+            }
+            _ => {}
+        }
+    }
+}
 
-            match app.screen {
-                Screen::Dependencies => {
-                    if app.dep_install_confirm.is_some() {
-                        // Modal is active
-                        match key.code {
-                            KeyCode::Char('y') => {
-                                let confirm = app.dep_install_confirm.take().unwrap();
-                                let command = confirm.command.clone();
-
-                                // Suspend TUI
-                                disable_raw_mode()?;
-                                io::stdout().execute(LeaveAlternateScreen)?;
-
-                                // Run the install command
-                                let status = std::process::Command::new("sh")
-                                    .arg("-c")
-                                    .arg(&command)
-                                    .status();
-
-                                // Prompt to return
-                                match &status {
-                                    Ok(s) if s.success() => {
-                                        eprintln!(
-                                            "\n\x1b[32mInstall completed.\x1b[0m Press Enter to return..."
-                                        );
-                                    }
-                                    Ok(s) => {
-                                        eprintln!(
-                                            "\n\x1b[33mCommand exited with {}.\x1b[0m Press Enter to return...",
-                                            s
-                                        );
-                                    }
-                                    Err(e) => {
-                                        eprintln!(
-                                            "\n\x1b[31mFailed to run command: {}\x1b[0m Press Enter to return...",
-                                            e
-                                        );
-                                    }
-                                }
+/// Carry out the effects an action asked for: in the background, or with
+/// the terminal handed over for the ones that need it.
+fn perform<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    console: &mut impl Console,
+    effects: Vec<Effect>,
+) -> Result<()> {
+    for effect in effects {
+        if effect.is_foreground() {
+            console.suspend()?;
+            let outcome = effect::run(effect);
+            console.resume()?;
+            terminal.clear()?;
+            let effects = app.update(Action::Completed(outcome));
+            perform(terminal, app, console, effects)?;
+        } else {
+            app.spawn_effect(effect);
+        }
+    }
+    Ok(())
+}
 
-                                // Wait for Enter
-                                let mut buf = String::new();
-                                let _ = std::io::stdin().read_line(&mut buf);
-
-                                // Resume TUI
-                                enable_raw_mode()?;
-                                io::stdout().execute(EnterAlternateScreen)?;
-                                terminal = ratatui::Terminal::new(
-                                    ratatui::backend::CrosstermBackend::new(io::stdout()),
-                                )?;
-                                terminal.clear()?;
-
-                                // Re-check deps
-                                app.dependencies = check_dependencies();
-                                if app.dep_selected >= app.dependencies.len() {
-                                    app.dep_selected = app.dependencies.len().saturating_sub(1);
-                                }
+fn draw(frame: &mut Frame, app: &App) {
+    match app.screen {
+        Screen::RepoSelect => ui_repo_select(frame, &app.repo_select, app.local_mode),
+        Screen::Board => ui(frame, app),
+        Screen::Dependencies => ui_dependencies(
+            frame,
+            &app.dependencies,
+            app.dep_selected,
+            app.dep_install_confirm.as_ref(),
+        ),
+        Screen::Configuration => ui_configuration(frame, app),
+        Screen::AiSetup => {
+            if let Some(state) = &app.ai_setup {
+                ui::ui_ai_setup(frame, state);
+            }
+        }
+    }
+}
 
-                                let target = confirm.install_target;
-                                let still_missing = app
-                                    .dependencies
-                                    .iter()
-                                    .any(|d| !d.available && d.name.contains(&target));
-                                if still_missing {
-                                    app.set_status(format!(
-                                        "{} still not found after install",
-                                        target
-                                    ));
-                                } else {
-                                    app.set_status(format!("{} installed successfully", target));
-                                }
-                            }
-                            KeyCode::Char('n') | KeyCode::Esc => {
-                                app.dep_install_confirm = None;
-                            }
-                            _ => {}
+/// Pick up the timers and background results the board waits on.
+/// Returns whether anything changed that needs a redraw.
+fn poll_background(app: &mut App) -> bool {
+    // Auto-refresh when interval has elapsed and on Board screen in Normal mode
+    let auto_refresh_secs = config::get_auto_refresh_secs();
+    if auto_refresh_secs > 0
+        && app.screen == Screen::Board
+        && app.mode == Mode::Normal
+        && app.last_refresh.elapsed() >= Duration::from_secs(auto_refresh_secs)
+        && !app.is_section_loading()
+    {
+        app.start_async_refresh();
+    }
+
+    // Delayed refresh after PR merge (gives GitHub API time to propagate)
+    if app.screen == Screen::Board
+        && app.mode == Mode::Normal
+        && !app.is_section_loading()
+        && app
+            .pending_refresh
+            .is_some_and(|t| t <= std::time::Instant::now())
+    {
+        app.pending_refresh = None;
+        app.start_async_refresh();
+    }
+
+    // Track whether any async result was received so we can re-render immediately
+    let mut needs_redraw = false;
+
+    // Poll per-section async refresh results
+    if let Some(rx) = app.section_rx.take() {
+        while let Ok(data) = rx.try_recv() {
+            needs_redraw = true;
+            match data {
+                SectionData::Issues(issues) => {
+                    app.issues = issues;
+                    app.section_loading[0] = false;
+                }
+                SectionData::Worktrees(worktrees) => {
+                    app.worktrees = worktrees;
+                    app.section_loading[1] = false;
+                }
+                SectionData::Sessions(sessions) => {
+                    app.sessions = sessions;
+                    app.section_loading[2] = false;
+                }
+                SectionData::PullRequests(prs) => {
+                    app.pull_requests = prs;
+                    app.section_loading[3] = false;
+                }
+                SectionData::GithubBoard(board) => {
+                    app.apply_board(board);
+                    app.section_loading[0] = false;
+                    app.section_loading[3] = false;
+                }
+                SectionData::MainBehindCount(count) => {
+                    app.main_behind_count = count;
+                }
+            }
+        }
+        if app.is_section_loading() {
+            app.section_rx = Some(rx);
+        } else {
+            for effect in app.post_refresh_cleanup() {
+                app.spawn_effect(effect);
+            }
+        }
+    }
+
+    // Apply what finished effects came back with
+    for outcome in app.take_outcomes() {
+        needs_redraw = true;
+        for effect in app.update(Action::Completed(outcome)) {
+            app.spawn_effect(effect);
+        }
+    }
+
+    // Check for issue submission results from background thread
+    if let Some(rx) = &app.issue_submit_rx {
+        if let Ok(result) = rx.try_recv() {
+            needs_redraw = true;
+            app.issue_submit_rx = None;
+            match result {
+                IssueSubmitResult::Success {
+                    number,
+                    worktree_result,
+                    ..
+                } => {
+                    app.issue_modal = None;
+                    app.mode = Mode::Normal;
+                    app.refresh_data();
+                    match worktree_result {
+                        Some(Ok(())) => {
+                            app.worktrees = fetch_worktrees(&app.repo);
+                            app.sessions = fetch_sessions(&app.session_states, app.multiplexer);
+                            app.clamp_selected();
+                            app.set_status(format!(
+                                "Created issue #{} with worktree and session",
+                                number
+                            ));
                         }
-                    } else {
-                        // Normal deps screen keys
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Esc => {
-                                if app.repo.is_empty() {
-                                    break;
-                                } else {
-                                    app.screen = Screen::Board;
-                                }
-                            }
-                            KeyCode::Char('j') | KeyCode::Down => {
-                                if !app.dependencies.is_empty()
-                                    && app.dep_selected < app.dependencies.len() - 1
-                                {
-                                    app.dep_selected += 1;
-                                }
-                            }
-                            KeyCode::Char('k') | KeyCode::Up => {
-                                if app.dep_selected > 0 {
-                                    app.dep_selected -= 1;
-                                }
-                            }
-                            KeyCode::Char('i') => {
-                                if let Some(dep) = app.dependencies.get(app.dep_selected) {
-                                    if !dep.available {
-                                        let pm = detect_package_manager();
-                                        let target =
-                                            if let Some(choices) = compound_choices(dep.name) {
-                                                choices[0].to_string()
-                                            } else {
-                                                dep.name.to_string()
-                                            };
-                                        if let Some(cmd) = install_command(&target, pm) {
-                                            app.dep_install_confirm = Some(DepInstallConfirm {
-                                                install_target: target,
-                                                command: cmd,
-                                            });
-                                        } else {
-                                            app.set_status(format!(
-                                                "No known install command for {} — install it manually",
-                                                target
-                                            ));
-                                        }
-                                    }
-                                }
-                            }
-                            KeyCode::Char('r') | KeyCode::Char('R') => {
-                                app.dependencies = check_dependencies();
-                                if app.dep_selected >= app.dependencies.len() {
-                                    app.dep_selected = app.dependencies.len().saturating_sub(1);
-                                }
-                            }
-                            KeyCode::Enter => {
-                                if has_missing_required(&app.dependencies) {
-                                    // Stay on deps screen
-                                } else if app.repo.is_empty() {
-                                    app.screen = Screen::RepoSelect;
-                                } else {
-                                    app.screen = Screen::Board;
-                                }
-                            }
-                            _ => {}
+                        Some(Err(e)) => {
+                            app.set_status(format!(
+                                "Created issue #{} but failed to create worktree: {}",
+                                number, e
+                            ));
+                        }
+                        None => {
+                            app.set_status(format!("Created issue #{}", number));
                         }
                     }
                 }
-                Screen::AiSetup => {
-                    if let Some(setup) = &mut app.ai_setup {
-                        match key.code {
-                            KeyCode::Char('j') | KeyCode::Down => {
-                                if setup.selected == 0 {
-                                    setup.selected = 1;
-                                }
-                            }
-                            KeyCode::Char('k') | KeyCode::Up => {
-                                if setup.selected == 1 {
-                                    setup.selected = 0;
-                                }
-                            }
-                            KeyCode::Enter => {
-                                let cmd = if setup.selected == 0 {
-                                    "{claude}"
-                                } else {
-                                    "{cursor}"
-                                };
-                                let _ = config::set_default_session_command(cmd);
-                                app.ai_setup = None;
-
-                                // Now proceed with repo detection
-                                let detected_repo = detect_current_repo();
-                                let configured_repo =
-                                    load_config().map(|c| c.repo).filter(|r| !r.is_empty());
-                                let repo = detected_repo.or(configured_repo);
-
-                                if let Some(repo) = repo {
-                                    app.repo = repo.clone();
-                                    let _ = save_config(&repo);
-                                    app.selected_card = [0; 4];
-                                    app.screen = Screen::Board;
-                                    app.start_async_refresh();
-                                } else {
-                                    app.screen = Screen::RepoSelect;
-                                }
+                IssueSubmitResult::Error(e) => {
+                    if let Some(modal) = &mut app.issue_modal {
+                        modal.submitting = false;
+                        modal.error = Some(e);
+                    }
+                }
+            }
+        }
+    }
+
+    // Check for issue edit results from background thread
+    if let Some(rx) = &app.issue_edit_rx {
+        if let Ok(result) = rx.try_recv() {
+            needs_redraw = true;
+            app.issue_edit_rx = None;
+            match result {
+                IssueEditResult::Success { number } => {
+                    app.edit_issue_modal = None;
+                    app.mode = Mode::Normal;
+                    app.refresh_data();
+                    app.set_status(format!("Updated issue #{}", number));
+                }
+                IssueEditResult::Error(e) => {
+                    if let Some(modal) = &mut app.edit_issue_modal {
+                        modal.submitting = false;
+                        modal.error = Some(e);
+                    }
+                }
+            }
+        }
+    }
+
+    // Check for worktree/session creation results from background thread,
+    // which sends one per issue and is done when it hangs up
+    let mut created = Vec::new();
+    if let Some(rx) = &app.worktree_create_rx {
+        loop {
+            match rx.try_recv() {
+                Ok(result) => created.push(result),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    needs_redraw = true;
+                    app.worktree_create_rx = None;
+                    app.loading_message = None;
+                    break;
+                }
+            }
+        }
+    }
+    for result in created {
+        needs_redraw = true;
+        match result {
+            WorktreeCreateResult::WorktreeAndSession { number, result } => match result {
+                Ok(()) => {
+                    app.worktrees = fetch_worktrees(&app.repo);
+                    app.sessions = fetch_sessions(&app.session_states, app.multiplexer);
+                    app.clamp_selected();
+                    app.last_refresh = std::time::Instant::now();
+                    app.set_status(format!(
+                        "Created worktree and session for issue #{}",
+                        number
+                    ));
+                }
+                Err(e) => {
+                    app.set_status(format!("Error: issue #{}: {}", number, e));
+                }
+            },
+            WorktreeCreateResult::SessionOnly { branch, result } => match result {
+                Ok(()) => {
+                    app.sessions = fetch_sessions(&app.session_states, app.multiplexer);
+                    app.clamp_selected();
+                    app.last_refresh = std::time::Instant::now();
+                    app.set_status(format!("Created session for '{}'", branch));
+                }
+                Err(e) => {
+                    app.set_status(format!("Error: {}", e));
+                }
+            },
+        }
+    }
+
+    // Check for local-to-GitHub sync results from background thread
+    if let Some(rx) = &app.sync_rx {
+        if let Ok(result) = rx.try_recv() {
+            needs_redraw = true;
+            app.sync_rx = None;
+            app.loading_message = None;
+            match result {
+                Ok(summary) => {
+                    // Carry hook-reported state over to the renamed sessions
+                    if let Ok(mut states) = app.session_states.lock() {
+                        for (old, new) in &summary.renamed_sessions {
+                            if let Some(state) = states.remove(old) {
+                                states.insert(new.clone(), state);
                             }
-                            _ => {}
                         }
                     }
+                    app.set_status(summary.describe());
+                    app.start_async_refresh();
                 }
-                Screen::Configuration => {
-                    if let Some(config_edit) = &mut app.config_edit {
-                        match key.code {
-                            KeyCode::Esc => {
-                                app.config_edit = None;
-                                app.screen = Screen::Board;
-                            }
-                            KeyCode::Tab => {
-                                config_edit.active_field = (config_edit.active_field + 1) % 7;
-                            }
-                            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                                let verify_cmd =
-                                    config_edit.verify_command.value().trim().to_string();
-                                let editor_cmd =
-                                    config_edit.editor_command.value().trim().to_string();
-                                let claude_cmd =
-                                    config_edit.session_command.value().trim().to_string();
-                                let repo = app.repo.clone();
-
-                                let pr_ready = config_edit.pr_ready;
-                                let auto_open_pr = config_edit.auto_open_pr;
-
-                                if let Some(mut config) = load_config() {
-                                    if verify_cmd.is_empty() {
-                                        config.verify_commands.remove(&repo);
-                                    } else {
-                                        config
-                                            .verify_commands
-                                            .insert(repo.clone(), verify_cmd.clone());
-                                    }
-                                    if editor_cmd.is_empty() {
-                                        config.editor_commands.remove(&repo);
-                                    } else {
-                                        config
-                                            .editor_commands
-                                            .insert(repo.clone(), editor_cmd.clone());
-                                    }
-                                    if pr_ready {
-                                        config.pr_ready.insert(repo.clone(), true);
-                                    } else {
-                                        config.pr_ready.remove(&repo);
-                                    }
-                                    if !auto_open_pr {
-                                        config.auto_open_pr.insert(repo.clone(), false);
-                                    } else {
-                                        config.auto_open_pr.remove(&repo);
-                                    }
-                                    if claude_cmd.is_empty() {
-                                        config.session_commands.remove(&repo);
-                                    } else {
-                                        config
-                                            .session_commands
-                                            .insert(repo.clone(), claude_cmd.clone());
-                                    }
-                                    config.multiplexer = Some(config_edit.multiplexer);
-                                    let refresh_val: u64 = config_edit
-                                        .refresh_interval
-                                        .value()
-                                        .trim()
-                                        .parse()
-                                        .unwrap_or(0);
-                                    config.auto_refresh_secs = if refresh_val == 0 {
-                                        None
-                                    } else {
-                                        Some(refresh_val)
-                                    };
-                                    let _ = config::save_full_config(&config);
-                                }
+                Err(e) => {
+                    app.set_status(format!("Sync failed: {}", e));
+                }
+            }
+        }
+    }
 
-                                app.multiplexer = config_edit.multiplexer;
-                                app.set_status("Configuration saved".to_string());
-                                app.config_edit = None;
-                                app.screen = Screen::Board;
-                            }
-                            KeyCode::Backspace => match config_edit.active_field {
-                                0 => config_edit.verify_command.delete_back(),
-                                1 => config_edit.editor_command.delete_back(),
-                                4 => config_edit.session_command.delete_back(),
-                                6 => config_edit.refresh_interval.delete_back(),
-                                _ => {}
-                            },
-                            KeyCode::Left => match config_edit.active_field {
-                                0 => config_edit.verify_command.move_left(),
-                                1 => config_edit.editor_command.move_left(),
-                                4 => config_edit.session_command.move_left(),
-                                6 => config_edit.refresh_interval.move_left(),
-                                _ => {}
-                            },
-                            KeyCode::Right => match config_edit.active_field {
-                                0 => config_edit.verify_command.move_right(),
-                                1 => config_edit.editor_command.move_right(),
-                                4 => config_edit.session_command.move_right(),
-                                6 => config_edit.refresh_interval.move_right(),
-                                _ => {}
-                            },
-                            KeyCode::Home => match config_edit.active_field {
-                                0 => config_edit.verify_command.move_home(),
-                                1 => config_edit.editor_command.move_home(),
-                                4 => config_edit.session_command.move_home(),
-                                6 => config_edit.refresh_interval.move_home(),
-                                _ => {}
-                            },
-                            KeyCode::End => match config_edit.active_field {
-                                0 => config_edit.verify_command.move_end(),
-                                1 => config_edit.editor_command.move_end(),
-                                4 => config_edit.session_command.move_end(),
-                                6 => config_edit.refresh_interval.move_end(),
-                                _ => {}
-                            },
-                            KeyCode::Char(' ') | KeyCode::Enter
-                                if config_edit.active_field == 2 =>
-                            {
-                                config_edit.pr_ready = !config_edit.pr_ready;
-                            }
-                            KeyCode::Char(' ') | KeyCode::Enter
-                                if config_edit.active_field == 3 =>
-                            {
-                                config_edit.auto_open_pr = !config_edit.auto_open_pr;
-                            }
-                            KeyCode::Char(' ') | KeyCode::Enter
-                                if config_edit.active_field == 5 =>
-                            {
-                                config_edit.multiplexer = match config_edit.multiplexer {
-                                    Multiplexer::Tmux => Multiplexer::Screen,
-                                    Multiplexer::Screen => Multiplexer::Tmux,
-                                };
+    needs_redraw
+}
+
+/// Clicks and the scroll wheel, on the board and its confirmation modal.
+fn handle_mouse<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    console: &mut impl Console,
+    mouse: MouseEvent,
+) -> Result<()> {
+    let area = Rect::from((Position::ORIGIN, terminal.size()?));
+    if let Some(action) = action::from_mouse(app, mouse, area) {
+        let effects = app.update(action);
+        perform(terminal, app, console, effects)?;
+    }
+    Ok(())
+}
+
+/// Pasted text: in one piece into an issue body, typed out key by key
+/// anywhere else, as it was before the terminal told pastes apart.
+fn handle_paste<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    console: &mut impl Console,
+    text: &str,
+) -> Result<Flow> {
+    let body = match app.mode {
+        Mode::CreatingIssue => app
+            .issue_modal
+            .as_mut()
+            .filter(|m| m.active_field == 1 && !m.submitting)
+            .map(|m| &mut m.body),
+        Mode::EditingIssue => app
+            .edit_issue_modal
+            .as_mut()
+            .filter(|m| m.active_field == 1 && !m.submitting)
+            .map(|m| &mut m.body),
+        _ => None,
+    };
+    if let Some(body) = body {
+        body.insert_str(text);
+        return Ok(Flow::Continue);
+    }
+    for c in text.chars().filter(|c| *c != '\r') {
+        let code = if c == '\n' {
+            KeyCode::Enter
+        } else {
+            KeyCode::Char(c)
+        };
+        if let Flow::Quit = handle_key(terminal, app, console, KeyEvent::from(code))? {
+            return Ok(Flow::Quit);
+        }
+    }
+    Ok(Flow::Continue)
+}
+
+/// Hand `text` to the user's editor (`$VISUAL`, then `$EDITOR`, then `vi`)
+/// through a temp file and return what they saved, or why that failed.
+fn edit_in_editor<B: Backend>(
+    terminal: &mut Terminal<B>,
+    console: &mut impl Console,
+    text: &str,
+) -> Result<std::result::Result<String, String>> {
+    let path = match write_private_temp_file("issue.md", text) {
+        Ok(path) => path,
+        Err(e) => return Ok(Err(format!("Could not write the issue for editing: {}", e))),
+    };
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    console.suspend()?;
+    // Through the shell, so editors that need flags (`code --wait`) work.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    console.resume()?;
+    terminal.clear()?;
+
+    let result = match status {
+        Ok(s) if s.success() => fs::read_to_string(&path)
+            .map(|saved| saved.trim_end_matches('\n').to_string())
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e)),
+        Ok(s) => Err(format!("{} exited with {}", editor, s)),
+        Err(e) => Err(format!("Could not run {}: {}", editor, e)),
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::remove_dir_all(dir);
+    }
+    Ok(result)
+}
+
+/// Write `text` to a new file `name` in a fresh directory only the current
+/// user can enter, so other users can neither read it nor swap it for a
+/// symlink while the editor has it open.
+fn write_private_temp_file(name: &str, text: &str) -> std::io::Result<std::path::PathBuf> {
+    use std::io::Write;
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let dir = std::env::temp_dir().join(format!("octopai-{}-{}", std::process::id(), nanos));
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    let path = dir.join(name);
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()));
+    if let Err(e) = written {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// Handle one key press on the current screen.
+fn handle_key<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    console: &mut impl Console,
+    key: KeyEvent,
+) -> Result<Flow> {
+    match app.screen {
+        Screen::Dependencies => {
+            if app.dep_install_confirm.is_some() {
+                // Modal is active
+                match key.code {
+                    KeyCode::Char('y') => {
+                        let confirm = app.dep_install_confirm.take().unwrap();
+                        let command = confirm.command.clone();
+
+                        // Suspend TUI
+                        console.suspend()?;
+
+                        // Run the install command
+                        let status = std::process::Command::new("sh")
+                            .arg("-c")
+                            .arg(&command)
+                            .status();
+
+                        // Prompt to return
+                        match &status {
+                            Ok(s) if s.success() => {
+                                eprintln!(
+                                    "\n\x1b[32mInstall completed.\x1b[0m Press Enter to return..."
+                                );
+                            }
+                            Ok(s) => {
+                                eprintln!(
+                                    "\n\x1b[33mCommand exited with {}.\x1b[0m Press Enter to return...",
+                                    s
+                                );
+                            }
+                            Err(e) => {
+                                eprintln!(
+                                    "\n\x1b[31mFailed to run command: {}\x1b[0m Press Enter to return...",
+                                    e
+                                );
                             }
-                            KeyCode::Char(c) => match config_edit.active_field {
-                                0 => config_edit.verify_command.insert(c),
-                                1 => config_edit.editor_command.insert(c),
-                                4 => config_edit.session_command.insert(c),
-                                6 if c.is_ascii_digit() => config_edit.refresh_interval.insert(c),
-                                _ => {}
-                            },
-                            _ => {}
+                        }
+
+                        // Wait for Enter
+                        let mut buf = String::new();
+                        let _ = std::io::stdin().read_line(&mut buf);
+
+                        // Resume TUI
+                        console.resume()?;
+                        terminal.clear()?;
+
+                        // Re-check deps
+                        app.dependencies = check_dependencies();
+                        if app.dep_selected >= app.dependencies.len() {
+                            app.dep_selected = app.dependencies.len().saturating_sub(1);
+                        }
+
+                        let target = confirm.install_target;
+                        let still_missing = app
+                            .dependencies
+                            .iter()
+                            .any(|d| !d.available && d.name.contains(&target));
+                        if still_missing {
+                            app.set_status(format!("{} still not found after install", target));
+                        } else {
+                            app.set_status(format!("{} installed successfully", target));
                         }
                     }
+                    KeyCode::Char('n') | KeyCode::Esc => {
+                        app.dep_install_confirm = None;
+                    }
+                    _ => {}
                 }
-                Screen::RepoSelect => {
-                    match app.repo_select.phase {
-                        RepoSelectPhase::Typing => match key.code {
-                            KeyCode::Esc => {
-                                if app.repo.is_empty() {
-                                    break; // quit if no board to return to
+            } else {
+                // Normal deps screen keys
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        if app.repo.is_empty() {
+                            return Ok(Flow::Quit);
+                        } else {
+                            app.screen = Screen::Board;
+                        }
+                    }
+                    KeyCode::Char('j') | KeyCode::Down
+                        if !app.dependencies.is_empty()
+                            && app.dep_selected < app.dependencies.len() - 1 =>
+                    {
+                        app.dep_selected += 1;
+                    }
+                    KeyCode::Char('k') | KeyCode::Up if app.dep_selected > 0 => {
+                        app.dep_selected -= 1;
+                    }
+                    KeyCode::Char('i') => {
+                        if let Some(dep) = app.dependencies.get(app.dep_selected) {
+                            if !dep.available {
+                                let pm = detect_package_manager();
+                                let target = if let Some(choices) = compound_choices(dep.name) {
+                                    choices[0].to_string()
                                 } else {
-                                    app.screen = Screen::Board;
-                                }
-                            }
-                            KeyCode::Enter => {
-                                let input_val = app.repo_select.input.value().trim().to_string();
-                                if input_val.is_empty() {
-                                    app.repo_select.error = Some(if app.local_mode {
-                                        "Please enter owner/repo (e.g. user/my-project)".into()
-                                    } else {
-                                        "Please enter an org or user name".into()
+                                    dep.name.to_string()
+                                };
+                                if let Some(cmd) = install_command(&target, pm) {
+                                    app.dep_install_confirm = Some(DepInstallConfirm {
+                                        install_target: target,
+                                        command: cmd,
                                     });
-                                } else if app.local_mode {
-                                    // In local mode, accept "owner/repo" directly
-                                    if input_val.contains('/') {
-                                        app.repo = input_val;
-                                        app.screen = Screen::Board;
-                                        app.start_async_refresh();
-                                    } else {
-                                        app.repo_select.error = Some(
-                                            "In local mode, enter full owner/repo (e.g. user/my-project)".into(),
-                                        );
-                                    }
                                 } else {
-                                    app.repo_select.error = None;
-                                    app.repo_select.phase = RepoSelectPhase::Loading;
-                                    // We need to redraw to show loading state, then fetch
-                                    terminal.draw(|frame| {
-                                        ui_repo_select(frame, &app.repo_select, app.local_mode)
-                                    })?;
-
-                                    match fetch_repos(&input_val) {
-                                        Ok(repos) => {
-                                            app.repo_select.repos = repos;
-                                            app.repo_select.filter_query.clear();
-                                            app.repo_select.update_filtered();
-                                            app.repo_select.selected = 0;
-                                            app.repo_select.phase = RepoSelectPhase::Picking;
-                                        }
-                                        Err(e) => {
-                                            app.repo_select.error = Some(e);
-                                            app.repo_select.phase = RepoSelectPhase::Typing;
-                                        }
-                                    }
+                                    app.set_status(format!(
+                                        "No known install command for {} — install it manually",
+                                        target
+                                    ));
                                 }
                             }
-                            KeyCode::Backspace => {
-                                app.repo_select.input.delete_back();
-                            }
-                            KeyCode::Left => {
-                                app.repo_select.input.move_left();
-                            }
-                            KeyCode::Right => {
-                                app.repo_select.input.move_right();
-                            }
-                            KeyCode::Home => {
-                                app.repo_select.input.move_home();
-                            }
-                            KeyCode::End => {
-                                app.repo_select.input.move_end();
-                            }
-                            KeyCode::Char(c) => {
-                                app.repo_select.input.insert(c);
-                            }
-                            _ => {}
-                        },
-                        RepoSelectPhase::Loading => {
-                            // No input during loading
                         }
-                        RepoSelectPhase::Picking => match key.code {
-                            KeyCode::Esc => {
-                                app.repo_select.phase = RepoSelectPhase::Typing;
-                                app.repo_select.filter_query.clear();
-                            }
-                            KeyCode::Enter => {
-                                if let Some(repo) =
-                                    app.repo_select.filtered_repos.get(app.repo_select.selected)
-                                {
-                                    let repo = repo.clone();
-                                    let _ = save_config(&repo);
-                                    app.repo = repo;
-                                    app.selected_card = [0; 4];
-                                    app.screen = Screen::Board;
-                                    app.start_async_refresh();
-                                }
-                            }
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                if app.repo_select.selected > 0 {
-                                    app.repo_select.selected -= 1;
-                                }
-                            }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                if !app.repo_select.filtered_repos.is_empty()
-                                    && app.repo_select.selected
-                                        < app.repo_select.filtered_repos.len() - 1
-                                {
-                                    app.repo_select.selected += 1;
-                                }
-                            }
-                            KeyCode::Char('/') => {
-                                // Toggle filter — if already filtering, this adds '/' to query
-                                // Start fresh filter
-                                app.repo_select.filter_query.clear();
-                                app.repo_select.update_filtered();
-                            }
-                            KeyCode::Backspace => {
-                                app.repo_select.filter_query.delete_back();
-                                app.repo_select.update_filtered();
-                            }
-                            KeyCode::Left => {
-                                app.repo_select.filter_query.move_left();
-                            }
-                            KeyCode::Right => {
-                                app.repo_select.filter_query.move_right();
-                            }
-                            KeyCode::Char(c) => {
-                                if c != '/' {
-                                    app.repo_select.filter_query.insert(c);
-                                    app.repo_select.update_filtered();
-                                }
-                            }
-                            _ => {}
-                        },
                     }
+                    KeyCode::Char('r') | KeyCode::Char('R') => {
+                        app.dependencies = check_dependencies();
+                        if app.dep_selected >= app.dependencies.len() {
+                            app.dep_selected = app.dependencies.len().saturating_sub(1);
+                        }
+                    }
+                    KeyCode::Enter => {
+                        if has_missing_required(&app.dependencies) {
+                            // Stay on deps screen
+                        } else if app.repo.is_empty() {
+                            app.screen = Screen::RepoSelect;
+                        } else {
+                            app.screen = Screen::Board;
+                        }
+                    }
+                    _ => {}
                 }
-                Screen::Board => {
-                    match &mut app.mode {
-                        Mode::Filtering { query, focused } if *focused => match key.code {
-                            KeyCode::Esc => {
-                                // Clear any server-side search when clearing the filter
-                                if app.active_section == 0
-                                    && !app.local_mode
-                                    && app.issue_search_query.is_some()
-                                {
-                                    app.issue_search_query = None;
-                                    app.start_async_refresh();
-                                }
-                                app.mode = Mode::Normal;
-                            }
-                            KeyCode::Enter => {
-                                // For the Issues column in GitHub mode, trigger
-                                // a server-side search so results come from GitHub
-                                // rather than filtering a small local set.
-                                if app.active_section == 0 && !app.local_mode {
-                                    let q = query.value().to_string();
-                                    if q.is_empty() {
-                                        if app.issue_search_query.is_some() {
-                                            app.issue_search_query = None;
-                                            app.start_async_refresh();
-                                        }
-                                    } else {
-                                        app.issue_search_query = Some(q);
-                                        app.start_async_refresh();
-                                    }
-                                }
-                                if let Mode::Filtering { focused, .. } = &mut app.mode {
-                                    *focused = false;
-                                }
-                            }
-                            KeyCode::Backspace => {
-                                query.delete_back();
-                                app.clamp_selected();
-                            }
-                            KeyCode::Left => {
-                                query.move_left();
-                            }
-                            KeyCode::Right => {
-                                query.move_right();
-                            }
-                            KeyCode::Home => {
-                                query.move_home();
-                            }
-                            KeyCode::End => {
-                                query.move_end();
-                            }
-                            KeyCode::Up => {
-                                app.move_card_up();
-                            }
-                            KeyCode::Down => {
-                                app.move_card_down();
-                            }
-                            KeyCode::Char(c) => {
-                                query.insert(c);
-                                app.clamp_selected();
+            }
+        }
+        Screen::AiSetup => {
+            if let Some(setup) = &mut app.ai_setup {
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down if setup.selected == 0 => {
+                        setup.selected = 1;
+                    }
+                    KeyCode::Char('k') | KeyCode::Up if setup.selected == 1 => {
+                        setup.selected = 0;
+                    }
+                    KeyCode::Enter => {
+                        let cmd = if setup.selected == 0 {
+                            "{claude}"
+                        } else {
+                            "{cursor}"
+                        };
+                        log::warn_on_err("config", config::set_default_session_command(cmd));
+                        app.ai_setup = None;
+
+                        // Now proceed with repo detection
+                        let detected_repo = detect_current_repo();
+                        let configured_repo =
+                            load_config().map(|c| c.repo).filter(|r| !r.is_empty());
+                        let repo = detected_repo.or(configured_repo);
+
+                        if let Some(repo) = repo {
+                            app.repo = repo.clone();
+                            log::warn_on_err("config", save_config(&repo));
+                            app.selected_card = [0; 4];
+                            app.screen = Screen::Board;
+                            app.start_async_refresh();
+                        } else {
+                            app.screen = Screen::RepoSelect;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Screen::Configuration => {
+            if let Some(config_edit) = &mut app.config_edit {
+                match key.code {
+                    KeyCode::Esc => {
+                        app.config_edit = None;
+                        app.screen = Screen::Board;
+                    }
+                    KeyCode::Tab => {
+                        config_edit.active_field = (config_edit.active_field + 1) % 10;
+                    }
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let verify_cmd = config_edit.verify_command.value().trim().to_string();
+                        let editor_cmd = config_edit.editor_command.value().trim().to_string();
+                        let claude_cmd = config_edit.session_command.value().trim().to_string();
+                        let repo = app.repo.clone();
+
+                        let pr_ready = config_edit.pr_ready;
+                        let auto_open_pr = config_edit.auto_open_pr;
+
+                        let config = match config::read_config() {
+                            Ok(config) => config,
+                            Err(e) => {
+                                app.set_status(format!("Could not save configuration: {}", e));
+                                return Ok(Flow::Continue);
+                            }
+                        };
+                        if let Some(mut config) = config {
+                            if verify_cmd.is_empty() {
+                                config.verify_commands.remove(&repo);
+                            } else {
+                                config
+                                    .verify_commands
+                                    .insert(repo.clone(), verify_cmd.clone());
+                            }
+                            if editor_cmd.is_empty() {
+                                config.editor_commands.remove(&repo);
+                            } else {
+                                config
+                                    .editor_commands
+                                    .insert(repo.clone(), editor_cmd.clone());
+                            }
+                            if pr_ready {
+                                config.pr_ready.insert(repo.clone(), true);
+                            } else {
+                                config.pr_ready.remove(&repo);
+                            }
+                            if !auto_open_pr {
+                                config.auto_open_pr.insert(repo.clone(), false);
+                            } else {
+                                config.auto_open_pr.remove(&repo);
+                            }
+                            if claude_cmd.is_empty() {
+                                config.session_commands.remove(&repo);
+                            } else {
+                                config
+                                    .session_commands
+                                    .insert(repo.clone(), claude_cmd.clone());
+                            }
+                            config.multiplexer = Some(config_edit.multiplexer);
+                            let refresh_val: u64 = config_edit
+                                .refresh_interval
+                                .value()
+                                .trim()
+                                .parse()
+                                .unwrap_or(0);
+                            config.auto_refresh_secs = if refresh_val == 0 {
+                                None
+                            } else {
+                                Some(refresh_val)
+                            };
+                            let dir_template = config_edit.worktree_dir_template.value().trim();
+                            if dir_template.is_empty() {
+                                config.worktree_dir_templates.remove(&repo);
+                            } else {
+                                config
+                                    .worktree_dir_templates
+                                    .insert(repo.clone(), dir_template.to_string());
+                            }
+                            let branch_template = config_edit.branch_template.value().trim();
+                            if branch_template.is_empty() {
+                                config.branch_templates.remove(&repo);
+                            } else {
+                                config
+                                    .branch_templates
+                                    .insert(repo.clone(), branch_template.to_string());
+                            }
+                            let base_branch = config_edit.base_branch.value().trim();
+                            if base_branch.is_empty() {
+                                config.base_branches.remove(&repo);
+                            } else {
+                                config
+                                    .base_branches
+                                    .insert(repo.clone(), base_branch.to_string());
+                            }
+                            if let Err(e) = config::save_full_config(&config) {
+                                app.set_status(format!("Could not save configuration: {}", e));
+                                return Ok(Flow::Continue);
                             }
-                            _ => {}
-                        },
-                        Mode::Normal | Mode::Filtering { .. } => {
-                            let is_filtering = matches!(app.mode, Mode::Filtering { .. });
-                            match key.code {
-                                KeyCode::Char('q') if !is_filtering => break,
-                                KeyCode::Esc => {
-                                    if is_filtering {
-                                        // Clear server-side search when clearing the filter
-                                        if app.active_section == 0
-                                            && !app.local_mode
-                                            && app.issue_search_query.is_some()
-                                        {
-                                            app.issue_search_query = None;
-                                            app.start_async_refresh();
-                                        }
-                                        app.mode = Mode::Normal;
-                                    } else {
-                                        break;
-                                    }
-                                }
-                                KeyCode::Char('/') => {
-                                    if let Mode::Filtering { focused, .. } = &mut app.mode {
-                                        *focused = true;
-                                    } else {
-                                        app.mode = Mode::Filtering {
-                                            query: TextInput::new(),
-                                            focused: true,
-                                        };
-                                    }
-                                }
-                                KeyCode::Enter if !is_filtering => {
-                                    app.enter_repo_select();
-                                }
-                                KeyCode::Tab | KeyCode::Char('l') => {
-                                    app.active_section = (app.active_section + 1) % 4;
-                                }
-                                KeyCode::BackTab | KeyCode::Char('h') => {
-                                    app.active_section = (app.active_section + 3) % 4;
-                                }
-                                KeyCode::Char('R') => {
-                                    if !app.is_section_loading() {
-                                        app.start_async_refresh();
-                                        app.set_status("Refreshing…".to_string());
-                                    }
-                                }
-                                KeyCode::Char('p') => match pull_main() {
-                                    Ok(branch) => {
-                                        app.main_behind_count = 0;
-                                        app.set_status(format!(
-                                            "Pulled latest changes for {}",
-                                            branch
-                                        ));
-                                    }
-                                    Err(e) => {
-                                        app.set_status(format!("Pull failed: {}", e));
-                                    }
-                                },
-                                KeyCode::Char('D') => {
-                                    app.dependencies = check_dependencies();
-                                    app.screen = Screen::Dependencies;
-                                }
-                                KeyCode::Char('n') if !is_filtering => {
-                                    app.mode = Mode::CreatingIssue;
-                                    app.issue_modal = Some(IssueModal::new());
-                                }
-                                KeyCode::Char('w')
-                                    if app.active_section == 0
-                                        && app.worktree_create_rx.is_none() =>
-                                {
-                                    if let Some(card) = app.issues.get(app.selected_card[0]) {
-                                        // Extract issue number from id "issue-N" or "local-issue-N"
-                                        if let Some(number) = extract_issue_number(&card.id) {
-                                            let title = card.title.clone();
-                                            let body =
-                                                card.full_description.clone().unwrap_or_default();
-                                            let repo = app.repo.clone();
-                                            let pr_ready = get_pr_ready(&repo);
-                                            let auto_open_pr = get_auto_open_pr(&repo);
-                                            let claude_cmd = get_session_command(&repo);
-                                            let hook_script = app.hook_script_path.clone();
-                                            let mux = app.multiplexer;
-                                            let is_local = app.local_mode;
-                                            let (tx, rx) = mpsc::channel();
-                                            app.worktree_create_rx = Some(rx);
-                                            app.loading_message = Some(format!(
-                                                "Creating worktree and session for issue #{}...",
-                                                number
-                                            ));
-                                            std::thread::spawn(move || {
-                                                let result = create_worktree_and_session(
-                                                    &repo,
-                                                    number,
-                                                    &title,
-                                                    &body,
-                                                    hook_script.as_deref(),
-                                                    pr_ready,
-                                                    auto_open_pr,
-                                                    claude_cmd.as_deref(),
-                                                    mux,
-                                                    is_local,
-                                                )
-                                                .map_err(|e| e.to_string());
-                                                let _ = tx.send(
-                                                    WorktreeCreateResult::WorktreeAndSession {
-                                                        number,
-                                                        result,
-                                                    },
-                                                );
-                                            });
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('d')
-                                    if app.active_section == 0
-                                        && app.issue_state_filter == StateFilter::Open =>
-                                {
-                                    if let Some(card) = app.issues.get(app.selected_card[0]) {
-                                        if let Some(number) = extract_issue_number(&card.id) {
-                                            app.confirm_modal = Some(ConfirmModal {
-                                                message: format!(
-                                                    "Close issue #{}?\n\n{}",
-                                                    number, card.title
-                                                ),
-                                                on_confirm: ConfirmAction::CloseIssue { number },
-                                            });
-                                            app.mode = Mode::Confirming;
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('e') if app.active_section == 0 => {
-                                    if let Some(card) = app.issues.get(app.selected_card[0]) {
-                                        if let Some(number) = extract_issue_number(&card.id) {
-                                            // Extract title without the "#N " prefix
-                                            let title = card
-                                                .title
-                                                .strip_prefix(&format!("#{} ", number))
-                                                .unwrap_or(&card.title)
-                                                .to_string();
-                                            let body =
-                                                card.full_description.clone().unwrap_or_default();
-                                            app.edit_issue_modal =
-                                                Some(EditIssueModal::new(number, title, body));
-                                            app.mode = Mode::EditingIssue;
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('w')
-                                    if app.active_section == 1
-                                        && app.worktree_create_rx.is_none() =>
-                                {
-                                    if let Some(card) = app.worktrees.get(app.selected_card[1]) {
-                                        let branch = card.title.clone();
-                                        let worktree_path = card.description.clone();
-                                        // Extract issue number from branch name "issue-N" or "local-issue-N"
-                                        if let Some(number) = extract_issue_number(&branch) {
-                                            // Check if a session already exists
-                                            let has_session =
-                                                app.sessions.iter().any(|s| s.title == branch);
-                                            if has_session {
-                                                app.set_status(format!(
-                                                    "Session '{}' already exists — use 'a' to attach",
-                                                    branch
-                                                ));
-                                            } else {
-                                                let repo = app.repo.clone();
-                                                let hook_script = app.hook_script_path.clone();
-                                                let mux = app.multiplexer;
-                                                let branch_clone = branch.clone();
-                                                let is_local = app.local_mode;
-                                                let (tx, rx) = mpsc::channel();
-                                                app.worktree_create_rx = Some(rx);
-                                                app.loading_message = Some(format!(
-                                                    "Creating session for '{}'...",
-                                                    branch
-                                                ));
-                                                std::thread::spawn(move || {
-                                                    let result = if is_local {
-                                                        local::fetch_local_issue(&repo, number)
-                                                    } else {
-                                                        fetch_issue(&repo, number)
-                                                    }
-                                                    .and_then(|(title, body)| {
-                                                        let pr_ready = get_pr_ready(&repo);
-                                                        let auto_open_pr = get_auto_open_pr(&repo);
-                                                        let claude_cmd = get_session_command(&repo);
-                                                        create_session_for_worktree(
-                                                            &repo,
-                                                            number,
-                                                            &title,
-                                                            &body,
-                                                            &branch_clone,
-                                                            &worktree_path,
-                                                            hook_script.as_deref(),
-                                                            pr_ready,
-                                                            auto_open_pr,
-                                                            claude_cmd.as_deref(),
-                                                            mux,
-                                                            is_local,
-                                                        )
-                                                    })
-                                                    .map_err(|e| e.to_string());
-                                                    let _ = tx.send(
-                                                        WorktreeCreateResult::SessionOnly {
-                                                            branch: branch_clone,
-                                                            result,
-                                                        },
-                                                    );
-                                                });
-                                            }
-                                        } else {
-                                            app.set_status(
-                                                "Cannot create session: branch is not an issue branch".to_string(),
-                                            );
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('d') if app.active_section == 1 => {
-                                    if let Some(card) = app.worktrees.get(app.selected_card[1]) {
-                                        let branch = card.title.clone();
-                                        if branch == "main" || branch == "master" {
-                                            app.set_status(
-                                                "Cannot remove main/master worktree".to_string(),
-                                            );
-                                        } else {
-                                            let path = card.description.clone();
-                                            app.confirm_modal = Some(ConfirmModal {
-                                                message: format!(
-                                                    "Remove worktree '{}'?\n\nPath: {}\nThis will also delete the branch and kill any associated session.",
-                                                    branch, path
-                                                ),
-                                                on_confirm: ConfirmAction::RemoveWorktree {
-                                                    path,
-                                                    branch,
-                                                },
-                                            });
-                                            app.mode = Mode::Confirming;
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('v') if app.active_section == 1 => {
-                                    if let Some(card) = app.worktrees.get(app.selected_card[1]) {
-                                        let worktree_path = card.description.clone();
-                                        if let Some(cmd) = get_verify_command(&app.repo) {
-                                            let expanded =
-                                                expand_editor_command(&cmd, &worktree_path);
-                                            let result =
-                                                Command::new("sh").args(["-c", &expanded]).spawn();
-                                            match result {
-                                                Ok(_) => {
-                                                    app.set_status(format!(
-                                                        "Launched verify for '{}'",
-                                                        card.title
-                                                    ));
-                                                }
-                                                Err(e) => {
-                                                    app.set_status(format!(
-                                                        "Failed to launch verify command: {}",
-                                                        e
-                                                    ));
-                                                }
-                                            }
-                                        } else {
-                                            // No verify command configured — prompt user
-                                            app.mode = Mode::EditingVerifyCommand {
-                                                input: TextInput::new(),
-                                            };
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('e') if app.active_section == 1 => {
-                                    if let Some(card) = app.worktrees.get(app.selected_card[1]) {
-                                        let worktree_path = card.description.clone();
-                                        if let Some(cmd) = get_editor_command(&app.repo) {
-                                            let expanded =
-                                                expand_editor_command(&cmd, &worktree_path);
-                                            let result =
-                                                Command::new("sh").args(["-c", &expanded]).spawn();
-                                            match result {
-                                                Ok(_) => {
-                                                    app.set_status(format!(
-                                                        "Opened editor for '{}'",
-                                                        card.title
-                                                    ));
-                                                }
-                                                Err(e) => {
-                                                    app.set_status(format!(
-                                                        "Failed to launch editor: {}",
-                                                        e
-                                                    ));
-                                                }
-                                            }
-                                        } else {
-                                            app.mode = Mode::EditingEditorCommand {
-                                                input: TextInput::new(),
-                                            };
-                                        }
-                                    }
-                                }
-                                // Create a local PR from a worktree (local mode only)
-                                KeyCode::Char('P') if app.active_section == 1 && app.local_mode => {
-                                    if let Some(card) = app.worktrees.get(app.selected_card[1]) {
-                                        let branch = card.title.clone();
-                                        let repo = app.repo.clone();
-                                        if local::has_local_pr_for_branch(&repo, &branch) {
-                                            app.set_status(format!(
-                                                "Local PR already exists for '{}'",
-                                                branch
-                                            ));
-                                        } else {
-                                            let pr_ready = get_pr_ready(&repo);
-                                            let title = format!("PR for {}", branch);
-                                            match local::create_local_pr(
-                                                &repo, &title, "", &branch, !pr_ready,
-                                            ) {
-                                                Ok(number) => {
-                                                    app.refresh_data();
-                                                    app.set_status(format!(
-                                                        "Created local PR #{} for '{}'",
-                                                        number, branch
-                                                    ));
-                                                }
-                                                Err(e) => {
-                                                    app.set_status(format!("Error: {}", e));
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('L') => {
-                                    let has_gh = gh_available();
-                                    if !has_gh && app.local_mode {
-                                        app.set_status(
-                                            "Cannot disable local mode: gh CLI not available"
-                                                .to_string(),
-                                        );
-                                    } else {
-                                        app.local_mode = !app.local_mode;
-                                        let _ = config::set_local_mode(app.local_mode);
-                                        let mode_label =
-                                            if app.local_mode { "LOCAL" } else { "GITHUB" };
-                                        app.set_status(format!("Switched to {} mode", mode_label));
-                                        app.refresh_data();
-                                    }
-                                }
-                                KeyCode::Char('C') => {
-                                    let current_verify =
-                                        get_verify_command(&app.repo).unwrap_or_default();
-                                    let current_editor =
-                                        get_editor_command(&app.repo).unwrap_or_default();
-                                    let current_pr_ready = get_pr_ready(&app.repo);
-                                    let current_auto_open_pr = get_auto_open_pr(&app.repo);
-                                    let current_claude =
-                                        get_session_command(&app.repo).unwrap_or_default();
-                                    let current_refresh_secs = config::get_auto_refresh_secs();
-                                    app.config_edit = Some(ConfigEditState::new(
-                                        current_verify,
-                                        current_editor,
-                                        current_pr_ready,
-                                        current_auto_open_pr,
-                                        current_claude,
-                                        app.multiplexer,
-                                        current_refresh_secs,
-                                    ));
-                                    app.screen = Screen::Configuration;
-                                }
-                                // Main worktree Claude session: create if needed, then attach
-                                KeyCode::Char('T') => {
-                                    match ensure_main_session(app.multiplexer) {
-                                        Ok(created) => {
-                                            if created {
-                                                app.set_status(
-                                                    "Created main explore session".to_string(),
-                                                );
-                                            }
-                                        }
-                                        Err(e) => {
-                                            app.set_status(format!(
-                                                "Failed to create main session: {}",
-                                                e
-                                            ));
-                                        }
-                                    }
-                                    // Suspend TUI and attach to the session
-                                    disable_raw_mode()?;
-                                    io::stdout().execute(LeaveAlternateScreen)?;
-                                    let _ = app.multiplexer.attach(MAIN_SESSION_NAME);
-                                    enable_raw_mode()?;
-                                    io::stdout().execute(EnterAlternateScreen)?;
-                                    terminal.clear()?;
-                                    app.refresh_data();
-                                }
-                                // PR actions: 'o' to open in browser, 'r' to mark ready
-                                KeyCode::Char('o') if app.active_section == 3 => {
-                                    if let Some(card) = app.pull_requests.get(app.selected_card[3])
-                                    {
-                                        if let Some(url) = &card.url {
-                                            let _ = Command::new("open").arg(url).output();
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('r') if app.active_section == 3 => {
-                                    if let Some(card) = app.pull_requests.get(app.selected_card[3])
-                                    {
-                                        if card.is_draft == Some(true) {
-                                            if let Some(number) = card.pr_number {
-                                                let repo = app.repo.clone();
-                                                if app.local_mode {
-                                                    match local::mark_local_pr_ready(&repo, number)
-                                                    {
-                                                        Ok(()) => {
-                                                            app.refresh_data();
-                                                            app.set_status(format!(
-                                                                "PR #{} marked as ready",
-                                                                number
-                                                            ));
-                                                        }
-                                                        Err(e) => {
-                                                            app.set_status(format!("Error: {}", e));
-                                                        }
-                                                    }
-                                                } else {
-                                                    let output = Command::new("gh")
-                                                        .args([
-                                                            "pr",
-                                                            "ready",
-                                                            "--repo",
-                                                            &repo,
-                                                            &number.to_string(),
-                                                        ])
-                                                        .output();
-                                                    match output {
-                                                        Ok(o) if o.status.success() => {
-                                                            app.refresh_data();
-                                                            app.set_status(format!(
-                                                                "PR #{} marked as ready",
-                                                                number
-                                                            ));
-                                                        }
-                                                        Ok(o) => {
-                                                            let stderr =
-                                                                String::from_utf8_lossy(&o.stderr);
-                                                            app.set_status(format!(
-                                                                "Error: {}",
-                                                                stderr.trim()
-                                                            ));
-                                                        }
-                                                        Err(e) => {
-                                                            app.set_status(format!("Error: {}", e));
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        } else {
-                                            app.set_status("PR is already ready".to_string());
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('V') if app.active_section == 3 => {
-                                    if app.local_mode {
-                                        app.set_status(
-                                            "Revert is not available in local mode".to_string(),
-                                        );
-                                    } else if let Some(card) =
-                                        app.pull_requests.get(app.selected_card[3])
-                                    {
-                                        if let Some(number) = card.pr_number {
-                                            if card.is_merged != Some(true) {
-                                                app.set_status(
-                                                    "Can only revert merged PRs".to_string(),
-                                                );
-                                            } else {
-                                                app.confirm_modal = Some(ConfirmModal {
-                                                    message: format!(
-                                                        "Revert PR #{}? This will create a new PR that undoes its changes.",
-                                                        number
-                                                    ),
-                                                    on_confirm: ConfirmAction::RevertPr { number },
-                                                });
-                                                app.mode = Mode::Confirming;
-                                            }
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('M') if app.active_section == 3 => {
-                                    if let Some(card) = app.pull_requests.get(app.selected_card[3])
-                                    {
-                                        if let Some(number) = card.pr_number {
-                                            if card.is_draft == Some(true) {
-                                                app.set_status(
-                                                    "Cannot merge a draft PR".to_string(),
-                                                );
-                                            } else if app.local_mode {
-                                                let branch = card.head_branch.clone();
-                                                app.confirm_modal = Some(ConfirmModal {
-                                                    message: format!(
-                                                        "Merge local PR #{} (git merge)?",
-                                                        number
-                                                    ),
-                                                    on_confirm: ConfirmAction::MergeLocalPr {
-                                                        number,
-                                                        branch,
-                                                    },
-                                                });
-                                                app.mode = Mode::Confirming;
-                                            } else {
-                                                let branch = card.head_branch.clone();
-                                                app.confirm_modal = Some(ConfirmModal {
-                                                    message: format!(
-                                                        "Merge PR #{} with merge strategy?",
-                                                        number
-                                                    ),
-                                                    on_confirm: ConfirmAction::MergePr {
-                                                        number,
-                                                        strategy: MergeStrategy::Merge,
-                                                        branch,
-                                                    },
-                                                });
-                                                app.mode = Mode::Confirming;
-                                            }
-                                        }
-                                    }
-                                }
-                                KeyCode::Char('d') if app.active_section == 2 => {
-                                    if let Some(card) = app.sessions.get(app.selected_card[2]) {
-                                        let session_name = card.title.clone();
-                                        app.confirm_modal = Some(ConfirmModal {
-                                            message: format!("Kill session '{}'?", session_name),
-                                            on_confirm: ConfirmAction::KillSession {
-                                                name: session_name,
-                                            },
-                                        });
-                                        app.mode = Mode::Confirming;
-                                    }
-                                }
-                                KeyCode::Char('a') if app.active_section == 2 => {
-                                    if let Some(card) = app.sessions.get(app.selected_card[2]) {
-                                        let session_name = card.title.clone();
-                                        // Suspend TUI, attach to session, resume on detach
-                                        disable_raw_mode()?;
-                                        io::stdout().execute(LeaveAlternateScreen)?;
-                                        let _ = app.multiplexer.attach(&session_name);
-                                        enable_raw_mode()?;
-                                        io::stdout().execute(EnterAlternateScreen)?;
-                                        terminal.clear()?;
-                                        // Refresh all state after returning (Claude may have created PRs)
-                                        app.refresh_data();
-                                    }
+                        }
+
+                        app.multiplexer = config_edit.multiplexer;
+                        app.set_status("Configuration saved".to_string());
+                        app.config_edit = None;
+                        app.screen = Screen::Board;
+                    }
+                    KeyCode::Backspace => match config_edit.active_field {
+                        0 => config_edit.verify_command.delete_back(),
+                        1 => config_edit.editor_command.delete_back(),
+                        4 => config_edit.session_command.delete_back(),
+                        6 => config_edit.refresh_interval.delete_back(),
+                        7 => config_edit.worktree_dir_template.delete_back(),
+                        8 => config_edit.branch_template.delete_back(),
+                        9 => config_edit.base_branch.delete_back(),
+                        _ => {}
+                    },
+                    KeyCode::Left => match config_edit.active_field {
+                        0 => config_edit.verify_command.move_left(),
+                        1 => config_edit.editor_command.move_left(),
+                        4 => config_edit.session_command.move_left(),
+                        6 => config_edit.refresh_interval.move_left(),
+                        7 => config_edit.worktree_dir_template.move_left(),
+                        8 => config_edit.branch_template.move_left(),
+                        9 => config_edit.base_branch.move_left(),
+                        _ => {}
+                    },
+                    KeyCode::Right => match config_edit.active_field {
+                        0 => config_edit.verify_command.move_right(),
+                        1 => config_edit.editor_command.move_right(),
+                        4 => config_edit.session_command.move_right(),
+                        6 => config_edit.refresh_interval.move_right(),
+                        7 => config_edit.worktree_dir_template.move_right(),
+                        8 => config_edit.branch_template.move_right(),
+                        9 => config_edit.base_branch.move_right(),
+                        _ => {}
+                    },
+                    KeyCode::Home => match config_edit.active_field {
+                        0 => config_edit.verify_command.move_home(),
+                        1 => config_edit.editor_command.move_home(),
+                        4 => config_edit.session_command.move_home(),
+                        6 => config_edit.refresh_interval.move_home(),
+                        7 => config_edit.worktree_dir_template.move_home(),
+                        8 => config_edit.branch_template.move_home(),
+                        9 => config_edit.base_branch.move_home(),
+                        _ => {}
+                    },
+                    KeyCode::End => match config_edit.active_field {
+                        0 => config_edit.verify_command.move_end(),
+                        1 => config_edit.editor_command.move_end(),
+                        4 => config_edit.session_command.move_end(),
+                        6 => config_edit.refresh_interval.move_end(),
+                        7 => config_edit.worktree_dir_template.move_end(),
+                        8 => config_edit.branch_template.move_end(),
+                        9 => config_edit.base_branch.move_end(),
+                        _ => {}
+                    },
+                    KeyCode::Char(' ') | KeyCode::Enter if config_edit.active_field == 2 => {
+                        config_edit.pr_ready = !config_edit.pr_ready;
+                    }
+                    KeyCode::Char(' ') | KeyCode::Enter if config_edit.active_field == 3 => {
+                        config_edit.auto_open_pr = !config_edit.auto_open_pr;
+                    }
+                    KeyCode::Char(' ') | KeyCode::Enter if config_edit.active_field == 5 => {
+                        config_edit.multiplexer = match config_edit.multiplexer {
+                            Multiplexer::Tmux => Multiplexer::Screen,
+                            Multiplexer::Screen => Multiplexer::Tmux,
+                        };
+                    }
+                    KeyCode::Char(c) => match config_edit.active_field {
+                        0 => config_edit.verify_command.insert(c),
+                        1 => config_edit.editor_command.insert(c),
+                        4 => config_edit.session_command.insert(c),
+                        6 if c.is_ascii_digit() => config_edit.refresh_interval.insert(c),
+                        7 => config_edit.worktree_dir_template.insert(c),
+                        8 => config_edit.branch_template.insert(c),
+                        9 if c != ' ' => config_edit.base_branch.insert(c),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+        }
+        Screen::RepoSelect => {
+            match app.repo_select.phase {
+                RepoSelectPhase::Typing => match key.code {
+                    KeyCode::Esc => {
+                        if app.repo.is_empty() {
+                            return Ok(Flow::Quit); // quit if no board to return to
+                        } else {
+                            app.screen = Screen::Board;
+                        }
+                    }
+                    KeyCode::Enter => {
+                        let input_val = app.repo_select.input.value().trim().to_string();
+                        if input_val.is_empty() {
+                            app.repo_select.error = Some(if app.local_mode {
+                                "Please enter owner/repo (e.g. user/my-project)".into()
+                            } else {
+                                "Please enter an org or user name".into()
+                            });
+                        } else if app.local_mode {
+                            // In local mode, accept "owner/repo" directly
+                            if input_val.contains('/') {
+                                app.repo = input_val;
+                                app.screen = Screen::Board;
+                                app.start_async_refresh();
+                            } else {
+                                app.repo_select.error = Some(
+                                    "In local mode, enter full owner/repo (e.g. user/my-project)"
+                                        .into(),
+                                );
+                            }
+                        } else {
+                            app.repo_select.error = None;
+                            app.repo_select.phase = RepoSelectPhase::Loading;
+                            // We need to redraw to show loading state, then fetch
+                            terminal.draw(|frame| {
+                                ui_repo_select(frame, &app.repo_select, app.local_mode)
+                            })?;
+
+                            match fetch_repos(&input_val) {
+                                Ok(repos) => {
+                                    app.repo_select.repos = repos;
+                                    app.repo_select.filter_query.clear();
+                                    app.repo_select.update_filtered();
+                                    app.repo_select.selected = 0;
+                                    app.repo_select.phase = RepoSelectPhase::Picking;
                                 }
-                                // Filter toggles for Issues and Pull Requests
-                                KeyCode::Char('s')
-                                    if app.active_section == 0 || app.active_section == 3 =>
-                                {
-                                    if app.active_section == 0 {
-                                        app.issue_state_filter = app.issue_state_filter.toggle();
-                                    } else {
-                                        app.pr_state_filter = app.pr_state_filter.toggle();
-                                    }
-                                    app.refresh_data();
+                                Err(e) => {
+                                    app.repo_select.error = Some(e.to_string());
+                                    app.repo_select.phase = RepoSelectPhase::Typing;
                                 }
-                                KeyCode::Char('m')
-                                    if app.active_section == 0 || app.active_section == 3 =>
-                                {
-                                    if app.active_section == 0 {
-                                        app.issue_assignee_filter =
-                                            app.issue_assignee_filter.toggle();
-                                    } else {
-                                        app.pr_assignee_filter = app.pr_assignee_filter.toggle();
-                                    }
-                                    app.refresh_data();
+                            }
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        app.repo_select.input.delete_back();
+                    }
+                    KeyCode::Left => {
+                        app.repo_select.input.move_left();
+                    }
+                    KeyCode::Right => {
+                        app.repo_select.input.move_right();
+                    }
+                    KeyCode::Home => {
+                        app.repo_select.input.move_home();
+                    }
+                    KeyCode::End => {
+                        app.repo_select.input.move_end();
+                    }
+                    KeyCode::Char(c) => {
+                        app.repo_select.input.insert(c);
+                    }
+                    _ => {}
+                },
+                RepoSelectPhase::Loading => {
+                    // No input during loading
+                }
+                RepoSelectPhase::Picking => match key.code {
+                    KeyCode::Esc => {
+                        app.repo_select.phase = RepoSelectPhase::Typing;
+                        app.repo_select.filter_query.clear();
+                    }
+                    KeyCode::Enter => {
+                        if let Some(repo) =
+                            app.repo_select.filtered_repos.get(app.repo_select.selected)
+                        {
+                            let repo = repo.clone();
+                            log::warn_on_err("config", save_config(&repo));
+                            app.repo = repo;
+                            app.selected_card = [0; 4];
+                            app.screen = Screen::Board;
+                            app.start_async_refresh();
+                        }
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.repo_select.selected = app.repo_select.selected.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        app.repo_select.selected = (app.repo_select.selected + 1)
+                            .min(app.repo_select.filtered_repos.len().saturating_sub(1));
+                    }
+                    KeyCode::Char('/') => {
+                        // Toggle filter — if already filtering, this adds '/' to query
+                        // Start fresh filter
+                        app.repo_select.filter_query.clear();
+                        app.repo_select.update_filtered();
+                    }
+                    KeyCode::Backspace => {
+                        app.repo_select.filter_query.delete_back();
+                        app.repo_select.update_filtered();
+                    }
+                    KeyCode::Left => {
+                        app.repo_select.filter_query.move_left();
+                    }
+                    KeyCode::Right => {
+                        app.repo_select.filter_query.move_right();
+                    }
+                    KeyCode::Char(c) if c != '/' => {
+                        app.repo_select.filter_query.insert(c);
+                        app.repo_select.update_filtered();
+                    }
+                    _ => {}
+                },
+            }
+        }
+        Screen::Board => {
+            if let Some(action) = action::from_key(app, key) {
+                if let Action::Quit = action {
+                    return Ok(Flow::Quit);
+                }
+                let effects = app.update(action);
+                perform(terminal, app, console, effects)?;
+                return Ok(Flow::Continue);
+            }
+            match &mut app.mode {
+                Mode::Filtering { query, focused } if *focused => match key.code {
+                    KeyCode::Esc => {
+                        app.update(Action::ClearFilter);
+                    }
+                    KeyCode::Enter => {
+                        // For the Issues and Pull Requests columns in GitHub
+                        // mode, trigger a server-side search so results come
+                        // from GitHub rather than filtering a small local set.
+                        let q = query.value().to_string();
+                        let search = match app.active_section {
+                            0 => Some(&mut app.issue_search_query),
+                            3 => Some(&mut app.pr_search_query),
+                            _ => None,
+                        };
+                        if let Some(search) = search.filter(|_| !app.local_mode) {
+                            // An empty search still replaces the view's
+                            if search.as_deref() != Some(q.as_str()) {
+                                *search = Some(q);
+                                app.start_async_refresh();
+                            }
+                        }
+                        if let Mode::Filtering { focused, .. } = &mut app.mode {
+                            *focused = false;
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        query.delete_back();
+                        app.clamp_selected();
+                    }
+                    KeyCode::Left => {
+                        query.move_left();
+                    }
+                    KeyCode::Right => {
+                        query.move_right();
+                    }
+                    KeyCode::Home => {
+                        query.move_home();
+                    }
+                    KeyCode::End => {
+                        query.move_end();
+                    }
+                    KeyCode::Up => {
+                        app.move_card_up();
+                    }
+                    KeyCode::Down => {
+                        app.move_card_down();
+                    }
+                    KeyCode::Char(c) => {
+                        query.insert(c);
+                        app.clamp_selected();
+                    }
+                    _ => {}
+                },
+                Mode::CreatingIssue => {
+                    if let Some(modal) = &mut app.issue_modal {
+                        // Block input while submitting (only allow Esc)
+                        if modal.submitting && key.code != KeyCode::Esc {
+                            return Ok(Flow::Continue);
+                        }
+                        // The repo's templates are offered before the form.
+                        if let Some(selected) = modal.choosing_template {
+                            let count = modal.templates.len() + 1;
+                            match key.code {
+                                KeyCode::Esc => {
+                                    app.issue_modal = None;
+                                    app.mode = Mode::Normal;
                                 }
                                 KeyCode::Up | KeyCode::Char('k') => {
-                                    app.move_card_up();
+                                    modal.choosing_template = Some((selected + count - 1) % count);
                                 }
                                 KeyCode::Down | KeyCode::Char('j') => {
-                                    app.move_card_down();
-                                }
-                                KeyCode::Char('x') => {
-                                    app.show_messages = !app.show_messages;
-                                    if !app.show_messages {
-                                        app.messages_expanded = false;
-                                    }
-                                }
-                                KeyCode::Char('X') if app.show_messages => {
-                                    app.messages_expanded = !app.messages_expanded;
+                                    modal.choosing_template = Some((selected + 1) % count);
                                 }
+                                KeyCode::Enter => modal.apply_template(selected),
                                 _ => {}
                             }
+                            return Ok(Flow::Continue);
                         }
-                        Mode::Confirming => match key.code {
-                            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                                if let Some(modal) = app.confirm_modal.take() {
-                                    match modal.on_confirm {
-                                        ConfirmAction::CloseIssue { number } => {
-                                            let repo = app.repo.clone();
-                                            let result = if app.local_mode {
-                                                local::close_local_issue(&repo, number)
-                                            } else {
-                                                close_issue(&repo, number)
-                                            };
-                                            match result {
-                                                Ok(()) => {
-                                                    app.refresh_data();
-                                                    app.set_status(format!(
-                                                        "Closed issue #{}",
-                                                        number
-                                                    ));
-                                                }
-                                                Err(e) => {
-                                                    app.set_status(format!("Error: {}", e));
-                                                }
-                                            }
-                                        }
-                                        ConfirmAction::RemoveWorktree { path, branch } => {
-                                            match remove_worktree(&path, &branch, app.multiplexer) {
-                                                Ok(()) => {
-                                                    app.worktrees = fetch_worktrees();
-                                                    app.sessions = fetch_sessions(
-                                                        &app.session_states,
-                                                        app.multiplexer,
-                                                    );
-                                                    app.clamp_selected();
-                                                    app.last_refresh = std::time::Instant::now();
-                                                    app.set_status(format!(
-                                                        "Removed worktree '{}'",
-                                                        branch
-                                                    ));
-                                                }
-                                                Err(e) => {
-                                                    app.set_status(format!("Error: {}", e));
-                                                }
-                                            }
-                                        }
-                                        ConfirmAction::KillSession { name } => {
-                                            app.multiplexer.kill_session(&name);
-                                            app.sessions = fetch_sessions(
-                                                &app.session_states,
-                                                app.multiplexer,
-                                            );
-                                            app.clamp_selected();
-                                            app.last_refresh = std::time::Instant::now();
-                                            app.set_status(format!("Killed session '{}'", name));
-                                        }
-                                        ConfirmAction::RevertPr { number } => {
-                                            let repo = app.repo.clone();
-                                            // Get the PR's GraphQL node ID
-                                            let id_output = Command::new("gh")
-                                                .args([
-                                                    "pr",
-                                                    "view",
-                                                    &number.to_string(),
-                                                    "--repo",
-                                                    &repo,
-                                                    "--json",
-                                                    "id",
-                                                    "--jq",
-                                                    ".id",
-                                                ])
-                                                .output();
-                                            match id_output {
-                                                Ok(o) if o.status.success() => {
-                                                    let node_id =
-                                                        String::from_utf8_lossy(&o.stdout)
-                                                            .trim()
-                                                            .to_string();
-                                                    let query = format!(
-                                                        r#"mutation {{ revertPullRequest(input: {{pullRequestId: "{}"}}) {{ revertPullRequest {{ number url }} }} }}"#,
-                                                        node_id
-                                                    );
-                                                    let revert_output = Command::new("gh")
-                                                        .args([
-                                                            "api",
-                                                            "graphql",
-                                                            "-f",
-                                                            &format!("query={}", query),
-                                                        ])
-                                                        .output();
-                                                    match revert_output {
-                                                        Ok(o) if o.status.success() => {
-                                                            app.pull_requests = fetch_prs(
-                                                                &repo,
-                                                                app.pr_state_filter,
-                                                                app.pr_assignee_filter,
-                                                            );
-                                                            app.clamp_selected();
-                                                            app.last_refresh =
-                                                                std::time::Instant::now();
-                                                            app.set_status(format!(
-                                                                "Created revert PR for #{}",
-                                                                number
-                                                            ));
-                                                        }
-                                                        Ok(o) => {
-                                                            let stderr =
-                                                                String::from_utf8_lossy(&o.stderr);
-                                                            app.set_status(format!(
-                                                                "Error: {}",
-                                                                stderr.trim()
-                                                            ));
-                                                        }
-                                                        Err(e) => {
-                                                            app.set_status(format!("Error: {}", e));
-                                                        }
-                                                    }
-                                                }
-                                                Ok(o) => {
-                                                    let stderr = String::from_utf8_lossy(&o.stderr);
-                                                    app.set_status(format!(
-                                                        "Error: {}",
-                                                        stderr.trim()
-                                                    ));
-                                                }
-                                                Err(e) => {
-                                                    app.set_status(format!("Error: {}", e));
-                                                }
-                                            }
-                                        }
-                                        ConfirmAction::MergeLocalPr { number, branch } => {
-                                            let repo = app.repo.clone();
-                                            // Merge the branch locally using git merge
-                                            if let Some(ref branch_name) = branch {
-                                                match git::merge_branch(branch_name) {
-                                                    Ok(()) => {
-                                                        // Mark the local PR as merged
-                                                        let _ =
-                                                            local::merge_local_pr(&repo, number);
-                                                        // Close the linked local issue
-                                                        if let Some(issue_num) =
-                                                            git::extract_issue_number(branch_name)
-                                                        {
-                                                            let _ = local::close_local_issue(
-                                                                &repo, issue_num,
-                                                            );
-                                                        }
-                                                        // Clean up the worktree
-                                                        if let Some(wt) = app
-                                                            .worktrees
-                                                            .iter()
-                                                            .find(|w| w.title == *branch_name)
-                                                        {
-                                                            let wt_path = wt.description.clone();
-                                                            let wt_branch = wt.title.clone();
-                                                            let _ = remove_worktree(
-                                                                &wt_path,
-                                                                &wt_branch,
-                                                                app.multiplexer,
-                                                            );
-                                                        }
-                                                        app.set_status(format!(
-                                                            "Merged local PR #{} (branch: {})",
-                                                            number, branch_name
-                                                        ));
-                                                    }
-                                                    Err(e) => {
-                                                        app.set_status(format!(
-                                                            "Merge failed: {}",
-                                                            e
-                                                        ));
-                                                    }
-                                                }
-                                            } else {
-                                                // No branch, just mark as merged
-                                                if let Ok(branch_name) =
-                                                    local::merge_local_pr(&repo, number)
-                                                {
-                                                    // Close the linked local issue
-                                                    if let Some(issue_num) =
-                                                        git::extract_issue_number(&branch_name)
-                                                    {
-                                                        let _ = local::close_local_issue(
-                                                            &repo, issue_num,
-                                                        );
-                                                    }
-                                                }
-                                                app.set_status(format!(
-                                                    "Merged local PR #{}",
-                                                    number
-                                                ));
-                                            }
-                                            app.refresh_data();
-                                        }
-                                        ConfirmAction::MergePr {
-                                            number,
-                                            strategy,
-                                            branch,
-                                        } => {
-                                            let repo = app.repo.clone();
-                                            let output = Command::new("gh")
-                                                .args([
-                                                    "pr",
-                                                    "merge",
-                                                    &number.to_string(),
-                                                    strategy.flag(),
-                                                    "--delete-branch",
-                                                    "--repo",
-                                                    &repo,
-                                                ])
-                                                .output();
-                                            match output {
-                                                Ok(o) if o.status.success() => {
-                                                    // Immediately clean up the worktree for the
-                                                    // merged branch instead of waiting for the
-                                                    // next refresh cycle and GitHub API to reflect
-                                                    // the merged state.
-                                                    if let Some(ref branch_name) = branch {
-                                                        if let Some(wt) = app
-                                                            .worktrees
-                                                            .iter()
-                                                            .find(|w| w.title == *branch_name)
-                                                        {
-                                                            let wt_path = wt.description.clone();
-                                                            let wt_branch = wt.title.clone();
-                                                            if remove_worktree(
-                                                                &wt_path,
-                                                                &wt_branch,
-                                                                app.multiplexer,
-                                                            )
-                                                            .is_ok()
-                                                            {
-                                                                app.set_status(format!(
-                                                                    "Merged PR #{} ({}) — cleaned up worktree '{}'",
-                                                                    number,
-                                                                    strategy.label(),
-                                                                    wt_branch
-                                                                ));
-                                                            }
-                                                        }
-                                                    }
-                                                    app.pull_requests = fetch_prs(
-                                                        &repo,
-                                                        app.pr_state_filter,
-                                                        app.pr_assignee_filter,
-                                                    );
-                                                    app.worktrees = fetch_worktrees();
-                                                    app.sessions = fetch_sessions(
-                                                        &app.session_states,
-                                                        app.multiplexer,
-                                                    );
-                                                    app.clamp_selected();
-                                                    app.last_refresh = std::time::Instant::now();
-                                                    // Schedule a delayed refresh so GitHub
-                                                    // API changes (e.g. linked issues closing)
-                                                    // are picked up.
-                                                    app.pending_refresh = Some(
-                                                        std::time::Instant::now()
-                                                            + std::time::Duration::from_secs(3),
-                                                    );
-                                                    if branch.is_none()
-                                                        || app.worktrees.iter().any(|w| {
-                                                            branch.as_deref() == Some(&w.title)
-                                                        })
-                                                    {
-                                                        app.set_status(format!(
-                                                            "Merged PR #{} ({})",
-                                                            number,
-                                                            strategy.label()
-                                                        ));
-                                                    }
-                                                }
-                                                Ok(o) => {
-                                                    let stderr = String::from_utf8_lossy(&o.stderr);
-                                                    app.set_status(format!(
-                                                        "Error: {}",
-                                                        stderr.trim()
-                                                    ));
-                                                }
-                                                Err(e) => {
-                                                    app.set_status(format!("Error: {}", e));
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
+                        match key.code {
+                            KeyCode::Esc => {
+                                app.issue_submit_rx = None;
+                                app.issue_modal = None;
                                 app.mode = Mode::Normal;
                             }
-                            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                                app.confirm_modal = None;
-                                app.mode = Mode::Normal;
+                            KeyCode::Tab => {
+                                modal.active_field = modal.next_field();
                             }
-                            _ => {}
-                        },
-                        Mode::CreatingIssue => {
-                            if let Some(modal) = &mut app.issue_modal {
-                                // Block input while submitting (only allow Esc)
-                                if modal.submitting && key.code != KeyCode::Esc {
-                                    continue;
-                                }
-                                match key.code {
-                                    KeyCode::Esc => {
-                                        app.issue_submit_rx = None;
-                                        app.issue_modal = None;
-                                        app.mode = Mode::Normal;
-                                    }
-                                    KeyCode::Tab => {
-                                        modal.active_field = match modal.active_field {
-                                            0 => 1,
-                                            1 => 2,
-                                            _ => 0,
-                                        };
-                                    }
-                                    KeyCode::Enter if modal.active_field == 0 => {
-                                        modal.active_field = 1;
-                                    }
-                                    KeyCode::Char(' ') if modal.active_field == 2 => {
-                                        modal.create_worktree = !modal.create_worktree;
-                                    }
-                                    KeyCode::Enter if modal.active_field == 2 => {
-                                        modal.create_worktree = !modal.create_worktree;
-                                    }
-                                    KeyCode::Char('s')
-                                        if key.modifiers.contains(KeyModifiers::CONTROL)
-                                            && !modal.submitting =>
-                                    {
-                                        let title = modal.title.value().trim().to_string();
-                                        if title.is_empty() {
-                                            modal.error = Some("Title cannot be empty".to_string());
+                            KeyCode::Enter if modal.active_field == 0 => {
+                                modal.active_field = 1;
+                            }
+                            KeyCode::Char(' ') | KeyCode::Enter if modal.active_field == 5 => {
+                                modal.create_worktree = !modal.create_worktree;
+                            }
+                            KeyCode::Char('s')
+                                if key.modifiers.contains(KeyModifiers::CONTROL)
+                                    && !modal.submitting =>
+                            {
+                                let title = modal.title.value().trim().to_string();
+                                if title.is_empty() {
+                                    modal.error = Some("Title cannot be empty".to_string());
+                                } else {
+                                    modal.submitting = true;
+                                    modal.error = None;
+                                    let body = modal.body.value().to_string();
+                                    let metadata = modal.metadata();
+                                    let repo = app.repo.clone();
+                                    let hook_script = app.hook_script_path.clone();
+                                    let claude_cmd = get_session_command(&repo);
+                                    let mux = app.multiplexer;
+                                    let create_worktree = modal.create_worktree;
+                                    let is_local = app.local_mode;
+                                    let (tx, rx) = mpsc::channel();
+                                    app.issue_submit_rx = Some(rx);
+                                    std::thread::spawn(move || {
+                                        let issue_result = if is_local {
+                                            local::create_local_issue(
+                                                &repo,
+                                                &title,
+                                                &body,
+                                                &metadata.labels,
+                                            )
+                                            .map_err(Error::from)
                                         } else {
-                                            modal.submitting = true;
-                                            modal.error = None;
-                                            let body = modal.body.value().to_string();
-                                            let repo = app.repo.clone();
-                                            let hook_script = app.hook_script_path.clone();
-                                            let claude_cmd = get_session_command(&repo);
-                                            let mux = app.multiplexer;
-                                            let create_worktree = modal.create_worktree;
-                                            let is_local = app.local_mode;
-                                            let (tx, rx) = mpsc::channel();
-                                            app.issue_submit_rx = Some(rx);
-                                            std::thread::spawn(move || {
-                                                let issue_result = if is_local {
-                                                    local::create_local_issue(&repo, &title, &body)
+                                            create_issue(&repo, &title, &body, &metadata)
+                                                .map_err(Error::from)
+                                        };
+                                        match issue_result {
+                                            Ok(number) => {
+                                                let worktree_result = if create_worktree {
+                                                    let pr_ready = get_pr_ready(&repo);
+                                                    let auto_open_pr = get_auto_open_pr(&repo);
+                                                    let base = git::default_base_branch(&repo);
+                                                    Some(create_worktree_and_session(
+                                                        &repo,
+                                                        number,
+                                                        &title,
+                                                        &body,
+                                                        base.as_deref(),
+                                                        hook_script.as_deref(),
+                                                        pr_ready,
+                                                        auto_open_pr,
+                                                        claude_cmd.as_deref(),
+                                                        mux,
+                                                        is_local,
+                                                    ))
                                                 } else {
-                                                    create_issue(&repo, &title, &body)
+                                                    None
                                                 };
-                                                match issue_result {
-                                                    Ok(number) => {
-                                                        let worktree_result = if create_worktree {
-                                                            let pr_ready = get_pr_ready(&repo);
-                                                            let auto_open_pr =
-                                                                get_auto_open_pr(&repo);
-                                                            Some(create_worktree_and_session(
-                                                                &repo,
-                                                                number,
-                                                                &title,
-                                                                &body,
-                                                                hook_script.as_deref(),
-                                                                pr_ready,
-                                                                auto_open_pr,
-                                                                claude_cmd.as_deref(),
-                                                                mux,
-                                                                is_local,
-                                                            ))
-                                                        } else {
-                                                            None
-                                                        };
-                                                        let _ =
-                                                            tx.send(IssueSubmitResult::Success {
-                                                                number,
-                                                                worktree_result,
-                                                            });
-                                                    }
-                                                    Err(e) => {
-                                                        let _ =
-                                                            tx.send(IssueSubmitResult::Error(e));
-                                                    }
-                                                }
-                                            });
-                                        }
-                                    }
-                                    KeyCode::Backspace => {
-                                        if modal.active_field == 0 {
-                                            modal.title.delete_back();
-                                        } else {
-                                            modal.body.delete_back();
-                                        }
-                                    }
-                                    KeyCode::Left => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_left();
-                                        } else {
-                                            modal.body.move_left();
-                                        }
-                                    }
-                                    KeyCode::Right => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_right();
-                                        } else {
-                                            modal.body.move_right();
-                                        }
-                                    }
-                                    KeyCode::Home => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_home();
-                                        } else {
-                                            modal.body.move_home();
-                                        }
-                                    }
-                                    KeyCode::End => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_end();
-                                        } else {
-                                            modal.body.move_end();
-                                        }
-                                    }
-                                    KeyCode::Char(c) if modal.active_field != 2 => {
-                                        if modal.active_field == 0 {
-                                            modal.title.insert(c);
-                                        } else {
-                                            modal.body.insert(c);
+                                                let _ = tx.send(IssueSubmitResult::Success {
+                                                    number,
+                                                    worktree_result,
+                                                });
+                                            }
+                                            Err(e) => {
+                                                let _ = tx
+                                                    .send(IssueSubmitResult::Error(e.to_string()));
+                                            }
                                         }
-                                    }
-                                    KeyCode::Enter if modal.active_field == 1 => {
-                                        modal.body.insert('\n');
-                                    }
-                                    _ => {}
+                                    });
                                 }
                             }
-                        }
-                        Mode::EditingVerifyCommand { input } => match key.code {
-                            KeyCode::Esc => {
-                                app.mode = Mode::Normal;
-                            }
-                            KeyCode::Enter => {
-                                let cmd = input.value().trim().to_string();
-                                if !cmd.is_empty() {
-                                    let repo = app.repo.clone();
-                                    let _ = set_verify_command(&repo, &cmd);
-                                    app.set_status(format!("Saved verify command: {}", cmd));
-
-                                    // Now execute the verify command
-                                    if let Some(card) = app.worktrees.get(app.selected_card[1]) {
-                                        let worktree_path = card.description.clone();
-                                        let expanded = expand_editor_command(&cmd, &worktree_path);
-                                        let _ = Command::new("sh").args(["-c", &expanded]).spawn();
+                            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                modal.active_field = 1;
+                                match edit_in_editor(terminal, console, modal.body.value())? {
+                                    Ok(body) => {
+                                        modal.body.set_value(body);
+                                        modal.error = None;
                                     }
+                                    Err(e) => modal.error = Some(e),
                                 }
-                                app.mode = Mode::Normal;
-                            }
-                            KeyCode::Backspace => {
-                                input.delete_back();
-                            }
-                            KeyCode::Left => {
-                                input.move_left();
                             }
-                            KeyCode::Right => {
-                                input.move_right();
+                            _ if modal.active_field == 1 => {
+                                modal.body.handle_key(key);
                             }
-                            KeyCode::Home => {
-                                input.move_home();
-                            }
-                            KeyCode::End => {
-                                input.move_end();
-                            }
-                            KeyCode::Char(c) => {
-                                input.insert(c);
+                            _ if modal.active_field != 0 => {
+                                if let Some(choice) = modal.choice_mut(modal.active_field) {
+                                    match key.code {
+                                        KeyCode::Left => choice.prev(),
+                                        KeyCode::Right => choice.next(),
+                                        KeyCode::Char(' ') | KeyCode::Enter => choice.toggle(),
+                                        _ => {}
+                                    }
+                                }
                             }
+                            KeyCode::Backspace => modal.title.delete_back(),
+                            KeyCode::Left => modal.title.move_left(),
+                            KeyCode::Right => modal.title.move_right(),
+                            KeyCode::Home => modal.title.move_home(),
+                            KeyCode::End => modal.title.move_end(),
+                            KeyCode::Char(c) => modal.title.insert(c),
                             _ => {}
-                        },
-                        Mode::EditingIssue => {
-                            if let Some(modal) = &mut app.edit_issue_modal {
-                                if modal.submitting && key.code != KeyCode::Esc {
-                                    continue;
+                        }
+                    }
+                }
+                Mode::EditingVerifyCommand { input } => match key.code {
+                    KeyCode::Esc => {
+                        app.mode = Mode::Normal;
+                    }
+                    KeyCode::Enter => {
+                        let cmd = input.value().trim().to_string();
+                        if !cmd.is_empty() {
+                            let repo = app.repo.clone();
+                            match set_verify_command(&repo, &cmd) {
+                                Ok(()) => app.set_status(format!("Saved verify command: {}", cmd)),
+                                Err(e) => {
+                                    app.set_status(format!("Could not save verify command: {}", e))
                                 }
-                                match key.code {
-                                    KeyCode::Esc => {
-                                        app.issue_edit_rx = None;
-                                        app.edit_issue_modal = None;
-                                        app.mode = Mode::Normal;
-                                    }
-                                    KeyCode::Tab => {
-                                        modal.active_field =
-                                            if modal.active_field == 0 { 1 } else { 0 };
-                                    }
-                                    KeyCode::Enter if modal.active_field == 0 => {
-                                        modal.active_field = 1;
-                                    }
-                                    KeyCode::Char('s')
-                                        if key.modifiers.contains(KeyModifiers::CONTROL)
-                                            && !modal.submitting =>
-                                    {
-                                        let title = modal.title.value().trim().to_string();
-                                        if title.is_empty() {
-                                            modal.error = Some("Title cannot be empty".to_string());
-                                        } else {
-                                            modal.submitting = true;
-                                            modal.error = None;
-                                            let body = modal.body.value().to_string();
-                                            let repo = app.repo.clone();
-                                            let number = modal.number;
-                                            let is_local = app.local_mode;
-                                            let (tx, rx) = mpsc::channel();
-                                            app.issue_edit_rx = Some(rx);
-                                            std::thread::spawn(move || {
-                                                let result = if is_local {
-                                                    local::edit_local_issue(
-                                                        &repo, number, &title, &body,
-                                                    )
-                                                } else {
-                                                    edit_issue(&repo, number, &title, &body)
-                                                };
-                                                match result {
-                                                    Ok(()) => {
-                                                        let _ = tx.send(IssueEditResult::Success {
-                                                            number,
-                                                        });
-                                                    }
-                                                    Err(e) => {
-                                                        let _ = tx.send(IssueEditResult::Error(e));
-                                                    }
-                                                }
-                                            });
-                                        }
-                                    }
-                                    KeyCode::Backspace => {
-                                        if modal.active_field == 0 {
-                                            modal.title.delete_back();
-                                        } else {
-                                            modal.body.delete_back();
-                                        }
-                                    }
-                                    KeyCode::Left => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_left();
-                                        } else {
-                                            modal.body.move_left();
-                                        }
-                                    }
-                                    KeyCode::Right => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_right();
-                                        } else {
-                                            modal.body.move_right();
-                                        }
-                                    }
-                                    KeyCode::Home => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_home();
-                                        } else {
-                                            modal.body.move_home();
-                                        }
-                                    }
-                                    KeyCode::End => {
-                                        if modal.active_field == 0 {
-                                            modal.title.move_end();
-                                        } else {
-                                            modal.body.move_end();
-                                        }
-                                    }
-                                    KeyCode::Char(c) => {
-                                        if modal.active_field == 0 {
-                                            modal.title.insert(c);
-                                        } else {
-                                            modal.body.insert(c);
-                                        }
-                                    }
-                                    KeyCode::Enter if modal.active_field == 1 => {
-                                        modal.body.insert('\n');
-                                    }
-                                    _ => {}
+                            }
+
+                            // Now execute the verify command
+                            if let Some(card) = app.selected(1) {
+                                let worktree_path = card.description.clone();
+                                let expanded = expand_editor_command(&cmd, &worktree_path);
+                                if let Err(e) = Command::new("sh").args(["-c", &expanded]).spawn() {
+                                    app.set_status(format!("Failed to launch {}: {}", expanded, e));
                                 }
                             }
                         }
-                        Mode::EditingEditorCommand { input } => match key.code {
-                            KeyCode::Esc => {
-                                app.mode = Mode::Normal;
+                        app.mode = Mode::Normal;
+                    }
+                    KeyCode::Backspace => {
+                        input.delete_back();
+                    }
+                    KeyCode::Left => {
+                        input.move_left();
+                    }
+                    KeyCode::Right => {
+                        input.move_right();
+                    }
+                    KeyCode::Home => {
+                        input.move_home();
+                    }
+                    KeyCode::End => {
+                        input.move_end();
+                    }
+                    KeyCode::Char(c) => {
+                        input.insert(c);
+                    }
+                    _ => {}
+                },
+                Mode::NamingView { input, filter } => match key.code {
+                    KeyCode::Esc => {
+                        // Back to the filter the view would have taken
+                        app.mode = match filter.take() {
+                            Some(filter) => Mode::Filtering {
+                                query: TextInput::from(filter),
+                                focused: false,
+                            },
+                            None => Mode::Normal,
+                        };
+                    }
+                    KeyCode::Enter => {
+                        let name = input.value().trim().to_string();
+                        if !name.is_empty() {
+                            let filter = filter.take();
+                            app.save_view(&name, filter);
+                        }
+                    }
+                    _ => {
+                        input.handle_key(key);
+                    }
+                },
+                Mode::ChoosingBase { issue_id, input } => match key.code {
+                    KeyCode::Esc => {
+                        app.mode = Mode::Normal;
+                    }
+                    KeyCode::Enter => {
+                        let mut base = input.value().trim().to_string();
+                        let issue_id = issue_id.clone();
+                        // "#N" stacks the work on the branch of issue N
+                        if let Some(number) = base.strip_prefix('#') {
+                            let key = if app.local_mode {
+                                format!("local-issue-{}", number)
+                            } else {
+                                format!("issue-{}", number)
+                            };
+                            let links = git::fetch_issue_links();
+                            match app.worktrees.iter().find(|w| {
+                                git::issue_key_for_branch(&links, &w.title).as_deref()
+                                    == Some(key.as_str())
+                            }) {
+                                Some(wt) => base = wt.title.clone(),
+                                None => {
+                                    app.set_status(format!(
+                                        "No worktree found for issue #{}",
+                                        number
+                                    ));
+                                    return Ok(Flow::Continue);
+                                }
                             }
-                            KeyCode::Enter => {
-                                let cmd = input.value().trim().to_string();
-                                if !cmd.is_empty() {
-                                    let repo = app.repo.clone();
-                                    let _ = set_editor_command(&repo, &cmd);
-                                    app.set_status(format!("Saved editor command: {}", cmd));
-
-                                    // Now launch the editor
-                                    if let Some(card) = app.worktrees.get(app.selected_card[1]) {
-                                        let worktree_path = card.description.clone();
-                                        let expanded = expand_editor_command(&cmd, &worktree_path);
-                                        let _ = Command::new("sh").args(["-c", &expanded]).spawn();
-                                    }
+                        }
+                        if !git::base_exists(&base) {
+                            app.set_status(format!(
+                                "Branch '{}' not found locally or on origin",
+                                base
+                            ));
+                            return Ok(Flow::Continue);
+                        }
+                        app.mode = Mode::Normal;
+                        let issue = app.issues.iter().find(|c| c.id == issue_id);
+                        if let (Some(card), Some(number)) = (issue, extract_issue_number(&issue_id))
+                        {
+                            let title = card.title.clone();
+                            let body = card.full_description.clone().unwrap_or_default();
+                            app.start_issue_worktree(number, title, body, Some(base));
+                        }
+                    }
+                    _ => {
+                        input.handle_key(key);
+                    }
+                },
+                Mode::ExportingStore { input } | Mode::ImportingStore { input } => {
+                    match key.code {
+                        KeyCode::Esc => {
+                            app.mode = Mode::Normal;
+                        }
+                        KeyCode::Enter => {
+                            let path = input.value().trim().to_string();
+                            let exporting = matches!(app.mode, Mode::ExportingStore { .. });
+                            app.mode = Mode::Normal;
+                            if path.is_empty() {
+                                return Ok(Flow::Continue);
+                            }
+                            let repo = app.repo.clone();
+                            let format = transfer::Format::from_path(&path);
+                            app.spawn_effect(if exporting {
+                                Effect::ExportStore { repo, path, format }
+                            } else {
+                                // Dry run first so the confirmation shows what will change
+                                Effect::ImportStore {
+                                    repo,
+                                    path,
+                                    format,
+                                    dry_run: true,
                                 }
+                            });
+                        }
+                        _ => {
+                            input.handle_key(key);
+                        }
+                    }
+                }
+                Mode::EditingIssue => {
+                    if let Some(modal) = &mut app.edit_issue_modal {
+                        if modal.submitting && key.code != KeyCode::Esc {
+                            return Ok(Flow::Continue);
+                        }
+                        match key.code {
+                            KeyCode::Esc => {
+                                app.issue_edit_rx = None;
+                                app.edit_issue_modal = None;
                                 app.mode = Mode::Normal;
                             }
-                            KeyCode::Backspace => {
-                                input.delete_back();
-                            }
-                            KeyCode::Left => {
-                                input.move_left();
+                            KeyCode::Tab => {
+                                modal.active_field = if modal.active_field == 0 { 1 } else { 0 };
                             }
-                            KeyCode::Right => {
-                                input.move_right();
+                            KeyCode::Enter if modal.active_field == 0 => {
+                                modal.active_field = 1;
                             }
-                            KeyCode::Home => {
-                                input.move_home();
+                            KeyCode::Char('s')
+                                if key.modifiers.contains(KeyModifiers::CONTROL)
+                                    && !modal.submitting =>
+                            {
+                                let title = modal.title.value().trim().to_string();
+                                if title.is_empty() {
+                                    modal.error = Some("Title cannot be empty".to_string());
+                                } else {
+                                    modal.submitting = true;
+                                    modal.error = None;
+                                    let body = modal.body.value().to_string();
+                                    let repo = app.repo.clone();
+                                    let number = modal.number;
+                                    let is_local = app.local_mode;
+                                    let (tx, rx) = mpsc::channel();
+                                    app.issue_edit_rx = Some(rx);
+                                    std::thread::spawn(move || {
+                                        let result = if is_local {
+                                            local::edit_local_issue(&repo, number, &title, &body)
+                                                .map_err(Error::from)
+                                        } else {
+                                            edit_issue(&repo, number, &title, &body)
+                                                .map_err(Error::from)
+                                        };
+                                        match result {
+                                            Ok(()) => {
+                                                let _ =
+                                                    tx.send(IssueEditResult::Success { number });
+                                            }
+                                            Err(e) => {
+                                                let _ =
+                                                    tx.send(IssueEditResult::Error(e.to_string()));
+                                            }
+                                        }
+                                    });
+                                }
                             }
-                            KeyCode::End => {
-                                input.move_end();
+                            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                modal.active_field = 1;
+                                match edit_in_editor(terminal, console, modal.body.value())? {
+                                    Ok(body) => {
+                                        modal.body.set_value(body);
+                                        modal.error = None;
+                                    }
+                                    Err(e) => modal.error = Some(e),
+                                }
                             }
-                            KeyCode::Char(c) => {
-                                input.insert(c);
+                            _ if modal.active_field == 1 => {
+                                modal.body.handle_key(key);
                             }
+                            KeyCode::Backspace => modal.title.delete_back(),
+                            KeyCode::Left => modal.title.move_left(),
+                            KeyCode::Right => modal.title.move_right(),
+                            KeyCode::Home => modal.title.move_home(),
+                            KeyCode::End => modal.title.move_end(),
+                            KeyCode::Char(c) => modal.title.insert(c),
                             _ => {}
-                        },
+                        }
                     }
                 }
+                Mode::EditingEditorCommand { input } => match key.code {
+                    KeyCode::Esc => {
+                        app.mode = Mode::Normal;
+                    }
+                    KeyCode::Enter => {
+                        let cmd = input.value().trim().to_string();
+                        if !cmd.is_empty() {
+                            let repo = app.repo.clone();
+                            match set_editor_command(&repo, &cmd) {
+                                Ok(()) => app.set_status(format!("Saved editor command: {}", cmd)),
+                                Err(e) => {
+                                    app.set_status(format!("Could not save editor command: {}", e))
+                                }
+                            }
+
+                            // Now launch the editor
+                            if let Some(card) = app.selected(1) {
+                                let worktree_path = card.description.clone();
+                                let expanded = expand_editor_command(&cmd, &worktree_path);
+                                if let Err(e) = Command::new("sh").args(["-c", &expanded]).spawn() {
+                                    app.set_status(format!("Failed to launch {}: {}", expanded, e));
+                                }
+                            }
+                        }
+                        app.mode = Mode::Normal;
+                    }
+                    KeyCode::Backspace => {
+                        input.delete_back();
+                    }
+                    KeyCode::Left => {
+                        input.move_left();
+                    }
+                    KeyCode::Right => {
+                        input.move_right();
+                    }
+                    KeyCode::Home => {
+                        input.move_home();
+                    }
+                    KeyCode::End => {
+                        input.move_end();
+                    }
+                    KeyCode::Char(c) => {
+                        input.insert(c);
+                    }
+                    _ => {}
+                },
+                // Board keys and the confirmation modal go through action::from_key
+                Mode::Normal | Mode::Filtering { .. } | Mode::Confirming => {}
             }
         }
     }
 
-    disable_raw_mode()?;
-    io::stdout().execute(LeaveAlternateScreen)?;
-    let _ = fs::remove_file(SOCKET_PATH);
-    Ok(())
+    Ok(Flow::Continue)
 }
 
 #[cfg(test)]
@@ -2083,6 +1639,10 @@ mod tests {
     use std::fs;
     use std::process::Command;
 
+    use crossterm::event::KeyCode;
+
+    use crate::testing::{gh_board, gh_issue, gh_pr, Sandbox, Tui};
+
     fn tmux_available() -> bool {
         Command::new("tmux").arg("-V").output().is_ok()
     }
@@ -2207,6 +1767,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_commands_for_a_missing_session_are_reported() {
+        if !tmux_available() {
+            eprintln!("Skipping: tmux not available");
+            return;
+        }
+
+        let session = "octopai-test-missing";
+        let _ = Command::new("tmux")
+            .args(["kill-session", "-t", session])
+            .output();
+
+        // Keys for a session that is gone are reported, not dropped
+        let mux = crate::session::Multiplexer::Tmux;
+        assert!(mux.send_keys(session, "echo").is_err());
+        assert!(mux.kill_session(session).is_err());
+    }
+
     #[test]
     fn test_send_keys_with_shell_expansion() {
         if !tmux_available() {
@@ -2262,4 +1840,67 @@ mod tests {
             pane_contents
         );
     }
+
+    #[test]
+    fn editor_file_is_private_to_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = super::write_private_temp_file("issue.md", "Title\n\nBody").unwrap();
+        let dir = path.parent().unwrap().to_path_buf();
+        let mode = |p: &std::path::Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Title\n\nBody");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn issue_to_merged_pr_flow() {
+        let Some(sandbox) = Sandbox::enter("tests::issue_to_merged_pr_flow") else {
+            return;
+        };
+        let issue = gh_issue(7, "Fix login");
+        sandbox.respond(
+            "api graphql",
+            &gh_board(std::slice::from_ref(&issue), &[], &[]),
+        );
+        let mut tui = Tui::new();
+        assert!(tui.screen().contains("#7 Fix login"));
+
+        // w on the issue: worktree, branch linked to the issue, session
+        tui.press(KeyCode::Char('w'));
+        tui.settle();
+        let worktree = sandbox.root().join("widgets-issue-7");
+        assert!(worktree.is_dir());
+        assert!(tui.app.worktrees.iter().any(|w| w.title == "issue-7"));
+        assert_eq!(sandbox.sessions(), vec!["issue-7"]);
+        assert!(sandbox.called("gh issue edit --repo acme/widgets 7 --add-assignee @me"));
+        assert!(tui.screen().contains("issue-7"));
+
+        // The agent opens a PR for the branch
+        let pr = gh_pr(12, "Fix login", "issue-7");
+        sandbox.respond("api graphql", &gh_board(&[issue], &[pr], &[]));
+        tui.app.refresh_data();
+        assert!(tui.screen().contains("#12 Fix login"));
+
+        // Merge it from the PR column; its worktree and session go away
+        for _ in 0..3 {
+            tui.press(KeyCode::Tab);
+        }
+        tui.press(KeyCode::Char('M'));
+        tui.press(KeyCode::Char('y'));
+        tui.settle();
+        assert!(sandbox.called("gh pr merge 12 --merge --delete-branch --repo acme/widgets"));
+        assert!(sandbox.called("tmux kill-session -t issue-7"));
+        assert!(!worktree.exists());
+        assert!(sandbox.sessions().is_empty());
+        assert!(tui.app.worktrees.is_empty());
+        assert!(
+            tui.messages()
+                .iter()
+                .any(|m| m.contains("cleaned up worktree 'issue-7'")),
+            "{:?}",
+            tui.messages()
+        );
+    }
 }
@@ -0,0 +1,347 @@
+//! Just enough Markdown for issue and PR bodies in the card detail view:
+//! headings, lists and task lists, block quotes, fenced code, rules and the
+//! inline `code`, **bold**, *emphasis*, ~~strikethrough~~ and links. HTML
+//! comments (as left by issue templates) are dropped. Anything else is
+//! shown as written.
+
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::theme::current as theme;
+
+/// Render `text` as styled lines, one per source line (wrapping is left to
+/// the widget that shows them).
+pub fn render(text: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut in_comment = false;
+
+    for raw in text.lines() {
+        let trimmed = raw.trim_start();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            } else {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", raw),
+                    Style::default().fg(theme().accent),
+                )));
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        let (line, still_in_comment) = strip_comments(raw, in_comment);
+        let dropped = in_comment || still_in_comment || line.len() != raw.len();
+        in_comment = still_in_comment;
+        if dropped && line.trim().is_empty() {
+            continue;
+        }
+        lines.push(block(&line));
+    }
+    lines
+}
+
+/// `line` without the parts inside `<!-- -->`, and whether a comment is
+/// still open at its end.
+fn strip_comments(line: &str, mut in_comment: bool) -> (String, bool) {
+    let mut out = String::new();
+    let mut rest = line;
+    loop {
+        if in_comment {
+            match rest.find("-->") {
+                Some(end) => {
+                    rest = &rest[end + 3..];
+                    in_comment = false;
+                }
+                None => return (out, true),
+            }
+        } else {
+            match rest.find("<!--") {
+                Some(start) => {
+                    out.push_str(&rest[..start]);
+                    rest = &rest[start + 4..];
+                    in_comment = true;
+                }
+                None => {
+                    out.push_str(rest);
+                    return (out, false);
+                }
+            }
+        }
+    }
+}
+
+/// One line outside a code block.
+fn block(line: &str) -> Line<'static> {
+    let trimmed = line.trim_start();
+    let indent = " ".repeat(line.len() - trimmed.len());
+
+    let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+        let mut style = Style::default()
+            .fg(theme().accent)
+            .add_modifier(Modifier::BOLD);
+        if hashes == 1 {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        return Line::from(inline(trimmed[hashes..].trim(), style));
+    }
+
+    let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|c| compact.chars().all(|x| x.to_string() == *c))
+    {
+        return Line::from(Span::styled(
+            "─".repeat(40),
+            Style::default().fg(theme().dim),
+        ));
+    }
+
+    if let Some(quote) = trimmed.strip_prefix('>') {
+        let mut spans = vec![Span::styled("│ ", Style::default().fg(theme().dim))];
+        spans.extend(inline(
+            quote.trim_start(),
+            Style::default()
+                .fg(theme().muted)
+                .add_modifier(Modifier::ITALIC),
+        ));
+        return Line::from(spans);
+    }
+
+    let text_style = Style::default().fg(theme().text);
+    if let Some((marker, item)) = list_item(trimmed) {
+        let mut spans = vec![Span::raw(indent)];
+        let task = item
+            .strip_prefix("[ ] ")
+            .map(|rest| (false, rest))
+            .or_else(|| {
+                item.strip_prefix("[x] ")
+                    .or_else(|| item.strip_prefix("[X] "))
+                    .map(|rest| (true, rest))
+            });
+        match task {
+            Some((done, rest)) => {
+                let (symbol, color) = if done {
+                    ("☑ ", theme().success)
+                } else {
+                    ("☐ ", theme().dim)
+                };
+                spans.push(Span::styled(symbol, Style::default().fg(color)));
+                let style = if done {
+                    Style::default().fg(theme().muted)
+                } else {
+                    text_style
+                };
+                spans.extend(inline(rest, style));
+            }
+            None => {
+                spans.push(Span::styled(marker, Style::default().fg(theme().warning)));
+                spans.extend(inline(item, text_style));
+            }
+        }
+        return Line::from(spans);
+    }
+
+    let mut spans = vec![Span::raw(indent)];
+    spans.extend(inline(trimmed, text_style));
+    Line::from(spans)
+}
+
+/// The marker to show and the rest of a list item: `• ` for `-`, `*` and
+/// `+` bullets, the number itself for ordered items.
+fn list_item(line: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return Some(("• ".to_string(), rest));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+    if digits > 0 && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        return Some((format!("{} ", &line[..digits + 1]), &rest[2..]));
+    }
+    None
+}
+
+/// Inline markup in `text`, on top of `base`.
+fn inline(text: &str, base: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut buf = String::new();
+    let mut bold = false;
+    let mut italic = false;
+    let mut strike = false;
+    let style = |bold: bool, italic: bool, strike: bool| {
+        let mut style = base;
+        if bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if strike {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
+        style
+    };
+    let flush = |buf: &mut String, spans: &mut Vec<Span<'static>>, style: Style| {
+        if !buf.is_empty() {
+            spans.push(Span::styled(std::mem::take(buf), style));
+        }
+    };
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let current = style(bold, italic, strike);
+        let after = &rest[c.len_utf8()..];
+
+        if c == '\\' && after.starts_with(|n: char| n.is_ascii_punctuation()) {
+            let escaped = after.chars().next().unwrap_or_default();
+            buf.push(escaped);
+            rest = &after[escaped.len_utf8()..];
+            continue;
+        }
+        if c == '`' {
+            if let Some(end) = after.find('`') {
+                flush(&mut buf, &mut spans, current);
+                spans.push(Span::styled(
+                    after[..end].to_string(),
+                    base.fg(theme().accent),
+                ));
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+        if let Some(marker) = ["**", "__", "~~"].into_iter().find(|m| rest.starts_with(m)) {
+            let open = if marker == "~~" { strike } else { bold };
+            if open || rest[2..].contains(marker) {
+                flush(&mut buf, &mut spans, current);
+                if marker == "~~" {
+                    strike = !strike;
+                } else {
+                    bold = !bold;
+                }
+                rest = &rest[2..];
+                continue;
+            }
+        }
+        if c == '*' && (italic || (!after.starts_with(' ') && after.contains('*'))) {
+            flush(&mut buf, &mut spans, current);
+            italic = !italic;
+            rest = after;
+            continue;
+        }
+        let image = c == '!' && after.starts_with('[');
+        let link_start = if image { after } else { rest };
+        if c == '[' || image {
+            if let Some((label, url, tail)) = link(link_start) {
+                flush(&mut buf, &mut spans, current);
+                if image {
+                    spans.push(Span::styled(
+                        format!("[image: {}]", label),
+                        current.fg(theme().dim),
+                    ));
+                } else {
+                    spans.push(Span::styled(
+                        label.to_string(),
+                        current
+                            .fg(theme().accent)
+                            .add_modifier(Modifier::UNDERLINED),
+                    ));
+                    if url != label {
+                        spans.push(Span::styled(
+                            format!(" ({})", url),
+                            Style::default().fg(theme().dim),
+                        ));
+                    }
+                }
+                rest = tail;
+                continue;
+            }
+        }
+        buf.push(c);
+        rest = after;
+    }
+    flush(&mut buf, &mut spans, style(bold, italic, strike));
+    spans
+}
+
+/// A `[label](url)` at the start of `text`, and what follows it.
+fn link(text: &str) -> Option<(&str, &str, &str)> {
+    let label_end = text.find("](")?;
+    let label = &text[1..label_end];
+    let after = &text[label_end + 2..];
+    let url_end = after.find(')')?;
+    Some((label, &after[..url_end], &after[url_end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    fn span<'a>(line: &'a Line, content: &str) -> &'a Span<'a> {
+        line.spans
+            .iter()
+            .find(|s| s.content == content)
+            .unwrap_or_else(|| panic!("no span {:?} in {:?}", content, text(line)))
+    }
+
+    #[test]
+    fn renders_the_markdown_issues_are_written_in() {
+        let body = "\
+## Steps
+<!-- Describe how to reproduce -->
+1. Sign in with **SSO** and `--no-email`
+- [x] reproduced
+- [ ] fixed, see [the docs](https://example.com/sso)
+> blank page
+```sh
+cargo run -- *not* markup
+```
+---
+Cost is 2 * 3 and snake_case_names stay";
+        let lines = render(body);
+        let texts: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(
+            texts,
+            [
+                "Steps",
+                "1. Sign in with SSO and --no-email",
+                "☑ reproduced",
+                "☐ fixed, see the docs (https://example.com/sso)",
+                "│ blank page",
+                "  cargo run -- *not* markup",
+                &"─".repeat(40),
+                "Cost is 2 * 3 and snake_case_names stay",
+            ]
+        );
+
+        assert!(lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert!(span(&lines[1], "SSO")
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert_eq!(span(&lines[1], "--no-email").style.fg, Some(theme().accent));
+        assert!(span(&lines[3], "the docs")
+            .style
+            .add_modifier
+            .contains(Modifier::UNDERLINED));
+        assert!(lines[4].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::ITALIC));
+    }
+}
@@ -1,14 +1,43 @@
 use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::style::Color;
 
+use crate::templates::IssueTemplate;
+use crate::textarea::TextArea;
+use crate::theme;
+
 pub const SOCKET_PATH: &str = "/tmp/octopai-events.sock";
 pub const MAX_MESSAGES: usize = 100;
+pub const SECTION_NAMES: [&str; 4] = ["Issues", "Worktrees", "Sessions", "Pull Requests"];
 
 pub type SessionStates = Arc<Mutex<HashMap<String, String>>>;
 pub type MessageLog = Arc<Mutex<VecDeque<String>>>;
 
+/// Append a message to a shared log, trimming it to `MAX_MESSAGES`.
+pub fn push_message(message_log: &MessageLog, msg: &str) {
+    if let Ok(mut log) = message_log.lock() {
+        log.push_back(msg.to_string());
+        while log.len() > MAX_MESSAGES {
+            log.pop_front();
+        }
+    }
+}
+
+/// A card's one-line description of `body`, shortened by character to 80
+/// at most; `None` for an empty body.
+pub fn short_description(body: &str) -> Option<String> {
+    if body.is_empty() {
+        None
+    } else if body.chars().count() > 80 {
+        Some(format!("{}...", body.chars().take(77).collect::<String>()))
+    } else {
+        Some(body.to_string())
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct Card {
     pub id: String,
     pub title: String,
@@ -23,6 +52,144 @@ pub struct Card {
     pub is_merged: Option<bool>,
     pub head_branch: Option<String>,
     pub is_assigned: Option<bool>,
+    pub worktree_status: Option<WorktreeStatus>,
+    /// Set when the card's branch is stacked on another card's branch in the
+    /// same column.
+    pub stack: Option<StackPosition>,
+    /// Combined CI check state of a PR's head commit.
+    pub checks: Option<CheckStatus>,
+    /// All of an issue's or PR's labels; `tag` shows the first.
+    pub labels: Vec<String>,
+    /// Logins of the issue's or PR's assignees.
+    pub assignees: Vec<String>,
+    /// Title of the issue's or PR's milestone.
+    pub milestone: Option<String>,
+    /// Unix timestamp of the issue's or PR's last update on GitHub.
+    pub updated_at: Option<u64>,
+}
+
+/// Rolled-up state of the checks on a PR's head commit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CheckStatus {
+    Pending,
+    Success,
+    Failure,
+}
+
+impl CheckStatus {
+    /// Map GitHub's `StatusState` (`SUCCESS`, `FAILURE`, `PENDING`, ...).
+    pub fn from_rollup(state: &str) -> Option<Self> {
+        match state {
+            "SUCCESS" => Some(CheckStatus::Success),
+            "FAILURE" | "ERROR" => Some(CheckStatus::Failure),
+            "PENDING" | "EXPECTED" => Some(CheckStatus::Pending),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            CheckStatus::Pending => "●",
+            CheckStatus::Success => "✓",
+            CheckStatus::Failure => "✗",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            CheckStatus::Pending => theme::current().warning,
+            CheckStatus::Success => theme::current().success,
+            CheckStatus::Failure => theme::current().error,
+        }
+    }
+}
+
+/// Where a stacked card sits in its stack.
+#[derive(Clone, Debug)]
+pub struct StackPosition {
+    /// Branch this card's branch was started from.
+    pub parent: String,
+    /// 1 for a direct child of an unstacked branch, 2 for its child, etc.
+    pub depth: usize,
+}
+
+/// Reorder `cards` so every stacked card directly follows its parent, and
+/// fill in `stack`. A card is stacked when `bases` maps its `head_branch` to
+/// the `head_branch` of another card; everything else keeps its order.
+pub fn order_stacks(cards: Vec<Card>, bases: &HashMap<String, String>) -> Vec<Card> {
+    let index: HashMap<String, usize> = cards
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| Some((c.head_branch.clone()?, i)))
+        .collect();
+    let parent_of = |card: &Card| {
+        let branch = card.head_branch.as_ref()?;
+        let parent = bases.get(branch).filter(|p| *p != branch)?;
+        index.get(parent).copied()
+    };
+
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, card) in cards.iter().enumerate() {
+        match parent_of(card) {
+            Some(parent) => children.entry(parent).or_default().push(i),
+            None => roots.push(i),
+        }
+    }
+
+    // Depth-first from each root; cards in a cycle are never reached and
+    // are appended unstacked at the end.
+    let mut order: Vec<(usize, usize)> = Vec::with_capacity(cards.len());
+    let mut stack: Vec<(usize, usize)> = roots.into_iter().rev().map(|i| (i, 0)).collect();
+    while let Some((i, depth)) = stack.pop() {
+        order.push((i, depth));
+        if let Some(kids) = children.get(&i) {
+            stack.extend(kids.iter().rev().map(|&k| (k, depth + 1)));
+        }
+    }
+    let mut seen = vec![false; cards.len()];
+    for &(i, _) in &order {
+        seen[i] = true;
+    }
+    order.extend((0..cards.len()).filter(|&i| !seen[i]).map(|i| (i, 0)));
+
+    let mut slots: Vec<Option<Card>> = cards.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|(i, depth)| {
+            let mut card = slots[i].take()?;
+            card.stack = if depth > 0 {
+                card.head_branch
+                    .as_ref()
+                    .and_then(|b| bases.get(b))
+                    .map(|parent| StackPosition {
+                        parent: parent.clone(),
+                        depth,
+                    })
+            } else {
+                None
+            };
+            Some(card)
+        })
+        .collect()
+}
+
+/// Branch and working-tree state of a worktree, shown on worktree cards.
+#[derive(Clone, Debug, Default)]
+pub struct WorktreeStatus {
+    /// Commits on the branch that are not on main.
+    pub ahead: usize,
+    /// Commits on main that are not on the branch.
+    pub behind: usize,
+    /// Whether merging main would conflict; `None` if it could not be checked.
+    pub conflicts: Option<bool>,
+    /// Tracked files with staged or unstaged changes.
+    pub changed: usize,
+    /// Untracked files.
+    pub untracked: usize,
+    pub last_commit_subject: Option<String>,
+    /// Unix timestamp of the last commit.
+    pub last_commit_time: Option<u64>,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -69,6 +236,7 @@ impl AssigneeFilter {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MergeStrategy {
     Merge,
 }
@@ -115,6 +283,27 @@ pub enum ConfirmAction {
         number: u64,
         branch: Option<String>,
     },
+    SyncLocalToGithub,
+    /// Rebase the branches stacked on `parent` onto it, or when `onto` is set
+    /// (the parent was merged), move them onto `onto` and drop `parent`.
+    RestackChildren {
+        parent: String,
+        onto: Option<String>,
+    },
+    ImportStore {
+        path: String,
+        format: crate::transfer::Format,
+    },
+    StartWorktree {
+        number: u64,
+        title: String,
+        body: String,
+    },
+    MarkPrReady {
+        number: u64,
+    },
+    /// One of the above for each marked card, confirmed together.
+    Bulk(Vec<ConfirmAction>),
 }
 
 pub struct ConfirmModal {
@@ -125,12 +314,35 @@ pub struct ConfirmModal {
 #[derive(PartialEq)]
 pub enum Mode {
     Normal,
-    Filtering { query: TextInput, focused: bool },
+    Filtering {
+        query: TextInput,
+        focused: bool,
+    },
     CreatingIssue,
     EditingIssue,
     Confirming,
-    EditingVerifyCommand { input: TextInput },
-    EditingEditorCommand { input: TextInput },
+    EditingVerifyCommand {
+        input: TextInput,
+    },
+    EditingEditorCommand {
+        input: TextInput,
+    },
+    ExportingStore {
+        input: TextInput,
+    },
+    ImportingStore {
+        input: TextInput,
+    },
+    ChoosingBase {
+        issue_id: String,
+        input: TextInput,
+    },
+    /// Naming a saved view; `filter` is the `/` filter that was applied to
+    /// the focused column, which the view takes.
+    NamingView {
+        input: TextInput,
+        filter: Option<String>,
+    },
 }
 
 #[derive(PartialEq)]
@@ -160,10 +372,14 @@ pub struct ConfigEditState {
     pub session_command: TextInput,
     pub multiplexer: crate::session::Multiplexer,
     pub refresh_interval: TextInput,
-    pub active_field: usize, // 0 = verify, 1 = editor, 2 = pr_ready, 3 = auto_open_pr, 4 = session_command, 5 = multiplexer, 6 = refresh_interval
+    pub worktree_dir_template: TextInput,
+    pub branch_template: TextInput,
+    pub base_branch: TextInput,
+    pub active_field: usize, // 0 = verify, 1 = editor, 2 = pr_ready, 3 = auto_open_pr, 4 = session_command, 5 = multiplexer, 6 = refresh_interval, 7 = worktree_dir_template, 8 = branch_template, 9 = base_branch
 }
 
 impl ConfigEditState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         verify_command: String,
         editor_command: String,
@@ -172,6 +388,9 @@ impl ConfigEditState {
         session_command: String,
         multiplexer: crate::session::Multiplexer,
         auto_refresh_secs: u64,
+        worktree_dir_template: String,
+        branch_template: String,
+        base_branch: String,
     ) -> Self {
         let refresh_text = if auto_refresh_secs == 0 {
             String::new()
@@ -186,6 +405,9 @@ impl ConfigEditState {
             session_command: TextInput::from(session_command),
             multiplexer,
             refresh_interval: TextInput::from(refresh_text),
+            worktree_dir_template: TextInput::from(worktree_dir_template),
+            branch_template: TextInput::from(branch_template),
+            base_branch: TextInput::from(base_branch),
             active_field: 0,
         }
     }
@@ -242,10 +464,113 @@ impl RepoSelectState {
     }
 }
 
+/// What a new issue can be labelled with, assigned to and filed under.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IssueOptions {
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    pub milestones: Vec<String>,
+}
+
+/// Labels, assignees and milestone for a new issue.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IssueMetadata {
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    pub milestone: Option<String>,
+}
+
+/// A field picked from a list: browse with Left/Right, toggle with Space.
+pub struct ChoiceField {
+    pub options: Vec<String>,
+    pub chosen: Vec<String>,
+    /// The option being browsed.
+    pub cursor: usize,
+    /// Whether more than one option can be chosen.
+    pub multiple: bool,
+}
+
+impl ChoiceField {
+    pub fn new(multiple: bool) -> Self {
+        Self {
+            options: Vec::new(),
+            chosen: Vec::new(),
+            cursor: 0,
+            multiple,
+        }
+    }
+
+    /// Replace the options, keeping what was chosen among them.
+    pub fn set_options(&mut self, options: Vec<String>) {
+        self.options = options;
+        for chosen in &self.chosen {
+            if !self.options.contains(chosen) {
+                self.options.push(chosen.clone());
+            }
+        }
+        self.cursor = self.cursor.min(self.options.len().saturating_sub(1));
+    }
+
+    /// Choose `values`, adding any the options lack.
+    pub fn choose(&mut self, values: &[String]) {
+        for value in values {
+            if !self.options.contains(value) {
+                self.options.push(value.clone());
+            }
+            if !self.chosen.contains(value) {
+                if !self.multiple {
+                    self.chosen.clear();
+                }
+                self.chosen.push(value.clone());
+            }
+        }
+    }
+
+    pub fn next(&mut self) {
+        if !self.options.is_empty() {
+            self.cursor = (self.cursor + 1) % self.options.len();
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if !self.options.is_empty() {
+            self.cursor = (self.cursor + self.options.len() - 1) % self.options.len();
+        }
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.options.get(self.cursor).map(String::as_str)
+    }
+
+    /// Choose the option being browsed, or unchoose it if it already is.
+    pub fn toggle(&mut self) {
+        let Some(option) = self.options.get(self.cursor).cloned() else {
+            return;
+        };
+        if let Some(i) = self.chosen.iter().position(|c| *c == option) {
+            self.chosen.remove(i);
+        } else {
+            if !self.multiple {
+                self.chosen.clear();
+            }
+            self.chosen.push(option);
+        }
+    }
+}
+
 pub struct IssueModal {
     pub title: TextInput,
-    pub body: TextInput,
-    pub active_field: usize, // 0 = title, 1 = body, 2 = create_worktree toggle
+    pub body: TextArea,
+    /// 0 = title, 1 = body, 2 = labels, 3 = assignees, 4 = milestone,
+    /// 5 = create_worktree toggle
+    pub active_field: usize,
+    pub labels: ChoiceField,
+    pub assignees: ChoiceField,
+    pub milestone: ChoiceField,
+    pub templates: Vec<IssueTemplate>,
+    /// The highlighted entry while a template is being picked; one past the
+    /// last template is a blank issue.
+    pub choosing_template: Option<usize>,
     pub error: Option<String>,
     pub submitting: bool,
     pub create_worktree: bool,
@@ -253,21 +578,99 @@ pub struct IssueModal {
 
 impl IssueModal {
     pub fn new() -> Self {
+        let mut assignees = ChoiceField::new(true);
+        assignees.choose(&["@me".to_string()]);
         Self {
             title: TextInput::new(),
-            body: TextInput::new(),
+            body: TextArea::new(),
             active_field: 0,
+            labels: ChoiceField::new(true),
+            assignees,
+            milestone: ChoiceField::new(false),
+            templates: Vec::new(),
+            choosing_template: None,
             error: None,
             submitting: false,
             create_worktree: true,
         }
     }
+
+    /// Offer `templates` once they are loaded, unless the form was already
+    /// started.
+    pub fn offer_templates(&mut self, templates: Vec<IssueTemplate>) {
+        let untouched = self.title.value().is_empty() && self.body.value().is_empty();
+        if untouched && !templates.is_empty() {
+            self.choosing_template = Some(0);
+        }
+        self.templates = templates;
+    }
+
+    /// Fill the form in from template `index`; past the end, leave it blank.
+    pub fn apply_template(&mut self, index: usize) {
+        self.choosing_template = None;
+        let Some(template) = self.templates.get(index) else {
+            return;
+        };
+        self.title = TextInput::from(template.title.clone());
+        self.body = TextArea::from(template.body.clone());
+        self.labels.choose(&template.labels);
+        self.assignees.choose(&template.assignees);
+    }
+
+    pub fn set_options(&mut self, options: IssueOptions) {
+        self.labels.set_options(options.labels);
+        let mut assignees = options.assignees;
+        if self.assignees.options.iter().any(|a| a == "@me") {
+            assignees.insert(0, "@me".to_string());
+        }
+        self.assignees.set_options(assignees);
+        self.milestone.set_options(options.milestones);
+    }
+
+    /// The field Tab moves to from `active_field`, skipping lists with
+    /// nothing to choose from.
+    pub fn next_field(&self) -> usize {
+        let mut field = self.active_field;
+        loop {
+            field = (field + 1) % 6;
+            if self.choice(field).is_none_or(|c| !c.options.is_empty()) {
+                return field;
+            }
+        }
+    }
+
+    /// The list field at `field`, if it is one.
+    pub fn choice(&self, field: usize) -> Option<&ChoiceField> {
+        match field {
+            2 => Some(&self.labels),
+            3 => Some(&self.assignees),
+            4 => Some(&self.milestone),
+            _ => None,
+        }
+    }
+
+    pub fn choice_mut(&mut self, field: usize) -> Option<&mut ChoiceField> {
+        match field {
+            2 => Some(&mut self.labels),
+            3 => Some(&mut self.assignees),
+            4 => Some(&mut self.milestone),
+            _ => None,
+        }
+    }
+
+    pub fn metadata(&self) -> IssueMetadata {
+        IssueMetadata {
+            labels: self.labels.chosen.clone(),
+            assignees: self.assignees.chosen.clone(),
+            milestone: self.milestone.chosen.first().cloned(),
+        }
+    }
 }
 
 pub enum IssueSubmitResult {
     Success {
         number: u64,
-        worktree_result: Option<std::result::Result<(), String>>,
+        worktree_result: Option<std::result::Result<(), crate::error::Error>>,
     },
     Error(String),
 }
@@ -275,7 +678,7 @@ pub enum IssueSubmitResult {
 pub struct EditIssueModal {
     pub number: u64,
     pub title: TextInput,
-    pub body: TextInput,
+    pub body: TextArea,
     pub active_field: usize, // 0 = title, 1 = body
     pub error: Option<String>,
     pub submitting: bool,
@@ -286,7 +689,7 @@ impl EditIssueModal {
         Self {
             number,
             title: TextInput::from(title),
-            body: TextInput::from(body),
+            body: TextArea::from(body),
             active_field: 0,
             error: None,
             submitting: false,
@@ -302,11 +705,11 @@ pub enum IssueEditResult {
 pub enum WorktreeCreateResult {
     WorktreeAndSession {
         number: u64,
-        result: std::result::Result<(), String>,
+        result: std::result::Result<(), crate::error::Error>,
     },
     SessionOnly {
         branch: String,
-        result: std::result::Result<(), String>,
+        result: std::result::Result<(), crate::error::Error>,
     },
 }
 
@@ -316,6 +719,8 @@ pub enum SectionData {
     Worktrees(Vec<Card>),
     Sessions(Vec<Card>),
     PullRequests(Vec<Card>),
+    /// Issues, PRs and merged branches from one GitHub query.
+    GithubBoard(Result<crate::github::Board, crate::error::GhError>),
     MainBehindCount(usize),
 }
 
@@ -399,6 +804,24 @@ impl TextInput {
         &self.text
     }
 
+    /// Handle a key meant for the field. Returns false for keys it has no
+    /// use for, so the caller can act on them instead.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace => self.delete_back(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
     /// Text before the cursor position
     pub fn before_cursor(&self) -> &str {
         let idx = self.byte_index();
@@ -425,18 +848,32 @@ pub fn card_matches(card: &Card, query: &str) -> bool {
     fuzzy_match(query, &card.title) || fuzzy_match(query, &card.description)
 }
 
-pub fn label_color(name: &str) -> Color {
-    match name.to_lowercase().as_str() {
-        s if s.contains("bug") => Color::Red,
-        s if s.contains("feature") || s.contains("enhancement") => Color::Green,
-        s if s.contains("documentation") || s.contains("docs") => Color::Blue,
-        s if s.contains("good first issue") || s.contains("help wanted") => Color::Cyan,
-        s if s.contains("duplicate") || s.contains("wontfix") || s.contains("invalid") => {
-            Color::Gray
-        }
-        s if s.contains("priority") || s.contains("critical") || s.contains("urgent") => {
-            Color::LightRed
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branch_card(branch: &str) -> Card {
+        Card {
+            id: branch.to_string(),
+            title: branch.to_string(),
+            head_branch: Some(branch.to_string()),
+            ..Default::default()
         }
-        _ => Color::Yellow,
+    }
+
+    #[test]
+    fn order_stacks_nests_children_under_parents() {
+        let cards = ["c", "a", "b", "d"].into_iter().map(branch_card).collect();
+        let bases: HashMap<String, String> = [("c", "b"), ("b", "a"), ("a", "main")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let ordered = order_stacks(cards, &bases);
+        let layout: Vec<(&str, usize)> = ordered
+            .iter()
+            .map(|c| (c.title.as_str(), c.stack.as_ref().map_or(0, |s| s.depth)))
+            .collect();
+        assert_eq!(layout, [("a", 0), ("b", 1), ("c", 2), ("d", 0)]);
+        assert_eq!(ordered[2].stack.as_ref().unwrap().parent, "b");
     }
 }
@@ -0,0 +1,204 @@
+//! The card filter language, a subset of GitHub's search syntax:
+//! `label:bug -label:wontfix is:draft author:me sort:updated fix login`.
+//! Qualifiers the board knows about are checked against the cards
+//! themselves; all of them, with the free words, also go to GitHub's search
+//! for the Issues and Pull Requests columns, which handles the ones a card
+//! can't answer (`author:`, `milestone:`, `sort:`, ...).
+
+use crate::models::{card_matches, Card, CheckStatus};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Query {
+    qualifiers: Vec<Qualifier>,
+    /// Everything that isn't a qualifier, fuzzy-matched against the title
+    /// and description.
+    words: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Qualifier {
+    negated: bool,
+    key: String,
+    value: String,
+}
+
+impl Query {
+    /// Split `text` into qualifiers and words. Values may be quoted
+    /// (`label:"good first issue"`); a `key:` GitHub has no use for is kept
+    /// as a word.
+    pub fn parse(text: &str) -> Query {
+        let mut query = Query::default();
+        for term in split_terms(text) {
+            let (negated, rest) = match term.strip_prefix('-') {
+                Some(rest) if rest.contains(':') => (true, rest),
+                _ => (false, term.as_str()),
+            };
+            match rest.split_once(':') {
+                Some((key, value)) if is_qualifier(key) && !value.is_empty() => {
+                    query.qualifiers.push(Qualifier {
+                        negated,
+                        key: key.to_lowercase(),
+                        value: value.trim_matches('"').to_string(),
+                    })
+                }
+                _ => query.words.push(term.replace('"', "")),
+            }
+        }
+        query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.qualifiers.is_empty() && self.words.is_empty()
+    }
+
+    /// Whether `card` could be a result. Qualifiers that need data a card
+    /// doesn't carry let every card through.
+    pub fn matches(&self, card: &Card) -> bool {
+        let words = self.words.join(" ");
+        (words.is_empty() || card_matches(card, &words))
+            && self
+                .qualifiers
+                .iter()
+                .all(|q| q.matches(card).is_none_or(|m| m != q.negated))
+    }
+
+    /// The query as GitHub search qualifiers: `me` becomes `@me` and a sort
+    /// without a direction sorts descending, e.g. `sort:updated-desc`.
+    pub fn search(&self) -> String {
+        let mut terms: Vec<String> = self
+            .qualifiers
+            .iter()
+            .map(|q| {
+                let value = match (q.key.as_str(), q.value.as_str()) {
+                    (key, "me") if key != "label" => "@me".to_string(),
+                    ("sort", v) if !v.ends_with("-asc") && !v.ends_with("-desc") => {
+                        format!("{}-desc", v)
+                    }
+                    (_, v) if v.contains(' ') => format!("\"{}\"", v),
+                    (_, v) => v.to_string(),
+                };
+                format!("{}{}:{}", if q.negated { "-" } else { "" }, q.key, value)
+            })
+            .collect();
+        terms.extend(self.words.iter().cloned());
+        terms.join(" ")
+    }
+}
+
+impl Qualifier {
+    /// Whether `card` has what the qualifier asks for, or `None` if the
+    /// card can't tell.
+    fn matches(&self, card: &Card) -> Option<bool> {
+        let value = self.value.to_lowercase();
+        let has = |names: &[String]| names.iter().any(|n| n.to_lowercase() == value);
+        match (self.key.as_str(), value.as_str()) {
+            ("label", _) => Some(has(&card.labels)),
+            ("assignee", "me" | "@me") => card.is_assigned,
+            ("assignee", _) => Some(has(&card.assignees)),
+            ("no", "label") => Some(card.labels.is_empty()),
+            ("no", "assignee") => Some(card.assignees.is_empty()),
+            ("is", "draft") => card.is_draft,
+            ("is", "ready") => card.is_draft.map(|d| !d),
+            ("is", "merged") => card.is_merged,
+            ("is", "unmerged") => card.is_merged.map(|m| !m),
+            ("status", "success") => card.checks.map(|c| c == CheckStatus::Success),
+            ("status", "failure") => card.checks.map(|c| c == CheckStatus::Failure),
+            ("status", "pending") => card.checks.map(|c| c == CheckStatus::Pending),
+            _ => None,
+        }
+    }
+}
+
+/// Whether GitHub's issue search knows `key`.
+fn is_qualifier(key: &str) -> bool {
+    matches!(
+        key.to_lowercase().as_str(),
+        "label"
+            | "assignee"
+            | "author"
+            | "mentions"
+            | "involves"
+            | "commenter"
+            | "milestone"
+            | "project"
+            | "is"
+            | "no"
+            | "status"
+            | "review"
+            | "reviewed-by"
+            | "review-requested"
+            | "head"
+            | "base"
+            | "in"
+            | "created"
+            | "updated"
+            | "closed"
+            | "merged"
+            | "comments"
+            | "reactions"
+            | "sort"
+    )
+}
+
+/// Whitespace-separated terms, keeping quoted stretches together.
+fn split_terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                term.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(title: &str, labels: &[&str], is_draft: Option<bool>) -> Card {
+        Card {
+            id: "pr-1".to_string(),
+            title: title.to_string(),
+            pr_number: Some(1),
+            is_draft,
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn filters_cards_and_builds_github_searches() {
+        let query = Query::parse(
+            r#"label:"good first issue" -label:wontfix is:draft author:me sort:updated parser"#,
+        );
+        assert_eq!(
+            query.search(),
+            r#"label:"good first issue" -label:wontfix is:draft author:@me sort:updated-desc parser"#
+        );
+
+        let first = ["Good First Issue"];
+        assert!(query.matches(&card("Fix the parser", &first, Some(true))));
+        assert!(!query.matches(&card("Fix the parser", &[first[0], "wontfix"], Some(true))));
+        assert!(!query.matches(&card("Fix the parser", &first, Some(false))));
+        // Whether an issue is a draft is unknown, so only GitHub can say
+        assert!(query.matches(&card("Fix the parser", &first, None)));
+        assert!(!query.matches(&card("Unrelated", &first, Some(true))));
+
+        assert_eq!(Query::parse("fix: crash").search(), "fix: crash");
+        assert!(Query::parse("  ").is_empty());
+    }
+}
@@ -0,0 +1,375 @@
+//! In-process view of the repository, read through libgit2 instead of
+//! forking `git` for every ref lookup on each refresh.
+//!
+//! A [`Snapshot`] of branches, worktrees and `branch.*` config is kept
+//! until the files git updates when refs, worktrees or config change
+//! (`HEAD`, `packed-refs`, `config`, `refs/`, `worktrees/`) get a new mtime.
+//! Ahead/behind counts and merge-conflict checks are cached by the commit
+//! ids they were computed for, so they are only redone when a branch or its
+//! base moves. Working-tree status can change without touching any ref and
+//! is read fresh every time. The mtimes are re-read at most once per
+//! [`RESTAMP_INTERVAL`] unless [`invalidate`] is called after octopai runs
+//! git itself, so a refresh asking for many branches walks `refs/` once.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use git2::{BranchType, Oid, Repository, StatusOptions};
+
+use crate::models::WorktreeStatus;
+
+/// A checked-out worktree.
+#[derive(Clone)]
+pub struct WorktreeEntry {
+    pub path: String,
+    /// `None` for a detached HEAD.
+    pub branch: Option<String>,
+}
+
+/// Refs, worktrees and branch config as of the last change on disk.
+#[derive(Default)]
+pub struct Snapshot {
+    /// The worktree the repository was cloned into.
+    pub main: Option<WorktreeEntry>,
+    /// Linked worktrees, i.e. everything `git worktree add` created.
+    pub worktrees: Vec<WorktreeEntry>,
+    pub local_branches: HashMap<String, Oid>,
+    /// Branches on origin, keyed by name without the `origin/` prefix.
+    pub origin_branches: HashMap<String, Oid>,
+    /// The branch `origin/HEAD` points at.
+    pub origin_head: Option<String>,
+    /// `branch.<name>.<key>` values, keyed by `key` then branch.
+    pub branch_config: HashMap<String, HashMap<String, String>>,
+}
+
+impl Snapshot {
+    /// Resolve a branch the way `git rev-parse` would for the names octopai
+    /// passes around: a local branch, or `origin/<name>`.
+    pub fn resolve(&self, name: &str) -> Option<Oid> {
+        self.local_branches.get(name).copied().or_else(|| {
+            name.strip_prefix("origin/")
+                .and_then(|n| self.origin_branches.get(n).copied())
+        })
+    }
+
+    /// Commits some local or origin branch currently points at.
+    fn tips(&self) -> HashSet<Oid> {
+        self.local_branches
+            .values()
+            .chain(self.origin_branches.values())
+            .copied()
+            .collect()
+    }
+}
+
+/// Commits `branch` has that `base` lacks and vice versa, and whether
+/// merging `base` into `branch` would conflict (`None` if unknown).
+#[derive(Clone, Copy)]
+pub struct Divergence {
+    pub ahead: usize,
+    pub behind: usize,
+    pub conflicts: Option<bool>,
+}
+
+/// How long a ref stamp is trusted before the files are checked again.
+const RESTAMP_INTERVAL: Duration = Duration::from_millis(500);
+
+struct Cache {
+    repo: Repository,
+    stamp: u64,
+    stamped_at: Option<Instant>,
+    snapshot: Snapshot,
+    divergence: HashMap<(Oid, Oid), Divergence>,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+/// Check the refs again on the next lookup instead of trusting the last
+/// stamp; call after running a git command that may have changed them.
+pub fn invalidate() {
+    let mut guard = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(cache) = guard.as_mut() {
+        cache.stamped_at = None;
+    }
+}
+
+/// Run `f` against the up-to-date snapshot of the repository in the
+/// current directory. Returns `None` outside a git repository.
+pub fn with_snapshot<T>(f: impl FnOnce(&Snapshot) -> T) -> Option<T> {
+    with_cache(|cache| f(&cache.snapshot))
+}
+
+/// Ahead/behind counts and conflict state of `branch` against `base`; both
+/// are names accepted by [`Snapshot::resolve`].
+pub fn divergence(base: &str, branch: &str) -> Option<Divergence> {
+    with_cache(|cache| {
+        let base = cache.snapshot.resolve(base)?;
+        let branch = cache.snapshot.resolve(branch)?;
+        if let Some(d) = cache.divergence.get(&(base, branch)) {
+            return Some(*d);
+        }
+        let (ahead, behind) = cache.repo.graph_ahead_behind(branch, base).ok()?;
+        // Nothing to merge if the base has not moved since the branch was cut
+        let conflicts = if behind == 0 {
+            Some(false)
+        } else {
+            merge_conflicts(&cache.repo, base, branch)
+        };
+        let d = Divergence {
+            ahead,
+            behind,
+            conflicts,
+        };
+        cache.divergence.insert((base, branch), d);
+        Some(d)
+    })
+    .flatten()
+}
+
+/// Summaries of the commits on `branch` that `base` lacks, oldest first.
+pub fn commits_between(base: &str, branch: &str) -> Vec<String> {
+    with_cache(|cache| {
+        let (Some(base), Some(branch)) =
+            (cache.snapshot.resolve(base), cache.snapshot.resolve(branch))
+        else {
+            return Vec::new();
+        };
+        let Ok(mut walk) = cache.repo.revwalk() else {
+            return Vec::new();
+        };
+        let _ = walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE);
+        if walk.push(branch).is_err() || walk.hide(base).is_err() {
+            return Vec::new();
+        }
+        walk.filter_map(|oid| {
+            let commit = cache.repo.find_commit(oid.ok()?).ok()?;
+            Some(commit.summary().unwrap_or_default().to_string())
+        })
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Set `branch.<name>.<key>` in the repository's own config.
+pub fn set_branch_config(branch: &str, key: &str, value: &str) -> Result<(), String> {
+    with_cache(|cache| {
+        let mut config = cache.repo.config().map_err(|e| e.message().to_string())?;
+        config
+            .set_str(&format!("branch.{}.{}", branch, key), value)
+            .map_err(|e| e.message().to_string())?;
+        cache.stamped_at = None;
+        Ok(())
+    })
+    .unwrap_or_else(|| Err("Not a git repository".to_string()))
+}
+
+/// Fill in uncommitted (staged or not) and untracked file counts and the
+/// last commit for the worktree at `path`.
+pub fn worktree_activity(path: &str, status: &mut WorktreeStatus) {
+    let Ok(repo) = Repository::open(path) else {
+        return;
+    };
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).include_ignored(false);
+    if let Ok(statuses) = repo.statuses(Some(&mut opts)) {
+        for entry in statuses.iter() {
+            if entry.status() == git2::Status::WT_NEW {
+                status.untracked += 1;
+            } else {
+                status.changed += 1;
+            }
+        }
+    }
+    let head = repo.head().and_then(|head| head.peel_to_commit());
+    if let Ok(commit) = head {
+        status.last_commit_time = Some(commit.time().seconds().max(0) as u64);
+        status.last_commit_subject = Some(commit.summary().unwrap_or_default().to_string());
+    }
+}
+
+fn with_cache<T>(f: impl FnOnce(&mut Cache) -> T) -> Option<T> {
+    let mut guard = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let repo = open_common_repo()?;
+        *guard = Some(Cache {
+            repo,
+            stamp: 0,
+            stamped_at: None,
+            snapshot: Snapshot::default(),
+            divergence: HashMap::new(),
+        });
+    }
+    let cache = guard.as_mut()?;
+    if cache
+        .stamped_at
+        .is_none_or(|at| at.elapsed() >= RESTAMP_INTERVAL)
+    {
+        let stamp = ref_stamp(cache.repo.path());
+        if stamp != cache.stamp {
+            cache.snapshot = load_snapshot(&cache.repo);
+            cache.stamp = stamp;
+            // Drop counts for commits that are no longer a branch tip
+            let tips = cache.snapshot.tips();
+            cache
+                .divergence
+                .retain(|(base, branch), _| tips.contains(base) && tips.contains(branch));
+        }
+        cache.stamped_at = Some(Instant::now());
+    }
+    Some(f(cache))
+}
+
+/// The repository shared by all worktrees, even when started from inside a
+/// linked worktree.
+fn open_common_repo() -> Option<Repository> {
+    let repo = Repository::discover(".").ok()?;
+    if !repo.is_worktree() {
+        return Some(repo);
+    }
+    let common = fs::read_to_string(repo.path().join("commondir")).ok()?;
+    Repository::open(repo.path().join(common.trim())).ok()
+}
+
+/// Hash of the mtimes of everything git rewrites when refs, worktrees or
+/// config change.
+fn ref_stamp(git_dir: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for name in ["HEAD", "packed-refs", "config"] {
+        stamp_path(&git_dir.join(name), &mut hasher);
+    }
+    stamp_tree(&git_dir.join("refs"), &mut hasher);
+    let worktrees = git_dir.join("worktrees");
+    stamp_path(&worktrees, &mut hasher);
+    if let Ok(entries) = fs::read_dir(&worktrees) {
+        for entry in entries.flatten() {
+            stamp_path(&entry.path().join("HEAD"), &mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn stamp_tree(dir: &Path, hasher: &mut DefaultHasher) {
+    stamp_path(dir, hasher);
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                stamp_tree(&path, hasher);
+            } else {
+                stamp_path(&path, hasher);
+            }
+        }
+    }
+}
+
+fn stamp_path(path: &Path, hasher: &mut DefaultHasher) {
+    path.hash(hasher);
+    if let Ok(meta) = fs::metadata(path) {
+        meta.modified().ok().hash(hasher);
+        meta.len().hash(hasher);
+    }
+}
+
+fn load_snapshot(repo: &Repository) -> Snapshot {
+    let mut snapshot = Snapshot {
+        main: repo.workdir().map(|dir| WorktreeEntry {
+            path: dir.to_string_lossy().trim_end_matches('/').to_string(),
+            branch: head_branch(&repo.path().join("HEAD")),
+        }),
+        ..Snapshot::default()
+    };
+
+    if let Ok(names) = repo.worktrees() {
+        for name in names.iter().flatten() {
+            let Ok(wt) = repo.find_worktree(name) else {
+                continue;
+            };
+            snapshot.worktrees.push(WorktreeEntry {
+                path: wt
+                    .path()
+                    .to_string_lossy()
+                    .trim_end_matches('/')
+                    .to_string(),
+                branch: head_branch(&repo.path().join("worktrees").join(name).join("HEAD")),
+            });
+        }
+    }
+
+    if let Ok(branches) = repo.branches(None) {
+        for (branch, kind) in branches.flatten() {
+            let (Ok(Some(name)), Some(oid)) = (branch.name(), branch.get().target()) else {
+                continue;
+            };
+            match kind {
+                BranchType::Local => {
+                    snapshot.local_branches.insert(name.to_string(), oid);
+                }
+                BranchType::Remote => {
+                    if let Some(name) = name.strip_prefix("origin/") {
+                        snapshot.origin_branches.insert(name.to_string(), oid);
+                    }
+                }
+            }
+        }
+    }
+
+    snapshot.origin_head = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .and_then(|r| {
+            r.symbolic_target()?
+                .strip_prefix("refs/remotes/origin/")
+                .map(String::from)
+        });
+
+    if let Ok(mut config) = repo.config().and_then(|mut c| c.snapshot()) {
+        snapshot.branch_config = load_branch_config(&mut config);
+    }
+    snapshot
+}
+
+/// `branch.<name>.<key>` entries; branch names may themselves contain dots.
+fn load_branch_config(config: &mut git2::Config) -> HashMap<String, HashMap<String, String>> {
+    let mut values: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let Ok(mut entries) = config.entries(Some(r"^branch\..*\.octopai-")) else {
+        return values;
+    };
+    while let Some(Ok(entry)) = entries.next() {
+        let (Some(name), Some(value)) = (entry.name(), entry.value()) else {
+            continue;
+        };
+        let Some((branch, key)) = name
+            .strip_prefix("branch.")
+            .and_then(|rest| rest.rsplit_once('.'))
+        else {
+            continue;
+        };
+        values
+            .entry(key.to_string())
+            .or_default()
+            .insert(branch.to_string(), value.to_string());
+    }
+    values
+}
+
+/// The branch a `HEAD` file points at, or `None` when detached.
+fn head_branch(head_file: &Path) -> Option<String> {
+    let head = fs::read_to_string(head_file).ok()?;
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(String::from)
+}
+
+/// Whether merging `base` into `branch` would conflict, checked in memory
+/// without touching any worktree.
+fn merge_conflicts(repo: &Repository, base: Oid, branch: Oid) -> Option<bool> {
+    let ours = repo.find_commit(branch).ok()?;
+    let theirs = repo.find_commit(base).ok()?;
+    let index = repo.merge_commits(&ours, &theirs, None).ok()?;
+    Some(index.has_conflicts())
+}
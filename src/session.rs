@@ -1,12 +1,16 @@
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 use std::process::Command;
 
-use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Error, GitError, SessionError};
 use crate::git::{get_repo_name, trust_directory};
 use crate::hooks::write_worktree_hook_config;
+use crate::log;
 use crate::models::{Card, SessionStates};
+use crate::theme;
 
 /// Session name for the main worktree exploration session.
 pub const MAIN_SESSION_NAME: &str = "main-explore";
@@ -125,16 +129,19 @@ impl Multiplexer {
     }
 
     /// Create a new detached session with a shell in the given directory.
-    pub fn create_session(self, name: &str, working_dir: &str) -> Result<(), String> {
+    pub fn create_session(self, name: &str, working_dir: &str) -> Result<(), SessionError> {
         match self {
             Multiplexer::Tmux => {
                 let output = Command::new("tmux")
                     .args(["new-session", "-d", "-s", name, "-c", working_dir])
                     .output()
-                    .map_err(|e| format!("Failed to create tmux session: {}", e))?;
+                    .map_err(|e| SessionError::spawn("tmux", e))?;
                 if !output.status.success() {
                     let stderr = String::from_utf8_lossy(&output.stderr);
-                    return Err(format!("tmux error: {}", stderr.trim()));
+                    return Err(SessionError::Failed(format!(
+                        "tmux error: {}",
+                        stderr.trim()
+                    )));
                 }
                 Ok(())
             }
@@ -145,10 +152,13 @@ impl Multiplexer {
                 let output = Command::new("screen")
                     .args(["-dmS", name, "sh", "-c", &shell_cmd])
                     .output()
-                    .map_err(|e| format!("Failed to create screen session: {}", e))?;
+                    .map_err(|e| SessionError::spawn("screen", e))?;
                 if !output.status.success() {
                     let stderr = String::from_utf8_lossy(&output.stderr);
-                    return Err(format!("screen error: {}", stderr.trim()));
+                    return Err(SessionError::Failed(format!(
+                        "screen error: {}",
+                        stderr.trim()
+                    )));
                 }
                 Ok(())
             }
@@ -156,29 +166,32 @@ impl Multiplexer {
     }
 
     /// Send a command string to the session's active pane.
-    pub fn send_keys(self, session: &str, cmd: &str) {
+    pub fn send_keys(self, session: &str, cmd: &str) -> Result<(), SessionError> {
         match self {
             Multiplexer::Tmux => {
                 let pane_target = format!("{}:.0", session);
-                let _ = Command::new("tmux")
-                    .args(["send-keys", "-t", &pane_target, "-l", cmd])
-                    .output();
-                let _ = Command::new("tmux")
-                    .args(["send-keys", "-t", &pane_target, "Enter"])
-                    .output();
+                self.run(
+                    Command::new("tmux").args(["send-keys", "-t", &pane_target, "-l", cmd]),
+                    "send-keys",
+                )?;
+                self.run(
+                    Command::new("tmux").args(["send-keys", "-t", &pane_target, "Enter"]),
+                    "send-keys",
+                )
             }
             Multiplexer::Screen => {
                 // screen -X stuff sends literal characters; append \n for Enter.
                 let stuffed = format!("{}\n", cmd);
-                let _ = Command::new("screen")
-                    .args(["-S", session, "-X", "stuff", &stuffed])
-                    .output();
+                self.run(
+                    Command::new("screen").args(["-S", session, "-X", "stuff", &stuffed]),
+                    "stuff",
+                )
             }
         }
     }
 
     /// Attach to an existing session (blocks until detach).
-    pub fn attach(self, session: &str) -> Result<(), String> {
+    pub fn attach(self, session: &str) -> Result<(), SessionError> {
         match self {
             Multiplexer::Tmux => {
                 Command::new("tmux")
@@ -187,7 +200,7 @@ impl Multiplexer {
                     .stdout(std::process::Stdio::inherit())
                     .stderr(std::process::Stdio::inherit())
                     .status()
-                    .map_err(|e| format!("Failed to attach: {}", e))?;
+                    .map_err(|e| SessionError::spawn("tmux", e))?;
                 Ok(())
             }
             Multiplexer::Screen => {
@@ -197,37 +210,77 @@ impl Multiplexer {
                     .stdout(std::process::Stdio::inherit())
                     .stderr(std::process::Stdio::inherit())
                     .status()
-                    .map_err(|e| format!("Failed to attach: {}", e))?;
+                    .map_err(|e| SessionError::spawn("screen", e))?;
                 Ok(())
             }
         }
     }
 
+    /// Rename a session.
+    pub fn rename_session(self, session: &str, new_name: &str) -> Result<(), SessionError> {
+        match self {
+            Multiplexer::Tmux => self.run(
+                Command::new("tmux").args(["rename-session", "-t", session, new_name]),
+                "rename",
+            ),
+            Multiplexer::Screen => self.run(
+                Command::new("screen").args(["-S", session, "-X", "sessionname", new_name]),
+                "rename",
+            ),
+        }
+    }
+
     /// Kill a session by name.
-    pub fn kill_session(self, session: &str) {
+    pub fn kill_session(self, session: &str) -> Result<(), SessionError> {
         match self {
-            Multiplexer::Tmux => {
-                let _ = Command::new("tmux")
-                    .args(["kill-session", "-t", session])
-                    .output();
-            }
-            Multiplexer::Screen => {
-                let _ = Command::new("screen")
-                    .args(["-S", session, "-X", "quit"])
-                    .output();
-            }
+            Multiplexer::Tmux => self.run(
+                Command::new("tmux").args(["kill-session", "-t", session]),
+                "kill",
+            ),
+            Multiplexer::Screen => self.run(
+                Command::new("screen").args(["-S", session, "-X", "quit"]),
+                "kill",
+            ),
+        }
+    }
+
+    /// Run a multiplexer `command`, failing with its stderr as "<mux>
+    /// `what` failed: ...".
+    fn run(self, command: &mut Command, what: &str) -> Result<(), SessionError> {
+        let output = command
+            .output()
+            .map_err(|e| SessionError::spawn(self.label(), e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(SessionError::Failed(format!(
+                "{} {} failed: {}",
+                self.label(),
+                what,
+                stderr.trim()
+            )))
         }
     }
 }
 
 pub fn fetch_sessions(socket_states: &SessionStates, mux: Multiplexer) -> Vec<Card> {
-    let session_names = mux.list_sessions();
     let states = socket_states.lock().unwrap_or_else(|e| e.into_inner());
+    session_cards(&states, mux)
+}
+
+/// Cards for the issue sessions that are running, in the state the agent
+/// hooks last reported (`states`, by session name) or, failing that, the
+/// one their pane shows.
+pub fn session_cards(states: &HashMap<String, String>, mux: Multiplexer) -> Vec<Card> {
+    let session_names = mux.list_sessions();
+    let links = crate::git::fetch_issue_links();
 
     session_names
         .into_iter()
-        .filter(|name| name.starts_with("issue-") || name.starts_with("local-issue-"))
-        .map(|name| {
+        // Issue sessions are named after their branch
+        .filter_map(|name| crate::git::issue_key_for_branch(&links, &name).map(|key| (name, key)))
+        .map(|(name, issue_key)| {
             // Use socket-derived state if available, otherwise fall back
             // to pane content detection.
             let claude_state = if let Some(status) = states.get(&name) {
@@ -261,30 +314,28 @@ pub fn fetch_sessions(socket_states: &SessionStates, mux: Multiplexer) -> Vec<Ca
             };
 
             let (tag, tag_color, description) = match claude_state {
-                "processing" => ("processing", Color::Cyan, "Processing..."),
-                "working" => ("working", Color::Green, "Using tools..."),
-                "permission" => ("permission", Color::Yellow, "Awaiting permission"),
-                "idle" => ("idle", Color::DarkGray, "Waiting for prompt"),
-                _ => ("unknown", Color::DarkGray, "Unknown state"),
+                "processing" => ("processing", theme::current().accent, "Processing..."),
+                "working" => ("working", theme::current().success, "Using tools..."),
+                "permission" => (
+                    "permission",
+                    theme::current().warning,
+                    "Awaiting permission",
+                ),
+                "idle" => ("idle", theme::current().dim, "Waiting for prompt"),
+                _ => ("unknown", theme::current().dim, "Unknown state"),
             };
 
             // Link to the related issue card
-            let related = vec![name.clone()];
+            let related = vec![issue_key];
 
             Card {
                 id: format!("session-{}", name),
                 title: name,
                 description: description.to_string(),
-                full_description: None,
                 tag: tag.to_string(),
                 tag_color,
                 related,
-                url: None,
-                pr_number: None,
-                is_draft: None,
-                is_merged: None,
-                head_branch: None,
-                is_assigned: None,
+                ..Default::default()
             }
         })
         .collect()
@@ -308,10 +359,91 @@ pub const TEMPLATE_FIELDS: &[(&str, &str)] = &[
     ("{repo}", "Full repo name (owner/repo)"),
     ("{title}", "Issue title"),
     ("{body}", "Cleaned issue body text"),
-    ("{branch}", "Branch name (e.g. issue-42)"),
+    ("{branch}", "Branch name (e.g. issue-42, or as set by the branch template)"),
     ("{worktree_path}", "Path to the git worktree"),
 ];
 
+/// Default branch name template for new worktrees.
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "issue-{issue_number}";
+
+/// Default worktree directory template, relative to the main worktree.
+pub const DEFAULT_WORKTREE_DIR_TEMPLATE: &str = "../{repo_name}-{branch}";
+
+/// Available template fields for the branch and worktree directory templates.
+/// `{branch}` is only available in the directory template.
+pub const WORKTREE_TEMPLATE_FIELDS: &[(&str, &str)] = &[
+    ("{repo_name}", "Repository name without the owner"),
+    ("{issue_number}", "Issue number"),
+    (
+        "{slug}",
+        "Issue title in lowercase-with-dashes, at most 40 characters",
+    ),
+    ("{branch}", "Rendered branch name, with '/' replaced by '-'"),
+];
+
+/// Lowercase `title`, keep ASCII alphanumerics and join the words with dashes.
+fn slugify(title: &str) -> String {
+    let slug = title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.len() <= 40 {
+        return slug;
+    }
+    slug[..40].trim_end_matches('-').to_string()
+}
+
+/// Render the branch name and worktree directory for a new issue worktree
+/// from the repo's configured templates. Local-mode branches get a `local-`
+/// prefix so they never collide with branches synced from GitHub.
+pub fn worktree_names(repo: &str, number: u64, title: &str, local_mode: bool) -> (String, String) {
+    let branch_template = crate::config::get_branch_template(repo)
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_BRANCH_TEMPLATE.to_string());
+    let dir_template = crate::config::get_worktree_dir_template(repo)
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_WORKTREE_DIR_TEMPLATE.to_string());
+    render_worktree_names(
+        &branch_template,
+        &dir_template,
+        get_repo_name(repo),
+        number,
+        title,
+        local_mode,
+    )
+}
+
+fn render_worktree_names(
+    branch_template: &str,
+    dir_template: &str,
+    repo_name: &str,
+    number: u64,
+    title: &str,
+    local_mode: bool,
+) -> (String, String) {
+    let slug = slugify(title);
+    let render = |template: &str| {
+        template
+            .replace("{repo_name}", repo_name)
+            .replace("{issue_number}", &number.to_string())
+            .replace("{slug}", &slug)
+    };
+
+    let mut branch = render(branch_template);
+    if local_mode {
+        branch = format!("local-{}", branch);
+    }
+    let mut path = render(dir_template).replace("{branch}", &branch.replace('/', "-"));
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            path = home.join(rest).to_string_lossy().to_string();
+        }
+    }
+    (branch, path)
+}
+
 /// Default editor command template. Users can override this entirely.
 pub const DEFAULT_EDITOR_COMMAND: &str = "{alacritty} nvim";
 
@@ -472,28 +604,21 @@ pub fn create_session_for_worktree(
     session_command: Option<&str>,
     mux: Multiplexer,
     local_mode: bool,
-) -> std::result::Result<(), String> {
+) -> Result<(), Error> {
     // Pre-trust the worktree directory for Claude
-    let _ = trust_directory(worktree_path);
+    log::warn_on_err("session", trust_directory(worktree_path));
 
     // Write Claude hook config for event socket integration
     if let Some(script) = hook_script {
-        let _ = write_worktree_hook_config(worktree_path, script);
+        log::warn_on_err(
+            "hooks",
+            write_worktree_hook_config(worktree_path, script, branch),
+        );
     }
 
     // Auto-assign the issue to the current user (skip in local mode)
     if !local_mode {
-        let _ = Command::new("gh")
-            .args([
-                "issue",
-                "edit",
-                "--repo",
-                repo,
-                &number.to_string(),
-                "--add-assignee",
-                "@me",
-            ])
-            .output();
+        log::warn_on_err("github", crate::github::assign_issue(repo, number));
     }
 
     // Create session with a shell in the worktree directory
@@ -521,6 +646,13 @@ pub fn create_session_for_worktree(
         } else {
             "open a draft pull request"
         };
+        let pr_instruction = match crate::git::base_for_branch(repo, branch) {
+            Some(base) => format!(
+                "{} against the '{}' branch (--base {})",
+                pr_instruction, base, base
+            ),
+            None => pr_instruction.to_string(),
+        };
         format!(
             "You are working on GitHub issue #{} for the repo {}. Title: {}. {} Please investigate the codebase and implement a solution for this issue. When you are confident the problem is solved, commit your changes and {} with a clear title and description that explains what was changed and why. Reference the issue with 'Closes #{}' in the PR body. Use '--assignee @me' when creating the pull request to auto-assign it.",
             number, repo, title, body_clean, pr_instruction, number
@@ -533,8 +665,9 @@ pub fn create_session_for_worktree(
     };
 
     // Write prompt to a temp file for safe shell expansion
-    let prompt_file = format!("/tmp/octopai-prompt-{}.txt", branch);
-    fs::write(&prompt_file, &prompt).map_err(|e| format!("Failed to write prompt file: {}", e))?;
+    let prompt_file = format!("/tmp/octopai-prompt-{}.txt", branch.replace('/', "-"));
+    fs::write(&prompt_file, &prompt)
+        .map_err(|e| Error::Io(format!("Failed to write prompt file: {}", e)))?;
 
     // Send session command to the single pane
     let global_default = crate::config::get_default_session_command();
@@ -555,138 +688,93 @@ pub fn create_session_for_worktree(
     // Wait for shell to initialize, then send the command
     std::thread::sleep(std::time::Duration::from_millis(500));
 
-    mux.send_keys(branch, &shell_cmd);
+    mux.send_keys(branch, &shell_cmd)?;
 
     Ok(())
 }
 
+/// Create a worktree and branch for an issue, named from the repo's templates
+/// (see `worktree_names`), link the branch to the issue, and start a session.
+/// The branch starts from `base`, or from HEAD when no base is known.
 #[allow(clippy::too_many_arguments)]
 pub fn create_worktree_and_session(
     repo: &str,
     number: u64,
     title: &str,
     body: &str,
+    base: Option<&str>,
     hook_script: Option<&str>,
     pr_ready: bool,
     auto_open_pr: bool,
     session_command: Option<&str>,
     mux: Multiplexer,
     local_mode: bool,
-) -> std::result::Result<(), String> {
-    let repo_name = get_repo_name(repo);
-    let branch = if local_mode {
-        format!("local-issue-{}", number)
-    } else {
-        format!("issue-{}", number)
-    };
-    let worktree_path = if local_mode {
-        format!("../{}-local-issue-{}", repo_name, number)
-    } else {
-        format!("../{}-issue-{}", repo_name, number)
-    };
+) -> Result<(), Error> {
+    // Issue cards carry a "#N " prefix that shouldn't end up in the slug
+    let plain_title = title
+        .strip_prefix(&format!("#{} ", number))
+        .unwrap_or(title);
+    let (branch, worktree_path) = worktree_names(repo, number, plain_title, local_mode);
+
+    // Templates may place worktrees in directories that don't exist yet
+    if let Some(parent) = Path::new(&worktree_path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::Io(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+    }
 
     // Create worktree with new branch
+    let mut args = vec![
+        "worktree".to_string(),
+        "add".to_string(),
+        worktree_path.clone(),
+        "-b".to_string(),
+        branch.clone(),
+    ];
+    if let Some(base) = base {
+        args.push(crate::git::base_ref(base));
+    }
     let output = Command::new("git")
-        .args(["worktree", "add", &worktree_path, "-b", &branch])
+        .args(&args)
         .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
+        .map_err(|e| GitError::spawn("git worktree add", e))?;
+    crate::repo_state::invalidate();
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("git worktree add error: {}", stderr.trim()));
+        return Err(GitError::from_output("git worktree add", &output).into());
     }
-
-    // Pre-trust the worktree directory for Claude
-    let _ = trust_directory(&worktree_path);
-
-    // Write Claude hook config for event socket integration
-    if let Some(script) = hook_script {
-        let _ = write_worktree_hook_config(&worktree_path, script);
+    if let Some(base) = base {
+        crate::git::set_branch_base(&branch, base)?;
     }
 
-    // Auto-assign the issue to the current user (skip in local mode)
-    if !local_mode {
-        let _ = Command::new("gh")
-            .args([
-                "issue",
-                "edit",
-                "--repo",
-                repo,
-                &number.to_string(),
-                "--add-assignee",
-                "@me",
-            ])
-            .output();
-    }
-
-    // Create session with a shell in the worktree directory
-    mux.create_session(&branch, &worktree_path)?;
-
-    // Build the Claude prompt and write to a temp file
-    let body_clean = if body.is_empty() {
-        "No description provided.".to_string()
-    } else {
-        body.lines()
-            .map(|l| l.trim())
-            .filter(|l| !l.is_empty())
-            .collect::<Vec<_>>()
-            .join(" ")
-    };
-
-    let prompt = if local_mode {
-        format!(
-            "You are working on local issue #{} for the repo {}. Title: {}. {} Please investigate the codebase and implement a solution for this issue. When you are confident the problem is solved, commit your changes.",
-            number, repo, title, body_clean
-        )
-    } else if auto_open_pr {
-        let pr_instruction = if pr_ready {
-            "open a pull request"
-        } else {
-            "open a draft pull request"
-        };
-        format!(
-            "You are working on GitHub issue #{} for the repo {}. Title: {}. {} Please investigate the codebase and implement a solution for this issue. When you are confident the problem is solved, commit your changes and {} with a clear title and description that explains what was changed and why. Reference the issue with 'Closes #{}' in the PR body. Use '--assignee @me' when creating the pull request to auto-assign it.",
-            number, repo, title, body_clean, pr_instruction, number
-        )
+    let issue_key = if local_mode {
+        format!("local-issue-{}", number)
     } else {
-        format!(
-            "You are working on GitHub issue #{} for the repo {}. Title: {}. {} Please investigate the codebase and implement a solution for this issue. When you are confident the problem is solved, commit your changes and push the branch.",
-            number, repo, title, body_clean
-        )
+        format!("issue-{}", number)
     };
+    crate::git::link_branch_to_issue(&branch, &issue_key)?;
 
-    // Write prompt to a temp file for safe shell expansion
-    let prompt_file = format!("/tmp/octopai-prompt-{}.txt", branch);
-    fs::write(&prompt_file, &prompt).map_err(|e| format!("Failed to write prompt file: {}", e))?;
-
-    // Send session command to the single pane
-    let global_default = crate::config::get_default_session_command();
-    let template = session_command
-        .or(global_default.as_deref())
-        .unwrap_or(DEFAULT_CLAUDE_COMMAND);
-    let shell_cmd = expand_template(
-        template,
-        &prompt_file,
-        number,
+    create_session_for_worktree(
         repo,
+        number,
         title,
-        &body_clean,
+        body,
         &branch,
         &worktree_path,
-    );
-
-    // Wait for shell to initialize, then send the command
-    std::thread::sleep(std::time::Duration::from_millis(500));
-
-    mux.send_keys(&branch, &shell_cmd);
-
-    Ok(())
+        hook_script,
+        pr_ready,
+        auto_open_pr,
+        session_command,
+        mux,
+        local_mode,
+    )
 }
 
 /// Create a Claude session on the main worktree for exploration (no prompt).
 ///
 /// Returns `true` if a new session was created, `false` if one already existed.
-pub fn ensure_main_session(mux: Multiplexer) -> Result<bool, String> {
+pub fn ensure_main_session(mux: Multiplexer) -> Result<bool, Error> {
     let existing = mux.list_sessions();
     if existing.iter().any(|s| s == MAIN_SESSION_NAME) {
         return Ok(false);
@@ -694,7 +782,7 @@ pub fn ensure_main_session(mux: Multiplexer) -> Result<bool, String> {
 
     // Use the current working directory (the main worktree)
     let cwd = std::env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?
+        .map_err(|e| Error::Io(format!("Failed to get current directory: {}", e)))?
         .to_string_lossy()
         .to_string();
 
@@ -702,7 +790,37 @@ pub fn ensure_main_session(mux: Multiplexer) -> Result<bool, String> {
 
     // Wait for shell to initialize, then launch claude with no prompt
     std::thread::sleep(std::time::Duration::from_millis(500));
-    mux.send_keys(MAIN_SESSION_NAME, "claude");
+    mux.send_keys(MAIN_SESSION_NAME, "claude")?;
 
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worktree_names_render_templates() {
+        let (branch, path) = render_worktree_names(
+            "feat/{issue_number}-{slug}",
+            "/wt/{repo_name}/{branch}",
+            "octopai",
+            42,
+            "Fix: the *board* crashes!",
+            false,
+        );
+        assert_eq!(branch, "feat/42-fix-the-board-crashes");
+        assert_eq!(path, "/wt/octopai/feat-42-fix-the-board-crashes");
+
+        let (branch, path) = render_worktree_names(
+            DEFAULT_BRANCH_TEMPLATE,
+            DEFAULT_WORKTREE_DIR_TEMPLATE,
+            "octopai",
+            7,
+            "",
+            true,
+        );
+        assert_eq!(branch, "local-issue-7");
+        assert_eq!(path, "../octopai-local-issue-7");
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, GitError};
+use crate::git::{
+    base_for_branch, branch_exists, extract_issue_number, fetch_issue_links, fetch_worktrees,
+    issue_key_for_branch, link_branch_to_issue, move_worktree, push_branch, rename_branch,
+    set_branch_base, stack_children,
+};
+use crate::github::{create_issue, create_pr};
+use crate::hooks::{ensure_hook_script, write_worktree_hook_config};
+use crate::local::{self, LinkKind};
+use crate::models::{push_message, IssueMetadata, MessageLog};
+use crate::session::{worktree_names, Multiplexer};
+
+/// Outcome of syncing the local store to GitHub.
+pub struct SyncSummary {
+    pub issues_created: usize,
+    pub prs_created: usize,
+    pub branches_relinked: usize,
+    /// Session renames performed, as `(old_name, new_name)`.
+    pub renamed_sessions: Vec<(String, String)>,
+    pub failures: usize,
+}
+
+impl SyncSummary {
+    pub fn describe(&self) -> String {
+        let mut msg = format!(
+            "Synced to GitHub: {} issue(s), {} PR(s), {} branch(es) re-linked",
+            self.issues_created, self.prs_created, self.branches_relinked
+        );
+        if self.failures > 0 {
+            msg.push_str(&format!(" — {} failed, see messages", self.failures));
+        }
+        msg
+    }
+}
+
+/// Push the local backlog to GitHub.
+///
+/// Open local issues become GitHub issues, branches linked to local issues
+/// are renamed (with their worktrees and sessions) to the names the repo's
+/// templates give the new GitHub issue, and open local PRs are
+/// pushed and opened as GitHub PRs. Every mapping is recorded in the local
+/// store, so running the sync again only picks up what is still missing.
+pub fn sync_local_to_github(
+    repo: &str,
+    mux: Multiplexer,
+    log: &MessageLog,
+) -> Result<SyncSummary, Error> {
+    let (issues, prs) = local::fetch_unsynced(repo)?;
+    let mut summary = SyncSummary {
+        issues_created: 0,
+        prs_created: 0,
+        branches_relinked: 0,
+        renamed_sessions: Vec::new(),
+        failures: 0,
+    };
+
+    // Synced issues are assigned to whoever syncs them, like new ones.
+    let metadata = IssueMetadata {
+        assignees: vec!["@me".to_string()],
+        ..IssueMetadata::default()
+    };
+    for issue in &issues {
+        match create_issue(repo, &issue.title, &issue.body, &metadata) {
+            Ok(number) => {
+                push_message(
+                    log,
+                    &format!("[sync] Local issue #{} -> #{}", issue.number, number),
+                );
+                summary.issues_created += 1;
+                // Without the link the next sync would create the issue again
+                if let Err(e) =
+                    local::record_github_link(repo, LinkKind::Issue, issue.number, number)
+                {
+                    push_message(
+                        log,
+                        &format!(
+                            "[sync] Created #{} but could not link local issue #{} to it: {}",
+                            number, issue.number, e
+                        ),
+                    );
+                    summary.failures += 1;
+                }
+            }
+            Err(e) => {
+                push_message(
+                    log,
+                    &format!(
+                        "[sync] Failed to create issue for local #{}: {}",
+                        issue.number, e
+                    ),
+                );
+                summary.failures += 1;
+            }
+        }
+    }
+
+    // Re-link every branch still linked to a local issue that now has a
+    // GitHub number, including ones synced by an earlier, partially failed run.
+    let links = fetch_issue_links();
+    let mut branches: Vec<String> = fetch_worktrees(repo).into_iter().map(|w| w.title).collect();
+    branches.extend(prs.iter().map(|pr| pr.branch.clone()));
+    branches.sort();
+    branches.dedup();
+    for branch in &branches {
+        let is_local =
+            issue_key_for_branch(&links, branch).is_some_and(|key| key.starts_with("local-issue-"));
+        if !is_local {
+            continue;
+        }
+        if let Err(e) = relink_branch(repo, &links, branch, mux, &mut summary) {
+            push_message(log, &format!("[sync] Failed to re-link {}: {}", branch, e));
+            summary.failures += 1;
+        }
+    }
+
+    // Local PRs still record their pre-sync branch names
+    let links = fetch_issue_links();
+    for pr in &prs {
+        let issue_number = github_issue_for_branch(repo, &links, &pr.branch);
+        let branch = issue_number
+            .and_then(|n| branch_for_issue(&links, &format!("issue-{}", n)))
+            .unwrap_or_else(|| pr.branch.clone());
+        let body = match issue_number {
+            Some(n) if pr.body.is_empty() => format!("Closes #{}", n),
+            Some(n) => format!("{}\n\nCloses #{}", pr.body, n),
+            None => pr.body.clone(),
+        };
+        let base = pr.base.clone().or_else(|| base_for_branch(repo, &branch));
+        let result = push_branch(&branch).map_err(Error::from).and_then(|_| {
+            create_pr(
+                repo,
+                &branch,
+                base.as_deref(),
+                &pr.title,
+                &body,
+                pr.is_draft,
+            )
+            .map_err(Error::from)
+        });
+        match result {
+            Ok(number) => {
+                push_message(
+                    log,
+                    &format!("[sync] Local PR #{} -> #{} ({})", pr.number, number, branch),
+                );
+                summary.prs_created += 1;
+                if let Err(e) = local::record_github_link(repo, LinkKind::Pr, pr.number, number) {
+                    push_message(
+                        log,
+                        &format!(
+                            "[sync] Opened #{} but could not link local PR #{} to it: {}",
+                            number, pr.number, e
+                        ),
+                    );
+                    summary.failures += 1;
+                }
+            }
+            Err(e) => {
+                push_message(
+                    log,
+                    &format!("[sync] Failed to open PR for local #{}: {}", pr.number, e),
+                );
+                summary.failures += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// The GitHub issue number a branch linked to a local issue has been synced to.
+fn github_issue_for_branch(
+    repo: &str,
+    links: &HashMap<String, String>,
+    branch: &str,
+) -> Option<u64> {
+    let key = issue_key_for_branch(links, branch)?;
+    let local_number = extract_issue_number(key.strip_prefix("local-")?)?;
+    local::github_link(repo, LinkKind::Issue, local_number)
+}
+
+/// The branch linked to `issue_key`, if any.
+fn branch_for_issue(links: &HashMap<String, String>, issue_key: &str) -> Option<String> {
+    links
+        .iter()
+        .find(|(_, key)| key.as_str() == issue_key)
+        .map(|(branch, _)| branch.clone())
+}
+
+/// Rename a branch linked to a local issue, its worktree directory and its
+/// session to the names the templates give the synced GitHub issue, and link
+/// the branch to that issue. Branches whose issue has not been synced are
+/// left alone.
+fn relink_branch(
+    repo: &str,
+    links: &HashMap<String, String>,
+    branch: &str,
+    mux: Multiplexer,
+    summary: &mut SyncSummary,
+) -> Result<(), Error> {
+    let Some(number) = github_issue_for_branch(repo, links, branch) else {
+        return Ok(());
+    };
+    if !branch_exists(branch) {
+        return Ok(());
+    }
+    let local_number = issue_key_for_branch(links, branch)
+        .as_deref()
+        .and_then(extract_issue_number)
+        .unwrap_or(number);
+    let title = local::fetch_local_issue(repo, local_number)
+        .map(|(title, _)| title)
+        .unwrap_or_default();
+    let (new_branch, new_path) = worktree_names(repo, number, &title, false);
+    if branch_exists(&new_branch) {
+        return Err(
+            GitError::failed("git branch -m", format!("'{}' already exists", new_branch)).into(),
+        );
+    }
+    // Branches stacked on this one still name it as their base
+    let children = stack_children(branch);
+    rename_branch(branch, &new_branch)?;
+    link_branch_to_issue(&new_branch, &format!("issue-{}", number))?;
+    for child in &children {
+        set_branch_base(child, &new_branch)?;
+        local::retarget_local_prs(repo, child, &new_branch)?;
+    }
+    summary.branches_relinked += 1;
+
+    // Only move worktrees that still sit where the templates put them
+    if let Some(wt) = fetch_worktrees(repo)
+        .into_iter()
+        .find(|w| w.title == new_branch)
+    {
+        let (_, old_path) = worktree_names(repo, local_number, &title, true);
+        let mut path = wt.description.clone();
+        if Path::new(&path).file_name() == Path::new(&old_path).file_name() {
+            if let Some(parent) = Path::new(&new_path).parent() {
+                fs::create_dir_all(parent).map_err(|e| {
+                    Error::Io(format!("Failed to create {}: {}", parent.display(), e))
+                })?;
+            }
+            move_worktree(&path, &new_path)?;
+            path = new_path;
+        }
+        // The agent hooks report status under the session name
+        let hooks = ensure_hook_script().and_then(|script| {
+            write_worktree_hook_config(&path, &script.to_string_lossy(), &new_branch)
+        });
+        crate::log::warn_on_err("hooks", hooks);
+    }
+
+    if mux.list_sessions().iter().any(|s| s == branch) {
+        mux.rename_session(branch, &new_branch)?;
+        summary
+            .renamed_sessions
+            .push((branch.to_string(), new_branch));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fetch_issue_links;
+    use crate::session::create_worktree_and_session;
+    use crate::testing::Sandbox;
+
+    #[test]
+    fn local_work_moves_to_github_and_resumes_after_a_failure() {
+        let Some(sandbox) =
+            Sandbox::enter("sync::tests::local_work_moves_to_github_and_resumes_after_a_failure")
+        else {
+            return;
+        };
+        let repo = "acme/widgets";
+        let mux = Multiplexer::Tmux;
+        let log = MessageLog::default();
+
+        // A local issue being worked on, with a PR opened for it
+        let local_number = local::create_local_issue(repo, "Login bug", "", &[]).unwrap();
+        create_worktree_and_session(
+            repo,
+            local_number,
+            "Login bug",
+            "",
+            None,
+            None,
+            false,
+            false,
+            None,
+            mux,
+            true,
+        )
+        .unwrap();
+        let (old_branch, _) = worktree_names(repo, local_number, "Login bug", true);
+        local::create_local_pr(repo, "Fix login", "", &old_branch, None, false).unwrap();
+        let (new_branch, new_path) = worktree_names(repo, 42, "Login bug", false);
+
+        // The issue is created but the PR is not
+        sandbox.respond_text(
+            "issue create",
+            "https://github.com/acme/widgets/issues/42\n",
+        );
+        sandbox.exit_with("pr create", 1);
+        let summary = sync_local_to_github(repo, mux, &log).unwrap();
+        assert_eq!(summary.issues_created, 1);
+        assert_eq!(summary.prs_created, 0);
+        assert_eq!(summary.failures, 1);
+        assert_eq!(summary.branches_relinked, 1);
+        assert_eq!(
+            summary.renamed_sessions,
+            vec![(old_branch.clone(), new_branch.clone())]
+        );
+        assert!(branch_exists(&new_branch) && !branch_exists(&old_branch));
+        assert_eq!(
+            fetch_issue_links().get(&new_branch).map(String::as_str),
+            Some("issue-42")
+        );
+        assert_eq!(sandbox.sessions(), vec![new_branch.clone()]);
+        assert!(Path::new(&new_path)
+            .join(".claude/settings.local.json")
+            .exists());
+
+        // Running it again only opens the missing PR, from the renamed branch
+        sandbox.exit_with("pr create", 0);
+        sandbox.respond_text("pr create", "https://github.com/acme/widgets/pull/43\n");
+        let summary = sync_local_to_github(repo, mux, &log).unwrap();
+        assert_eq!(summary.issues_created, 0);
+        assert_eq!(summary.prs_created, 1);
+        assert_eq!(summary.failures, 0);
+
+        let calls = sandbox.calls();
+        let count = |prefix: &str| calls.iter().filter(|c| c.starts_with(prefix)).count();
+        assert_eq!(
+            count("gh issue create --repo acme/widgets --title Login bug"),
+            1
+        );
+        assert_eq!(count(&format!("git push -u origin {}", new_branch)), 2);
+        assert_eq!(
+            count(&format!(
+                "gh pr create --repo acme/widgets --head {} --title Fix login --body Closes #42",
+                new_branch
+            )),
+            2
+        );
+        assert_eq!(count(&format!("git push -u origin {}", old_branch)), 0);
+    }
+}
@@ -0,0 +1,271 @@
+//! The repository's issue templates from `.github/ISSUE_TEMPLATE`: markdown
+//! templates with front matter, and YAML issue forms turned into the
+//! markdown body GitHub would produce from them (a `### Label` heading per
+//! field), so they can be filled in as text.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::repo_state;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IssueTemplate {
+    pub name: String,
+    /// The one-line description shown in the template picker.
+    pub about: String,
+    /// Title to start from, e.g. `[Bug]: `.
+    pub title: String,
+    pub body: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+}
+
+/// The templates in the repository's checkout, in file name order (as
+/// GitHub lists them). Files that can't be read or parsed are skipped.
+pub fn load_templates() -> Vec<IssueTemplate> {
+    let root = repo_state::with_snapshot(|s| s.main.as_ref().map(|m| m.path.clone()))
+        .flatten()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    load_templates_from(&root.join(".github").join("ISSUE_TEMPLATE"))
+}
+
+fn load_templates_from(dir: &Path) -> Vec<IssueTemplate> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let file_name = path.file_name()?.to_str()?;
+            let text = fs::read_to_string(path).ok()?;
+            parse_template(file_name, &text)
+        })
+        .collect()
+}
+
+/// A template from the contents of `file_name`. `config.yml` (the chooser's
+/// own settings) and anything that isn't markdown or YAML give `None`.
+pub fn parse_template(file_name: &str, text: &str) -> Option<IssueTemplate> {
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    match extension.to_lowercase().as_str() {
+        "md" | "markdown" => Some(markdown_template(stem, text)),
+        "yml" | "yaml" if stem != "config" => form_template(stem, text),
+        _ => None,
+    }
+}
+
+fn markdown_template(stem: &str, text: &str) -> IssueTemplate {
+    let (meta, body) = match text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+        .and_then(|rest| {
+            let end = rest.find("\n---")?;
+            let body = rest[end + 4..].trim_start_matches(['-', '\r']);
+            Some((parse_yaml(&rest[..end]), body.trim_start_matches('\n')))
+        }) {
+        Some((meta, body)) => (meta, body),
+        None => (Value::Null, text),
+    };
+    IssueTemplate {
+        name: string(&meta["name"]).unwrap_or_else(|| stem.to_string()),
+        about: string(&meta["about"]).unwrap_or_default(),
+        title: string(&meta["title"]).unwrap_or_default(),
+        body: body.to_string(),
+        labels: list(&meta["labels"]),
+        assignees: list(&meta["assignees"]),
+    }
+}
+
+fn form_template(stem: &str, text: &str) -> Option<IssueTemplate> {
+    let form = parse_yaml(text);
+    let fields = form["body"].as_array()?;
+    let mut body = String::new();
+    for field in fields {
+        let attributes = &field["attributes"];
+        let label = string(&attributes["label"]).unwrap_or_default();
+        let section = match field["type"].as_str().unwrap_or_default() {
+            // Instructions for whoever fills the form in; GitHub leaves them
+            // out of the issue, so they only show up while editing.
+            "markdown" => match string(&attributes["value"]) {
+                Some(value) => format!("<!--\n{}\n-->", value.trim_end()),
+                None => continue,
+            },
+            "textarea" | "input" => format!(
+                "### {}\n\n{}",
+                label,
+                string(&attributes["value"]).unwrap_or_default().trim_end()
+            ),
+            "dropdown" => format!(
+                "### {}\n\n<!-- One of: {} -->",
+                label,
+                list(&attributes["options"]).join(", ")
+            ),
+            "checkboxes" => {
+                let options: Vec<String> = attributes["options"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|option| string(&option["label"]).or_else(|| string(option)))
+                    .map(|option| format!("- [ ] {}", option))
+                    .collect();
+                format!("### {}\n\n{}", label, options.join("\n"))
+            }
+            _ => continue,
+        };
+        body.push_str(section.trim_end());
+        body.push_str("\n\n");
+    }
+    Some(IssueTemplate {
+        name: string(&form["name"]).unwrap_or_else(|| stem.to_string()),
+        about: string(&form["description"]).unwrap_or_default(),
+        title: string(&form["title"]).unwrap_or_default(),
+        body: body.trim_end().to_string(),
+        labels: list(&form["labels"]),
+        assignees: list(&form["assignees"]),
+    })
+}
+
+/// A scalar as text; YAML reads unquoted `2` or `true` as a number or
+/// boolean, which templates mean as plain text.
+fn string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// A list that templates may write as a YAML sequence or as one
+/// comma-separated string.
+fn list(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().filter_map(string).collect(),
+        Value::String(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parse YAML into a JSON value; text that isn't valid YAML gives `Null`
+/// so the template falls back to its defaults.
+fn parse_yaml(text: &str) -> Value {
+    YamlLoader::load_from_str(text)
+        .ok()
+        .and_then(|docs| docs.into_iter().next())
+        .map(yaml_to_json)
+        .unwrap_or(Value::Null)
+}
+
+/// Floats are kept as written, so a version like `1.10` stays `1.10`.
+fn yaml_to_json(yaml: Yaml) -> Value {
+    match yaml {
+        Yaml::String(s) | Yaml::Real(s) => Value::String(s),
+        Yaml::Integer(n) => Value::from(n),
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Array(items) => Value::Array(items.into_iter().map(yaml_to_json).collect()),
+        Yaml::Hash(map) => Value::Object(
+            map.into_iter()
+                .filter_map(|(key, value)| {
+                    let key = match yaml_to_json(key) {
+                        Value::String(s) => s,
+                        Value::Number(n) => n.to_string(),
+                        Value::Bool(b) => b.to_string(),
+                        _ => return None,
+                    };
+                    Some((key, yaml_to_json(value)))
+                })
+                .collect(),
+        ),
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_markdown_templates_and_issue_forms() {
+        let markdown = "\
+---
+name: Feature request
+about: Suggest an idea
+title: '[Feature]: '
+labels: enhancement, needs triage
+assignees: ''
+---
+
+## What problem does it solve?
+";
+        let template = parse_template("feature.md", markdown).unwrap();
+        assert_eq!(template.name, "Feature request");
+        assert_eq!(template.title, "[Feature]: ");
+        assert_eq!(template.labels, ["enhancement", "needs triage"]);
+        assert!(template.assignees.is_empty());
+        assert_eq!(template.body, "## What problem does it solve?\n");
+
+        let form = r#"
+name: Bug report
+description: "Something isn't working"
+title: "[Bug]: "
+labels: ["bug", triage]
+assignees:
+- octocat
+body:
+  - type: markdown
+    attributes:
+      value: |
+        Thanks for taking the time!
+  - type: textarea
+    id: what-happened
+    attributes:
+      label: What happened?
+      value: >
+        A bug
+        happened!
+    validations:
+      required: true
+  - type: dropdown
+    attributes:
+      label: Version
+      options:
+        - 1.0.2 (Default)
+        - 1.0.3 # edge
+        - 2
+  - type: checkboxes
+    attributes:
+      label: Terms
+      options:
+        - label: I checked for duplicates
+          required: true
+"#;
+        let template = parse_template("bug_report.yml", form).unwrap();
+        assert_eq!(template.name, "Bug report");
+        assert_eq!(template.about, "Something isn't working");
+        assert_eq!(template.labels, ["bug", "triage"]);
+        assert_eq!(template.assignees, ["octocat"]);
+        assert_eq!(
+            template.body,
+            "<!--\nThanks for taking the time!\n-->\n\n\
+             ### What happened?\n\nA bug happened!\n\n\
+             ### Version\n\n<!-- One of: 1.0.2 (Default), 1.0.3, 2 -->\n\n\
+             ### Terms\n\n- [ ] I checked for duplicates"
+        );
+
+        assert_eq!(
+            parse_template("config.yml", "blank_issues_enabled: false"),
+            None
+        );
+    }
+}
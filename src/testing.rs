@@ -0,0 +1,487 @@
+//! End-to-end test harness: runs the board against scripted `gh` and `tmux`
+//! executables and a recording `git` wrapper in a throwaway repository, and
+//! drives it through a ratatui `TestBackend` without a terminal or network.
+//!
+//! `PATH`, `HOME` and the working directory are process-wide, so a sandboxed
+//! test re-runs itself in a child test process pointed at its sandbox; see
+//! [`Sandbox::enter`].
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Style};
+use ratatui::{Frame, Terminal};
+use serde_json::{json, Value};
+
+use crate::app::App;
+use crate::models::Screen;
+use crate::session::Multiplexer;
+use crate::{draw, handle_key, poll_background, Console};
+
+/// Set in the child process to the sandbox directory.
+const SANDBOX_VAR: &str = "OCTOPAI_SANDBOX";
+
+/// Records every call, then answers `gh <a> <b> ...` with `gh/<a>-<b>.json`
+/// and exits with `gh/<a>-<b>.exit` when those exist.
+const FAKE_GH: &str = r#"#!/bin/sh
+printf '%s\n' "gh $(echo "$*" | tr '\n' ' ')" >> "$OCTOPAI_SANDBOX/calls.log"
+key="$OCTOPAI_SANDBOX/gh/$1-$2"
+[ -f "$key.json" ] && cat "$key.json"
+[ -f "$key.exit" ] && exit "$(cat "$key.exit")"
+exit 0
+"#;
+
+/// Keeps session names in a file so sessions outlive the call that made them.
+const FAKE_TMUX: &str = r#"#!/bin/sh
+printf '%s\n' "tmux $(echo "$*" | tr '\n' ' ')" >> "$OCTOPAI_SANDBOX/calls.log"
+sessions="$OCTOPAI_SANDBOX/tmux-sessions"
+touch "$sessions"
+cmd=$1; shift
+name=""
+new=""
+while [ $# -gt 0 ]; do
+  case "$1" in -s|-t) name=${2%%:*}; shift ;; -*) ;; *) new=$1 ;; esac
+  shift
+done
+case "$cmd" in
+  new-session) echo "$name" >> "$sessions" ;;
+  kill-session) grep -vx "$name" "$sessions" > "$sessions.tmp"; mv "$sessions.tmp" "$sessions" ;;
+  has-session) grep -qx "$name" "$sessions" ;;
+  list-sessions) cat "$sessions" ;;
+  rename-session) sed "s/^$name\$/$new/" "$sessions" > "$sessions.tmp"; mv "$sessions.tmp" "$sessions" ;;
+esac
+"#;
+
+/// Records every call and runs the real git, except for commands that would
+/// talk to a remote.
+const FAKE_GIT: &str = r#"#!/bin/sh
+echo "git $*" >> "$OCTOPAI_SANDBOX/calls.log"
+sub=$1
+[ "$sub" = "-C" ] && sub=$3
+case "$sub" in push|fetch|pull) exit 0 ;; esac
+exec "@GIT@" "$@"
+"#;
+
+/// A throwaway home directory, repository (`acme/widgets`, branch `main`,
+/// with an `origin`) and `bin/` of fakes.
+pub struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    /// In the test process, build a sandbox and run the test `name` (its
+    /// path from the crate root, e.g. `tests::merge_flow`) again in a child
+    /// process inside it, failing if the child fails, and return `None`.
+    /// In that child, return the sandbox to run the test body in.
+    pub fn enter(name: &str) -> Option<Sandbox> {
+        Sandbox::enter_with_env(name, &[])
+    }
+
+    /// Like [`Sandbox::enter`], with extra environment for the child, e.g.
+    /// to point `XDG_CONFIG_HOME` somewhere that does not exist.
+    pub fn enter_with_env(name: &str, extra_env: &[(&str, &str)]) -> Option<Sandbox> {
+        if let Ok(root) = std::env::var(SANDBOX_VAR) {
+            return Some(Sandbox { root: root.into() });
+        }
+
+        let root = std::env::temp_dir().join(format!(
+            "octopai-sandbox-{}-{}",
+            std::process::id(),
+            name.replace("::", "-")
+        ));
+        let _ = fs::remove_dir_all(&root);
+        let sandbox = Sandbox::create(&root);
+
+        let path = format!(
+            "{}:{}",
+            root.join("bin").display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let output = Command::new(std::env::current_exe().expect("test binary"))
+            .args([name, "--exact", "--nocapture", "--test-threads=1"])
+            .current_dir(sandbox.repo())
+            .env(SANDBOX_VAR, &root)
+            .env("PATH", path)
+            .envs(sandbox.env())
+            .envs(extra_env.iter().copied())
+            .output()
+            .expect("Failed to run sandboxed test");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success() && stdout.contains("1 passed"),
+            "sandboxed test {} failed:\n{}\n{}",
+            name,
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let _ = fs::remove_dir_all(&root);
+        None
+    }
+
+    fn create(root: &Path) -> Sandbox {
+        let sandbox = Sandbox {
+            root: root.to_path_buf(),
+        };
+        for dir in ["bin", "home/.config", "gh"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let real_git = which("git");
+        for (name, script) in [
+            ("gh", FAKE_GH.to_string()),
+            ("tmux", FAKE_TMUX.to_string()),
+            ("git", FAKE_GIT.replace("@GIT@", &real_git)),
+        ] {
+            let path = root.join("bin").join(name);
+            fs::write(&path, script).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let git = |dir: &Path, args: &[&str]| {
+            let output = Command::new(&real_git)
+                .args(args)
+                .current_dir(dir)
+                .envs(sandbox.env())
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "git {:?}: {}",
+                args,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        };
+        let repo = sandbox.repo();
+        fs::create_dir_all(&repo).unwrap();
+        fs::write(repo.join("README.md"), "widgets\n").unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "Initial commit"]);
+        git(root, &["clone", "-q", "--bare", "widgets", "origin.git"]);
+        git(&repo, &["remote", "add", "origin", "../origin.git"]);
+        git(&repo, &["fetch", "-q", "origin"]);
+        git(&repo, &["remote", "set-head", "origin", "main"]);
+        sandbox
+    }
+
+    /// Environment the fakes and git run with, besides `PATH`.
+    fn env(&self) -> HashMap<&'static str, String> {
+        let home = self.root.join("home");
+        HashMap::from([
+            ("HOME", home.display().to_string()),
+            (
+                "XDG_CONFIG_HOME",
+                home.join(".config").display().to_string(),
+            ),
+            ("GIT_CONFIG_NOSYSTEM", "1".to_string()),
+            ("GIT_AUTHOR_NAME", "Octopai Test".to_string()),
+            ("GIT_AUTHOR_EMAIL", "test@example.com".to_string()),
+            ("GIT_COMMITTER_NAME", "Octopai Test".to_string()),
+            ("GIT_COMMITTER_EMAIL", "test@example.com".to_string()),
+        ])
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The main worktree of the repository, the child's working directory.
+    pub fn repo(&self) -> PathBuf {
+        self.root.join("widgets")
+    }
+
+    /// Answer `gh <command> ...` (e.g. `"api graphql"`) with `json` from now on.
+    pub fn respond(&self, command: &str, json: &Value) {
+        let path = self.root.join("gh").join(command.replace(' ', "-"));
+        fs::write(path.with_extension("json"), json.to_string()).unwrap();
+    }
+
+    /// Answer `gh <command> ...` with the plain `text`, e.g. the URL `gh issue create` prints.
+    pub fn respond_text(&self, command: &str, text: &str) {
+        let path = self.root.join("gh").join(command.replace(' ', "-"));
+        fs::write(path.with_extension("json"), text).unwrap();
+    }
+
+    /// Make `gh <command> ...` exit with `code` from now on; 0 undoes it.
+    pub fn exit_with(&self, command: &str, code: i32) {
+        let path = self.root.join("gh").join(command.replace(' ', "-"));
+        fs::write(path.with_extension("exit"), code.to_string()).unwrap();
+    }
+
+    /// Every `gh`, `git` and `tmux` call so far, e.g. `tmux kill-session -t issue-7`.
+    pub fn calls(&self) -> Vec<String> {
+        fs::read_to_string(self.root.join("calls.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(|l| l.trim_end().to_string())
+            .collect()
+    }
+
+    pub fn called(&self, call: &str) -> bool {
+        self.calls().iter().any(|c| c == call)
+    }
+
+    /// Names of the sessions the fake tmux has running.
+    pub fn sessions(&self) -> Vec<String> {
+        fs::read_to_string(self.root.join("tmux-sessions"))
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+fn which(program: &str) -> String {
+    let output = Command::new("sh")
+        .args(["-c", &format!("command -v {}", program)])
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// The `gh api graphql` response for a board refresh.
+pub fn gh_board(issues: &[Value], prs: &[Value], merged: &[&str]) -> Value {
+    let page = |nodes: &[Value]| {
+        json!({
+            "pageInfo": { "hasNextPage": false, "endCursor": null },
+            "nodes": nodes,
+        })
+    };
+    let merged: Vec<Value> = merged.iter().map(|b| json!({ "headRefName": b })).collect();
+    json!({
+        "data": {
+            "rateLimit": {
+                "limit": 5000,
+                "remaining": 4999,
+                "resetAt": "2099-01-01T00:00:00Z",
+                "cost": 1,
+            },
+            "issues": page(issues),
+            "prs": page(prs),
+            "merged": { "nodes": merged },
+        }
+    })
+}
+
+/// An open issue node as the board query returns it.
+pub fn gh_issue(number: u64, title: &str) -> Value {
+    json!({
+        "number": number,
+        "title": title,
+        "body": "",
+        "state": "OPEN",
+        "labels": { "nodes": [] },
+    })
+}
+
+/// An open, assigned, ready pull request node for `branch`.
+pub fn gh_pr(number: u64, title: &str, branch: &str) -> Value {
+    json!({
+        "number": number,
+        "title": title,
+        "body": "",
+        "isDraft": false,
+        "url": format!("https://github.com/acme/widgets/pull/{}", number),
+        "headRefName": branch,
+        "baseRefName": "main",
+        "state": "OPEN",
+        "mergedAt": null,
+        "assignees": { "totalCount": 1 },
+        "commits": { "nodes": [] },
+    })
+}
+
+/// A console with no keyboard; keys come from [`Tui::press`].
+struct NoConsole;
+
+impl Console for NoConsole {
+    fn poll(&mut self, _timeout: Duration) -> std::io::Result<bool> {
+        Ok(false)
+    }
+
+    fn read(&mut self) -> std::io::Result<crossterm::event::Event> {
+        unreachable!("NoConsole never has events")
+    }
+
+    fn suspend(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn resume(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The board for `acme/widgets`, drawn to an in-memory terminal.
+pub struct Tui {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Tui {
+    /// Open the board and load it the way startup does.
+    pub fn new() -> Tui {
+        let mut app = App::new(
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::default(),
+            Multiplexer::Tmux,
+        );
+        app.repo = "acme/widgets".to_string();
+        app.screen = Screen::Board;
+        app.refresh_data();
+        Tui {
+            app,
+            terminal: Terminal::new(TestBackend::new(160, 40)).unwrap(),
+        }
+    }
+
+    /// Handle a key press, then pick up any result that is already there.
+    pub fn press(&mut self, code: KeyCode) {
+        let key = KeyEvent::new(code, KeyModifiers::NONE);
+        handle_key(&mut self.terminal, &mut self.app, &mut NoConsole, key).unwrap();
+        poll_background(&mut self.app);
+    }
+
+    /// Wait for background work (refreshes, worktree creation, ...) to finish.
+    pub fn settle(&mut self) {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        loop {
+            poll_background(&mut self.app);
+            let app = &self.app;
+            let busy = app.issue_submit_rx.is_some()
+                || app.issue_edit_rx.is_some()
+                || app.worktree_create_rx.is_some()
+                || app.sync_rx.is_some()
+                || app.effects_running > 0
+                || app.is_section_loading();
+            if !busy {
+                return;
+            }
+            assert!(Instant::now() < deadline, "background work did not finish");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Draw the current screen and return its text, one line per row.
+    pub fn screen(&mut self) -> String {
+        self.terminal.draw(|frame| draw(frame, &self.app)).unwrap();
+        buffer_text(self.terminal.backend().buffer())
+    }
+
+    /// The latest entries of the message log.
+    pub fn messages(&self) -> Vec<String> {
+        self.app
+            .message_log
+            .lock()
+            .map(|log| log.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The characters of `buffer`, one line per row.
+pub fn buffer_text(buffer: &Buffer) -> String {
+    let width = buffer.area.width as usize;
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `buffer` as stored in a snapshot: its text, then every run of styled
+/// cells as `row:start-end style`.
+fn snapshot_text(buffer: &Buffer) -> String {
+    let mut out = buffer_text(buffer);
+    out.push_str("\n-- styles\n");
+    let width = buffer.area.width as usize;
+    for (y, row) in buffer.content.chunks(width).enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            let style = row[x].style();
+            let start = x;
+            while x < row.len() && row[x].style() == style {
+                x += 1;
+            }
+            let text = style_text(style);
+            if !text.is_empty() {
+                out.push_str(&format!("{}:{}-{} {}\n", y, start, x - 1, text));
+            }
+        }
+    }
+    out
+}
+
+/// A style as e.g. `fg=Cyan bg=Rgb(60, 60, 60) +BOLD`; empty for the
+/// terminal's default look.
+fn style_text(style: Style) -> String {
+    let mut parts = Vec::new();
+    if let Some(fg) = style.fg.filter(|c| *c != Color::Reset) {
+        parts.push(format!("fg={:?}", fg));
+    }
+    if let Some(bg) = style.bg.filter(|c| *c != Color::Reset) {
+        parts.push(format!("bg={:?}", bg));
+    }
+    if !style.add_modifier.is_empty() {
+        parts.push(format!("+{:?}", style.add_modifier));
+    }
+    if !style.sub_modifier.is_empty() {
+        parts.push(format!("-{:?}", style.sub_modifier));
+    }
+    parts.join(" ")
+}
+
+/// Render `draw` at each of `sizes` and compare the result with the stored
+/// snapshot `src/snapshots/<name>.snap`. On a mismatch the new rendering is
+/// written next to it as `<name>.snap.new` for review; run the tests with
+/// `UPDATE_SNAPSHOTS=1` to accept it.
+pub fn check_snapshot(
+    name: &str,
+    sizes: &[(u16, u16)],
+    draw: impl Fn(&mut Frame),
+) -> Result<(), String> {
+    let mut actual = String::new();
+    for &(width, height) in sizes {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| draw(frame)).unwrap();
+        actual.push_str(&format!("== {}x{}\n", width, height));
+        actual.push_str(&snapshot_text(terminal.backend().buffer()));
+    }
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/snapshots");
+    let path = dir.join(format!("{}.snap", name));
+    let new_path = dir.join(format!("{}.snap.new", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, &actual).unwrap();
+        let _ = fs::remove_file(&new_path);
+        return Ok(());
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    if expected == actual {
+        let _ = fs::remove_file(&new_path);
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(&new_path, &actual).unwrap();
+    let line = expected
+        .lines()
+        .zip(actual.lines())
+        .position(|(e, a)| e != a)
+        .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+    Err(format!(
+        "{} differs from the snapshot at line {}:\n- {}\n+ {}\n(review {}, rerun with UPDATE_SNAPSHOTS=1 to accept)",
+        name,
+        line + 1,
+        expected.lines().nth(line).unwrap_or("<missing>"),
+        actual.lines().nth(line).unwrap_or("<missing>"),
+        new_path.display()
+    ))
+}
@@ -0,0 +1,445 @@
+//! A multi-line text field for issue bodies: newlines, word movement, a
+//! selection, paste and undo. Positions are character indices, as in
+//! `TextInput`.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// How many edits `undo` can step back through.
+const UNDO_LIMIT: usize = 200;
+
+/// What the last change was, so a run of typing or deleting undoes as one.
+#[derive(Clone, Copy, PartialEq)]
+enum Edit {
+    None,
+    Insert,
+    Delete,
+}
+
+pub struct TextArea {
+    text: String,
+    cursor: usize,
+    /// The other end of the selection, when there is one.
+    anchor: Option<usize>,
+    /// The column up and down aim for, kept across short lines.
+    goal_column: Option<usize>,
+    undo: Vec<(String, usize)>,
+    redo: Vec<(String, usize)>,
+    last_edit: Edit,
+    /// What Ctrl+X and Ctrl+C took, for Ctrl+V.
+    clipboard: String,
+}
+
+impl TextArea {
+    pub fn new() -> Self {
+        Self::from(String::new())
+    }
+
+    pub fn from(s: String) -> Self {
+        let cursor = s.chars().count();
+        Self {
+            text: s,
+            cursor,
+            anchor: None,
+            goal_column: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit: Edit::None,
+            clipboard: String::new(),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The selected range, start first, if anything is selected.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+        (anchor != self.cursor).then(|| (anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    /// Replace the whole text (say, with what an external editor saved),
+    /// as one step `undo` can take back.
+    pub fn set_value(&mut self, text: String) {
+        if text == self.text {
+            return;
+        }
+        self.checkpoint(Edit::None);
+        self.cursor = text.chars().count();
+        self.text = text;
+        self.anchor = None;
+    }
+
+    /// Handle a key meant for the field. Returns false for keys it has no
+    /// use for, so the caller can act on them instead.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.select_all(),
+            KeyCode::Char('z') if ctrl && shift => self.redo(),
+            KeyCode::Char('Z') if ctrl => self.redo(),
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Char('x') if ctrl => self.cut(),
+            KeyCode::Char('c') if ctrl => self.copy(),
+            KeyCode::Char('v') if ctrl => {
+                let clipboard = self.clipboard.clone();
+                self.insert_str(&clipboard);
+            }
+            KeyCode::Char('w') if ctrl => self.delete_word_back(),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Enter => self.insert('\n'),
+            KeyCode::Backspace if ctrl || alt => self.delete_word_back(),
+            KeyCode::Backspace => self.delete_back(),
+            KeyCode::Delete => self.delete_forward(),
+            KeyCode::Left if ctrl || alt => self.move_to(self.word_left(), shift),
+            KeyCode::Right if ctrl || alt => self.move_to(self.word_right(), shift),
+            KeyCode::Left => self.move_left(shift),
+            KeyCode::Right => self.move_right(shift),
+            KeyCode::Up => self.move_vertically(-1, shift),
+            KeyCode::Down => self.move_vertically(1, shift),
+            KeyCode::Home if ctrl => self.move_to(0, shift),
+            KeyCode::End if ctrl => self.move_to(self.len(), shift),
+            KeyCode::Home => self.move_to(self.line_start(self.cursor), shift),
+            KeyCode::End => self.move_to(self.line_end(self.cursor), shift),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn insert(&mut self, c: char) {
+        // Each word typed is its own undo step.
+        let edit = if c.is_whitespace() {
+            Edit::None
+        } else {
+            Edit::Insert
+        };
+        self.checkpoint(edit);
+        self.delete_selection();
+        let idx = self.byte_index(self.cursor);
+        self.text.insert(idx, c);
+        self.cursor += 1;
+    }
+
+    /// Insert pasted text in one go, replacing the selection.
+    pub fn insert_str(&mut self, s: &str) {
+        let s = s.replace("\r\n", "\n").replace('\r', "\n");
+        if s.is_empty() && self.selection().is_none() {
+            return;
+        }
+        self.checkpoint(Edit::None);
+        self.delete_selection();
+        let idx = self.byte_index(self.cursor);
+        self.text.insert_str(idx, &s);
+        self.cursor += s.chars().count();
+    }
+
+    pub fn delete_back(&mut self) {
+        if self.selection().is_some() {
+            self.checkpoint(Edit::None);
+            self.delete_selection();
+        } else if self.cursor > 0 {
+            self.checkpoint(Edit::Delete);
+            self.remove(self.cursor - 1, self.cursor);
+        }
+    }
+
+    pub fn delete_forward(&mut self) {
+        if self.selection().is_some() {
+            self.checkpoint(Edit::None);
+            self.delete_selection();
+        } else if self.cursor < self.len() {
+            self.checkpoint(Edit::Delete);
+            self.remove(self.cursor, self.cursor + 1);
+        }
+    }
+
+    pub fn delete_word_back(&mut self) {
+        if self.selection().is_some() {
+            return self.delete_back();
+        }
+        let start = self.word_left();
+        if start < self.cursor {
+            self.checkpoint(Edit::None);
+            self.remove(start, self.cursor);
+        }
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.len();
+        self.settle();
+    }
+
+    pub fn undo(&mut self) {
+        if let Some((text, cursor)) = self.undo.pop() {
+            let current = std::mem::replace(&mut self.text, text);
+            self.redo.push((current, self.cursor));
+            self.cursor = cursor;
+            self.anchor = None;
+            self.settle();
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some((text, cursor)) = self.redo.pop() {
+            let current = std::mem::replace(&mut self.text, text);
+            self.undo.push((current, self.cursor));
+            self.cursor = cursor;
+            self.anchor = None;
+            self.settle();
+        }
+    }
+
+    fn cut(&mut self) {
+        if let Some((start, end)) = self.selection() {
+            self.clipboard = self.slice(start, end).to_string();
+            self.checkpoint(Edit::None);
+            self.delete_selection();
+        }
+    }
+
+    fn copy(&mut self) {
+        if let Some((start, end)) = self.selection() {
+            self.clipboard = self.slice(start, end).to_string();
+        }
+    }
+
+    pub fn move_left(&mut self, select: bool) {
+        match self.selection() {
+            Some((start, _)) if !select => self.move_to(start, false),
+            _ => self.move_to(self.cursor.saturating_sub(1), select),
+        }
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        match self.selection() {
+            Some((_, end)) if !select => self.move_to(end, false),
+            _ => self.move_to((self.cursor + 1).min(self.len()), select),
+        }
+    }
+
+    /// Move the cursor to `pos`, extending the selection or dropping it.
+    fn move_to(&mut self, pos: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = pos;
+        self.settle();
+    }
+
+    /// Move `rows` lines up or down, keeping to the column the cursor
+    /// started from.
+    fn move_vertically(&mut self, rows: isize, select: bool) {
+        let (row, column) = self.row_column(self.cursor);
+        let goal = self.goal_column.unwrap_or(column);
+        let target = row as isize + rows;
+        let pos = if target < 0 {
+            0
+        } else if target as usize >= self.line_count() {
+            self.len()
+        } else {
+            let start = self.row_start(target as usize);
+            (start + goal).min(self.line_end(start))
+        };
+        self.move_to(pos, select);
+        self.goal_column = Some(goal);
+    }
+
+    /// The start of the word before the cursor.
+    fn word_left(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut pos = self.cursor;
+        while pos > 0 && !is_word(chars[pos - 1]) {
+            pos -= 1;
+        }
+        while pos > 0 && is_word(chars[pos - 1]) {
+            pos -= 1;
+        }
+        pos
+    }
+
+    /// The end of the word after the cursor.
+    fn word_right(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut pos = self.cursor;
+        while pos < chars.len() && !is_word(chars[pos]) {
+            pos += 1;
+        }
+        while pos < chars.len() && is_word(chars[pos]) {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Line and column of the character at `pos`.
+    pub fn row_column(&self, pos: usize) -> (usize, usize) {
+        let before = self.slice(0, pos);
+        let row = before.matches('\n').count();
+        let column = match before.rfind('\n') {
+            Some(i) => before[i + 1..].chars().count(),
+            None => before.chars().count(),
+        };
+        (row, column)
+    }
+
+    fn row_start(&self, row: usize) -> usize {
+        if row == 0 {
+            return 0;
+        }
+        self.text
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| *c == '\n')
+            .nth(row - 1)
+            .map(|(i, _)| i + 1)
+            .unwrap_or(self.len())
+    }
+
+    fn line_start(&self, pos: usize) -> usize {
+        self.row_start(self.row_column(pos).0)
+    }
+
+    fn line_end(&self, pos: usize) -> usize {
+        let start = self.byte_index(pos);
+        match self.text[start..].find('\n') {
+            Some(i) => pos + self.text[start..start + i].chars().count(),
+            None => self.len(),
+        }
+    }
+
+    fn line_count(&self) -> usize {
+        self.text.matches('\n').count() + 1
+    }
+
+    /// Remember the text before a change. Consecutive changes of the same
+    /// kind share one snapshot.
+    fn checkpoint(&mut self, edit: Edit) {
+        if edit == Edit::None || edit != self.last_edit {
+            self.undo.push((self.text.clone(), self.cursor));
+            if self.undo.len() > UNDO_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.last_edit = edit;
+        self.goal_column = None;
+    }
+
+    /// After a move or undo: the next edit starts a new undo step.
+    fn settle(&mut self) {
+        self.last_edit = Edit::None;
+        self.goal_column = None;
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some((start, end)) = self.selection() {
+            self.remove(start, end);
+        }
+        self.anchor = None;
+    }
+
+    fn remove(&mut self, start: usize, end: usize) {
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.text.replace_range(range, "");
+        self.cursor = start;
+    }
+
+    fn slice(&self, start: usize, end: usize) -> &str {
+        &self.text[self.byte_index(start)..self.byte_index(end)]
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, pos: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(pos)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(area: &mut TextArea, code: KeyCode, modifiers: KeyModifiers) {
+        assert!(area.handle_key(KeyEvent::new(code, modifiers)));
+    }
+
+    fn type_text(area: &mut TextArea, text: &str) {
+        for c in text.chars() {
+            let code = if c == '\n' {
+                KeyCode::Enter
+            } else {
+                KeyCode::Char(c)
+            };
+            press(area, code, KeyModifiers::NONE);
+        }
+    }
+
+    #[test]
+    fn edits_bodies_like_a_text_editor() {
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let shift = KeyModifiers::SHIFT;
+        let mut area = TextArea::new();
+
+        type_text(&mut area, "Steps to reproduce\nrun it twice");
+        assert_eq!(area.row_column(area.cursor()), (1, 12));
+
+        // Up keeps the column, Home and End stay on the line.
+        press(&mut area, KeyCode::Up, none);
+        assert_eq!(area.row_column(area.cursor()), (0, 12));
+        press(&mut area, KeyCode::End, none);
+        assert_eq!(area.cursor(), 18);
+
+        // Word movement and a shift selection, replaced by typing.
+        press(&mut area, KeyCode::Left, ctrl);
+        press(&mut area, KeyCode::Left, ctrl);
+        press(&mut area, KeyCode::Right, ctrl | shift);
+        assert_eq!(area.selection(), Some((6, 8)));
+        type_text(&mut area, "for");
+        assert_eq!(area.value(), "Steps for reproduce\nrun it twice");
+
+        // The replacement undoes in one step, and redo brings it back.
+        press(&mut area, KeyCode::Char('z'), ctrl);
+        assert_eq!(area.value(), "Steps to reproduce\nrun it twice");
+        press(&mut area, KeyCode::Char('y'), ctrl);
+        assert_eq!(area.value(), "Steps for reproduce\nrun it twice");
+
+        // A paste lands in one piece, with Windows line endings folded.
+        press(&mut area, KeyCode::End, ctrl);
+        area.insert_str("\r\nthen\r\nreload");
+        assert_eq!(
+            area.value(),
+            "Steps for reproduce\nrun it twice\nthen\nreload"
+        );
+        press(&mut area, KeyCode::Backspace, ctrl);
+        press(&mut area, KeyCode::Backspace, ctrl);
+        assert_eq!(area.value(), "Steps for reproduce\nrun it twice\n");
+        press(&mut area, KeyCode::Char('z'), ctrl);
+        press(&mut area, KeyCode::Char('z'), ctrl);
+        press(&mut area, KeyCode::Char('z'), ctrl);
+        assert_eq!(area.value(), "Steps for reproduce\nrun it twice");
+
+        // Ctrl and Alt letters the field has no use for go to the caller.
+        assert!(!area.handle_key(KeyEvent::new(KeyCode::Char('s'), ctrl)));
+    }
+}
@@ -0,0 +1,388 @@
+//! Colours the board is drawn with. Everything that picks a colour goes
+//! through a named slot of the [`Theme`] (`accent`, `warning`, the column
+//! colours, ...) instead of naming a colour, so a preset or a theme from the
+//! config file can change the whole look. Setting `NO_COLOR` drops colour
+//! altogether, whatever the config says.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// The built-in themes, by the name used for them in the config file.
+pub const PRESETS: [&str; 4] = ["dark", "light", "high-contrast", "monochrome"];
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Use `theme` from now on. Only the first call has an effect.
+pub fn init(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The theme in use; the dark preset until [`init`] is called.
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(Theme::dark)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Titles and typed text.
+    pub text: Color,
+    /// Descriptions and secondary text.
+    pub muted: Color,
+    /// Hints, placeholders and inactive borders.
+    pub dim: Color,
+    /// Focus, cursors and the header.
+    pub accent: Color,
+    /// Text drawn on a coloured background (tags, active titles).
+    pub on_accent: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    /// Labels marked priority, critical or urgent.
+    pub critical: Color,
+    pub info: Color,
+    /// Stacked branches and merged PRs.
+    pub special: Color,
+    /// Background of keys in the hint bars.
+    pub key_bg: Color,
+    /// Border of the selected card.
+    pub selected: Color,
+    /// Border of cards related to the selected one.
+    pub related: Color,
+    /// Background of the selected row in lists.
+    pub selection_bg: Color,
+    /// Background of a selected row that needs attention.
+    pub error_bg: Color,
+    /// Issues, worktrees, sessions and pull requests, in board order.
+    pub columns: [Color; 4],
+    /// Draw coloured backgrounds as reversed text instead.
+    monochrome: bool,
+    /// Configured label colours by lower-cased label name.
+    labels: HashMap<String, Color>,
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            text: Color::White,
+            muted: Color::Gray,
+            dim: Color::DarkGray,
+            accent: Color::Cyan,
+            on_accent: Color::Black,
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            critical: Color::LightRed,
+            info: Color::Blue,
+            special: Color::Magenta,
+            key_bg: Color::Rgb(60, 60, 60),
+            selected: Color::Rgb(255, 200, 50),
+            related: Color::Rgb(180, 160, 100),
+            selection_bg: Color::Rgb(30, 30, 50),
+            error_bg: Color::Rgb(50, 30, 30),
+            columns: [Color::Red, Color::Yellow, Color::Blue, Color::Magenta],
+            monochrome: false,
+            labels: HashMap::new(),
+        }
+    }
+
+    /// For terminals with a light background.
+    pub fn light() -> Theme {
+        let amber = Color::Rgb(175, 110, 0);
+        Theme {
+            text: Color::Black,
+            muted: Color::Rgb(70, 70, 70),
+            dim: Color::Rgb(130, 130, 130),
+            accent: Color::Rgb(0, 110, 160),
+            on_accent: Color::White,
+            success: Color::Rgb(0, 130, 60),
+            warning: amber,
+            error: Color::Rgb(190, 30, 30),
+            critical: Color::Rgb(220, 60, 60),
+            info: Color::Rgb(40, 80, 200),
+            special: Color::Rgb(150, 50, 160),
+            key_bg: Color::Rgb(215, 215, 215),
+            selected: Color::Rgb(220, 120, 0),
+            related: Color::Rgb(160, 140, 90),
+            selection_bg: Color::Rgb(220, 225, 245),
+            error_bg: Color::Rgb(250, 220, 220),
+            columns: [
+                Color::Rgb(190, 30, 30),
+                amber,
+                Color::Rgb(40, 80, 200),
+                Color::Rgb(150, 50, 160),
+            ],
+            monochrome: false,
+            labels: HashMap::new(),
+        }
+    }
+
+    /// Bright, saturated colours on a dark background.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            text: Color::White,
+            muted: Color::White,
+            dim: Color::Gray,
+            accent: Color::LightCyan,
+            on_accent: Color::Black,
+            success: Color::LightGreen,
+            warning: Color::LightYellow,
+            error: Color::LightRed,
+            critical: Color::LightRed,
+            info: Color::LightBlue,
+            special: Color::LightMagenta,
+            key_bg: Color::Blue,
+            selected: Color::LightYellow,
+            related: Color::Yellow,
+            selection_bg: Color::Blue,
+            error_bg: Color::Red,
+            columns: [
+                Color::LightRed,
+                Color::LightYellow,
+                Color::LightBlue,
+                Color::LightMagenta,
+            ],
+            monochrome: false,
+            labels: HashMap::new(),
+        }
+    }
+
+    /// No colours at all: everything in the terminal's own colours, with
+    /// tags and active titles reversed so they still stand out.
+    pub fn monochrome() -> Theme {
+        Theme {
+            text: Color::Reset,
+            muted: Color::Reset,
+            dim: Color::Reset,
+            accent: Color::Reset,
+            on_accent: Color::Reset,
+            success: Color::Reset,
+            warning: Color::Reset,
+            error: Color::Reset,
+            critical: Color::Reset,
+            info: Color::Reset,
+            special: Color::Reset,
+            key_bg: Color::Reset,
+            selected: Color::Reset,
+            related: Color::Reset,
+            selection_bg: Color::Reset,
+            error_bg: Color::Reset,
+            columns: [Color::Reset; 4],
+            monochrome: true,
+            labels: HashMap::new(),
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "monochrome" => Some(Theme::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// The theme named in the config file, logging what was wrong with it.
+    pub fn load() -> Theme {
+        let config = crate::config::load_config().unwrap_or_default();
+        let no_color = std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty());
+        let (theme, problems) = Theme::from_config(
+            config.theme.as_deref(),
+            &config.themes,
+            &config.label_colors,
+            no_color,
+        );
+        for problem in problems {
+            crate::log::warn("theme", problem);
+        }
+        theme
+    }
+
+    /// Theme `name` — a preset, or one of the custom `themes` (slot name →
+    /// colour, plus an optional `base` preset they start from) — with
+    /// `label_colors` applied, and what was wrong with the config.
+    /// `no_color` overrides all of it with [`Theme::monochrome`].
+    pub fn from_config(
+        name: Option<&str>,
+        themes: &HashMap<String, HashMap<String, String>>,
+        label_colors: &HashMap<String, String>,
+        no_color: bool,
+    ) -> (Theme, Vec<String>) {
+        let mut problems = Vec::new();
+        if no_color {
+            return (Theme::monochrome(), problems);
+        }
+        let name = name.unwrap_or("dark");
+        let mut theme = match themes.get(name) {
+            Some(slots) => {
+                let base = slots.get("base").map(String::as_str).unwrap_or("dark");
+                let mut theme = Theme::preset(base).unwrap_or_else(|| {
+                    problems.push(format!("Unknown base theme '{}' for {}", base, name));
+                    Theme::dark()
+                });
+                let mut slot_names: Vec<&String> = slots.keys().filter(|s| *s != "base").collect();
+                slot_names.sort();
+                for slot in slot_names {
+                    match parse_color(&slots[slot]) {
+                        Some(color) if theme.set(slot, color) => {}
+                        Some(_) => problems.push(format!("Unknown colour slot '{}'", slot)),
+                        None => {
+                            problems.push(format!("Unknown colour '{}' for {}", slots[slot], slot))
+                        }
+                    }
+                }
+                theme
+            }
+            None => Theme::preset(name).unwrap_or_else(|| {
+                problems.push(format!(
+                    "Unknown theme '{}' (presets: {})",
+                    name,
+                    PRESETS.join(", ")
+                ));
+                Theme::dark()
+            }),
+        };
+        if !theme.monochrome {
+            for (label, spec) in label_colors {
+                match parse_color(spec) {
+                    Some(color) => {
+                        theme.labels.insert(label.to_lowercase(), color);
+                    }
+                    None => problems.push(format!("Unknown colour '{}' for label {}", spec, label)),
+                }
+            }
+        }
+        (theme, problems)
+    }
+
+    /// Set slot `name` (a field name, or a column name like `issues`);
+    /// false if there is no such slot.
+    fn set(&mut self, name: &str, color: Color) -> bool {
+        let slot = match name {
+            "text" => &mut self.text,
+            "muted" => &mut self.muted,
+            "dim" => &mut self.dim,
+            "accent" => &mut self.accent,
+            "on_accent" => &mut self.on_accent,
+            "success" => &mut self.success,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "critical" => &mut self.critical,
+            "info" => &mut self.info,
+            "special" => &mut self.special,
+            "key_bg" => &mut self.key_bg,
+            "selected" => &mut self.selected,
+            "related" => &mut self.related,
+            "selection_bg" => &mut self.selection_bg,
+            "error_bg" => &mut self.error_bg,
+            "issues" => &mut self.columns[0],
+            "worktrees" => &mut self.columns[1],
+            "sessions" => &mut self.columns[2],
+            "pull_requests" => &mut self.columns[3],
+            _ => return false,
+        };
+        *slot = color;
+        true
+    }
+
+    /// Text on a `bg` background, for tags and active titles.
+    pub fn badge(&self, bg: Color) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(self.on_accent).bg(bg)
+        }
+    }
+
+    /// The colour for an issue label: the configured one if there is one,
+    /// otherwise by what the name suggests.
+    pub fn label_color(&self, name: &str) -> Color {
+        let name = name.to_lowercase();
+        if let Some(color) = self.labels.get(&name) {
+            return *color;
+        }
+        match name.as_str() {
+            s if s.contains("bug") => self.error,
+            s if s.contains("feature") || s.contains("enhancement") => self.success,
+            s if s.contains("documentation") || s.contains("docs") => self.info,
+            s if s.contains("good first issue") || s.contains("help wanted") => self.accent,
+            s if s.contains("duplicate") || s.contains("wontfix") || s.contains("invalid") => {
+                self.muted
+            }
+            s if s.contains("priority") || s.contains("critical") || s.contains("urgent") => {
+                self.critical
+            }
+            _ => self.warning,
+        }
+    }
+}
+
+/// A colour name (`red`, `light-blue`, `dark-gray`), `#rrggbb` or an ANSI
+/// index (`208`).
+fn parse_color(spec: &str) -> Option<Color> {
+    Color::from_str(spec.trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn custom_themes_start_from_a_preset() {
+        let themes = HashMap::from([(
+            "mine".to_string(),
+            slots(&[
+                ("base", "light"),
+                ("accent", "#ff8800"),
+                ("issues", "208"),
+                ("nope", "red"),
+            ]),
+        )]);
+        let labels = slots(&[("Needs Design", "light-magenta"), ("bug", "purple")]);
+        let (theme, problems) = Theme::from_config(Some("mine"), &themes, &labels, false);
+
+        assert_eq!(theme.accent, Color::Rgb(255, 136, 0));
+        assert_eq!(theme.columns[0], Color::Indexed(208));
+        assert_eq!(theme.text, Theme::light().text);
+        assert_eq!(theme.label_color("needs design"), Color::LightMagenta);
+        assert_eq!(theme.label_color("bug"), theme.error);
+        assert_eq!(theme.label_color("docs"), theme.info);
+        assert_eq!(
+            problems,
+            [
+                "Unknown colour slot 'nope'",
+                "Unknown colour 'purple' for label bug"
+            ]
+        );
+
+        let (theme, problems) = Theme::from_config(Some("solarized"), &themes, &labels, false);
+        assert_eq!(theme.accent, Theme::dark().accent);
+        assert_eq!(problems.len(), 2);
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("Unknown theme 'solarized'")));
+    }
+
+    #[test]
+    fn no_color_wins_over_the_config() {
+        let labels = slots(&[("bug", "red")]);
+        let (theme, problems) = Theme::from_config(Some("light"), &HashMap::new(), &labels, true);
+        assert!(problems.is_empty());
+        assert_eq!(theme, Theme::monochrome());
+        assert_eq!(theme.label_color("bug"), Color::Reset);
+        assert_eq!(
+            theme.badge(theme.accent),
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+    }
+}
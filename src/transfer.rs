@@ -0,0 +1,336 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::local::{self, ConflictPolicy, ImportReport, LocalIssue, LocalPr, LocalStore};
+
+/// File formats the local store can be exported to or imported from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    /// The `LocalStore` layout, as written by `octopai export`.
+    Json,
+    /// One row per issue or PR, distinguished by the `kind` column.
+    Csv,
+    /// Output of `gh issue list --json number,title,body,state,labels`.
+    Github,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "github" => Some(Format::Github),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from a file extension, defaulting to JSON.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Format::Csv,
+            _ => Format::Json,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CsvRow {
+    kind: String,
+    number: u64,
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    state: String,
+    /// Semicolon-separated label names (issues only).
+    #[serde(default)]
+    labels: String,
+    /// Head branch (PRs only).
+    #[serde(default)]
+    branch: String,
+    #[serde(default)]
+    is_draft: bool,
+    /// Base branch (PRs only); empty means the repo default.
+    #[serde(default)]
+    base: String,
+}
+
+#[derive(Deserialize)]
+struct GithubIssue {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    labels: Vec<GithubLabel>,
+}
+
+#[derive(Deserialize)]
+struct GithubLabel {
+    name: String,
+}
+
+/// Write the local store for `repo` to `path`. Returns a summary line.
+pub fn export_to_file(repo: &str, path: &str, format: Format) -> Result<String, String> {
+    let store = local::export_store(repo).map_err(|e| e.to_string())?;
+    let data = match format {
+        Format::Json => serde_json::to_string_pretty(&store)
+            .map_err(|e| format!("Failed to serialize store: {}", e))?,
+        Format::Csv => to_csv(&store)?,
+        Format::Github => return Err("Cannot export in GitHub format".to_string()),
+    };
+    fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(format!(
+        "Exported {} issue(s) and {} PR(s) to {}",
+        store.issues.len(),
+        store.prs.len(),
+        path
+    ))
+}
+
+/// Read `path` and merge it into the local store for `repo`.
+pub fn import_from_file(
+    repo: &str,
+    path: &str,
+    format: Format,
+    policy: ConflictPolicy,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let store = match format {
+        Format::Csv => from_csv(&data)?,
+        // A top-level array can only be a GitHub issues export
+        Format::Json if data.trim_start().starts_with('[') => from_github(&data)?,
+        Format::Json => {
+            serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", path, e))?
+        }
+        Format::Github => from_github(&data)?,
+    };
+    local::import_store(repo, &store, policy, dry_run).map_err(|e| e.to_string())
+}
+
+fn to_csv(store: &LocalStore) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let issues = store.issues.iter().map(|issue| CsvRow {
+        kind: "issue".to_string(),
+        number: issue.number,
+        title: issue.title.clone(),
+        body: issue.body.clone(),
+        state: issue.state.clone(),
+        labels: issue.labels.join(";"),
+        branch: String::new(),
+        is_draft: false,
+        base: String::new(),
+    });
+    let prs = store.prs.iter().map(|pr| CsvRow {
+        kind: "pr".to_string(),
+        number: pr.number,
+        title: pr.title.clone(),
+        body: pr.body.clone(),
+        state: pr.state.clone(),
+        labels: String::new(),
+        branch: pr.branch.clone(),
+        is_draft: pr.is_draft,
+        base: pr.base.clone().unwrap_or_default(),
+    });
+    for row in issues.chain(prs) {
+        writer
+            .serialize(row)
+            .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Failed to write CSV: {}", e))
+}
+
+fn from_csv(data: &str) -> Result<LocalStore, String> {
+    let mut store = LocalStore::default();
+    let mut reader = csv::Reader::from_reader(data.as_bytes());
+    for (i, row) in reader.deserialize::<CsvRow>().enumerate() {
+        // Row 1 is the header
+        let row = row.map_err(|e| format!("CSV row {}: {}", i + 2, e))?;
+        let state = local::normalize_state(&row.state, row.kind == "pr")
+            .ok_or_else(|| format!("CSV row {}: unknown state '{}'", i + 2, row.state))?
+            .to_string();
+        match row.kind.as_str() {
+            "issue" => store.issues.push(LocalIssue {
+                number: row.number,
+                title: row.title,
+                body: row.body,
+                state,
+                labels: row
+                    .labels
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(String::from)
+                    .collect(),
+            }),
+            "pr" => store.prs.push(LocalPr {
+                number: row.number,
+                title: row.title,
+                body: row.body,
+                branch: row.branch,
+                state,
+                is_draft: row.is_draft,
+                base: Some(row.base).filter(|b| !b.is_empty()),
+            }),
+            other => return Err(format!("CSV row {}: unknown kind '{}'", i + 2, other)),
+        }
+    }
+    Ok(store)
+}
+
+fn from_github(data: &str) -> Result<LocalStore, String> {
+    let issues: Vec<GithubIssue> = serde_json::from_str(data)
+        .map_err(|e| format!("Failed to parse GitHub issues export: {}", e))?;
+    Ok(LocalStore {
+        issues: issues
+            .into_iter()
+            .map(|issue| LocalIssue {
+                number: issue.number,
+                title: issue.title,
+                body: issue.body,
+                state: if issue.state.eq_ignore_ascii_case("closed") {
+                    "closed".to_string()
+                } else {
+                    "open".to_string()
+                },
+                labels: issue.labels.into_iter().map(|l| l.name).collect(),
+            })
+            .collect(),
+        ..LocalStore::default()
+    })
+}
+
+const USAGE: &str = "usage:
+  octopai export <file> [--format json|csv] [--repo owner/name]
+  octopai import <file> [--format json|csv|github] [--on-conflict renumber|skip|overwrite]
+                        [--dry-run] [--repo owner/name]";
+
+/// Handle the `export` and `import` subcommands. Returns `None` when `args`
+/// is not one of them, so the TUI should start as usual.
+pub fn run_cli(args: &[String]) -> Option<Result<String, String>> {
+    let command = args.get(1)?.as_str();
+    if command != "export" && command != "import" {
+        return None;
+    }
+    Some(run_command(command, &args[2..]))
+}
+
+fn run_command(command: &str, args: &[String]) -> Result<String, String> {
+    let mut path = None;
+    let mut format = None;
+    let mut policy = ConflictPolicy::Renumber;
+    let mut dry_run = false;
+    let mut repo = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                let value = iter.next().ok_or(USAGE)?;
+                format = Some(
+                    Format::parse(value).ok_or_else(|| format!("Unknown format '{}'", value))?,
+                );
+            }
+            "--on-conflict" => {
+                let value = iter.next().ok_or(USAGE)?;
+                policy = ConflictPolicy::parse(value)
+                    .ok_or_else(|| format!("Unknown conflict policy '{}'", value))?;
+            }
+            "--dry-run" => dry_run = true,
+            "--repo" => repo = Some(iter.next().ok_or(USAGE)?.clone()),
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg.clone()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let path = path.ok_or(USAGE)?;
+    let format = format.unwrap_or_else(|| Format::from_path(&path));
+    let repo = repo
+        .or_else(crate::git::detect_repo_from_git)
+        .or_else(|| crate::config::load_config().map(|c| c.repo))
+        .filter(|r| !r.is_empty())
+        .ok_or("Could not determine repository; pass --repo owner/name")?;
+
+    if command == "export" {
+        export_to_file(&repo, &path, format)
+    } else {
+        let report = import_from_file(&repo, &path, format, policy, dry_run)?;
+        Ok(format!(
+            "{}{}: {}",
+            if dry_run {
+                "Dry run — would import "
+            } else {
+                "Imported "
+            },
+            path,
+            report.describe()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_round_trips_issues_and_prs() {
+        let store = LocalStore {
+            issues: vec![LocalIssue {
+                number: 3,
+                title: "Fix, with a comma".to_string(),
+                body: "line one\nline two".to_string(),
+                state: "open".to_string(),
+                labels: vec!["bug".to_string(), "ui".to_string()],
+            }],
+            prs: vec![LocalPr {
+                number: 1,
+                title: "Fix it".to_string(),
+                body: String::new(),
+                branch: "local-issue-3".to_string(),
+                state: "open".to_string(),
+                is_draft: true,
+                base: Some("release/1.2".to_string()),
+            }],
+            ..LocalStore::default()
+        };
+        let parsed = from_csv(&to_csv(&store).unwrap()).unwrap();
+        assert_eq!(parsed.issues[0].title, "Fix, with a comma");
+        assert_eq!(parsed.issues[0].body, "line one\nline two");
+        assert_eq!(parsed.issues[0].labels, vec!["bug", "ui"]);
+        assert_eq!(parsed.prs[0].branch, "local-issue-3");
+        assert!(parsed.prs[0].is_draft);
+        assert_eq!(parsed.prs[0].base.as_deref(), Some("release/1.2"));
+    }
+
+    #[test]
+    fn csv_states_are_normalized_or_rejected() {
+        let csv = "kind,number,title,state\nissue,1,a,Closed\npr,1,b,MERGED\nissue,2,c,\n";
+        let store = from_csv(csv).unwrap();
+        assert_eq!(store.issues[0].state, "closed");
+        assert_eq!(store.prs[0].state, "merged");
+        assert_eq!(store.issues[1].state, "open");
+
+        let rejected = from_csv("kind,number,title,state\nissue,1,a,open\npr,2,b,done\n");
+        assert_eq!(
+            rejected.err().as_deref(),
+            Some("CSV row 3: unknown state 'done'")
+        );
+    }
+
+    #[test]
+    fn github_export_states_are_normalized() {
+        let data =
+            r#"[{"number":7,"title":"t","body":"","state":"CLOSED","labels":[{"name":"bug"}]}]"#;
+        let store = from_github(data).unwrap();
+        assert_eq!(store.issues[0].state, "closed");
+        assert_eq!(store.issues[0].labels, vec!["bug"]);
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::ops::RangeInclusive;
 use std::time::Duration;
 
 use ratatui::{
@@ -10,16 +11,23 @@ use ratatui::{
 };
 
 use crate::app::App;
+use crate::arrange::{GroupMode, Row, SortMode};
 use crate::config::config_path;
 use crate::deps::Dependency;
+use crate::markdown;
 use crate::models::{
-    card_matches, AiSetupState, Card, ConfirmModal, DepInstallConfirm, EditIssueModal, IssueModal,
-    Mode, RepoSelectPhase, RepoSelectState, StateFilter, TextInput,
+    AiSetupState, Card, CheckStatus, ChoiceField, ConfirmModal, DepInstallConfirm, EditIssueModal,
+    IssueModal, Mode, RepoSelectPhase, RepoSelectState, StateFilter, TextInput, WorktreeStatus,
+    SECTION_NAMES,
 };
+use crate::query::Query;
 use crate::session::{
-    default_editor_command, COMMAND_SHORTCUTS, DEFAULT_CLAUDE_COMMAND, DEFAULT_EDITOR_COMMAND,
-    EDITOR_TEMPLATE_FIELDS, SESSION_SHORTCUTS, TEMPLATE_FIELDS,
+    default_editor_command, COMMAND_SHORTCUTS, DEFAULT_BRANCH_TEMPLATE, DEFAULT_CLAUDE_COMMAND,
+    DEFAULT_EDITOR_COMMAND, DEFAULT_WORKTREE_DIR_TEMPLATE, EDITOR_TEMPLATE_FIELDS,
+    SESSION_SHORTCUTS, TEMPLATE_FIELDS, WORKTREE_TEMPLATE_FIELDS,
 };
+use crate::textarea::TextArea;
+use crate::theme::current as theme;
 
 /// Build spans for a TextInput showing the cursor at the correct position.
 /// Returns spans: [before_cursor (styled), cursor_char (cursor_style), after_cursor (styled)]
@@ -49,6 +57,85 @@ fn text_input_spans(
     spans
 }
 
+/// Draw a TextArea in `block`, wrapped at the field's width and scrolled so
+/// the cursor stays in view. Wrapping is done here rather than by the
+/// Paragraph so the cursor's row is known.
+fn render_text_area(
+    frame: &mut Frame,
+    area: Rect,
+    input: &TextArea,
+    block: Block,
+    text_style: Style,
+    show_cursor: bool,
+) {
+    let inner = block.inner(area);
+    let width = usize::from(inner.width.max(1));
+    let cursor_style = theme().badge(theme().accent);
+    let selected_style = theme().badge(theme().dim);
+    let selection = input.selection().filter(|_| show_cursor);
+
+    let mut rows: Vec<Line> = Vec::new();
+    let mut cursor_row = 0;
+    let mut pos = 0;
+    for line in input.value().split('\n') {
+        let mut cells: Vec<(char, Style)> = Vec::new();
+        for c in line.chars() {
+            let style = if show_cursor && pos == input.cursor() {
+                cursor_style
+            } else if selection.is_some_and(|(start, end)| (start..end).contains(&pos)) {
+                selected_style
+            } else {
+                text_style
+            };
+            cells.push((c, style));
+            pos += 1;
+        }
+        if show_cursor && pos == input.cursor() {
+            cells.push(('_', cursor_style));
+        }
+        let line_start = pos - line.chars().count();
+        pos += 1; // the newline
+
+        // Break at the last space that fits, or mid-word if none does.
+        let mut breaks = Vec::new();
+        let mut start = 0;
+        while cells.len() - start > width {
+            let end = cells[start..start + width]
+                .iter()
+                .rposition(|(c, _)| *c == ' ')
+                .map_or(start + width, |i| start + i + 1);
+            breaks.push(start..end);
+            start = end;
+        }
+        breaks.push(start..cells.len());
+
+        if show_cursor && (line_start..pos).contains(&input.cursor()) {
+            let column = input.cursor() - line_start;
+            let row = breaks
+                .iter()
+                .position(|range| column < range.end)
+                .unwrap_or(breaks.len() - 1);
+            cursor_row = rows.len() + row;
+        }
+        for range in breaks {
+            let mut spans: Vec<Span> = Vec::new();
+            for &(c, style) in &cells[range] {
+                match spans.last_mut() {
+                    Some(span) if span.style == style => span.content.to_mut().push(c),
+                    _ => spans.push(Span::styled(c.to_string(), style)),
+                }
+            }
+            rows.push(Line::from(spans));
+        }
+    }
+
+    let scroll = cursor_row.saturating_sub(usize::from(inner.height.max(1)) - 1);
+    let paragraph = Paragraph::new(Text::from(rows))
+        .block(block)
+        .scroll((scroll as u16, 0));
+    frame.render_widget(paragraph, area);
+}
+
 pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bool) {
     let area = frame.area();
 
@@ -95,7 +182,7 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             let title = Paragraph::new(Line::from(vec![Span::styled(
                 prompt_text,
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(theme().accent)
                     .add_modifier(Modifier::BOLD),
             )]))
             .block(Block::default());
@@ -104,12 +191,12 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             // Input field
             let input_block = Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::White))
+                .border_style(Style::default().fg(theme().text))
                 .title(" Owner ");
             let text_style = Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD);
-            let cursor_style = Style::default().fg(Color::Black).bg(Color::Cyan);
+            let cursor_style = theme().badge(theme().accent);
             let input_text = Paragraph::new(Line::from(text_input_spans(
                 &state.input,
                 text_style,
@@ -123,7 +210,7 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             if let Some(err) = &state.error {
                 let err_text = Paragraph::new(Line::from(vec![Span::styled(
                     err.as_str(),
-                    Style::default().fg(Color::Red),
+                    Style::default().fg(theme().error),
                 )]));
                 frame.render_widget(err_text, chunks[2]);
             }
@@ -131,7 +218,7 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             // Hint
             let hint = Paragraph::new(Line::from(vec![Span::styled(
                 "Press Enter to fetch repos, Esc to go back",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme().dim),
             )]));
             frame.render_widget(hint, chunks[3]);
         }
@@ -139,7 +226,7 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             let loading = Paragraph::new(Line::from(vec![Span::styled(
                 "Fetching repositories...",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme().warning)
                     .add_modifier(Modifier::BOLD),
             )]))
             .block(Block::default());
@@ -169,12 +256,12 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
                 Span::styled(
                     "Select a repository",
                     Style::default()
-                        .fg(Color::Cyan)
+                        .fg(theme().accent)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
                     format!("  ({} repos)", state.filtered_repos.len()),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme().dim),
                 ),
             ]));
             frame.render_widget(title, chunks[0]);
@@ -183,14 +270,14 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             let filter_line = if state.filter_query.is_empty() {
                 Paragraph::new(Line::from(vec![Span::styled(
                     "Type to filter...",
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme().dim),
                 )]))
             } else {
-                let mut spans = vec![Span::styled("/ ", Style::default().fg(Color::Cyan))];
+                let mut spans = vec![Span::styled("/ ", Style::default().fg(theme().accent))];
                 let text_style = Style::default()
-                    .fg(Color::White)
+                    .fg(theme().text)
                     .add_modifier(Modifier::BOLD);
-                let cursor_style = Style::default().fg(Color::Black).bg(Color::Cyan);
+                let cursor_style = theme().badge(theme().accent);
                 spans.extend(text_input_spans(
                     &state.filter_query,
                     text_style,
@@ -204,7 +291,7 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             // Separator
             let sep = Paragraph::new(Line::from(vec![Span::styled(
                 "─".repeat(center.width as usize),
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme().dim),
             )]));
             frame.render_widget(sep, chunks[2]);
 
@@ -225,18 +312,18 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
                 let repo_name = &state.filtered_repos[repo_idx];
                 let line = if is_selected {
                     Line::from(vec![
-                        Span::styled(" > ", Style::default().fg(Color::Cyan)),
+                        Span::styled(" > ", Style::default().fg(theme().accent)),
                         Span::styled(
                             repo_name.as_str(),
                             Style::default()
-                                .fg(Color::White)
+                                .fg(theme().text)
                                 .add_modifier(Modifier::BOLD),
                         ),
                     ])
                 } else {
                     Line::from(vec![
                         Span::styled("   ", Style::default()),
-                        Span::styled(repo_name.as_str(), Style::default().fg(Color::Gray)),
+                        Span::styled(repo_name.as_str(), Style::default().fg(theme().muted)),
                     ])
                 };
                 frame.render_widget(Paragraph::new(line), chunks[3 + i]);
@@ -247,7 +334,7 @@ pub fn ui_repo_select(frame: &mut Frame, state: &RepoSelectState, local_mode: bo
             if hint_idx < chunks.len() {
                 let hint = Paragraph::new(Line::from(vec![Span::styled(
                     "j/k ↑/↓ navigate  Enter select  Esc back",
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme().dim),
                 )]));
                 frame.render_widget(hint, chunks[hint_idx]);
             }
@@ -275,12 +362,12 @@ pub fn ui_dependencies(
     // Title bar
     let title_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan))
+        .border_style(Style::default().fg(theme().accent))
         .title(" Dependencies ");
     let title_text = Paragraph::new(Line::from(vec![Span::styled(
         "  External Dependency Status",
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD),
     )]))
     .block(title_block);
@@ -289,7 +376,7 @@ pub fn ui_dependencies(
     // Dependency list
     let content_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray))
+        .border_style(Style::default().fg(theme().dim))
         .padding(Padding::new(2, 2, 1, 0));
     let inner = content_block.inner(vertical[1]);
     frame.render_widget(content_block, vertical[1]);
@@ -315,7 +402,7 @@ pub fn ui_dependencies(
             "Command", "Status", "Description", "Version"
         ),
         Style::default()
-            .fg(Color::Cyan)
+            .fg(theme().accent)
             .add_modifier(Modifier::BOLD),
     )]));
     frame.render_widget(header, rows[0]);
@@ -324,28 +411,28 @@ pub fn ui_dependencies(
     for (i, dep) in deps.iter().enumerate() {
         let is_selected = i == selected;
         let (status_text, status_color) = if dep.available {
-            ("OK", Color::Green)
+            ("OK", theme().success)
         } else if dep.required {
-            ("MISSING", Color::Red)
+            ("MISSING", theme().error)
         } else if dep.recommended {
-            ("RECOMMENDED", Color::Yellow)
+            ("RECOMMENDED", theme().warning)
         } else {
-            ("MISSING", Color::Yellow)
+            ("MISSING", theme().warning)
         };
 
         let version = dep.version.as_deref().unwrap_or("-");
 
         let pointer = if is_selected { "> " } else { "  " };
         let pointer_color = if is_selected {
-            Color::Cyan
+            theme().accent
         } else {
             Color::Reset
         };
 
         let row_bg = if is_selected && !dep.available {
-            Color::Rgb(50, 30, 30)
+            theme().error_bg
         } else if is_selected {
-            Color::Rgb(30, 30, 50)
+            theme().selection_bg
         } else {
             Color::Reset
         };
@@ -361,7 +448,7 @@ pub fn ui_dependencies(
             Span::styled(
                 format!("{:<14} ", dep.name),
                 Style::default()
-                    .fg(Color::White)
+                    .fg(theme().text)
                     .bg(row_bg)
                     .add_modifier(Modifier::BOLD),
             ),
@@ -374,9 +461,9 @@ pub fn ui_dependencies(
             ),
             Span::styled(
                 format!("{:<46} ", dep.description),
-                Style::default().fg(Color::Gray).bg(row_bg),
+                Style::default().fg(theme().muted).bg(row_bg),
             ),
-            Span::styled(version, Style::default().fg(Color::DarkGray).bg(row_bg)),
+            Span::styled(version, Style::default().fg(theme().dim).bg(row_bg)),
         ]);
         frame.render_widget(Paragraph::new(line), rows[1 + i]);
     }
@@ -391,14 +478,11 @@ pub fn ui_dependencies(
         .split(vertical[2]);
 
     let key_style = Style::default()
-        .fg(Color::White)
-        .bg(Color::Rgb(60, 60, 60))
-        .add_modifier(Modifier::BOLD);
-    let desc_style = Style::default().fg(Color::Gray);
-    let key_accent = Style::default()
-        .fg(Color::Black)
-        .bg(Color::Green)
+        .fg(theme().text)
+        .bg(theme().key_bg)
         .add_modifier(Modifier::BOLD);
+    let desc_style = Style::default().fg(theme().muted);
+    let key_accent = theme().badge(theme().success).add_modifier(Modifier::BOLD);
 
     let mut hints: Vec<Span> = vec![
         Span::styled(" j/k ", key_style),
@@ -429,14 +513,16 @@ pub fn ui_dependencies(
     if has_missing {
         let warning = Paragraph::new(Line::from(vec![Span::styled(
             " Install missing required dependencies to continue",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            Style::default()
+                .fg(theme().error)
+                .add_modifier(Modifier::BOLD),
         )]));
         frame.render_widget(warning, bottom_rows[1]);
     } else if has_recommended {
         let note = Paragraph::new(Line::from(vec![Span::styled(
             " Install gh for full GitHub integration — local mode is active without it",
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme().warning)
                 .add_modifier(Modifier::BOLD),
         )]));
         frame.render_widget(note, bottom_rows[1]);
@@ -449,14 +535,9 @@ pub fn ui_dependencies(
 
         let outer_block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
+            .border_style(Style::default().fg(theme().success))
             .title(" Install Dependency ")
-            .title_style(
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Green)
-                    .add_modifier(Modifier::BOLD),
-            )
+            .title_style(theme().badge(theme().success).add_modifier(Modifier::BOLD))
             .padding(Padding::new(1, 1, 1, 0));
         let inner = outer_block.inner(modal_area);
         frame.render_widget(outer_block, modal_area);
@@ -475,17 +556,17 @@ pub fn ui_dependencies(
         let label = Paragraph::new(Line::from(vec![Span::styled(
             format!("Install {}?", confirm.install_target),
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD),
         )]));
         frame.render_widget(label, chunks[0]);
 
         let cmd = Paragraph::new(Line::from(vec![
-            Span::styled("$ ", Style::default().fg(Color::DarkGray)),
+            Span::styled("$ ", Style::default().fg(theme().dim)),
             Span::styled(
                 &confirm.command,
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(theme().accent)
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
@@ -495,15 +576,17 @@ pub fn ui_dependencies(
             Span::styled(
                 "y",
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(theme().success)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(" run  ", Style::default().fg(Color::DarkGray)),
+            Span::styled(" run  ", Style::default().fg(theme().dim)),
             Span::styled(
                 "n",
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                Style::default()
+                    .fg(theme().error)
+                    .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(" cancel", Style::default().fg(Color::DarkGray)),
+            Span::styled(" cancel", Style::default().fg(theme().dim)),
         ]));
         frame.render_widget(hint, chunks[4]);
     }
@@ -549,14 +632,14 @@ pub fn ui_ai_setup(frame: &mut Frame, state: &AiSetupState) {
     let title = Paragraph::new(Line::from(vec![Span::styled(
         "Select AI Coding Assistant",
         Style::default()
-            .fg(Color::Cyan)
+            .fg(theme().accent)
             .add_modifier(Modifier::BOLD),
     )]));
     frame.render_widget(title, chunks[0]);
 
     let desc = Paragraph::new(Line::from(vec![Span::styled(
         "Both Claude and Cursor are installed. Choose your default:",
-        Style::default().fg(Color::Gray),
+        Style::default().fg(theme().muted),
     )]));
     frame.render_widget(desc, chunks[1]);
 
@@ -572,22 +655,22 @@ pub fn ui_ai_setup(frame: &mut Frame, state: &AiSetupState) {
                 Span::styled(
                     " > ",
                     Style::default()
-                        .fg(Color::Cyan)
+                        .fg(theme().accent)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
                     *label,
                     Style::default()
-                        .fg(Color::White)
+                        .fg(theme().text)
                         .add_modifier(Modifier::BOLD),
                 ),
-                Span::styled(format!("  {}", desc), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("  {}", desc), Style::default().fg(theme().dim)),
             ])
         } else {
             Line::from(vec![
                 Span::styled("   ", Style::default()),
-                Span::styled(*label, Style::default().fg(Color::Gray)),
-                Span::styled(format!("  {}", desc), Style::default().fg(Color::DarkGray)),
+                Span::styled(*label, Style::default().fg(theme().muted)),
+                Span::styled(format!("  {}", desc), Style::default().fg(theme().dim)),
             ])
         };
         frame.render_widget(Paragraph::new(line), chunks[3 + i]);
@@ -595,12 +678,22 @@ pub fn ui_ai_setup(frame: &mut Frame, state: &AiSetupState) {
 
     let hint = Paragraph::new(Line::from(vec![Span::styled(
         "j/k ↑/↓ navigate  Enter select",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme().dim),
     )]));
     frame.render_widget(hint, chunks[6]);
 }
 
-pub fn ui(frame: &mut Frame, app: &App) {
+/// Where the board's parts go on screen. Mouse clicks are resolved against
+/// the same rects, so they always match what is drawn.
+pub struct BoardLayout {
+    pub header: Rect,
+    pub columns: [Rect; 4],
+    /// Empty while the message center is hidden.
+    pub messages: Rect,
+    pub legend: Rect,
+}
+
+pub fn board_layout(area: Rect, app: &App) -> BoardLayout {
     let msg_height = if app.show_messages {
         if app.messages_expanded {
             10
@@ -619,68 +712,90 @@ pub fn ui(frame: &mut Frame, app: &App) {
             Constraint::Length(msg_height),
             Constraint::Length(2),
         ])
-        .split(frame.area());
+        .split(area);
+
+    // Four columns
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+        ])
+        .split(outer[1]);
+
+    BoardLayout {
+        header: outer[0],
+        columns: [columns[0], columns[1], columns[2], columns[3]],
+        messages: outer[2],
+        legend: outer[3],
+    }
+}
+
+pub fn ui(frame: &mut Frame, app: &App) {
+    let layout = board_layout(frame.area(), app);
 
     // Key styles (defined early so overlay spans can be built for the repo bar)
     let key_style = Style::default()
-        .fg(Color::White)
-        .bg(Color::Rgb(60, 60, 60))
-        .add_modifier(Modifier::BOLD);
-    let desc_style = Style::default().fg(Color::Gray);
-    let key_accent = Style::default()
-        .fg(Color::Black)
-        .bg(Color::Green)
+        .fg(theme().text)
+        .bg(theme().key_bg)
         .add_modifier(Modifier::BOLD);
+    let desc_style = Style::default().fg(theme().muted);
+    let key_accent = theme().badge(theme().success).add_modifier(Modifier::BOLD);
 
     // Build overlay spans (displayed in repo bar, top right)
+    let keymap = &app.keymap;
     let mut overlay_spans: Vec<Span> = Vec::new();
     if matches!(app.mode, Mode::Normal | Mode::Filtering { .. }) {
-        overlay_spans = vec![
-            Span::styled(" T ", key_style),
-            Span::styled(" Terminal ", desc_style),
-            Span::styled(" D ", key_style),
-            Span::styled(" Deps ", desc_style),
-            Span::styled(" C ", key_style),
-            Span::styled(" Config ", desc_style),
-            Span::styled(" L ", key_style),
-            Span::styled(
-                if app.local_mode {
-                    " GitHub "
-                } else {
-                    " Local "
-                },
-                desc_style,
-            ),
-            Span::styled(" x ", key_style),
-            Span::styled(
-                if app.show_messages {
-                    " Hide msgs "
-                } else {
-                    " Show msgs "
-                },
-                desc_style,
-            ),
-        ];
+        let hint = |spans: &mut Vec<Span>, name: &str, label: &'static str| {
+            push_hint(spans, keymap.keys(name), key_style, label, desc_style);
+        };
+        hint(&mut overlay_spans, "main_session", " Terminal ");
+        hint(&mut overlay_spans, "dependencies", " Deps ");
+        hint(&mut overlay_spans, "configure", " Config ");
+        hint(
+            &mut overlay_spans,
+            "toggle_local_mode",
+            if app.local_mode {
+                " GitHub "
+            } else {
+                " Local "
+            },
+        );
+        hint(
+            &mut overlay_spans,
+            "toggle_messages",
+            if app.show_messages {
+                " Hide msgs "
+            } else {
+                " Show msgs "
+            },
+        );
+        if app.local_mode {
+            hint(&mut overlay_spans, "export_store", " Export ");
+            hint(&mut overlay_spans, "import_store", " Import ");
+        }
         if app.show_messages {
-            overlay_spans.push(Span::styled(" X ", key_style));
-            overlay_spans.push(Span::styled(
+            hint(
+                &mut overlay_spans,
+                "expand_messages",
                 if app.messages_expanded {
                     " Collapse "
                 } else {
                     " Expand "
                 },
-                desc_style,
-            ));
+            );
         }
     }
 
     // Top bar — selected repository (bordered, with overlay keybinds on the right)
     let repo_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan))
+        .border_style(Style::default().fg(theme().accent))
         .title(" Repository ");
-    let repo_inner = repo_block.inner(outer[0]);
-    frame.render_widget(repo_block, outer[0]);
+    let repo_inner = repo_block.inner(layout.header);
+    frame.render_widget(repo_block, layout.header);
 
     // Split repo inner area: left = repo info, right = overlay keybinds
     let overlay_width: u16 = overlay_spans.iter().map(|s| s.content.len() as u16).sum();
@@ -694,7 +809,7 @@ pub fn ui(frame: &mut Frame, app: &App) {
         Span::styled(
             &app.repo,
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD),
         ),
     ];
@@ -702,10 +817,14 @@ pub fn ui(frame: &mut Frame, app: &App) {
         repo_spans.push(Span::raw("  "));
         repo_spans.push(Span::styled(
             "[LOCAL]",
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
+            theme().badge(theme().accent).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(view) = &app.active_view {
+        repo_spans.push(Span::raw("  "));
+        repo_spans.push(Span::styled(
+            format!(" {} ", view.name),
+            theme().badge(theme().special).add_modifier(Modifier::BOLD),
         ));
     }
     if app.main_behind_count > 0 {
@@ -716,17 +835,36 @@ pub fn ui(frame: &mut Frame, app: &App) {
                 if app.main_behind_count == 1 { "" } else { "s" }
             ),
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme().warning)
                 .add_modifier(Modifier::BOLD),
         ));
         repo_spans.push(Span::styled(
             " (p to pull)",
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme().dim),
         ));
     }
+    if let Some(rate) = app.rate_limit.filter(|_| !app.local_mode) {
+        let (text, color) = if rate.exhausted() {
+            (
+                format!(
+                    "  GitHub API limit reached, resumes in {}m",
+                    rate.minutes_to_reset()
+                ),
+                theme().error,
+            )
+        } else {
+            let color = if rate.remaining * 10 < rate.limit {
+                theme().warning
+            } else {
+                theme().dim
+            };
+            (format!("  API {}/{}", rate.remaining, rate.limit), color)
+        };
+        repo_spans.push(Span::styled(text, Style::default().fg(color)));
+    }
     repo_spans.push(Span::styled(
         "  (Enter to change)",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme().dim),
     ));
     let repo_text = Paragraph::new(Line::from(repo_spans));
     frame.render_widget(repo_text, repo_cols[0]);
@@ -738,33 +876,47 @@ pub fn ui(frame: &mut Frame, app: &App) {
         frame.render_widget(overlay_legend, repo_cols[1]);
     }
 
-    // Four columns
-    let columns = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-        ])
-        .split(outer[1]);
-
     let spinner_char = SPINNER_FRAMES[app.spinner_tick % SPINNER_FRAMES.len()];
 
+    // Cached GitHub data shown after a failed (or not yet finished) refresh
+    let stale_note = match app.board_fetched_at {
+        Some(fetched_at) if app.board_stale => format!(" stale {}", format_age(fetched_at)),
+        None if app.board_stale => " stale".to_string(),
+        _ => String::new(),
+    };
+    // Cards the active view shows; the `/` filter's count is on its own line
+    let count: [usize; 4] = std::array::from_fn(|section| {
+        let query = Query::parse(app.active_view.as_ref().map_or("", |v| v.query(section)));
+        let cards = app.section_cards(section);
+        cards.iter().filter(|c| query.matches(c)).count()
+    });
     let issue_title = if app.section_loading[0] {
         format!(" Issues {} ", spinner_char)
     } else if app.local_mode {
         format!(
             " Local Issues ({}) [{}] ",
-            app.issues.len(),
+            count[0],
             app.issue_state_filter.label(),
         )
     } else {
+        // Out of how many the search found, while there are more to load
+        let total = match app.issue_total {
+            Some(total) if app.issue_cursor.is_some() => format!("/{}", total),
+            _ => String::new(),
+        };
+        let more = if app.loading_more_issues {
+            format!(" {}", spinner_char)
+        } else {
+            String::new()
+        };
         format!(
-            " Issues ({}) [{}|{}] ",
-            app.issues.len(),
+            " Issues ({}{}{}) [{}|{}]{} ",
+            count[0],
+            total,
+            more,
             app.issue_state_filter.label(),
-            app.issue_assignee_filter.label()
+            app.issue_assignee_filter.label(),
+            stale_note
         )
     };
     let pr_title = if app.section_loading[3] {
@@ -772,33 +924,53 @@ pub fn ui(frame: &mut Frame, app: &App) {
     } else if app.local_mode {
         format!(
             " Local PRs ({}) [{}] ",
-            app.pull_requests.len(),
+            count[3],
             app.pr_state_filter.label(),
         )
     } else {
         format!(
-            " Pull Requests ({}) [{}|{}] ",
-            app.pull_requests.len(),
+            " Pull Requests ({}) [{}|{}]{} ",
+            count[3],
             app.pr_state_filter.label(),
-            app.pr_assignee_filter.label()
+            app.pr_assignee_filter.label(),
+            stale_note
         )
     };
     let worktree_title = if app.section_loading[1] {
         format!(" Worktrees {} ", spinner_char)
     } else {
-        format!(" Worktrees ({}) ", app.worktrees.len())
+        format!(" Worktrees ({}) ", count[1])
     };
     let session_title = if app.section_loading[2] {
         format!(" Sessions {} ", spinner_char)
     } else {
-        format!(" Sessions ({}) ", app.sessions.len())
+        format!(" Sessions ({}) ", count[2])
     };
-    let section_data: [(&str, Color, &[Card]); 4] = [
-        (&issue_title, Color::Red, &app.issues),
-        (&worktree_title, Color::Yellow, &app.worktrees),
-        (&session_title, Color::Blue, &app.sessions),
-        (&pr_title, Color::Magenta, &app.pull_requests),
-    ];
+    let [issue_color, worktree_color, session_color, pr_color] = theme().columns;
+    let titles = [issue_title, worktree_title, session_title, pr_title];
+    let colors = [issue_color, worktree_color, session_color, pr_color];
+    let section_data: [(String, Color); 4] = std::array::from_fn(|i| {
+        // How the column is sorted and grouped, unless it's as fetched, and
+        // how many of its cards are marked
+        let arrangement = app.arrangements[i];
+        let mut note = Vec::new();
+        if arrangement.sort != SortMode::Default {
+            note.push(arrangement.sort.label().to_string());
+        }
+        if arrangement.group != GroupMode::None {
+            note.push(format!("by {}", arrangement.group.label()));
+        }
+        let marked = app.marked_cards(i).len();
+        if marked > 0 {
+            note.push(format!("{} marked", marked));
+        }
+        if note.is_empty() {
+            (titles[i].clone(), colors[i])
+        } else {
+            let title = format!("{} [{}] ", titles[i].trim_end(), note.join(", "));
+            (title, colors[i])
+        }
+    });
 
     let (filter_query, filter_focused) = match &app.mode {
         Mode::Filtering { query, focused } => (Some(query), *focused),
@@ -807,7 +979,7 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
     let related_ids = app.selected_card_related_ids();
 
-    for (i, (title, color, cards)) in section_data.iter().enumerate() {
+    for (i, (title, color)) in section_data.iter().enumerate() {
         let is_active = i == app.active_section;
         let query = if is_active { filter_query } else { None };
         let selected = if is_active {
@@ -815,16 +987,21 @@ pub fn ui(frame: &mut Frame, app: &App) {
         } else {
             None
         };
+        let mark_range = if is_active { app.mark_range() } else { None };
         render_column(
             frame,
-            columns[i],
+            layout.columns[i],
             title,
             *color,
-            cards,
+            &app.rows(i),
+            app.visible_cards(i).len(),
+            count[i],
             is_active,
             query.map(|q| q.value()),
             is_active && filter_focused,
             selected,
+            &app.marked[i],
+            mark_range,
             &related_ids,
             app.section_loading[i],
             app.spinner_tick,
@@ -833,7 +1010,7 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
     // Message center
     if app.show_messages {
-        render_message_center(frame, outer[2], app);
+        render_message_center(frame, layout.messages, app);
     }
 
     // Bottom legend bar (two lines: global on top, area-specific on bottom)
@@ -846,24 +1023,85 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
     let global_mode_spans: Vec<Span> = match &app.mode {
         Mode::Normal => {
-            vec![
-                Span::styled(" q/Esc ", key_style),
-                Span::styled(" Quit ", desc_style),
-                Span::styled(" h/l Tab/S-Tab ", key_style),
-                Span::styled(" Switch column ", desc_style),
-                Span::styled(" j/k ↑/↓ ", key_style),
-                Span::styled(" Navigate ", desc_style),
-                Span::styled(" / ", key_style),
-                Span::styled(" Filter ", desc_style),
-                Span::styled(" Enter ", key_style),
-                Span::styled(" Change repo ", desc_style),
-                Span::styled(" R ", key_style),
-                Span::styled(" Refresh ", desc_style),
-                Span::styled(" p ", key_style),
-                Span::styled(" Pull ", desc_style),
-                Span::styled(" n ", key_accent),
-                Span::styled(" New issue ", desc_style),
-            ]
+            let mut spans = Vec::new();
+            push_hint(
+                &mut spans,
+                keymap.keys("quit"),
+                key_style,
+                " Quit ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.pair("prev_column", "next_column"),
+                key_style,
+                " Switch column ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.pair("down", "up"),
+                key_style,
+                " Navigate ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("filter"),
+                key_style,
+                " Filter ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("show_detail"),
+                key_style,
+                " Details ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("switch_repo"),
+                key_style,
+                " Change repo ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("refresh"),
+                key_style,
+                " Refresh ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("pull_base"),
+                key_style,
+                " Pull ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("new_issue"),
+                key_accent,
+                " New issue ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("cycle_sort"),
+                key_style,
+                " Sort ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("cycle_group"),
+                key_style,
+                " Group ",
+                desc_style,
+            );
+            spans
         }
         Mode::Filtering { focused, .. } if *focused => vec![
             Span::styled(" Esc ", key_style),
@@ -873,16 +1111,60 @@ pub fn ui(frame: &mut Frame, app: &App) {
             Span::styled(" ↑/↓ ", key_style),
             Span::styled(" Navigate ", desc_style),
         ],
-        Mode::Filtering { .. } => vec![
-            Span::styled(" Esc ", key_style),
-            Span::styled(" Clear filter ", desc_style),
-            Span::styled(" / ", key_style),
-            Span::styled(" Edit filter ", desc_style),
-            Span::styled(" h/l Tab/S-Tab ", key_style),
-            Span::styled(" Switch column ", desc_style),
-            Span::styled(" j/k ↑/↓ ", key_style),
-            Span::styled(" Navigate ", desc_style),
-        ],
+        Mode::Filtering { .. } => {
+            let mut spans = Vec::new();
+            push_hint(
+                &mut spans,
+                keymap.keys("clear_filter"),
+                key_style,
+                " Clear filter ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("filter"),
+                key_style,
+                " Edit filter ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("save_view"),
+                key_accent,
+                " Save view ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.pair("prev_column", "next_column"),
+                key_style,
+                " Switch column ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.pair("down", "up"),
+                key_style,
+                " Navigate ",
+                desc_style,
+            );
+            spans
+        }
+        Mode::CreatingIssue
+            if app
+                .issue_modal
+                .as_ref()
+                .is_some_and(|m| m.choosing_template.is_some()) =>
+        {
+            vec![
+                Span::styled(" Esc ", key_style),
+                Span::styled(" Cancel ", desc_style),
+                Span::styled(" ↑/↓ ", key_style),
+                Span::styled(" Choose ", desc_style),
+                Span::styled(" Enter ", key_accent),
+                Span::styled(" Use template ", desc_style),
+            ]
+        }
         Mode::CreatingIssue | Mode::EditingIssue => vec![
             Span::styled(" Esc ", key_style),
             Span::styled(" Cancel ", desc_style),
@@ -890,11 +1172,31 @@ pub fn ui(frame: &mut Frame, app: &App) {
             Span::styled(" Switch field ", desc_style),
             Span::styled(" Ctrl+S ", key_accent),
             Span::styled(" Submit ", desc_style),
+            Span::styled(" Ctrl+E ", key_style),
+            Span::styled(" Editor ", desc_style),
         ],
-        Mode::Confirming => vec![
-            Span::styled(" y ", key_accent),
-            Span::styled(" Confirm ", desc_style),
-            Span::styled(" n/Esc ", key_style),
+        Mode::Confirming => {
+            let mut spans = Vec::new();
+            push_hint(
+                &mut spans,
+                keymap.keys("confirm"),
+                key_accent,
+                " Confirm ",
+                desc_style,
+            );
+            push_hint(
+                &mut spans,
+                keymap.keys("cancel"),
+                key_style,
+                " Cancel ",
+                desc_style,
+            );
+            spans
+        }
+        Mode::NamingView { .. } => vec![
+            Span::styled(" Enter ", key_accent),
+            Span::styled(" Save view ", desc_style),
+            Span::styled(" Esc ", key_style),
             Span::styled(" Cancel ", desc_style),
         ],
         Mode::EditingVerifyCommand { .. } | Mode::EditingEditorCommand { .. } => vec![
@@ -903,12 +1205,19 @@ pub fn ui(frame: &mut Frame, app: &App) {
             Span::styled(" Esc ", key_style),
             Span::styled(" Cancel ", desc_style),
         ],
+        Mode::ExportingStore { .. } | Mode::ImportingStore { .. } | Mode::ChoosingBase { .. } => {
+            vec![
+                Span::styled(" Enter ", key_accent),
+                Span::styled(" Continue ", desc_style),
+                Span::styled(" Esc ", key_style),
+                Span::styled(" Cancel ", desc_style),
+            ]
+        }
     };
     global_spans.extend(global_mode_spans);
 
     // Bottom line: area-specific actions
-    let section_names = ["Issues", "Worktrees", "Sessions", "Pull Requests"];
-    let section_colors = [Color::Red, Color::Yellow, Color::Blue, Color::Magenta];
+    let section_colors = theme().columns;
     let mut area_spans: Vec<Span> = Vec::new();
 
     if matches!(
@@ -919,82 +1228,130 @@ pub fn ui(frame: &mut Frame, app: &App) {
             .fg(section_colors[app.active_section])
             .add_modifier(Modifier::BOLD);
         area_spans.push(Span::styled(
-            format!(" {} ", section_names[app.active_section]),
+            format!(" {} ", SECTION_NAMES[app.active_section]),
             section_label_style,
         ));
         area_spans.push(Span::styled("│ ", desc_style));
 
+        // The column's actions below apply to every marked card
+        let marking = app.mark_anchor.is_some() || !app.marked[app.active_section].is_empty();
+        if marking {
+            area_spans.push(Span::styled(
+                format!("{} marked ", app.marked_cards(app.active_section).len()),
+                Style::default()
+                    .fg(theme().special)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            push_hint(
+                &mut area_spans,
+                keymap.keys("clear_marks"),
+                key_style,
+                " Unmark ",
+                desc_style,
+            );
+            area_spans.push(Span::styled("│ ", desc_style));
+        }
+
+        let mut hint = |keys: String, style: Style, label: &'static str| {
+            push_hint(&mut area_spans, keys, style, label, desc_style);
+        };
         match app.active_section {
             0 => {
-                area_spans.push(Span::styled(" w ", key_accent));
-                area_spans.push(Span::styled(" Worktree+Session ", desc_style));
-                area_spans.push(Span::styled(" e ", key_accent));
-                area_spans.push(Span::styled(" Edit issue ", desc_style));
+                hint(
+                    keymap.keys("start_worktree"),
+                    key_accent,
+                    " Worktree+Session ",
+                );
+                hint(
+                    keymap.keys("start_worktree_from"),
+                    key_style,
+                    " From branch… ",
+                );
+                hint(keymap.keys("edit_issue"), key_accent, " Edit issue ");
                 if app.issue_state_filter == StateFilter::Open {
-                    area_spans.push(Span::styled(" d ", key_style));
-                    area_spans.push(Span::styled(" Close issue ", desc_style));
+                    hint(keymap.keys("close_issue"), key_style, " Close issue ");
                 }
-                area_spans.push(Span::styled(" s ", key_style));
-                area_spans.push(Span::styled(" Open/Closed ", desc_style));
-                area_spans.push(Span::styled(" m ", key_style));
-                area_spans.push(Span::styled(" Assigned to me ", desc_style));
+                hint(
+                    keymap.keys("toggle_issue_state"),
+                    key_style,
+                    " Open/Closed ",
+                );
+                hint(
+                    keymap.keys("toggle_issue_assignee"),
+                    key_style,
+                    " Assigned to me ",
+                );
             }
             1 => {
-                area_spans.push(Span::styled(" w ", key_accent));
-                area_spans.push(Span::styled(" New session ", desc_style));
-                area_spans.push(Span::styled(" e ", key_accent));
-                area_spans.push(Span::styled(" Editor ", desc_style));
-                area_spans.push(Span::styled(" v ", key_accent));
-                area_spans.push(Span::styled(" Verify ", desc_style));
+                hint(keymap.keys("start_session"), key_accent, " New session ");
+                hint(keymap.keys("open_editor"), key_accent, " Editor ");
+                hint(keymap.keys("verify"), key_accent, " Verify ");
                 if app.local_mode {
-                    area_spans.push(Span::styled(" P ", key_accent));
-                    area_spans.push(Span::styled(" Create local PR ", desc_style));
+                    hint(
+                        keymap.keys("create_local_pr"),
+                        key_accent,
+                        " Create local PR ",
+                    );
                 }
-                area_spans.push(Span::styled(" d ", key_style));
-                area_spans.push(Span::styled(" Remove worktree ", desc_style));
+                hint(
+                    keymap.pair("rebase_on_base", "merge_base"),
+                    key_style,
+                    " Rebase/merge base ",
+                );
+                hint(keymap.keys("restack"), key_style, " Restack ");
+                hint(
+                    keymap.keys("remove_worktree"),
+                    key_style,
+                    " Remove worktree ",
+                );
             }
             2 => {
-                area_spans.push(Span::styled(" a ", key_accent));
-                area_spans.push(Span::styled(" Attach session ", desc_style));
-                area_spans.push(Span::styled(" d ", key_style));
-                area_spans.push(Span::styled(" Kill session ", desc_style));
+                hint(
+                    keymap.keys("attach_session"),
+                    key_accent,
+                    " Attach session ",
+                );
+                hint(keymap.keys("kill_session"), key_style, " Kill session ");
             }
             3 => {
                 if !app.local_mode {
-                    area_spans.push(Span::styled(" o ", key_accent));
-                    area_spans.push(Span::styled(" Open in browser ", desc_style));
+                    hint(keymap.keys("open_pr"), key_accent, " Open in browser ");
                 }
-                area_spans.push(Span::styled(" r ", key_accent));
-                area_spans.push(Span::styled(" Mark ready ", desc_style));
-                area_spans.push(Span::styled(" M ", key_accent));
-                area_spans.push(Span::styled(" Merge ", desc_style));
+                hint(keymap.keys("mark_pr_ready"), key_accent, " Mark ready ");
+                hint(keymap.keys("merge_pr"), key_accent, " Merge ");
                 if !app.local_mode {
-                    area_spans.push(Span::styled(" V ", key_accent));
-                    area_spans.push(Span::styled(" Revert ", desc_style));
+                    hint(keymap.keys("revert_pr"), key_accent, " Revert ");
                 }
-                area_spans.push(Span::styled(" s ", key_style));
-                area_spans.push(Span::styled(
+                hint(
+                    keymap.keys("toggle_pr_state"),
+                    key_style,
                     if app.local_mode {
                         " Open/Merged "
                     } else {
                         " Open/Closed "
                     },
-                    desc_style,
-                ));
+                );
                 if !app.local_mode {
-                    area_spans.push(Span::styled(" m ", key_style));
-                    area_spans.push(Span::styled(" Assigned to me ", desc_style));
+                    hint(
+                        keymap.keys("toggle_pr_assignee"),
+                        key_style,
+                        " Assigned to me ",
+                    );
                 }
             }
             _ => {}
         }
+        if !marking {
+            hint(keymap.keys("toggle_mark"), key_style, " Mark ");
+            hint(keymap.keys("mark_range"), key_style, " Mark range ");
+        }
     }
 
     // Split bottom area into two rows
     let bottom_rows = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(1), Constraint::Length(1)])
-        .split(outer[3]);
+        .split(layout.legend);
 
     // Top row: global actions with timer on right (if auto-refresh enabled)
     let auto_refresh_secs = crate::config::get_auto_refresh_secs();
@@ -1017,10 +1374,10 @@ pub fn ui(frame: &mut Frame, app: &App) {
         let timer_text = format!(" {} {}s ", '\u{23F1}', secs);
         let timer_style = if secs <= 5 {
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme().warning)
                 .add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(theme().dim)
         };
         let timer = Paragraph::new(Line::from(Span::styled(timer_text, timer_style)))
             .alignment(ratatui::layout::Alignment::Right);
@@ -1041,6 +1398,11 @@ pub fn ui(frame: &mut Frame, app: &App) {
         ui_edit_issue_modal(frame, modal, app.spinner_tick);
     }
 
+    // Render the selected card's detail view if open
+    if let Some(scroll) = app.detail_scroll {
+        ui_card_detail(frame, app, scroll);
+    }
+
     // Render confirm modal overlay if open
     if let Some(modal) = &app.confirm_modal {
         ui_confirm_modal(frame, modal);
@@ -1053,17 +1415,64 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
     // Render verify command prompt overlay if in EditingVerifyCommand mode
     if let Mode::EditingVerifyCommand { input } = &app.mode {
-        ui_text_prompt(frame, input, "Set Verify Command", Color::Yellow,
+        ui_text_prompt(frame, input, "Set Verify Command", theme().warning, "Command",
             "No verify command configured. Use {directory} for the worktree path:",
             "e.g. alacritty --working-directory {directory} -e cargo run  |  Enter: save & run  Esc: cancel");
     }
 
     // Render editor command prompt overlay if in EditingEditorCommand mode
     if let Mode::EditingEditorCommand { input } = &app.mode {
-        ui_text_prompt(frame, input, "Set Editor Command", Color::Green,
+        ui_text_prompt(frame, input, "Set Editor Command", theme().success, "Command",
             "No editor configured. Use {directory} for the worktree path:",
             "e.g. alacritty --working-directory {directory} -e nvim  |  Enter: save & open  Esc: cancel");
     }
+
+    if let Mode::NamingView { input, .. } = &app.mode {
+        ui_text_prompt(
+            frame,
+            input,
+            "Save View",
+            theme().special,
+            "Name",
+            "Name for the column filters (1-9 switch views):",
+            "A view's name replaces it, no filters delete it  |  Enter: save  Esc: cancel",
+        );
+    }
+
+    // Render local store export/import path prompts
+    if let Mode::ExportingStore { input } = &app.mode {
+        ui_text_prompt(
+            frame,
+            input,
+            "Export Local Store",
+            theme().accent,
+            "Path",
+            "Write all local issues and PRs to a file:",
+            "A .csv extension exports CSV, anything else JSON  |  Enter: export  Esc: cancel",
+        );
+    }
+    if let Mode::ChoosingBase { input, .. } = &app.mode {
+        ui_text_prompt(
+            frame,
+            input,
+            "Start From Branch",
+            theme().warning,
+            "Base branch",
+            "Branch to create the worktree from; its PR will target it:",
+            "A branch (e.g. release/1.2) or #N to stack on issue N  |  Enter: create  Esc: cancel",
+        );
+    }
+    if let Mode::ImportingStore { input } = &app.mode {
+        ui_text_prompt(
+            frame,
+            input,
+            "Import Local Store",
+            theme().accent,
+            "Path",
+            "Import issues and PRs from a JSON, CSV or `gh issue list --json` file:",
+            "Conflicting numbers are renumbered  |  Enter: preview  Esc: cancel",
+        );
+    }
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
@@ -1101,7 +1510,7 @@ fn ui_loading_spinner(frame: &mut Frame, message: &str, spinner_tick: usize) {
     let spinner = SPINNER_FRAMES[spinner_tick % SPINNER_FRAMES.len()];
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(Style::default().fg(theme().accent));
     let inner = block.inner(rect);
     frame.render_widget(block, rect);
 
@@ -1109,13 +1518,13 @@ fn ui_loading_spinner(frame: &mut Frame, message: &str, spinner_tick: usize) {
         Span::styled(
             format!("{} ", spinner),
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme().accent)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::styled(
             message,
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme().warning)
                 .add_modifier(Modifier::BOLD),
         ),
     ]))
@@ -1124,25 +1533,31 @@ fn ui_loading_spinner(frame: &mut Frame, message: &str, spinner_tick: usize) {
 }
 
 fn ui_issue_modal(frame: &mut Frame, modal: &IssueModal, spinner_tick: usize) {
-    let area = centered_rect(50, 50, frame.area());
+    let area = centered_rect(60, 70, frame.area());
 
     frame.render_widget(Clear, area);
 
     let outer_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan))
+        .border_style(Style::default().fg(theme().accent))
         .title(" New Issue ")
-        .title_style(
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )
+        .title_style(theme().badge(theme().accent).add_modifier(Modifier::BOLD))
         .padding(Padding::new(1, 1, 1, 0));
     let inner = outer_block.inner(area);
     frame.render_widget(outer_block, area);
 
-    // Layout: title field (3), body field (remaining), checkbox (1), error/spinner (1), hint (1)
+    if let Some(selected) = modal.choosing_template {
+        ui_template_picker(frame, inner, modal, selected);
+        return;
+    }
+
+    // Layout: title field (3), body field (remaining), labels, assignees and
+    // milestone (1 each, when there is anything to pick), checkbox (1),
+    // error/spinner (1), hint (1)
+    let choice_height = |field: usize| {
+        let has_options = modal.choice(field).is_some_and(|c| !c.options.is_empty());
+        Constraint::Length(if has_options { 1 } else { 0 })
+    };
     let has_error = modal.error.is_some();
     let has_status = has_error || modal.submitting;
     let chunks = Layout::default()
@@ -1150,6 +1565,9 @@ fn ui_issue_modal(frame: &mut Frame, modal: &IssueModal, spinner_tick: usize) {
         .constraints([
             Constraint::Length(3),                              // title input
             Constraint::Min(3),                                 // body input
+            choice_height(2),                                   // labels
+            choice_height(3),                                   // assignees
+            choice_height(4),                                   // milestone
             Constraint::Length(1),                              // create worktree toggle
             Constraint::Length(if has_status { 1 } else { 0 }), // error or spinner
             Constraint::Length(1),                              // hint
@@ -1158,28 +1576,28 @@ fn ui_issue_modal(frame: &mut Frame, modal: &IssueModal, spinner_tick: usize) {
 
     // Title field
     let title_style = if modal.submitting {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     } else if modal.active_field == 0 {
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     };
     let title_block = Block::default()
         .borders(Borders::ALL)
         .border_style(title_style)
         .title(" Title ");
     let text_color = if modal.submitting {
-        Color::DarkGray
+        theme().dim
     } else {
-        Color::White
+        theme().text
     };
     let show_title_cursor = modal.active_field == 0 && !modal.submitting;
     let title_text = Paragraph::new(Line::from(text_input_spans(
         &modal.title,
         Style::default().fg(text_color),
-        Style::default().fg(Color::Black).bg(Color::Cyan),
+        theme().badge(theme().accent),
         show_title_cursor,
     )))
     .block(title_block);
@@ -1187,68 +1605,61 @@ fn ui_issue_modal(frame: &mut Frame, modal: &IssueModal, spinner_tick: usize) {
 
     // Body field
     let body_style = if modal.submitting {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     } else if modal.active_field == 1 {
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     };
     let body_block = Block::default()
         .borders(Borders::ALL)
         .border_style(body_style)
         .title(" Body ");
     let show_body_cursor = modal.active_field == 1 && !modal.submitting;
-    let body_spans = text_input_spans(
+    render_text_area(
+        frame,
+        chunks[1],
         &modal.body,
+        body_block,
         Style::default().fg(text_color),
-        Style::default().fg(Color::Black).bg(Color::Cyan),
         show_body_cursor,
     );
-    // Split spans into lines at newline boundaries for multi-line display
-    let mut lines: Vec<Line> = vec![Line::from(vec![])];
-    for span in body_spans {
-        let content = span.content.to_string();
-        let parts: Vec<&str> = content.split('\n').collect();
-        for (i, part) in parts.iter().enumerate() {
-            if i > 0 {
-                lines.push(Line::from(vec![]));
-            }
-            if !part.is_empty() {
-                let last = lines.last_mut().unwrap();
-                last.spans.push(Span::styled(part.to_string(), span.style));
-            }
+
+    for (field, name) in [(2, "Labels"), (3, "Assignees"), (4, "Milestone")] {
+        if let Some(choice) = modal.choice(field) {
+            let active = modal.active_field == field && !modal.submitting;
+            frame.render_widget(
+                Paragraph::new(choice_line(name, choice, active)),
+                chunks[field],
+            );
         }
     }
-    let body_paragraph = Paragraph::new(Text::from(lines))
-        .block(body_block)
-        .wrap(Wrap { trim: false });
-    frame.render_widget(body_paragraph, chunks[1]);
 
     // Create worktree checkbox
     let checkbox_icon = if modal.create_worktree { "[x]" } else { "[ ]" };
     let checkbox_style = if modal.submitting {
-        Style::default().fg(Color::DarkGray)
-    } else if modal.active_field == 2 {
+        Style::default().fg(theme().dim)
+    } else if modal.active_field == 5 {
         Style::default()
-            .fg(Color::Cyan)
+            .fg(theme().accent)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     };
     let checkbox = Paragraph::new(Line::from(vec![
         Span::styled(format!("{} ", checkbox_icon), checkbox_style),
         Span::styled(
             "Create worktree and session",
             if modal.submitting {
-                Style::default().fg(Color::DarkGray)
+                Style::default().fg(theme().dim)
             } else {
-                Style::default().fg(Color::White)
+                Style::default().fg(theme().text)
             },
         ),
     ]));
-    frame.render_widget(checkbox, chunks[2]);
+    frame.render_widget(checkbox, chunks[5]);
 
     // Spinner or error
     if modal.submitting {
@@ -1257,36 +1668,136 @@ fn ui_issue_modal(frame: &mut Frame, modal: &IssueModal, spinner_tick: usize) {
             Span::styled(
                 format!("{} ", spinner),
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(theme().accent)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
                 "Creating issue...",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme().warning)
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
-        frame.render_widget(spinner_text, chunks[3]);
+        frame.render_widget(spinner_text, chunks[6]);
     } else if let Some(err) = &modal.error {
         let err_text = Paragraph::new(Line::from(vec![Span::styled(
             err.as_str(),
-            Style::default().fg(Color::Red),
+            Style::default().fg(theme().error),
         )]));
-        frame.render_widget(err_text, chunks[3]);
+        frame.render_widget(err_text, chunks[6]);
     }
 
     // Hint
     let hint_text = if modal.submitting {
         "Esc: cancel"
+    } else if modal.active_field == 1 {
+        "Tab: switch field | Ctrl+E: open in $EDITOR | Ctrl+S: submit | Esc: cancel"
     } else {
         "Tab: switch field | Space: toggle | Ctrl+S: submit | Esc: cancel"
     };
     let hint = Paragraph::new(Line::from(vec![Span::styled(
         hint_text,
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme().dim),
     )]));
-    frame.render_widget(hint, chunks[4]);
+    frame.render_widget(hint, chunks[7]);
+}
+
+/// One line of the new issue form for a field picked from a list: what is
+/// chosen and, while it has focus, the option Left/Right have reached.
+fn choice_line<'a>(name: &'a str, choice: &'a ChoiceField, active: bool) -> Line<'a> {
+    let name_style = if active {
+        Style::default()
+            .fg(theme().accent)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme().dim)
+    };
+    let mut spans = vec![Span::styled(format!("{:<11}", name), name_style)];
+    if choice.chosen.is_empty() {
+        spans.push(Span::styled("none", Style::default().fg(theme().dim)));
+    } else {
+        spans.push(Span::styled(
+            choice.chosen.join(", "),
+            Style::default().fg(theme().text),
+        ));
+    }
+    if let Some(current) = choice.current().filter(|_| active) {
+        let mark = if choice.chosen.iter().any(|c| c == current) {
+            "✓ "
+        } else {
+            ""
+        };
+        spans.push(Span::styled("  ‹ ", Style::default().fg(theme().dim)));
+        spans.push(Span::styled(
+            format!("{}{}", mark, current),
+            theme().badge(theme().accent),
+        ));
+        spans.push(Span::styled(" ›", Style::default().fg(theme().dim)));
+    }
+    Line::from(spans)
+}
+
+/// The list of the repo's issue templates offered before the new issue
+/// form, ending with a blank issue.
+fn ui_template_picker(frame: &mut Frame, area: Rect, modal: &IssueModal, selected: usize) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Choose a template",
+            Style::default()
+                .fg(theme().text)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::default(),
+    ];
+    let blank = ("Blank issue", "Start from an empty form");
+    let entries = modal
+        .templates
+        .iter()
+        .map(|t| (t.name.as_str(), t.about.as_str()))
+        .chain(std::iter::once(blank));
+    for (i, (name, about)) in entries.enumerate() {
+        let (marker, name_style) = if i == selected {
+            (
+                "▸ ",
+                theme().badge(theme().accent).add_modifier(Modifier::BOLD),
+            )
+        } else {
+            ("  ", Style::default().fg(theme().text))
+        };
+        lines.push(Line::from(vec![
+            Span::styled(marker, Style::default().fg(theme().accent)),
+            Span::styled(format!(" {} ", name), name_style),
+        ]));
+        if !about.is_empty() {
+            lines.push(Line::from(Span::styled(
+                format!("   {}", about),
+                Style::default().fg(theme().muted),
+            )));
+        }
+    }
+    // Keep the highlighted template in view on short terminals.
+    let selected_line = lines
+        .iter()
+        .position(|l| l.spans.first().is_some_and(|s| s.content == "▸ "))
+        .unwrap_or(0);
+    let scroll = selected_line.saturating_sub(usize::from(chunks[0].height.max(1)) - 1);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .scroll((scroll as u16, 0)),
+        chunks[0],
+    );
+
+    let hint = Paragraph::new(Line::from(Span::styled(
+        "Enter: use template | Esc: cancel",
+        Style::default().fg(theme().dim),
+    )));
+    frame.render_widget(hint, chunks[1]);
 }
 
 fn ui_edit_issue_modal(frame: &mut Frame, modal: &EditIssueModal, spinner_tick: usize) {
@@ -1296,14 +1807,9 @@ fn ui_edit_issue_modal(frame: &mut Frame, modal: &EditIssueModal, spinner_tick:
 
     let outer_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan))
+        .border_style(Style::default().fg(theme().accent))
         .title(format!(" Edit Issue #{} ", modal.number))
-        .title_style(
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )
+        .title_style(theme().badge(theme().accent).add_modifier(Modifier::BOLD))
         .padding(Padding::new(1, 1, 1, 0));
     let inner = outer_block.inner(area);
     frame.render_widget(outer_block, area);
@@ -1322,28 +1828,28 @@ fn ui_edit_issue_modal(frame: &mut Frame, modal: &EditIssueModal, spinner_tick:
 
     // Title field
     let title_style = if modal.submitting {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     } else if modal.active_field == 0 {
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     };
     let title_block = Block::default()
         .borders(Borders::ALL)
         .border_style(title_style)
         .title(" Title ");
     let text_color = if modal.submitting {
-        Color::DarkGray
+        theme().dim
     } else {
-        Color::White
+        theme().text
     };
     let show_title_cursor = modal.active_field == 0 && !modal.submitting;
     let title_text = Paragraph::new(Line::from(text_input_spans(
         &modal.title,
         Style::default().fg(text_color),
-        Style::default().fg(Color::Black).bg(Color::Cyan),
+        theme().badge(theme().accent),
         show_title_cursor,
     )))
     .block(title_block);
@@ -1351,43 +1857,27 @@ fn ui_edit_issue_modal(frame: &mut Frame, modal: &EditIssueModal, spinner_tick:
 
     // Body field
     let body_style = if modal.submitting {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     } else if modal.active_field == 1 {
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     };
     let body_block = Block::default()
         .borders(Borders::ALL)
         .border_style(body_style)
         .title(" Body ");
     let show_body_cursor = modal.active_field == 1 && !modal.submitting;
-    let body_spans = text_input_spans(
+    render_text_area(
+        frame,
+        chunks[1],
         &modal.body,
+        body_block,
         Style::default().fg(text_color),
-        Style::default().fg(Color::Black).bg(Color::Cyan),
         show_body_cursor,
     );
-    let mut lines: Vec<Line> = vec![Line::from(vec![])];
-    for span in body_spans {
-        let content = span.content.to_string();
-        let parts: Vec<&str> = content.split('\n').collect();
-        for (i, part) in parts.iter().enumerate() {
-            if i > 0 {
-                lines.push(Line::from(vec![]));
-            }
-            if !part.is_empty() {
-                let last = lines.last_mut().unwrap();
-                last.spans.push(Span::styled(part.to_string(), span.style));
-            }
-        }
-    }
-    let body_paragraph = Paragraph::new(Text::from(lines))
-        .block(body_block)
-        .wrap(Wrap { trim: false });
-    frame.render_widget(body_paragraph, chunks[1]);
 
     // Spinner or error
     if modal.submitting {
@@ -1396,13 +1886,13 @@ fn ui_edit_issue_modal(frame: &mut Frame, modal: &EditIssueModal, spinner_tick:
             Span::styled(
                 format!("{} ", spinner),
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(theme().accent)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
                 "Updating issue...",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme().warning)
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
@@ -1410,7 +1900,7 @@ fn ui_edit_issue_modal(frame: &mut Frame, modal: &EditIssueModal, spinner_tick:
     } else if let Some(err) = &modal.error {
         let err_text = Paragraph::new(Line::from(vec![Span::styled(
             err.as_str(),
-            Style::default().fg(Color::Red),
+            Style::default().fg(theme().error),
         )]));
         frame.render_widget(err_text, chunks[2]);
     }
@@ -1418,75 +1908,324 @@ fn ui_edit_issue_modal(frame: &mut Frame, modal: &EditIssueModal, spinner_tick:
     // Hint
     let hint_text = if modal.submitting {
         "Esc: cancel"
+    } else if modal.active_field == 1 {
+        "Tab: switch field | Ctrl+E: open in $EDITOR | Ctrl+S: submit | Esc: cancel"
     } else {
         "Tab: switch field | Ctrl+S: submit | Esc: cancel"
     };
     let hint = Paragraph::new(Line::from(vec![Span::styled(
         hint_text,
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme().dim),
     )]));
     frame.render_widget(hint, chunks[3]);
 }
 
-fn ui_confirm_modal(frame: &mut Frame, modal: &ConfirmModal) {
-    let area = centered_rect(50, 20, frame.area());
-
-    frame.render_widget(Clear, area);
-
-    let outer_block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Red))
-        .title(" Confirm ")
-        .title_style(
-            Style::default()
-                .fg(Color::White)
-                .bg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-        )
-        .padding(Padding::new(1, 1, 1, 0));
-    let inner = outer_block.inner(area);
-    frame.render_widget(outer_block, area);
-
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(1), Constraint::Length(1)])
-        .split(inner);
+/// What the detail view of `card` shows: what is known about it, the cards
+/// linked to it and its body rendered as markdown.
+pub fn detail_lines(app: &App, card: &Card) -> Vec<Line<'static>> {
+    let key_style = Style::default().fg(theme().dim);
+    let value_style = Style::default().fg(theme().text);
+    let field = |name: &str, value: Vec<Span<'static>>| {
+        let mut spans = vec![Span::styled(format!("{:<11}", name), key_style)];
+        spans.extend(value);
+        Line::from(spans)
+    };
+    let mut lines = Vec::new();
 
-    let message = Paragraph::new(modal.message.as_str()).style(Style::default().fg(Color::White));
-    frame.render_widget(message, chunks[0]);
+    let tags: Vec<&str> = if card.labels.is_empty() {
+        vec![card.tag.as_str()]
+    } else {
+        card.labels.iter().map(String::as_str).collect()
+    };
+    let mut tag_spans = Vec::new();
+    for (i, tag) in tags.iter().enumerate() {
+        let color = if card.labels.is_empty() {
+            card.tag_color
+        } else {
+            theme().label_color(tag)
+        };
+        if i > 0 {
+            tag_spans.push(Span::raw(" "));
+        }
+        tag_spans.push(Span::styled(format!(" {} ", tag), theme().badge(color)));
+    }
+    let tags_name = if card.labels.len() > 1 {
+        "Labels"
+    } else {
+        "Tag"
+    };
+    lines.push(field(tags_name, tag_spans));
 
-    let hint = Paragraph::new(Line::from(vec![
-        Span::styled(
-            "y",
-            Style::default()
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" confirm  ", Style::default().fg(Color::DarkGray)),
-        Span::styled(
-            "n",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    if !card.assignees.is_empty() {
+        let logins: Vec<String> = card.assignees.iter().map(|a| format!("@{}", a)).collect();
+        lines.push(field(
+            "Assignees",
+            vec![Span::styled(logins.join(", "), value_style)],
+        ));
+    }
+    if let Some(branch) = &card.head_branch {
+        lines.push(field(
+            "Branch",
+            vec![Span::styled(branch.clone(), value_style)],
+        ));
+    }
+    if let Some(checks) = card.checks {
+        let label = match checks {
+            CheckStatus::Pending => "pending",
+            CheckStatus::Success => "passed",
+            CheckStatus::Failure => "failed",
+        };
+        lines.push(field(
+            "Checks",
+            vec![Span::styled(
+                format!("{} {}", checks.symbol(), label),
+                Style::default().fg(checks.color()),
+            )],
+        ));
+    }
+    if let Some(status) = &card.worktree_status {
+        lines.push(field(
+            "Path",
+            vec![Span::styled(card.description.clone(), value_style)],
+        ));
+        let mut spans = vec![Span::styled(
+            format!("↑{} ↓{} · ", status.ahead, status.behind),
+            value_style,
+        )];
+        spans.extend(worktree_summary_spans(status));
+        lines.push(field("Status", spans));
+    }
+    if let Some(stack) = &card.stack {
+        lines.push(field(
+            "Stacked",
+            vec![Span::styled(
+                format!("on {}", stack.parent),
+                Style::default().fg(theme().special),
+            )],
+        ));
+    }
+    if let Some(url) = &card.url {
+        lines.push(field(
+            "URL",
+            vec![Span::styled(
+                url.clone(),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::UNDERLINED),
+            )],
+        ));
+    }
+
+    // Cards linked through the issue, in board order
+    let related = app.selected_card_related_ids();
+    let column_names = ["Issue", "Worktree", "Session", "PR"];
+    for (section, name) in column_names.iter().enumerate() {
+        for linked in app.section_cards(section) {
+            if related.contains(&linked.id) {
+                lines.push(field(
+                    name,
+                    vec![Span::styled(
+                        linked.title.clone(),
+                        Style::default().fg(theme().columns[section]),
+                    )],
+                ));
+            }
+        }
+    }
+
+    lines.push(Line::default());
+    match &card.full_description {
+        Some(body) => lines.extend(markdown::render(body)),
+        None => lines.push(Line::from(Span::styled(
+            card.description.clone(),
+            Style::default().fg(theme().muted),
+        ))),
+    }
+    lines
+}
+
+fn ui_card_detail(frame: &mut Frame, app: &App, scroll: u16) {
+    let Some(card) = app.selected(app.active_section) else {
+        return;
+    };
+    let area = centered_rect(80, 85, frame.area());
+    frame.render_widget(Clear, area);
+
+    let color = theme().columns[app.active_section];
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color))
+        .title(format!(" {} ", card.title))
+        .title_style(theme().badge(color).add_modifier(Modifier::BOLD))
+        .padding(Padding::new(2, 2, 1, 0));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let lines = detail_lines(app, card);
+    let skip = (scroll as usize).min(lines.len().saturating_sub(1));
+    let body = Paragraph::new(lines[skip..].to_vec()).wrap(Wrap { trim: false });
+    frame.render_widget(body, chunks[0]);
+
+    let key_style = Style::default()
+        .fg(theme().text)
+        .bg(theme().key_bg)
+        .add_modifier(Modifier::BOLD);
+    let desc_style = Style::default().fg(theme().muted);
+    let keymap = &app.keymap;
+    let mut hints = Vec::new();
+    push_hint(
+        &mut hints,
+        keymap.pair("scroll_detail_down", "scroll_detail_up"),
+        key_style,
+        " Scroll ",
+        desc_style,
+    );
+    push_hint(
+        &mut hints,
+        keymap.pair("page_detail_down", "page_detail_up"),
+        key_style,
+        " Page ",
+        desc_style,
+    );
+    push_hint(
+        &mut hints,
+        keymap.keys("close_detail"),
+        key_style,
+        " Close ",
+        desc_style,
+    );
+    if skip > 0 {
+        hints.push(Span::styled(
+            format!(" line {}/{}", skip + 1, lines.len()),
+            Style::default().fg(theme().dim),
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(hints)), chunks[1]);
+}
+
+/// The confirmation modal's parts. Its buttons are the `y`/`n` hints, which
+/// can be clicked as well as pressed.
+pub struct ConfirmLayout {
+    pub modal: Rect,
+    pub message: Rect,
+    pub buttons: Rect,
+    pub confirm: Rect,
+    pub cancel: Rect,
+}
+
+const CONFIRM_BUTTON: (&str, &str) = ("y", " confirm");
+const CANCEL_BUTTON: (&str, &str) = ("n", " cancel");
+const BUTTON_GAP: u16 = 2;
+
+/// Lay out the confirmation modal for `message` in `area`, tall enough for
+/// every line of it, such as a bulk action's list of cards.
+pub fn confirm_layout(area: Rect, message: &str) -> ConfirmLayout {
+    let mut modal = centered_rect(50, 20, area);
+    // Borders, top padding and the buttons
+    let needed = (message.lines().count() as u16 + 4).min(area.height);
+    if needed > modal.height {
+        modal.y = area.y + (area.height - needed) / 2;
+        modal.height = needed;
+    }
+    let inner = Block::default()
+        .borders(Borders::ALL)
+        .padding(Padding::new(1, 1, 1, 0))
+        .inner(modal);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let width = |(key, label): (&str, &str)| (key.len() + label.len()) as u16;
+    let buttons = chunks[1];
+    let confirm = Rect {
+        width: width(CONFIRM_BUTTON).min(buttons.width),
+        ..buttons
+    };
+    let cancel_x = (confirm.right() + BUTTON_GAP).min(buttons.right());
+    let cancel = Rect {
+        x: cancel_x,
+        width: width(CANCEL_BUTTON).min(buttons.right() - cancel_x),
+        ..buttons
+    };
+    ConfirmLayout {
+        modal,
+        message: chunks[0],
+        buttons,
+        confirm,
+        cancel,
+    }
+}
+
+fn ui_confirm_modal(frame: &mut Frame, modal: &ConfirmModal) {
+    let layout = confirm_layout(frame.area(), &modal.message);
+
+    frame.render_widget(Clear, layout.modal);
+
+    let outer_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme().error))
+        .title(" Confirm ")
+        .title_style(
+            Style::default()
+                .fg(theme().text)
+                .bg(theme().error)
+                .add_modifier(Modifier::BOLD),
+        )
+        .padding(Padding::new(1, 1, 1, 0));
+    frame.render_widget(outer_block, layout.modal);
+
+    let message = Paragraph::new(modal.message.as_str()).style(Style::default().fg(theme().text));
+    frame.render_widget(message, layout.message);
+
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled(
+            CONFIRM_BUTTON.0,
+            Style::default()
+                .fg(theme().success)
+                .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" cancel", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            format!("{}{}", CONFIRM_BUTTON.1, " ".repeat(BUTTON_GAP as usize)),
+            Style::default().fg(theme().dim),
+        ),
+        Span::styled(
+            CANCEL_BUTTON.0,
+            Style::default()
+                .fg(theme().error)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(CANCEL_BUTTON.1, Style::default().fg(theme().dim)),
     ]));
-    frame.render_widget(hint, chunks[1]);
+    frame.render_widget(hint, layout.buttons);
 }
 
 fn render_message_center(frame: &mut Frame, area: Rect, app: &App) {
     let border_style = if app.messages_expanded {
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
+    };
+    let title = if app.message_scroll > 0 {
+        format!(" Messages ({} newer) ", app.message_scroll)
+    } else {
+        " Messages ".to_string()
     };
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(border_style)
-        .title(" Messages ")
+        .title(title)
         .title_style(
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme().accent)
                 .add_modifier(Modifier::BOLD),
         );
     let inner = block.inner(area);
@@ -1500,6 +2239,7 @@ fn render_message_center(frame: &mut Frame, area: Rect, app: &App) {
     let messages: Vec<String> = if let Ok(log) = app.message_log.lock() {
         log.iter()
             .rev()
+            .skip(app.message_scroll)
             .take(max_lines)
             .cloned()
             .collect::<Vec<_>>()
@@ -1514,9 +2254,9 @@ fn render_message_center(frame: &mut Frame, area: Rect, app: &App) {
         .iter()
         .map(|msg| {
             let style = if msg.starts_with("[hook]") {
-                Style::default().fg(Color::Blue)
+                Style::default().fg(theme().info)
             } else {
-                Style::default().fg(Color::Yellow)
+                Style::default().fg(theme().warning)
             };
             Line::from(Span::styled(msg.as_str(), style))
         })
@@ -1526,24 +2266,105 @@ fn render_message_center(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(paragraph, inner);
 }
 
+/// Where a column's contents go inside its border.
+pub struct ColumnLayout {
+    /// The filter input line, while filtering.
+    pub filter: Option<Rect>,
+    pub cards: Rect,
+    /// Index (among the column's rows) of the row in the first slot.
+    pub first: usize,
+    /// One rect per row on screen, top to bottom.
+    pub slots: Vec<Rect>,
+}
+
+/// Lay out a column in `area` with rows `heights` tall, scrolled so that
+/// `selected` is on screen.
+pub fn column_layout(
+    area: Rect,
+    filtering: bool,
+    heights: &[u16],
+    selected: Option<usize>,
+) -> ColumnLayout {
+    let inner = Block::default()
+        .borders(Borders::ALL)
+        .padding(Padding::new(1, 1, 1, 0))
+        .inner(area);
+
+    // Determine content area — if filtering, reserve a line for the search input
+    let (cards_area, filter_area) = if filtering {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(inner);
+        (split[1], Some(split[0]))
+    } else {
+        (inner, None)
+    };
+
+    // Scroll just far enough to keep the selected row visible
+    let mut scroll_offset = 0;
+    if let Some(sel) = selected.filter(|s| *s < heights.len()) {
+        let fits = |first: usize| {
+            heights[first..=sel]
+                .iter()
+                .map(|h| *h as usize)
+                .sum::<usize>()
+                <= cards_area.height as usize
+        };
+        while scroll_offset < sel && !fits(scroll_offset) {
+            scroll_offset += 1;
+        }
+    }
+
+    let mut used = 0;
+    let display_count = heights[scroll_offset.min(heights.len())..]
+        .iter()
+        .take_while(|h| {
+            used += **h;
+            used <= cards_area.height
+        })
+        .count();
+    let mut constraints: Vec<Constraint> = heights[scroll_offset..scroll_offset + display_count]
+        .iter()
+        .map(|h| Constraint::Length(*h))
+        .collect();
+    constraints.push(Constraint::Min(0));
+
+    let slots = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(cards_area);
+
+    ColumnLayout {
+        filter: filter_area,
+        cards: cards_area,
+        first: scroll_offset,
+        slots: slots[..display_count].to_vec(),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn render_column(
     frame: &mut Frame,
     area: Rect,
     title: &str,
     color: Color,
-    cards: &[Card],
+    rows: &[Row],
+    matching: usize,
+    total: usize,
     is_active: bool,
     filter_query: Option<&str>,
     filter_focused: bool,
     selected: Option<usize>,
+    marked: &HashSet<String>,
+    mark_range: Option<RangeInclusive<usize>>,
     related_ids: &HashSet<String>,
     loading: bool,
     spinner_tick: usize,
 ) {
     let border_style = if is_active {
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD)
     } else {
         Style::default().fg(color)
@@ -1553,70 +2374,48 @@ fn render_column(
         .border_style(border_style)
         .title(title)
         .title_style(if is_active {
-            Style::default()
-                .fg(Color::Black)
-                .bg(color)
-                .add_modifier(Modifier::BOLD)
+            theme().badge(color).add_modifier(Modifier::BOLD)
         } else {
             Style::default().fg(color).add_modifier(Modifier::BOLD)
         })
         .padding(Padding::new(1, 1, 1, 0));
-    let inner = col_block.inner(area);
     frame.render_widget(col_block, area);
 
-    // Determine content area — if filtering, reserve a line for the search input
-    let (cards_area, filter_area) = if filter_query.is_some() {
-        let split = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(1), Constraint::Min(0)])
-            .split(inner);
-        (split[1], Some(split[0]))
-    } else {
-        (inner, None)
-    };
+    let heights: Vec<u16> = rows.iter().map(Row::height).collect();
+    let layout = column_layout(area, filter_query.is_some(), &heights, selected);
+    let (cards_area, filter_area) = (layout.cards, layout.filter);
 
     // Render filter input if active
     if let (Some(area), Some(query)) = (filter_area, filter_query) {
         let mut spans = vec![
-            Span::styled("/ ", Style::default().fg(Color::Cyan)),
+            Span::styled("/ ", Style::default().fg(theme().accent)),
             Span::styled(
                 query,
                 Style::default()
-                    .fg(Color::White)
+                    .fg(theme().text)
                     .add_modifier(Modifier::BOLD),
             ),
         ];
         if filter_focused {
-            spans.push(Span::styled("_", Style::default().fg(Color::Cyan)));
+            spans.push(Span::styled("_", Style::default().fg(theme().accent)));
         }
         let input = Paragraph::new(Line::from(spans));
         frame.render_widget(input, area);
     }
 
-    // Filter cards
-    let visible_cards: Vec<&Card> = if let Some(query) = filter_query {
-        if query.is_empty() {
-            cards.iter().collect()
-        } else {
-            cards.iter().filter(|c| card_matches(c, query)).collect()
-        }
-    } else {
-        cards.iter().collect()
-    };
-
     // Render filter input if active
     if let (Some(area), Some(query)) = (filter_area, filter_query) {
-        let count_text = format!(" {}/{}", visible_cards.len(), cards.len());
+        let count_text = format!(" {}/{}", matching, total);
         let input = Paragraph::new(Line::from(vec![
-            Span::styled("/ ", Style::default().fg(Color::Cyan)),
+            Span::styled("/ ", Style::default().fg(theme().accent)),
             Span::styled(
                 query,
                 Style::default()
-                    .fg(Color::White)
+                    .fg(theme().text)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled("_", Style::default().fg(Color::Cyan)),
-            Span::styled(count_text, Style::default().fg(Color::DarkGray)),
+            Span::styled("_", Style::default().fg(theme().accent)),
+            Span::styled(count_text, Style::default().fg(theme().dim)),
         ]));
         frame.render_widget(input, area);
     }
@@ -1629,7 +2428,7 @@ fn render_column(
                 format!("{} ", spinner),
                 Style::default().fg(color).add_modifier(Modifier::BOLD),
             ),
-            Span::styled("Loading…", Style::default().fg(Color::DarkGray)),
+            Span::styled("Loading…", Style::default().fg(theme().dim)),
         ]))
         .alignment(ratatui::layout::Alignment::Center);
         // Center the spinner vertically in the cards area
@@ -1641,59 +2440,65 @@ fn render_column(
         return;
     }
 
-    let card_height = 4u16;
-    let max_visible = (cards_area.height / card_height) as usize;
-    let total = visible_cards.len();
-
-    // Calculate scroll offset to keep the selected card visible
-    let scroll_offset = if let Some(sel) = selected {
-        if max_visible == 0 {
-            0
-        } else if sel >= max_visible {
-            sel - max_visible + 1
-        } else {
-            0
-        }
-    } else {
-        0
-    };
-
-    let display_count = max_visible.min(total.saturating_sub(scroll_offset));
-    let display_cards: Vec<&Card> = visible_cards
-        .iter()
-        .skip(scroll_offset)
-        .take(display_count)
-        .copied()
-        .collect();
-
-    let mut constraints: Vec<Constraint> = display_cards
-        .iter()
-        .map(|_| Constraint::Length(card_height))
-        .collect();
-    constraints.push(Constraint::Min(0));
-
-    let slots = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(constraints)
-        .split(cards_area);
-
-    for (i, card) in display_cards.iter().enumerate() {
-        let original_idx = scroll_offset + i;
+    let display_rows = &rows[layout.first..layout.first + layout.slots.len()];
+    for (i, row) in display_rows.iter().enumerate() {
+        let original_idx = layout.first + i;
         let is_selected = selected.is_some_and(|s| s == original_idx);
-        let is_related = !is_selected && related_ids.contains(&card.id);
-        render_card(frame, slots[i], card, is_selected, is_related);
+        match row {
+            Row::Group {
+                name,
+                count,
+                collapsed,
+            } => {
+                let marker = if *collapsed { "▸" } else { "▾" };
+                let style = if is_selected {
+                    theme().badge(color).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(color).add_modifier(Modifier::BOLD)
+                };
+                let header = Paragraph::new(Line::from(Span::styled(
+                    format!("{} {} ({}) ", marker, name, count),
+                    style,
+                )));
+                frame.render_widget(header, layout.slots[i]);
+            }
+            Row::Card(card) => {
+                let is_related = !is_selected && related_ids.contains(&card.id);
+                let is_marked = marked.contains(&card.id)
+                    || mark_range
+                        .as_ref()
+                        .is_some_and(|r| r.contains(&original_idx));
+                render_card(
+                    frame,
+                    layout.slots[i],
+                    card,
+                    is_selected,
+                    is_related,
+                    is_marked,
+                );
+            }
+        }
     }
 }
 
-fn render_card(frame: &mut Frame, area: Rect, card: &Card, is_selected: bool, is_related: bool) {
+fn render_card(
+    frame: &mut Frame,
+    area: Rect,
+    card: &Card,
+    is_selected: bool,
+    is_related: bool,
+    is_marked: bool,
+) {
     let border_style = if is_selected {
         Style::default()
-            .fg(Color::Rgb(255, 200, 50))
+            .fg(theme().selected)
             .add_modifier(Modifier::BOLD)
+    } else if is_marked {
+        Style::default().fg(theme().special)
     } else if is_related {
-        Style::default().fg(Color::Rgb(180, 160, 100))
+        Style::default().fg(theme().related)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme().dim)
     };
     let card_block = Block::default()
         .borders(Borders::ALL)
@@ -1711,24 +2516,117 @@ fn render_card(frame: &mut Frame, area: Rect, card: &Card, is_selected: bool, is
         .split(inner);
 
     // Title line with tag
-    let tag = Span::styled(
-        format!(" {} ", card.tag),
-        Style::default().fg(Color::Black).bg(card.tag_color),
-    );
+    let tag = Span::styled(format!(" {} ", card.tag), theme().badge(card.tag_color));
     let title = Span::styled(
         format!(" {}", card.title),
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD),
     );
-    frame.render_widget(Paragraph::new(Line::from(vec![tag, title])), lines[0]);
+    let mut title_spans = Vec::new();
+    if is_marked {
+        title_spans.push(Span::styled(
+            "✓",
+            Style::default()
+                .fg(theme().special)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(stack) = &card.stack {
+        title_spans.push(Span::styled(
+            format!("{}↳ ", "  ".repeat(stack.depth - 1)),
+            Style::default().fg(theme().special),
+        ));
+    }
+    title_spans.push(tag);
+    if let Some(checks) = card.checks {
+        title_spans.push(Span::styled(
+            format!(" {}", checks.symbol()),
+            Style::default().fg(checks.color()),
+        ));
+    }
+    if let Some(status) = &card.worktree_status {
+        if status.ahead > 0 {
+            title_spans.push(Span::styled(
+                format!(" ↑{}", status.ahead),
+                Style::default().fg(theme().success),
+            ));
+        }
+        if status.behind > 0 {
+            title_spans.push(Span::styled(
+                format!(" ↓{}", status.behind),
+                Style::default().fg(theme().warning),
+            ));
+        }
+    }
+    title_spans.push(title);
+    frame.render_widget(Paragraph::new(Line::from(title_spans)), lines[0]);
+
+    // Description (worktree cards summarise the agent's work instead of the path)
+    let mut desc_spans = Vec::new();
+    if let Some(stack) = &card.stack {
+        desc_spans.push(Span::styled(
+            format!("on {} · ", stack.parent),
+            Style::default().fg(theme().special),
+        ));
+    }
+    match &card.worktree_status {
+        Some(status) => desc_spans.extend(worktree_summary_spans(status)),
+        None => desc_spans.push(Span::styled(
+            card.description.as_str(),
+            Style::default().fg(theme().muted),
+        )),
+    }
+    frame.render_widget(Paragraph::new(Line::from(desc_spans)), lines[1]);
+}
 
-    // Description
-    let desc = Paragraph::new(Span::styled(
-        &card.description,
-        Style::default().fg(Color::Gray),
-    ));
-    frame.render_widget(desc, lines[1]);
+fn worktree_summary_spans(status: &WorktreeStatus) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    if status.changed > 0 {
+        spans.push(Span::styled(
+            format!("{} changed ", status.changed),
+            Style::default().fg(theme().warning),
+        ));
+    }
+    if status.untracked > 0 {
+        spans.push(Span::styled(
+            format!("{} untracked ", status.untracked),
+            Style::default().fg(theme().accent),
+        ));
+    }
+    // With nothing ahead of main the last commit is main's, not the agent's
+    match (&status.last_commit_subject, status.ahead) {
+        (Some(subject), ahead) if ahead > 0 => {
+            let age = status
+                .last_commit_time
+                .map(|t| format!("{} · ", format_age(t)))
+                .unwrap_or_default();
+            spans.push(Span::styled(
+                format!("{}{}", age, subject),
+                Style::default().fg(theme().muted),
+            ));
+        }
+        _ => spans.push(Span::styled(
+            "no commits yet",
+            Style::default().fg(theme().dim),
+        )),
+    }
+    spans
+}
+
+/// Format the time since a Unix timestamp as a short age like `5m` or `3d`.
+fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(timestamp);
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
 }
 
 fn ui_text_prompt(
@@ -1736,6 +2634,7 @@ fn ui_text_prompt(
     input: &TextInput,
     title: &str,
     color: Color,
+    input_title: &str,
     label_text: &str,
     hint_text: &str,
 ) {
@@ -1747,12 +2646,7 @@ fn ui_text_prompt(
         .borders(Borders::ALL)
         .border_style(Style::default().fg(color))
         .title(format!(" {} ", title))
-        .title_style(
-            Style::default()
-                .fg(Color::Black)
-                .bg(color)
-                .add_modifier(Modifier::BOLD),
-        )
+        .title_style(theme().badge(color).add_modifier(Modifier::BOLD))
         .padding(Padding::new(1, 1, 1, 0));
     let inner = outer_block.inner(area);
     frame.render_widget(outer_block, area);
@@ -1768,18 +2662,18 @@ fn ui_text_prompt(
 
     let label = Paragraph::new(Line::from(vec![Span::styled(
         label_text,
-        Style::default().fg(Color::White),
+        Style::default().fg(theme().text),
     )]));
     frame.render_widget(label, chunks[0]);
 
     let input_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::White))
-        .title(" Command ");
+        .border_style(Style::default().fg(theme().text))
+        .title(format!(" {} ", input_title));
     let text_style = Style::default()
-        .fg(Color::White)
+        .fg(theme().text)
         .add_modifier(Modifier::BOLD);
-    let cursor_style = Style::default().fg(Color::Black).bg(Color::Cyan);
+    let cursor_style = theme().badge(theme().accent);
     let input_text = Paragraph::new(Line::from(text_input_spans(
         input,
         text_style,
@@ -1791,7 +2685,7 @@ fn ui_text_prompt(
 
     let hint = Paragraph::new(Line::from(vec![Span::styled(
         hint_text,
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme().dim),
     )]));
     frame.render_widget(hint, chunks[2]);
 }
@@ -1811,12 +2705,12 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
     // Title bar
     let title_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan))
+        .border_style(Style::default().fg(theme().accent))
         .title(" Configuration ");
     let title_text = Paragraph::new(Line::from(vec![Span::styled(
         format!("  Configuration for {}", app.repo),
         Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD),
     )]))
     .block(title_block);
@@ -1825,7 +2719,7 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
     // Content
     let content_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray))
+        .border_style(Style::default().fg(theme().dim))
         .padding(Padding::new(2, 2, 1, 0));
     let inner = content_block.inner(vertical[1]);
     frame.render_widget(content_block, vertical[1]);
@@ -1855,8 +2749,17 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
                 Constraint::Length(1), // 18: refresh interval label
                 Constraint::Length(3), // 19: refresh interval input
                 Constraint::Length(1), // 20: spacing
-                Constraint::Length(1), // 21: template fields header
-                Constraint::Min(0),    // 22: template fields list + config path
+                Constraint::Length(1), // 21: worktree directory label
+                Constraint::Length(3), // 22: worktree directory input
+                Constraint::Length(1), // 23: spacing
+                Constraint::Length(1), // 24: branch name label
+                Constraint::Length(3), // 25: branch name input
+                Constraint::Length(1), // 26: spacing
+                Constraint::Length(1), // 27: base branch label
+                Constraint::Length(3), // 28: base branch input
+                Constraint::Length(1), // 29: spacing
+                Constraint::Length(1), // 30: template fields header
+                Constraint::Min(0),    // 31: template fields list + config path
             ])
             .split(inner);
 
@@ -1867,15 +2770,18 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
         let session_active = config_edit.active_field == 4;
         let mux_active = config_edit.active_field == 5;
         let refresh_active = config_edit.active_field == 6;
+        let dir_template_active = config_edit.active_field == 7;
+        let branch_template_active = config_edit.active_field == 8;
+        let base_branch_active = config_edit.active_field == 9;
 
         // Verify command field
         let verify_label = Paragraph::new(Line::from(vec![Span::styled(
             "Verify Command",
             Style::default()
                 .fg(if verify_active {
-                    Color::Cyan
+                    theme().accent
                 } else {
-                    Color::Gray
+                    theme().muted
                 })
                 .add_modifier(Modifier::BOLD),
         )]));
@@ -1883,19 +2789,19 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
 
         let verify_border = if verify_active {
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(theme().dim)
         };
         let verify_block = Block::default()
             .borders(Borders::ALL)
             .border_style(verify_border)
             .title(" Command ");
         let text_style = Style::default()
-            .fg(Color::White)
+            .fg(theme().text)
             .add_modifier(Modifier::BOLD);
-        let cursor_style = Style::default().fg(Color::Black).bg(Color::Cyan);
+        let cursor_style = theme().badge(theme().accent);
         let verify_spans = text_input_spans(
             &config_edit.verify_command,
             text_style,
@@ -1910,9 +2816,9 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             "Editor Command",
             Style::default()
                 .fg(if editor_active {
-                    Color::Cyan
+                    theme().accent
                 } else {
-                    Color::Gray
+                    theme().muted
                 })
                 .add_modifier(Modifier::BOLD),
         )]));
@@ -1920,10 +2826,10 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
 
         let editor_border = if editor_active {
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(theme().dim)
         };
         let editor_block = Block::default()
             .borders(Borders::ALL)
@@ -1935,7 +2841,7 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             vec![Span::styled(
                 editor_placeholder,
                 Style::default()
-                    .fg(Color::DarkGray)
+                    .fg(theme().dim)
                     .add_modifier(Modifier::BOLD),
             )]
         } else {
@@ -1954,9 +2860,9 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             "Open PRs as Ready (not draft)",
             Style::default()
                 .fg(if pr_ready_active {
-                    Color::Cyan
+                    theme().accent
                 } else {
-                    Color::Gray
+                    theme().muted
                 })
                 .add_modifier(Modifier::BOLD),
         )]));
@@ -1964,9 +2870,9 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
 
         let checkbox = if config_edit.pr_ready { "[x]" } else { "[ ]" };
         let toggle_color = if pr_ready_active {
-            Color::White
+            theme().text
         } else {
-            Color::DarkGray
+            theme().dim
         };
         let pr_ready_text = Paragraph::new(Line::from(vec![
             Span::styled(
@@ -1981,7 +2887,7 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
                 } else {
                     "  Disabled — PRs will be opened as draft"
                 },
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme().dim),
             ),
         ]));
         frame.render_widget(pr_ready_text, chunks[7]);
@@ -1991,9 +2897,9 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             "Auto Open PRs",
             Style::default()
                 .fg(if auto_open_pr_active {
-                    Color::Cyan
+                    theme().accent
                 } else {
-                    Color::Gray
+                    theme().muted
                 })
                 .add_modifier(Modifier::BOLD),
         )]));
@@ -2005,9 +2911,9 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             "[ ]"
         };
         let auto_open_toggle_color = if auto_open_pr_active {
-            Color::White
+            theme().text
         } else {
-            Color::DarkGray
+            theme().dim
         };
         let auto_open_pr_text = Paragraph::new(Line::from(vec![
             Span::styled(
@@ -2022,7 +2928,7 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
                 } else {
                     "  Disabled — sessions will only commit and push"
                 },
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme().dim),
             ),
         ]));
         frame.render_widget(auto_open_pr_text, chunks[10]);
@@ -2032,9 +2938,9 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             "Session Command",
             Style::default()
                 .fg(if session_active {
-                    Color::Cyan
+                    theme().accent
                 } else {
-                    Color::Gray
+                    theme().muted
                 })
                 .add_modifier(Modifier::BOLD),
         )]));
@@ -2042,10 +2948,10 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
 
         let session_border = if session_active {
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(theme().dim)
         };
         let session_block = Block::default()
             .borders(Borders::ALL)
@@ -2057,7 +2963,7 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             vec![Span::styled(
                 session_placeholder,
                 Style::default()
-                    .fg(Color::DarkGray)
+                    .fg(theme().dim)
                     .add_modifier(Modifier::BOLD),
             )]
         } else {
@@ -2075,15 +2981,19 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
         let mux_label = Paragraph::new(Line::from(vec![Span::styled(
             "Terminal Multiplexer",
             Style::default()
-                .fg(if mux_active { Color::Cyan } else { Color::Gray })
+                .fg(if mux_active {
+                    theme().accent
+                } else {
+                    theme().muted
+                })
                 .add_modifier(Modifier::BOLD),
         )]));
         frame.render_widget(mux_label, chunks[15]);
 
         let mux_toggle_color = if mux_active {
-            Color::White
+            theme().text
         } else {
-            Color::DarkGray
+            theme().dim
         };
         let mux_text = Paragraph::new(Line::from(vec![
             Span::styled(
@@ -2100,7 +3010,7 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
                         crate::session::Multiplexer::Screen => "GNU Screen",
                     }
                 ),
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme().dim),
             ),
         ]));
         frame.render_widget(mux_text, chunks[16]);
@@ -2110,9 +3020,9 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             "Auto Refresh Interval (seconds)",
             Style::default()
                 .fg(if refresh_active {
-                    Color::Cyan
+                    theme().accent
                 } else {
-                    Color::Gray
+                    theme().muted
                 })
                 .add_modifier(Modifier::BOLD),
         )]));
@@ -2120,10 +3030,10 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
 
         let refresh_border = if refresh_active {
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(theme().dim)
         };
         let refresh_block = Block::default()
             .borders(Borders::ALL)
@@ -2133,7 +3043,7 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
             vec![Span::styled(
                 "0 (disabled)",
                 Style::default()
-                    .fg(Color::DarkGray)
+                    .fg(theme().dim)
                     .add_modifier(Modifier::BOLD),
             )]
         } else {
@@ -2147,85 +3057,165 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
         let refresh_text = Paragraph::new(Line::from(refresh_spans)).block(refresh_block);
         frame.render_widget(refresh_text, chunks[19]);
 
+        // Worktree directory, branch name template and base branch fields
+        let base_placeholder = match crate::git::origin_head_branch() {
+            Some(branch) => format!("{} (origin/HEAD)", branch),
+            None => "main".to_string(),
+        };
+        let template_inputs = [
+            (
+                "Worktree Directory Template",
+                &config_edit.worktree_dir_template,
+                dir_template_active,
+                DEFAULT_WORKTREE_DIR_TEMPLATE,
+                21,
+            ),
+            (
+                "Branch Name Template",
+                &config_edit.branch_template,
+                branch_template_active,
+                DEFAULT_BRANCH_TEMPLATE,
+                24,
+            ),
+            (
+                "Base Branch",
+                &config_edit.base_branch,
+                base_branch_active,
+                base_placeholder.as_str(),
+                27,
+            ),
+        ];
+        for (label, input, active, placeholder, row) in template_inputs {
+            let template_label = Paragraph::new(Line::from(vec![Span::styled(
+                label,
+                Style::default()
+                    .fg(if active {
+                        theme().accent
+                    } else {
+                        theme().muted
+                    })
+                    .add_modifier(Modifier::BOLD),
+            )]));
+            frame.render_widget(template_label, chunks[row]);
+
+            let template_border = if active {
+                Style::default()
+                    .fg(theme().text)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme().dim)
+            };
+            let template_block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(template_border)
+                .title(if row == 27 { " Branch " } else { " Template " });
+            let template_spans = if input.is_empty() && !active {
+                vec![Span::styled(
+                    placeholder,
+                    Style::default()
+                        .fg(theme().dim)
+                        .add_modifier(Modifier::BOLD),
+                )]
+            } else {
+                text_input_spans(input, text_style, cursor_style, active)
+            };
+            let template_text = Paragraph::new(Line::from(template_spans)).block(template_block);
+            frame.render_widget(template_text, chunks[row + 1]);
+        }
+
         // Template fields header
         let fields_header = Paragraph::new(Line::from(vec![Span::styled(
             "Available template fields:",
             Style::default()
-                .fg(Color::Gray)
+                .fg(theme().muted)
                 .add_modifier(Modifier::BOLD),
         )]));
-        frame.render_widget(fields_header, chunks[21]);
+        frame.render_widget(fields_header, chunks[30]);
 
         // Template fields list + config path in the remaining space
         let mut lines: Vec<Line> = Vec::new();
         // Editor/Verify template fields
         lines.push(Line::from(vec![Span::styled(
             "  Editor & Verify commands:",
-            Style::default().fg(Color::Gray),
+            Style::default().fg(theme().muted),
         )]));
         for (field, desc) in EDITOR_TEMPLATE_FIELDS {
             lines.push(Line::from(vec![
-                Span::styled(format!("    {} ", field), Style::default().fg(Color::Cyan)),
-                Span::styled(format!("- {}", desc), Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!("    {} ", field),
+                    Style::default().fg(theme().accent),
+                ),
+                Span::styled(format!("- {}", desc), Style::default().fg(theme().dim)),
             ]));
         }
         // Command shortcuts
         lines.push(Line::from(vec![Span::styled(
             "  Shortcuts (expand {directory} automatically):",
-            Style::default().fg(Color::Gray),
+            Style::default().fg(theme().muted),
         )]));
         for (shortcut, expansion, desc) in COMMAND_SHORTCUTS {
             lines.push(Line::from(vec![
                 Span::styled(
                     format!("    {} ", shortcut),
-                    Style::default().fg(Color::Cyan),
-                ),
-                Span::styled(format!("- {} ", desc), Style::default().fg(Color::DarkGray)),
-                Span::styled(
-                    format!("({})", expansion),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme().accent),
                 ),
+                Span::styled(format!("- {} ", desc), Style::default().fg(theme().dim)),
+                Span::styled(format!("({})", expansion), Style::default().fg(theme().dim)),
             ]));
         }
         // Session command shortcuts
         lines.push(Line::from(vec![Span::styled(
             "  Session command shortcuts:",
-            Style::default().fg(Color::Gray),
+            Style::default().fg(theme().muted),
         )]));
         for (shortcut, expansion, desc) in SESSION_SHORTCUTS {
             lines.push(Line::from(vec![
                 Span::styled(
                     format!("    {} ", shortcut),
-                    Style::default().fg(Color::Cyan),
-                ),
-                Span::styled(format!("- {} ", desc), Style::default().fg(Color::DarkGray)),
-                Span::styled(
-                    format!("({})", expansion),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme().accent),
                 ),
+                Span::styled(format!("- {} ", desc), Style::default().fg(theme().dim)),
+                Span::styled(format!("({})", expansion), Style::default().fg(theme().dim)),
             ]));
         }
         // Session template fields
         lines.push(Line::from(vec![Span::styled(
             "  Session command fields:",
-            Style::default().fg(Color::Gray),
+            Style::default().fg(theme().muted),
         )]));
         for (field, desc) in TEMPLATE_FIELDS {
             lines.push(Line::from(vec![
-                Span::styled(format!("    {} ", field), Style::default().fg(Color::Cyan)),
-                Span::styled(format!("- {}", desc), Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!("    {} ", field),
+                    Style::default().fg(theme().accent),
+                ),
+                Span::styled(format!("- {}", desc), Style::default().fg(theme().dim)),
+            ]));
+        }
+        // Worktree directory and branch name template fields
+        lines.push(Line::from(vec![Span::styled(
+            "  Worktree directory & branch name templates:",
+            Style::default().fg(theme().muted),
+        )]));
+        for (field, desc) in WORKTREE_TEMPLATE_FIELDS {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("    {} ", field),
+                    Style::default().fg(theme().accent),
+                ),
+                Span::styled(format!("- {}", desc), Style::default().fg(theme().dim)),
             ]));
         }
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
-            Span::styled("Config file: ", Style::default().fg(Color::DarkGray)),
+            Span::styled("Config file: ", Style::default().fg(theme().dim)),
             Span::styled(
                 config_path().to_string_lossy().to_string(),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(theme().muted),
             ),
         ]));
         let fields_list = Paragraph::new(lines);
-        frame.render_widget(fields_list, chunks[22]);
+        frame.render_widget(fields_list, chunks[31]);
     }
 
     // Bottom hint bar
@@ -2235,14 +3225,11 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
         .split(vertical[2]);
 
     let key_style = Style::default()
-        .fg(Color::White)
-        .bg(Color::Rgb(60, 60, 60))
-        .add_modifier(Modifier::BOLD);
-    let desc_style = Style::default().fg(Color::Gray);
-    let key_accent = Style::default()
-        .fg(Color::Black)
-        .bg(Color::Green)
+        .fg(theme().text)
+        .bg(theme().key_bg)
         .add_modifier(Modifier::BOLD);
+    let desc_style = Style::default().fg(theme().muted);
+    let key_accent = theme().badge(theme().success).add_modifier(Modifier::BOLD);
 
     let hints = vec![
         Span::styled(" Tab ", key_style),
@@ -2255,3 +3242,400 @@ pub fn ui_configuration(frame: &mut Frame, app: &App) {
 
     frame.render_widget(Paragraph::new(Line::from(hints)), bottom_rows[0]);
 }
+
+/// Add a key hint: the keys, then what they do. Left out when nothing is
+/// bound to the action.
+fn push_hint<'a>(
+    spans: &mut Vec<Span<'a>>,
+    keys: String,
+    key_style: Style,
+    label: &'a str,
+    desc_style: Style,
+) {
+    if !keys.is_empty() {
+        spans.push(Span::styled(format!(" {} ", keys), key_style));
+        spans.push(Span::styled(label, desc_style));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+    use crate::config::SavedView;
+    use crate::github::RateLimit;
+    use crate::models::{
+        push_message, CheckStatus, ConfigEditState, ConfirmAction, IssueOptions, Screen,
+        StackPosition,
+    };
+    use crate::session::Multiplexer;
+    use crate::templates::IssueTemplate;
+    use crate::testing::{check_snapshot, Sandbox};
+
+    const SIZES: [(u16, u16); 3] = [(80, 24), (120, 32), (180, 48)];
+
+    fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn card(id: &str, title: &str, description: &str, tag: &str, tag_color: Color) -> Card {
+        Card {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: description.to_string(),
+            tag: tag.to_string(),
+            tag_color,
+            ..Default::default()
+        }
+    }
+
+    /// A board with something in every column: labels, a stacked worktree
+    /// with local changes, sessions in each state and PRs with checks.
+    fn board() -> App {
+        let mut app = App::new(
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::default(),
+            Multiplexer::Tmux,
+        );
+        app.screen = Screen::Board;
+        app.repo = "acme/widgets".to_string();
+        app.issues = vec![
+            card(
+                "issue-7",
+                "#7 Login fails with SSO accounts that have no email address set",
+                "Users signing in through the corporate identity provider get a blank page",
+                "bug",
+                Color::Red,
+            ),
+            card(
+                "issue-8",
+                "#8 Dark mode",
+                "No description",
+                "open",
+                Color::Green,
+            ),
+        ];
+        app.issues[0].related = vec!["issue-7".to_string()];
+
+        let mut parent = card(
+            "wt-issue-7",
+            "issue-7",
+            "/src/widgets-issue-7",
+            "branch",
+            Color::Yellow,
+        );
+        parent.related = vec!["issue-7".to_string()];
+        parent.worktree_status = Some(WorktreeStatus {
+            ahead: 3,
+            behind: 1,
+            conflicts: Some(true),
+            changed: 2,
+            untracked: 1,
+            last_commit_subject: Some("Handle missing email claim".to_string()),
+            last_commit_time: Some(now() - 9000),
+        });
+        let mut child = card(
+            "wt-issue-9",
+            "issue-9",
+            "/src/widgets-issue-9",
+            "branch",
+            Color::Yellow,
+        );
+        child.stack = Some(StackPosition {
+            parent: "issue-7".to_string(),
+            depth: 1,
+        });
+        child.worktree_status = Some(WorktreeStatus::default());
+        app.worktrees = vec![parent, child];
+
+        app.sessions = vec![
+            card(
+                "session-issue-7",
+                "issue-7",
+                "working",
+                "working",
+                Color::Green,
+            ),
+            card("session-main", "main", "idle", "idle", Color::DarkGray),
+        ];
+
+        let mut pr = card(
+            "pr-12",
+            "#12 Fall back to the username when SSO has no email",
+            "Closes #7",
+            "ready",
+            Color::Green,
+        );
+        pr.pr_number = Some(12);
+        pr.head_branch = Some("issue-7".to_string());
+        pr.checks = Some(CheckStatus::Failure);
+        pr.related = vec!["issue-7".to_string()];
+        let mut draft = card(
+            "pr-13",
+            "#13 Dark mode",
+            "issue-8",
+            "draft",
+            Color::DarkGray,
+        );
+        draft.pr_number = Some(13);
+        draft.is_draft = Some(true);
+        draft.checks = Some(CheckStatus::Pending);
+        app.pull_requests = vec![pr, draft];
+
+        app.main_behind_count = 2;
+        app.rate_limit = Some(RateLimit {
+            remaining: 312,
+            limit: 5000,
+            reset_at: now() + 1800,
+        });
+        app.board_fetched_at = Some(now() - 330);
+        app.board_stale = true;
+        app.show_messages = true;
+        push_message(&app.message_log, "[github] Could not reach GitHub");
+        app
+    }
+
+    #[test]
+    fn screens_match_snapshots() {
+        // No config file, so every screen shows the defaults
+        let Some(_sandbox) = Sandbox::enter_with_env(
+            "ui::tests::screens_match_snapshots",
+            &[("XDG_CONFIG_HOME", "/nonexistent/octopai-snapshots")],
+        ) else {
+            return;
+        };
+        let mut failures = Vec::new();
+        let mut check = |name: &str, draw: &dyn Fn(&mut Frame)| {
+            if let Err(e) = check_snapshot(name, &SIZES, draw) {
+                failures.push(e);
+            }
+        };
+
+        let mut app = board();
+        check("board", &|f| ui(f, &app));
+
+        app.mode = Mode::Filtering {
+            query: TextInput::from("sso".to_string()),
+            focused: true,
+        };
+        check("board_filtering", &|f| ui(f, &app));
+
+        app.active_view = Some(SavedView {
+            name: "drafts".to_string(),
+            sessions: "main".to_string(),
+            pull_requests: "status:pending".to_string(),
+            ..SavedView::default()
+        });
+        app.mode = Mode::NamingView {
+            input: TextInput::from("drafts".to_string()),
+            filter: None,
+        };
+        check("board_view", &|f| ui(f, &app));
+        app.active_view = None;
+
+        app.mode = Mode::Normal;
+        app.issues[0].labels = vec!["bug".to_string()];
+        app.arrangements[0].group = GroupMode::Label;
+        app.collapsed_groups[0].insert("No label".to_string());
+        app.arrangements[2].sort = SortMode::State;
+        app.arrangements[3].group = GroupMode::State;
+        check("board_grouped", &|f| ui(f, &app));
+        app.arrangements = Default::default();
+        app.collapsed_groups = Default::default();
+
+        app.mode = Mode::CreatingIssue;
+        let mut modal = IssueModal::new();
+        modal.title = TextInput::from("Crash on empty board".to_string());
+        modal.error = Some("gh is not logged in — run `gh auth login` and refresh".to_string());
+        app.issue_modal = Some(modal);
+        check("issue_modal", &|f| ui(f, &app));
+
+        let template = IssueTemplate {
+            name: "Bug report".to_string(),
+            about: "Something isn't working".to_string(),
+            title: "[Bug]: ".to_string(),
+            body: "### What happened?\n\n### Steps to reproduce".to_string(),
+            labels: vec!["bug".to_string()],
+            assignees: Vec::new(),
+        };
+        let mut modal = IssueModal::new();
+        modal.offer_templates(vec![template]);
+        app.issue_modal = Some(modal);
+        check("issue_template_picker", &|f| ui(f, &app));
+
+        // The template filled in, with the repo's labels being browsed.
+        let mut modal = app.issue_modal.take().unwrap();
+        modal.apply_template(0);
+        modal.set_options(IssueOptions {
+            labels: vec!["bug".to_string(), "enhancement".to_string()],
+            assignees: vec!["octocat".to_string()],
+            milestones: vec!["v1.0".to_string()],
+        });
+        modal.active_field = 2;
+        modal.labels.next();
+        app.issue_modal = Some(modal);
+        check("issue_modal_fields", &|f| ui(f, &app));
+        app.issue_modal = None;
+
+        app.mode = Mode::EditingIssue;
+        app.edit_issue_modal = Some(EditIssueModal::new(
+            7,
+            "Login fails with SSO accounts".to_string(),
+            "Users signing in through the corporate identity provider get a blank page."
+                .to_string(),
+        ));
+        check("edit_issue_modal", &|f| ui(f, &app));
+
+        // The body with focus: a selection, ending at the cursor.
+        let mut modal = EditIssueModal::new(
+            7,
+            "Login fails with SSO accounts".to_string(),
+            "Users signing in through the corporate identity provider get a blank page.\n\n\
+             - Chrome\n- Firefox"
+                .to_string(),
+        );
+        modal.active_field = 1;
+        modal.body.handle_key(KeyEvent::from(KeyCode::Up));
+        modal.body.handle_key(KeyEvent::new(
+            KeyCode::Left,
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        ));
+        app.edit_issue_modal = Some(modal);
+        check("issue_body_selection", &|f| ui(f, &app));
+        app.edit_issue_modal = None;
+
+        app.mode = Mode::Confirming;
+        app.confirm_modal = Some(ConfirmModal {
+            message: "Merge PR #12 with merge strategy?".to_string(),
+            on_confirm: ConfirmAction::CloseIssue { number: 12 },
+        });
+        check("confirm_modal", &|f| ui(f, &app));
+        app.confirm_modal = None;
+
+        // Both issues marked and about to be closed together
+        let section = app.active_section;
+        app.mode = Mode::Normal;
+        app.active_section = 0;
+        app.marked[0] = HashSet::from(["issue-7".to_string(), "issue-8".to_string()]);
+        check("board_marked", &|f| ui(f, &app));
+        app.update(crate::action::Action::CloseIssue);
+        check("confirm_bulk", &|f| ui(f, &app));
+        app.confirm_modal = None;
+        app.marked = Default::default();
+        app.active_section = section;
+
+        app.mode = Mode::Normal;
+        app.active_section = 3;
+        let pr = &mut app.pull_requests[0];
+        pr.url = Some("https://github.com/acme/widgets/pull/12".to_string());
+        pr.labels = vec!["bug".to_string(), "priority".to_string()];
+        pr.assignees = vec!["octocat".to_string()];
+        pr.full_description = Some(
+            "## Summary\n\
+             Use the **username** when the `email` claim is missing.\n\n\
+             - [x] Fall back in the callback\n\
+             - [ ] Backfill existing accounts\n\n\
+             Closes #7, see [the SSO docs](https://example.com/sso)."
+                .to_string(),
+        );
+        app.detail_scroll = Some(0);
+        check("card_detail", &|f| ui(f, &app));
+        app.detail_scroll = None;
+        app.active_section = 0;
+
+        app.mode = Mode::ChoosingBase {
+            issue_id: "issue-8".to_string(),
+            input: TextInput::from("#7".to_string()),
+        };
+        check("base_prompt", &|f| ui(f, &app));
+
+        app.mode = Mode::Normal;
+        app.loading_message = Some("Creating worktree and session for issue #8...".to_string());
+        check("loading", &|f| ui(f, &app));
+        app.loading_message = None;
+
+        let mut local = board();
+        local.local_mode = true;
+        local.rate_limit = None;
+        local.board_stale = false;
+        local.section_loading = [false, true, false, false];
+        check("board_local", &|f| ui(f, &local));
+
+        let mut repo_select = RepoSelectState::new();
+        repo_select.input = TextInput::from("acme".to_string());
+        repo_select.error = Some("No repos found for 'acme'".to_string());
+        check("repo_select_typing", &|f| {
+            ui_repo_select(f, &repo_select, false)
+        });
+        repo_select.repos = vec!["acme/widgets".to_string(), "acme/gadgets".to_string()];
+        repo_select.update_filtered();
+        repo_select.error = None;
+        repo_select.phase = RepoSelectPhase::Picking;
+        repo_select.selected = 1;
+        check("repo_select_picking", &|f| {
+            ui_repo_select(f, &repo_select, false)
+        });
+
+        let deps = vec![
+            Dependency {
+                name: "git",
+                description: "Worktrees and branches",
+                required: true,
+                recommended: false,
+                available: true,
+                version: Some("2.45.0".to_string()),
+            },
+            Dependency {
+                name: "tmux / screen",
+                description: "Terminal sessions",
+                required: true,
+                recommended: false,
+                available: false,
+                version: None,
+            },
+            Dependency {
+                name: "gh",
+                description: "GitHub issues and PRs",
+                required: false,
+                recommended: true,
+                available: false,
+                version: None,
+            },
+        ];
+        check("dependencies", &|f| ui_dependencies(f, &deps, 1, None));
+        let confirm = DepInstallConfirm {
+            install_target: "tmux".to_string(),
+            command: "brew install tmux".to_string(),
+        };
+        check("dependencies_install", &|f| {
+            ui_dependencies(f, &deps, 1, Some(&confirm))
+        });
+
+        check("ai_setup", &|f| {
+            ui_ai_setup(f, &AiSetupState { selected: 1 })
+        });
+
+        app.config_edit = Some(ConfigEditState::new(
+            String::new(),
+            "code {directory}".to_string(),
+            false,
+            true,
+            String::new(),
+            Multiplexer::Tmux,
+            0,
+            String::new(),
+            String::new(),
+            String::new(),
+        ));
+        check("configuration", &|f| ui_configuration(f, &app));
+
+        assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+    }
+}